@@ -0,0 +1,350 @@
+//! Hash chain of assignment checks
+//!
+//! The chain starts with `blake3(salt || course || assignment_id || value)`, each next
+//! element is a hash of the previous one. Assignment checker keeps the tail of the chain and accepts
+//! its parent. The crate is `no_std` to be reused by off-chain verifiers and WASM clients.
+//!
+//! Keyed chains start with `blake3::keyed_hash(salt, course || assignment_id || value)` instead,
+//! the salt is the key of the hash rather than a part of the hashed input.
+//!
+//! Tooling that sets up many assignments enables `std` feature for `cache`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Whether `parent` hashes into `expected_tail`
+pub fn verify_parent(parent: &[u8; 32], expected_tail: &[u8; 32]) -> bool {
+    blake3::hash(parent).as_bytes() == expected_tail
+}
+
+/// Whether hashing `ancestor` `distance` times gives `expected_tail`
+///
+/// Zero distance is never verified, the tail itself isn't a proof of the solution.
+pub fn verify_ancestor(ancestor: &[u8; 32], distance: u32, expected_tail: &[u8; 32]) -> bool {
+    distance > 0 && hash_times(*ancestor, distance) == *expected_tail
+}
+
+/// First element of the hash chain of the assignment
+///
+/// The course address and assignment ID make parents assignment specific. Chains of
+/// different assignments with the same salt and value don't share elements.
+pub fn chain_start(
+    salt: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+    value: &[u8],
+) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(salt);
+    hasher.update(course);
+    hasher.update(assignment_id);
+    hasher.update(value);
+    *hasher.finalize().as_bytes()
+}
+
+/// First element of the keyed hash chain of the assignment, see `chain_start`
+pub fn keyed_chain_start(
+    salt: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+    value: &[u8],
+) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_keyed(salt);
+    hasher.update(course);
+    hasher.update(assignment_id);
+    hasher.update(value);
+    *hasher.finalize().as_bytes()
+}
+
+/// Hash of the course master secret kept by the course instead of the secret
+pub fn master_secret_hash(master_secret: &[u8]) -> [u8; 32] {
+    *blake3::hash(master_secret).as_bytes()
+}
+
+/// Salt of the assignment derived from `master_secret_hash` of the course
+///
+/// The assignment checker derives the same salt on-chain, so course authorities
+/// build chain tails of all assignments from one secret.
+pub fn derive_salt(
+    master_secret_hash: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_keyed(master_secret_hash);
+    hasher.update(b"assignment_salt");
+    hasher.update(course);
+    hasher.update(assignment_id);
+    *hasher.finalize().as_bytes()
+}
+
+/// Tail of the hash chain of length `len` for the salted value of the assignment
+///
+/// The first element of the chain has length 1.
+pub fn build_tail(
+    salt: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+    value: &[u8],
+    len: u16,
+) -> [u8; 32] {
+    build_iterated_tail(salt, course, assignment_id, value, len, 1)
+}
+
+/// Tail of the hash chain where each step applies hash `iterations_per_step` times
+pub fn build_iterated_tail(
+    salt: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+    value: &[u8],
+    len: u16,
+    iterations_per_step: u8,
+) -> [u8; 32] {
+    hash_times(
+        chain_start(salt, course, assignment_id, value),
+        u32::from(len.saturating_sub(1)) * u32::from(iterations_per_step),
+    )
+}
+
+/// Tail of the keyed hash chain where each step applies hash `iterations_per_step` times
+pub fn build_keyed_iterated_tail(
+    salt: &[u8; 32],
+    course: &[u8; 32],
+    assignment_id: &[u8; 16],
+    value: &[u8],
+    len: u16,
+    iterations_per_step: u8,
+) -> [u8; 32] {
+    hash_times(
+        keyed_chain_start(salt, course, assignment_id, value),
+        u32::from(len.saturating_sub(1)) * u32::from(iterations_per_step),
+    )
+}
+
+fn hash_times(hash: [u8; 32], times: u32) -> [u8; 32] {
+    (0..times).fold(hash, |hash, _| *blake3::hash(&hash).as_bytes())
+}
+
+/// Memoized tails of assignment chains built on all cores
+#[cfg(feature = "std")]
+pub mod cache {
+    use std::collections::{HashMap, HashSet};
+    use std::thread;
+
+    /// Inputs of the chain tail of an assignment
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct ChainSpec {
+        pub salt: [u8; 32],
+        pub course: [u8; 32],
+        pub assignment_id: [u8; 16],
+        pub value: Vec<u8>,
+        pub len: u16,
+        pub iterations_per_step: u8,
+        /// The chain starts with `keyed_chain_start`
+        pub keyed: bool,
+    }
+
+    impl ChainSpec {
+        /// Tail of the chain built without cache
+        pub fn build_tail(&self) -> [u8; 32] {
+            let build = if self.keyed {
+                super::build_keyed_iterated_tail
+            } else {
+                super::build_iterated_tail
+            };
+            build(
+                &self.salt,
+                &self.course,
+                &self.assignment_id,
+                &self.value,
+                self.len,
+                self.iterations_per_step,
+            )
+        }
+    }
+
+    /// Tails of built chains
+    ///
+    /// The tail depends on all inputs of `ChainSpec`, the course and the assignment ID
+    /// are part of the chain start.
+    #[derive(Clone, Debug, Default)]
+    pub struct TailCache {
+        tails: HashMap<ChainSpec, [u8; 32]>,
+    }
+
+    impl TailCache {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Tail of the chain, built once
+        pub fn tail(&mut self, spec: &ChainSpec) -> [u8; 32] {
+            if let Some(tail) = self.tails.get(spec) {
+                return *tail;
+            }
+            let tail = spec.build_tail();
+            self.tails.insert(spec.clone(), tail);
+            tail
+        }
+
+        /// Tails of the chains in order of `specs`
+        ///
+        /// Chains missing in the cache are split between threads of available cores.
+        pub fn tails(&mut self, specs: &[ChainSpec]) -> Vec<[u8; 32]> {
+            let missing: Vec<ChainSpec> = specs
+                .iter()
+                .filter(|spec| !self.tails.contains_key(*spec))
+                .cloned()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+            let chunk_len = missing.len().div_ceil(threads);
+            if chunk_len > 0 {
+                let workers: Vec<_> = missing
+                    .chunks(chunk_len)
+                    .map(|chunk| {
+                        let chunk = chunk.to_vec();
+                        thread::spawn(move || {
+                            chunk
+                                .into_iter()
+                                .map(|spec| {
+                                    let tail = spec.build_tail();
+                                    (spec, tail)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                for worker in workers {
+                    self.tails
+                        .extend(worker.join().expect("tail builder doesn't panic"));
+                }
+            }
+            specs.iter().map(|spec| self.tails[spec]).collect()
+        }
+
+        /// Number of cached tails
+        pub fn len(&self) -> usize {
+            self.tails.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.tails.is_empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: [u8; 32] = [7; 32];
+    const COURSE: [u8; 32] = [1; 32];
+    const ASSIGNMENT_ID: &[u8; 16] = b"space_hero______";
+    const VALUE: &[u8] = b"Gagarin";
+
+    #[test]
+    fn test_verify_parent() {
+        let parent = chain_start(&SALT, &COURSE, ASSIGNMENT_ID, VALUE);
+        let tail = hash_times(parent, 1);
+        assert!(verify_parent(&parent, &tail));
+        assert!(!verify_parent(&tail, &tail));
+        assert!(!verify_parent(&parent, &hash_times(parent, 2)));
+    }
+
+    #[test]
+    fn test_verify_ancestor() {
+        let ancestor = chain_start(&SALT, &COURSE, ASSIGNMENT_ID, VALUE);
+        let tail = hash_times(ancestor, 3);
+        assert!(verify_ancestor(&ancestor, 3, &tail));
+        assert!(!verify_ancestor(&ancestor, 2, &tail));
+        assert!(!verify_ancestor(&ancestor, 4, &tail));
+        // the parent is the ancestor of distance 1
+        let parent = hash_times(ancestor, 2);
+        assert_eq!(
+            verify_ancestor(&parent, 1, &tail),
+            verify_parent(&parent, &tail)
+        );
+        // the tail isn't its own ancestor
+        assert!(!verify_ancestor(&tail, 0, &tail));
+    }
+
+    #[test]
+    fn test_build_tail() {
+        let start = chain_start(&SALT, &COURSE, ASSIGNMENT_ID, VALUE);
+        // the chain of length 1 is its start, zero length saturates to it
+        assert_eq!(build_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 1), start);
+        assert_eq!(build_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 0), start);
+        let tail = build_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 5);
+        assert_eq!(tail, hash_times(start, 4));
+        assert!(verify_parent(
+            &build_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 4),
+            &tail
+        ));
+        assert!(verify_ancestor(&start, 4, &tail));
+        assert_eq!(
+            build_iterated_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 3, 2),
+            tail
+        );
+        // other assignments of the course don't share the chain
+        assert_ne!(
+            build_tail(&SALT, &COURSE, b"space_hero_2____", VALUE, 5),
+            tail
+        );
+    }
+
+    #[test]
+    fn test_keyed_chain_start() {
+        let start = chain_start(&SALT, &COURSE, ASSIGNMENT_ID, VALUE);
+        let keyed_start = keyed_chain_start(&SALT, &COURSE, ASSIGNMENT_ID, VALUE);
+        assert_ne!(keyed_start, start);
+        assert_eq!(
+            build_keyed_iterated_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 1, 1),
+            keyed_start
+        );
+        assert_eq!(
+            build_keyed_iterated_tail(&SALT, &COURSE, ASSIGNMENT_ID, VALUE, 4, 1),
+            hash_times(keyed_start, 3)
+        );
+        // the salt is the key, not the prefix of the hashed input
+        let mut input = COURSE.to_vec();
+        input.extend_from_slice(ASSIGNMENT_ID);
+        input.extend_from_slice(VALUE);
+        assert_eq!(keyed_start, *blake3::keyed_hash(&SALT, &input).as_bytes());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_tail_cache() {
+        use cache::{ChainSpec, TailCache};
+
+        let spec = |len: u16, keyed: bool| ChainSpec {
+            salt: SALT,
+            course: COURSE,
+            assignment_id: *ASSIGNMENT_ID,
+            value: VALUE.to_vec(),
+            len,
+            iterations_per_step: 1,
+            keyed,
+        };
+        let specs = [
+            spec(3, false),
+            spec(2, true),
+            spec(3, false),
+            spec(1, false),
+        ];
+        let mut cache = TailCache::new();
+        assert!(cache.is_empty());
+        let tails = cache.tails(&specs);
+        // tails follow the order of specs, repeated specs are built once
+        assert_eq!(
+            tails,
+            specs.iter().map(ChainSpec::build_tail).collect::<Vec<_>>()
+        );
+        assert_eq!(tails[0], tails[2]);
+        assert_ne!(tails[0], tails[1]);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.tail(&spec(2, true)), tails[1]);
+        assert_eq!(cache.len(), 3);
+        cache.tail(&spec(4, true));
+        assert_eq!(cache.len(), 4);
+    }
+}
@@ -1,34 +1,96 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::blake3;
+use anchor_lang::solana_program::pubkey::PUBKEY_BYTES;
+use anchor_lang::{error::ErrorCode, system_program, AccountsClose, Discriminator};
 
 declare_id!("Po1RaS8BEDbNcn5oXsFryAeQ6Wn8fvmE111DJaKCgPC");
 
 pub const COURSE_DATA_SEED: &[u8; 11] = b"course_data";
 pub const ASSIGNMENT_ID_SEED: &[u8; 13] = b"assignment_id";
 pub const STUDENT_ADDRESS_SEED: &[u8; 15] = b"student_address";
+/// Denominator of reward scales expressed in basis points
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Reservations of hash chain slots can be released after this period
+pub const RESERVATION_TTL_SECONDS: i64 = 10 * 60;
+/// Shortest hash chain that accepts a check
+///
+/// The chain start of length 1 is the reserved hash, it has no parent that could be checked and
+/// its own parent would be the answer. Chains of length 1 are fully used like empty ones.
+pub const MIN_CHECKABLE_HASH_CHAIN_LENGTH: u16 = 2;
+/// Length of the hash chain of the shared answer, see `convert_to_shared`
+pub const SHARED_ANSWER_HASH_CHAIN_LENGTH: u16 = 2;
+/// Max number of hash chain shards of the checker including the main chain
+pub const MAX_SHARDS: usize = 4;
+pub const CRITERIA_SEED: &[u8; 8] = b"criteria";
+/// Max number of grading criteria of the assignment, see `init_criteria`
+pub const MAX_CRITERIA: usize = 4;
+/// Max number of blake3 hashes of `AssignmentCheckerState::plaintext_parent`
+///
+/// The hashes are computed by the program without syscall, the limit bounds their compute.
+pub const MAX_PLAINTEXT_HASHES: u32 = 64;
+/// Layout version of new check results, see `CheckResult::from_versioned_data`
+///
+/// Version 1 adds `schema_version`, version 2 adds `reserved` bytes,
+/// version 3 takes `reward_claimable` from them, version 4 takes `migrated_to`,
+/// version 5 takes `passed_criteria` and `weighted_score_bps`, version 6 takes `finalized`,
+/// version 7 takes `generation`, version 8 takes `extra_attempts`, version 9 adds `proctor`.
+pub const CHECK_RESULT_SCHEMA_VERSION: u8 = 9;
+/// Zeroed bytes at the end of new checkers, future fields take them without realloc
+///
+/// New fields shrink it by their size, `AssignmentCheckerState::LEN` stays the same.
+pub const CHECKER_RESERVED_LEN: usize = 64;
+/// Zeroed bytes at the end of new check results, future fields take them without realloc
+pub const CHECK_RESULT_RESERVED_LEN: usize = 16;
 // Owner of AssignmentCheckerState and CheckResult accounts
 #[program]
 pub mod assignment_checker {
     use super::*;
 
-    pub fn init(
-        ctx: Context<Init>,
-        assignment_id: [u8; 16],
-        hash_chain_length: u16,
-        to_mint_on_successful_check: u16,
-        salt: [u8; 32],
-        // Creator of assignment checker is a trusted authority
-        // It should precompute ground truth hash chain tail
-        // to save nonfree compute operations of onchain program
-        // and not to send the ground truth assignment result value to public blockchain
-        ground_truth_hash_chain_tail: [u8; 32],
-    ) -> Result<()> {
+    /// Init assignment checker created by the result_processor_program
+    ///
+    /// The checker takes `checker_generation` of the course, the result_processor_program
+    /// registers the assignment by `course_manager::register_assignment` before the init.
+    /// Zero salt is derived from `master_secret_hash` of the course when it's set.
+    pub fn init(ctx: Context<Init>, checker: NewAssignmentChecker) -> Result<()> {
+        let NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length,
+            to_mint_on_successful_check,
+            salt,
+            per_student_salt,
+            hash_iterations_per_step,
+            ground_truth_hash_chain_tail,
+        } = checker;
+        if hash_iterations_per_step == 0 {
+            return Err(error!(AssignmentCheckerError::ZeroHashIterations));
+        }
+        let master_secret_hash = ctx.accounts.course.master_secret_hash;
+        let salt = if salt == [0; 32] && master_secret_hash != [0; 32] {
+            hash_chain::derive_salt(
+                &master_secret_hash,
+                &ctx.accounts.course.key().to_bytes(),
+                &assignment_id,
+            )
+        } else {
+            salt
+        };
+        if is_weak_salt(&salt, &assignment_id) {
+            return Err(error!(AssignmentCheckerError::WeakSalt));
+        }
         let checker_account = &mut ctx.accounts.assignment_checker;
         checker_account.assignment_id = assignment_id;
         checker_account.hash_chain_length = hash_chain_length;
+        checker_account.revealed_hash_chain_length = hash_chain_length;
+        checker_account.initial_hash_chain_length = hash_chain_length;
         checker_account.to_mint_on_successful_check = to_mint_on_successful_check;
         checker_account.salt = salt;
+        checker_account.per_student_salt = per_student_salt;
+        checker_account.hash_iterations_per_step = hash_iterations_per_step;
+        checker_account.reward_rounding = RewardRounding::Floor;
         *checker_account.ground_truth_hash_chain_tail() = ground_truth_hash_chain_tail;
+        checker_account.last_activity_unix_ts = Clock::get()?.unix_timestamp;
+        checker_account.generation = ctx.accounts.course.checker_generation;
+        checker_account.course = ctx.accounts.course.key();
         checker_account.bump_seed = *ctx
             .bumps
             .get("assignment_checker")
@@ -37,10 +99,388 @@ pub mod assignment_checker {
         Ok(())
     }
 
+    /// Close assignment checker and return rent to course authority
+    pub fn close(_ctx: Context<Close>) -> Result<()> {
+        // account is closed by Anchor
+        Ok(())
+    }
+
+    /// Close check result that hasn't passed and return rent to the student
+    ///
+    /// Passed check results keep the record of the rewarded pass, reservation
+    /// should be given up before closing. Check results with claimable or escrowed
+    /// rewards are rejected first, so the student learns about the pending reward.
+    /// Failed attempts are kept until the last one, so closing doesn't reset `max_attempts`.
+    pub fn close_result(
+        _ctx: Context<CloseResult>,
+        _course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        // account is closed by Anchor
+        Ok(())
+    }
+
+    /// Lock check result as the official submission of the student
+    ///
+    /// Finalized check results are included into grading snapshots. They cannot be
+    /// checked, reserved, migrated or closed anymore, pending rewards are still paid.
+    pub fn finalize_result(
+        ctx: Context<FinalizeResult>,
+        _course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.check_result.finalized = true;
+        msg!(
+            "check result {} is finalized",
+            ctx.accounts.check_result.key()
+        );
+        Ok(())
+    }
+
+    /// Move check result of the student to the PDA of another seed scheme
+    ///
+    /// The old check result is derived from `old_course_secret` and the new one from
+    /// `new_course_secret` by `check_result_student_seed`, `None` stands for the student
+    /// address. The new check result keeps pass state, attempts and rewards. Pending
+    /// rewards move with it, the old check result stays marked by `migrated_to`, so
+    /// it cannot be recreated, checked or paid again.
+    pub fn migrate_result(
+        ctx: Context<MigrateResult>,
+        _old_course_secret: Option<[u8; 32]>,
+        new_course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let old_check_result = &mut ctx.accounts.old_check_result;
+        if new_course_secret.is_some() && old_check_result.has_pending_reward() {
+            return Err(error!(AssignmentCheckerError::AnonymousPendingReward));
+        }
+        let new_check_result = &mut ctx.accounts.new_check_result;
+        let new_check_result_key = new_check_result.key();
+        **new_check_result = CheckResult {
+            student_seed: check_result_student_seed(
+                &ctx.accounts.student.key(),
+                new_course_secret.as_ref(),
+            ),
+            bump_seed: *ctx
+                .bumps
+                .get("new_check_result")
+                .expect("new_check_result pda is present"),
+            schema_version: CHECK_RESULT_SCHEMA_VERSION,
+            ..(**old_check_result).clone()
+        };
+        old_check_result.reward.escrowed = 0;
+        old_check_result.reward_claimable = 0;
+        old_check_result.migrated_to = new_check_result_key;
+        msg!(
+            "migrated check result {} to {}",
+            old_check_result.key(),
+            new_check_result_key
+        );
+        Ok(())
+    }
+
+    /// Set rounding mode applied to scaled mint amounts
+    pub fn set_reward_rounding(
+        ctx: Context<UpdateChecker>,
+        reward_rounding: RewardRounding,
+    ) -> Result<()> {
+        ctx.accounts.assignment_checker.reward_rounding = reward_rounding;
+        Ok(())
+    }
+
+    /// Cap number of successful checks over the checker lifetime, 0 removes the cap
+    ///
+    /// The cap bounds total reward emission of the checker and is enforced by `extend_chain`.
+    pub fn set_max_lifetime_passes(
+        ctx: Context<UpdateChecker>,
+        max_lifetime_passes: u16,
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        checker_account.max_lifetime_passes = max_lifetime_passes;
+        let uncapped_shared_answer = checker_account.shared_answer && max_lifetime_passes != 0;
+        if uncapped_shared_answer || !checker_account.lifetime_passes_within_cap() {
+            return Err(error!(AssignmentCheckerError::LifetimePassesExceeded));
+        }
+        Ok(())
+    }
+
+    /// Consume a slot of the main hash chain on each wrong answer
+    ///
+    /// Guessing costs a slot, so the chain should be generous. Shards and shared answer
+    /// have no slots to consume.
+    pub fn set_consume_on_failure(
+        ctx: Context<UpdateChecker>,
+        consume_on_failure: bool,
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if consume_on_failure && (checker_account.shared_answer || checker_account.added_shards > 0)
+        {
+            return Err(error!(AssignmentCheckerError::ConsumeOnFailure));
+        }
+        checker_account.consume_on_failure = consume_on_failure;
+        Ok(())
+    }
+
+    /// Close check result of the student who has failed `max_attempts` checks
+    ///
+    /// The failed check result holds no pass or reward, so its rent returns to the student.
+    /// The result_processor_program lets the student create it again only after
+    /// `extend_chain` or a new generation of the checker. 0 disables the limit.
+    pub fn set_max_attempts(ctx: Context<UpdateChecker>, max_attempts: u16) -> Result<()> {
+        ctx.accounts.assignment_checker.max_attempts = max_attempts;
+        Ok(())
+    }
+
+    /// Set how hash chains of the checker start, see `CommitmentMode`
+    ///
+    /// Students build parents with the mode, so it can't change once the checker has passes
+    /// or reserved slots.
+    pub fn set_commitment_mode(
+        ctx: Context<UpdateChecker>,
+        commitment_mode: CommitmentMode,
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if checker_account.first_time_passes() > 0 || checker_account.reserved_slots > 0 {
+            return Err(error!(AssignmentCheckerError::CommitmentModeLocked));
+        }
+        checker_account.commitment_mode = commitment_mode;
+        Ok(())
+    }
+
+    /// Commit to the length of the answer in bytes, zero removes the commitment
+    ///
+    /// Short answers of practice assignments are checked in plaintext, submissions
+    /// of another length are typos or guesses that aren't worth the hashing.
+    pub fn set_answer_len_commitment(
+        ctx: Context<UpdateChecker>,
+        answer_len_commitment: u16,
+    ) -> Result<()> {
+        ctx.accounts.assignment_checker.answer_len_commitment = answer_len_commitment;
+        Ok(())
+    }
+
+    /// Apply `Some` fields of the config update at once, the other fields are kept
+    ///
+    /// Saves a transaction per setter when several settings change together.
+    pub fn update_config(ctx: Context<UpdateChecker>, config: CheckerConfigUpdate) -> Result<()> {
+        ctx.accounts.assignment_checker.apply_config(&config);
+        Ok(())
+    }
+
+    /// Hide the checker from active listings without closing it
+    ///
+    /// Archived checker rejects checks, its hash chain and check results are kept.
+    pub fn set_archived(ctx: Context<UpdateChecker>, archived: bool) -> Result<()> {
+        ctx.accounts.assignment_checker.archived = archived;
+        Ok(())
+    }
+
+    /// Add the checker to the module of the course, zero `module_id` removes it
+    ///
+    /// The result_processor_program keeps assignment count of the module in sync.
+    pub fn set_module(ctx: Context<UpdateChecker>, module_id: [u8; 16]) -> Result<()> {
+        ctx.accounts.assignment_checker.module_id = module_id;
+        Ok(())
+    }
+
+    /// Reject new passes after `auto_pause_after_seconds` since creation or last extension
+    ///
+    /// Limits exposure of leaked answers of forgotten checkers, 0 disables the pause.
+    /// `extend_chain` resumes the paused checker.
+    pub fn set_auto_pause(
+        ctx: Context<UpdateChecker>,
+        auto_pause_after_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts.assignment_checker.auto_pause_after_seconds = auto_pause_after_seconds;
+        Ok(())
+    }
+
+    /// Replace the hash chain by a longer one with `extension` more slots
+    ///
+    /// Revealed part of the current chain is public, so the course authority provides
+    /// the tail of a new chain for the same value, a fresh salt and the same
+    /// `hash_iterations_per_step`. Its length is
+    /// `hash_chain_length + extension`. Reserved slots refer to the current chain
+    /// and should be checked or released before the extension. Extended chain
+    /// can't exceed `max_lifetime_passes`.
+    pub fn extend_chain(
+        ctx: Context<UpdateChecker>,
+        extension: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if checker_account.shared_answer {
+            return Err(error!(AssignmentCheckerError::SharedAnswer));
+        }
+        if checker_account.reserved_slots > 0 {
+            return Err(error!(AssignmentCheckerError::ReservationsPending));
+        }
+        checker_account.extend(extension)?;
+        if !checker_account.lifetime_passes_within_cap() {
+            return Err(error!(AssignmentCheckerError::LifetimePassesExceeded));
+        }
+        checker_account.salt = salt;
+        *checker_account.ground_truth_hash_chain_tail() = ground_truth_hash_chain_tail;
+        checker_account.last_activity_unix_ts = Clock::get()?.unix_timestamp;
+        msg!(
+            "extended hash chain to length {}",
+            checker_account.hash_chain_length
+        );
+        Ok(())
+    }
+
+    /// Add independent hash chain shard to the checker
+    ///
+    /// Students checking on different shards don't invalidate expected hash chain
+    /// lengths of each other. The shard has own `salt` and `ground_truth_hash_chain_tail`
+    /// of `hash_chain_length` for the same value and `hash_iterations_per_step`.
+    /// Reservations, per student salt and `extend_chain` use the main chain of shard 0.
+    pub fn add_shard(
+        ctx: Context<UpdateChecker>,
+        hash_chain_length: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if checker_account.shared_answer {
+            return Err(error!(AssignmentCheckerError::SharedAnswer));
+        }
+        if checker_account.per_student_salt {
+            return Err(error!(AssignmentCheckerError::MainShardOnly));
+        }
+        if hash_chain_length < 2 {
+            return Err(error!(AssignmentCheckerError::ShortHashChain));
+        }
+        if checker_account.consume_on_failure {
+            return Err(error!(AssignmentCheckerError::ConsumeOnFailure));
+        }
+        let index = usize::from(checker_account.added_shards);
+        let shard = checker_account
+            .shards
+            .get_mut(index)
+            .ok_or_else(|| error!(AssignmentCheckerError::ShardLimit))?;
+        *shard = ChainShard {
+            salt,
+            ground_truth_hash_chain_tail,
+            hash_chain_length,
+            initial_hash_chain_length: hash_chain_length,
+        };
+        checker_account.added_shards += 1;
+        if !checker_account.lifetime_passes_within_cap() {
+            return Err(error!(AssignmentCheckerError::LifetimePassesExceeded));
+        }
+        checker_account.debug_assert_chain_invariant();
+        msg!(
+            "added shard {} of length {}",
+            checker_account.added_shards,
+            hash_chain_length
+        );
+        Ok(())
+    }
+
+    /// Grade the assignment by several criteria with partial credit
+    ///
+    /// Each criterion has own hash chain of the answer to the criterion and a non-zero
+    /// weight. Students pass the criteria with `check_criterion`, the weighted fraction
+    /// of passed criteria is their score. Chains hash each step `hash_iterations_per_step`
+    /// times of the assignment checker.
+    pub fn init_criteria(ctx: Context<InitCriteria>, criteria: Vec<NewCriterion>) -> Result<()> {
+        if criteria.is_empty() || criteria.len() > MAX_CRITERIA {
+            return Err(error!(AssignmentCheckerError::InvalidCriteria));
+        }
+        let assignment_id = ctx.accounts.assignment_checker.assignment_id;
+        let assignment_criteria = &mut ctx.accounts.assignment_criteria;
+        assignment_criteria.assignment_id = assignment_id;
+        for (index, criterion) in criteria.iter().enumerate() {
+            if criterion.weight == 0
+                || criterion.hash_chain_length < MIN_CHECKABLE_HASH_CHAIN_LENGTH
+            {
+                return Err(error!(AssignmentCheckerError::InvalidCriteria));
+            }
+            if is_weak_salt(&criterion.salt, &assignment_id) {
+                return Err(error!(AssignmentCheckerError::WeakSalt));
+            }
+            assignment_criteria.chains[index] = ChainShard {
+                salt: criterion.salt,
+                ground_truth_hash_chain_tail: criterion.ground_truth_hash_chain_tail,
+                hash_chain_length: criterion.hash_chain_length,
+                initial_hash_chain_length: criterion.hash_chain_length,
+            };
+            assignment_criteria.weights[index] = criterion.weight;
+        }
+        assignment_criteria.criteria_count = criteria.len() as u8;
+        assignment_criteria.bump_seed = *ctx
+            .bumps
+            .get("assignment_criteria")
+            .expect("assignment_criteria pda is present");
+        msg!(
+            "init {} criteria of assignment checker {}",
+            criteria.len(),
+            ctx.accounts.assignment_checker.key()
+        );
+        Ok(())
+    }
+
+    /// Accept the same answer from any number of students instead of the hash chain
+    ///
+    /// `ground_truth_hash` is the tail of `SHARED_ANSWER_HASH_CHAIN_LENGTH` for the `salt`,
+    /// students pass with the same parent. Remaining slots of the chain are discarded.
+    /// Check results of passed students are kept. Conversion is rejected for checkers
+    /// with pending reservations, per student salt, shards or `max_lifetime_passes`.
+    pub fn convert_to_shared(
+        ctx: Context<UpdateChecker>,
+        salt: [u8; 32],
+        ground_truth_hash: [u8; 32],
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if checker_account.shared_answer {
+            return Err(error!(AssignmentCheckerError::SharedAnswer));
+        }
+        if checker_account.reserved_slots > 0 {
+            return Err(error!(AssignmentCheckerError::ReservationsPending));
+        }
+        if checker_account.per_student_salt || checker_account.added_shards > 0 {
+            return Err(error!(AssignmentCheckerError::NotConvertible));
+        }
+        if checker_account.max_lifetime_passes != 0 {
+            // shared answer has no limit of passes
+            return Err(error!(AssignmentCheckerError::LifetimePassesExceeded));
+        }
+        if checker_account.consume_on_failure {
+            return Err(error!(AssignmentCheckerError::ConsumeOnFailure));
+        }
+        checker_account.discarded_slots = checker_account
+            .discarded_slots
+            .checked_add(checker_account.hash_chain_length)
+            .ok_or_else(|| error!(AssignmentCheckerError::ChainCounterOverflow))?;
+        checker_account.hash_chain_length = 0;
+        checker_account.revealed_hash_chain_length = SHARED_ANSWER_HASH_CHAIN_LENGTH;
+        checker_account.salt = salt;
+        *checker_account.ground_truth_hash_chain_tail() = ground_truth_hash;
+        checker_account.shared_answer = true;
+        checker_account.debug_assert_chain_invariant();
+        msg!(
+            "assignment checker {} accepts shared answer",
+            checker_account.key()
+        );
+        Ok(())
+    }
+
     /// Init check result created by the result_processor_program
-    pub fn init_check_result(ctx: Context<InitCheckResult>, assignment_id: [u8; 16]) -> Result<()> {
+    ///
+    /// Anonymous check result is derived from `course_secret`, see `check_result_student_seed`.
+    /// Recreated check result keeps `extra_attempts` of the one closed by the last failed attempt.
+    pub fn init_check_result(
+        ctx: Context<InitCheckResult>,
+        assignment_id: [u8; 16],
+        course_secret: Option<[u8; 32]>,
+        extra_attempts: u16,
+    ) -> Result<()> {
+        let student_seed =
+            check_result_student_seed(&ctx.accounts.student.key(), course_secret.as_ref());
         let check_result = &mut ctx.accounts.check_result;
         check_result.assignment_id = assignment_id;
+        check_result.student_seed = student_seed;
+        check_result.schema_version = CHECK_RESULT_SCHEMA_VERSION;
+        check_result.extra_attempts = extra_attempts;
         check_result.bump_seed = *ctx
             .bumps
             .get("check_result")
@@ -49,15 +489,228 @@ pub mod assignment_checker {
         Ok(())
     }
 
+    /// Upgrade check result of an older layout to `CHECK_RESULT_SCHEMA_VERSION`
+    ///
+    /// The account grows to the current layout, the student pays the rent difference.
+    /// Check passes and rewards are kept, so upgraded results aren't rewarded again.
+    /// Current check results are left as is.
+    pub fn upgrade_check_result(ctx: Context<UpgradeCheckResult>) -> Result<()> {
+        let check_result_info = ctx.accounts.check_result.to_account_info();
+        let check_result = CheckResult::from_versioned_data(&check_result_info.try_borrow_data()?)?;
+        let check_result_address = Pubkey::create_program_address(
+            &[
+                STUDENT_ADDRESS_SEED,
+                check_result.student_seed.as_ref(),
+                COURSE_DATA_SEED,
+                ctx.accounts.course.key().as_ref(),
+                ASSIGNMENT_ID_SEED,
+                check_result.assignment_id.as_ref(),
+                &[check_result.bump_seed],
+            ],
+            ctx.accounts.result_processor_program.key,
+        )
+        .map_err(|_| error!(AssignmentCheckerError::InvalidCheckResult))?;
+        if check_result_address != check_result_info.key() {
+            return Err(error!(AssignmentCheckerError::InvalidCheckResult));
+        }
+        if check_result.schema_version == CHECK_RESULT_SCHEMA_VERSION
+            && check_result_info.data_len() == 8 + CheckResult::LEN
+        {
+            msg!("check result {} is up to date", check_result_info.key());
+            return Ok(());
+        }
+
+        let rent = Rent::get()?.minimum_balance(8 + CheckResult::LEN);
+        let rent_difference = rent.saturating_sub(check_result_info.lamports());
+        if rent_difference > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.student.to_account_info(),
+                        to: check_result_info.clone(),
+                    },
+                ),
+                rent_difference,
+            )?;
+        }
+        check_result_info.realloc(8 + CheckResult::LEN, false)?;
+        let upgraded = CheckResult {
+            schema_version: CHECK_RESULT_SCHEMA_VERSION,
+            ..check_result
+        };
+        upgraded.try_serialize(&mut &mut check_result_info.try_borrow_mut_data()?[..])?;
+        msg!(
+            "upgraded check result {} to schema version {}",
+            check_result_info.key(),
+            CHECK_RESULT_SCHEMA_VERSION
+        );
+        Ok(())
+    }
+
+    /// Grant extra attempts to the student above `max_attempts` of the checker
+    ///
+    /// Allowances add up. The result_processor_program keeps them for the check result
+    /// recreated after the failed last attempt.
+    pub fn grant_extra_attempts(
+        ctx: Context<GrantExtraAttempts>,
+        extra_attempts: u16,
+    ) -> Result<()> {
+        let check_result = &mut ctx.accounts.check_result;
+        check_result.extra_attempts = check_result
+            .extra_attempts
+            .checked_add(extra_attempts)
+            .ok_or_else(|| error!(AssignmentCheckerError::ExtraAttemptsOverflow))?;
+        Ok(())
+    }
+
+    /// Set commitment of the student for checkers with per student salt
+    ///
+    /// The commitment is `hash(student_hash_chain_tail_parent(salt, student, value))`
+    /// where hash is applied `hash_iterations_per_step` times.
+    /// It's precomputed by the course authority so that hash chain tail parent
+    /// accepted for one student is useless for others.
+    pub fn set_student_commitment(
+        ctx: Context<SetStudentCommitment>,
+        student_commitment: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.check_result.student_commitment = student_commitment;
+        Ok(())
+    }
+
+    /// Save reward bookkeeping of the check result
+    ///
+    /// Rewards are maintained by the result_processor_program, only it can sign for check_result
+    pub fn update_reward_ledger(
+        ctx: Context<UpdateRewardLedger>,
+        reward: RewardLedger,
+    ) -> Result<()> {
+        ctx.accounts.check_result.reward = reward;
+        Ok(())
+    }
+
+    /// Save reward that the result_processor_program pays on claim instead of the check
+    pub fn set_reward_claimable(
+        ctx: Context<UpdateRewardLedger>,
+        reward_claimable: u64,
+    ) -> Result<()> {
+        ctx.accounts.check_result.reward_claimable = reward_claimable;
+        Ok(())
+    }
+
+    /// Reserve the next hash chain slot for the student
+    ///
+    /// The slot is taken from `hash_chain_length` at once, so concurrent reservations
+    /// never conflict. The student submits the parent of the reserved tail to `check`
+    /// before `RESERVATION_TTL_SECONDS` pass. Expired or exposed reservation of the student
+    /// is replaced by the new one.
+    pub fn reserve(ctx: Context<Reserve>, _course_secret: Option<[u8; 32]>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let check_result_account = &mut ctx.accounts.check_result;
+        if check_result_account.check_passed {
+            return Err(error!(AssignmentCheckerError::AlreadyPassed));
+        }
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        check_result_account.bind_generation(checker_account.generation)?;
+        let reservation = check_result_account.reservation;
+        if reservation.is_reserved() {
+            if !reservation.is_releasable(checker_account, now) {
+                return Err(error!(AssignmentCheckerError::ReservationActive));
+            }
+            checker_account.release_reservation(reservation.hash_chain_length);
+        }
+        check_result_account.reservation = Reservation {
+            hash_chain_length: checker_account.take_slot(true)?,
+            expires_unix_ts: now + RESERVATION_TTL_SECONDS,
+        };
+        msg!(
+            "reserved hash chain length {} until {}",
+            check_result_account.reservation.hash_chain_length,
+            check_result_account.reservation.expires_unix_ts
+        );
+        Ok(())
+    }
+
+    /// Release expired or exposed reservation and return its slot to the hash chain
+    ///
+    /// Anyone can release abandoned reservations. Reservations of a closed checker
+    /// with the same assignment ID are released at once, their slots are gone with it.
+    pub fn release_reservation(ctx: Context<ReleaseReservation>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let check_result_account = &mut ctx.accounts.check_result;
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        let reservation = check_result_account.reservation;
+        if !reservation.is_reserved() {
+            return Err(error!(AssignmentCheckerError::NoReservation));
+        }
+        let stale = check_result_account.is_stale(checker_account);
+        if !stale && !reservation.is_releasable(checker_account, now) {
+            return Err(error!(AssignmentCheckerError::ReservationActive));
+        }
+        if !stale {
+            checker_account.release_reservation(reservation.hash_chain_length);
+        }
+        check_result_account.reservation = Reservation::default();
+        Ok(())
+    }
+
+    /// Give up active reservation of the student and return its slot to the hash chain
+    ///
+    /// Successful check clears the reservation, so committed slots cannot be released.
+    pub fn give_up_reservation(
+        ctx: Context<Reserve>,
+        _course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let check_result_account = &mut ctx.accounts.check_result;
+        if check_result_account.check_passed {
+            return Err(error!(AssignmentCheckerError::AlreadyPassed));
+        }
+        let reservation = check_result_account.reservation;
+        if !reservation.is_reserved() {
+            return Err(error!(AssignmentCheckerError::NoReservation));
+        }
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if !check_result_account.is_stale(checker_account) {
+            checker_account.release_reservation(reservation.hash_chain_length);
+        }
+        check_result_account.reservation = Reservation::default();
+        msg!(
+            "gave up reservation of hash chain length {}",
+            reservation.hash_chain_length
+        );
+        Ok(())
+    }
+
     /// Check assignment and save result into check_result account.
     ///
+    /// The student with a reservation submits the parent of the reserved tail,
+    /// `expected_hash_chain_length` is ignored then.
+    ///
+    /// The check makes no CPI calls while the checker state is borrowed. Programs called
+    /// by a future step of the check cannot re-enter it, the runtime rejects reentrancy
+    /// into a program that is already on the invocation stack.
+    ///
     /// Errors:
     ///     * Returns `AssignmentChecker::ZeroHashChainLength` when the hash
-    ///     chain is fully used.
+    ///     chain is fully used, i.e. shorter than `MIN_CHECKABLE_HASH_CHAIN_LENGTH`
+    ///     and only the reserved chain start is left.
     ///     * Returns `AssignmentChecker::ExpectedHashLengthDiffers` when client expects
     ///     different hash chain length than the checker currently has.  This
     ///     can happen during concurrent checks by multiple students and should
-    ///     be mitigated by retry with actual hash chain length
+    ///     be mitigated by retry with actual hash chain length or by `reserve`
+    ///     * Returns `AssignmentChecker::ReservationExpired` when the reservation has expired.
+    ///     * Returns `AssignmentChecker::ReservationExposed` when a lower slot was revealed
+    ///     before the reserved one, the student should reserve again
+    ///     * Returns `AssignmentChecker::CheckerArchived` when the checker is archived.
+    ///     * Returns `AssignmentChecker::ResultMigrated` when the check result was migrated.
+    ///     * Returns `AssignmentChecker::CriteriaGraded` when the student has passed criteria.
+    ///     * Returns `AssignmentChecker::ResultFinalized` when the check result is finalized.
+    ///     * Returns `AssignmentChecker::MaxAttemptsExceeded` when the check result has failed
+    ///     `max_attempts` checks. The failed check that reaches the limit closes the check result.
+    ///     * Returns `AssignmentChecker::StaleCheckResult` when the check result was used
+    ///     by a closed checker of the same assignment ID.
+    ///     * Returns `AssignmentChecker::ResultOwnershipMismatch` when the check result
+    ///     belongs to another student.
     pub fn check(
         ctx: Context<Check>,
         // used to validate the hash chain length
@@ -65,16 +718,207 @@ pub mod assignment_checker {
         expected_hash_chain_length: u16,
         // the hash before current hash chain tail
         hash_chain_tail_parent: [u8; 32],
+        // used to derive anonymous check result, seeds of `Check` take it
+        _course_secret: Option<[u8; 32]>,
+        // hash chain shard of the checker, see `student_shard`
+        shard: u8,
+    ) -> Result<()> {
+        check_answer(
+            &mut ctx.accounts.assignment_checker,
+            &mut ctx.accounts.check_result,
+            ctx.accounts.student.to_account_info(),
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            shard,
+        )
+    }
+
+    /// Check assignment on behalf of the student and record the proctor of the pass
+    ///
+    /// The proctor is authorized for the course by `set_proctor` of course_manager,
+    /// the student doesn't sign. The check result is derived from the student address,
+    /// anonymous check results aren't proctored. Other errors are the errors of `check`.
+    ///
+    /// Errors:
+    ///     * Returns `AssignmentChecker::ProctorNotAuthorized` when the signer is not
+    ///     the proctor of the course or proctored checks are not allowed.
+    pub fn proctored_check(
+        ctx: Context<ProctoredCheck>,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+        shard: u8,
+    ) -> Result<()> {
+        let repeat = ctx.accounts.check_result.check_passed;
+        check_answer(
+            &mut ctx.accounts.assignment_checker,
+            &mut ctx.accounts.check_result,
+            ctx.accounts.student.to_account_info(),
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            shard,
+        )?;
+        if !repeat && ctx.accounts.check_result.check_passed {
+            ctx.accounts.check_result.proctor = ctx.accounts.proctor.key();
+        }
+        Ok(())
+    }
+
+    /// Check the answer to a criterion of the assignment and update the score
+    ///
+    /// Passing all criteria passes the assignment. Check results graded by criteria
+    /// are not checked by `check` and the other way around. Passed criteria are kept,
+    /// checking them again doesn't change the result.
+    pub fn check_criterion(
+        ctx: Context<CheckCriterion>,
+        criterion: u8,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
     ) -> Result<()> {
+        let checker_account = &ctx.accounts.assignment_checker;
+        if checker_account.archived {
+            return Err(error!(AssignmentCheckerError::CheckerArchived));
+        }
+        if checker_account.is_paused(Clock::get()?.unix_timestamp) {
+            return Err(error!(AssignmentCheckerError::CheckerPaused));
+        }
+        let hash_iterations_per_step = checker_account.hash_iterations_per_step;
+        let assignment_criteria = &mut ctx.accounts.assignment_criteria;
+        if criterion >= assignment_criteria.criteria_count {
+            return Err(error!(AssignmentCheckerError::UnknownCriterion));
+        }
         let check_result_account = &mut ctx.accounts.check_result;
-        if check_result_account.check_passed {
-            // previous check succeded
-            // This check is no longer the first
-            check_result_account.passed_first_time = false;
+        check_result_account.bind_generation(checker_account.generation)?;
+        let criterion_mask = 1 << criterion;
+        if check_result_account.passed_criteria & criterion_mask != 0 {
+            msg!("criterion {} has already passed", criterion);
+            return Ok(());
+        }
+        check_result_account.attempts = check_result_account.attempts.saturating_add(1);
+        let passed = assignment_criteria.chains[usize::from(criterion)].check(
+            expected_hash_chain_length,
+            &hash_chain_tail_parent,
+            hash_iterations_per_step,
+        )?;
+        if passed {
+            check_result_account.passed_criteria |= criterion_mask;
+            check_result_account.weighted_score_bps =
+                assignment_criteria.weighted_score_bps(check_result_account.passed_criteria);
+            if u64::from(check_result_account.weighted_score_bps) == BPS_DENOMINATOR {
+                check_result_account.check_passed = true;
+                check_result_account.passed_first_time = true;
+            }
+            msg!(
+                "criterion {} is passed, weighted score is {} bps",
+                criterion,
+                check_result_account.weighted_score_bps
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Check the answer of `check` and `proctored_check`
+///
+/// Passes are recorded in check_result, the exhausted check result is closed to the student.
+fn check_answer<'info>(
+    assignment_checker: &mut Account<'info, AssignmentCheckerState>,
+    check_result: &mut Account<'info, CheckResult>,
+    student: AccountInfo<'info>,
+    expected_hash_chain_length: u16,
+    hash_chain_tail_parent: [u8; 32],
+    shard: u8,
+) -> Result<()> {
+    if assignment_checker.archived {
+        return Err(error!(AssignmentCheckerError::CheckerArchived));
+    }
+    if check_result.passed_criteria != 0 {
+        return Err(error!(AssignmentCheckerError::CriteriaGraded));
+    }
+    let check_result_account = &mut **check_result;
+    check_result_account.bind_generation(assignment_checker.generation)?;
+    // repeats of a passed check don't count in the pass rate
+    let repeat = check_result_account.check_passed;
+    if !check_result_account.check_passed
+        && assignment_checker.attempts_exhausted(check_result_account)
+    {
+        // the limit was lowered after the attempts
+        return Err(error!(AssignmentCheckerError::MaxAttemptsExceeded));
+    }
+    if !check_result_account.check_passed
+        && assignment_checker.is_paused(Clock::get()?.unix_timestamp)
+    {
+        return Err(error!(AssignmentCheckerError::CheckerPaused));
+    }
+    if check_result_account.check_passed {
+        // previous check succeded, the repeat keeps the persisted flags
+        msg!("check has already passed");
+    } else if assignment_checker.shared_answer {
+        // this check hasn't passed yet
+        let checker_account = &mut **assignment_checker;
+        check_result_account.attempts = check_result_account.attempts.saturating_add(1);
+        let passed = verify_hash_chain_parent(
+            &checker_account.ground_truth_hash_chain_tail,
+            SHARED_ANSWER_HASH_CHAIN_LENGTH,
+            &hash_chain_tail_parent,
+            SHARED_ANSWER_HASH_CHAIN_LENGTH - 1,
+            checker_account.hash_iterations_per_step,
+        );
+        if passed {
+            // check has passed the first time
+            check_result_account.check_passed = true;
+            check_result_account.passed_first_time = true;
+            checker_account.shared_answer_passes =
+                checker_account.shared_answer_passes.saturating_add(1);
+            checker_account.recent_passers.push(student.key());
+            checker_account.record_first_time_attempts(check_result_account.attempts);
+            msg!("check is passed with shared answer");
+        }
+    } else if shard != 0 {
+        // this check hasn't passed yet
+        let checker_account = &mut **assignment_checker;
+        check_result_account.attempts = check_result_account.attempts.saturating_add(1);
+        if check_result_account.reservation.is_reserved() || checker_account.per_student_salt {
+            return Err(error!(AssignmentCheckerError::MainShardOnly));
+        }
+        if checker_account
+            .recent_parents
+            .contains(&hash_chain_tail_parent)
+        {
+            return Err(error!(AssignmentCheckerError::DuplicateParentSubmission));
+        }
+        let passed = checker_account.check_shard(
+            shard,
+            expected_hash_chain_length,
+            &hash_chain_tail_parent,
+        )?;
+        if passed {
+            // check has passed the first time
+            check_result_account.check_passed = true;
+            check_result_account.passed_first_time = true;
+            checker_account.recent_passers.push(student.key());
+            checker_account.record_first_time_attempts(check_result_account.attempts);
+            checker_account.recent_parents.push(hash_chain_tail_parent);
+            msg!("check is passed on shard {}", shard);
+        }
+    } else {
+        // this check hasn't passed yet
+        let checker_account = &mut **assignment_checker;
+        check_result_account.attempts = check_result_account.attempts.saturating_add(1);
+        if checker_account
+            .recent_parents
+            .contains(&hash_chain_tail_parent)
+        {
+            // the parent was copied from the transaction of another student
+            return Err(error!(AssignmentCheckerError::DuplicateParentSubmission));
+        }
+        let reservation = check_result_account.reservation;
+        let reserved_hash_chain_length = if reservation.is_reserved() {
+            if Clock::get()?.unix_timestamp >= reservation.expires_unix_ts {
+                return Err(error!(AssignmentCheckerError::ReservationExpired));
+            }
+            reservation.hash_chain_length
         } else {
-            // this check hasn't passed yet
-            let checker_account = &mut ctx.accounts.assignment_checker;
-            if checker_account.hash_chain_length == 0 {
+            if checker_account.hash_chain_length < MIN_CHECKABLE_HASH_CHAIN_LENGTH {
                 // checker has used full hash chain
                 return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
             }
@@ -83,29 +927,87 @@ pub mod assignment_checker {
                 // client expects different hash chain length then the checker has at the moment
                 return Err(error!(AssignmentCheckerError::ExpectedHashLengthDiffers));
             }
+            checker_account.hash_chain_length
+        };
 
-            let tail_hash = blake3::hash(&hash_chain_tail_parent);
-            if tail_hash == blake3::Hash(checker_account.ground_truth_hash_chain_tail) {
-                // check has passed the first time
-                check_result_account.check_passed = true;
-                check_result_account.passed_first_time = true;
+        let passed = if checker_account.per_student_salt {
+            hash_chain::verify_ancestor(
+                &hash_chain_tail_parent,
+                checker_account.hash_iterations_per_step.into(),
+                &check_result_account.student_commitment,
+            )
+        } else {
+            if reservation.is_exposed(checker_account) {
+                // anyone can compute the parent from the revealed tail
+                return Err(error!(AssignmentCheckerError::ReservationExposed));
+            }
+            verify_hash_chain_parent(
+                &checker_account.ground_truth_hash_chain_tail,
+                checker_account.revealed_hash_chain_length,
+                &hash_chain_tail_parent,
+                reserved_hash_chain_length - 1,
+                checker_account.hash_iterations_per_step,
+            )
+        };
+        if passed {
+            // check has passed the first time
+            check_result_account.check_passed = true;
+            check_result_account.passed_first_time = true;
+            if reservation.is_reserved() {
+                // the tail was removed from the chain on reservation
+                checker_account.complete_reservation()?;
+                check_result_account.reservation = Reservation::default();
+            } else {
                 // remove tail from the chain
-                checker_account.hash_chain_length -= 1;
+                checker_account.take_slot(false)?;
+            }
+            if !checker_account.per_student_salt {
                 checker_account.ground_truth_hash_chain_tail = hash_chain_tail_parent;
-                msg!("check is passed");
+                checker_account.revealed_hash_chain_length = reserved_hash_chain_length - 1;
             }
-            // else: keep check_passed and passed_first_time as false
+            checker_account.recent_passers.push(student.key());
+            checker_account.record_first_time_attempts(check_result_account.attempts);
+            checker_account.recent_parents.push(hash_chain_tail_parent);
+            msg!("check is passed");
+        } else if checker_account.consume_on_failure {
+            // wrong answers are expensive to guess
+            checker_account.consume_slot(reservation.is_reserved())?;
+            check_result_account.reservation = Reservation::default();
+            msg!(
+                "wrong answer consumed a slot, hash chain length is {}",
+                checker_account.hash_chain_length
+            );
         }
-        Ok(())
+        // else: keep check_passed and passed_first_time as false
     }
+    let check_result_account = &mut **check_result;
+    if !repeat {
+        assignment_checker.record_check(check_result_account.check_passed)?;
+    }
+    if !check_result_account.check_passed
+        && assignment_checker.attempts_exhausted(check_result_account)
+    {
+        if check_result_account.reservation.is_reserved() {
+            assignment_checker
+                .release_reservation(check_result_account.reservation.hash_chain_length);
+        }
+        msg!(
+            "check result {} has failed max attempts and is closed",
+            check_result.key()
+        );
+        check_result.close(student)?;
+    }
+    Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16], hash_chain_length: u16)]
+#[instruction(checker: NewAssignmentChecker)]
 pub struct Init<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(has_one = authority)]
+    // course is read-only here, the result_processor_program may pass it
+    // writable to update course counters by course_manager
+    #[account(has_one = authority, constraint = !course.frozen @ AssignmentCheckerError::CourseFrozen)]
     pub course: Account<'info, course_manager::Course>,
 
     #[account(
@@ -115,55 +1017,239 @@ pub struct Init<'info> {
         COURSE_DATA_SEED,
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
-        assignment_id.as_ref(),
+        checker.assignment_id.as_ref(),
     ],
-    seeds::program = result_processor_program, bump, constraint = hash_chain_length >= 2)]
+    seeds::program = result_processor_program, bump, constraint = checker.hash_chain_length >= 2)]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
     #[account(executable)]
     pub result_processor_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16])]
-pub struct InitCheckResult<'info> {
+pub struct Close<'info> {
+    // rent recipient is the course authority bound by has_one of the course
     #[account(mut)]
-    pub student: Signer<'info>,
+    pub authority: Signer<'info>,
+    // course is read-only here, the result_processor_program may pass it
+    // writable to update course counters by course_manager
+    #[account(has_one = authority, constraint = !course.frozen @ AssignmentCheckerError::CourseFrozen)]
     pub course: Account<'info, course_manager::Course>,
 
-    #[account(zero,
+    #[account(mut,
         signer,
+        close = authority,
         seeds=[
-        STUDENT_ADDRESS_SEED,
-        student.key().as_ref(),
         COURSE_DATA_SEED,
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
-        assignment_id.as_ref(),
-    ],
-    seeds::program = result_processor_program,
-    bump)]
-    pub check_result: Account<'info, CheckResult>,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
     #[account(executable)]
     pub result_processor_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Check<'info> {
+#[instruction(course_secret: Option<[u8; 32]>)]
+pub struct CloseResult<'info> {
+    // rent recipient is the student bound by check_result seeds
     #[account(mut)]
     pub student: Signer<'info>,
     pub course: Account<'info, course_manager::Course>,
 
     #[account(mut,
         signer,
+        close = student,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.has_pending_reward() @ AssignmentCheckerError::UnclaimedRewardPresent,
+    constraint = !check_result.check_passed @ AssignmentCheckerError::AlreadyPassed,
+    constraint = !check_result.reservation.is_reserved() @ AssignmentCheckerError::ReservationActive,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    constraint = check_result.attempts == 0 @ AssignmentCheckerError::ResultHasAttempts,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(course_secret: Option<[u8; 32]>)]
+pub struct FinalizeResult<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.reservation.is_reserved() @ AssignmentCheckerError::ReservationActive,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(old_course_secret: Option<[u8; 32]>, new_course_secret: Option<[u8; 32]>)]
+pub struct MigrateResult<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), old_course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        old_check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=old_check_result.bump_seed,
+    constraint = !old_check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !old_check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    constraint = !old_check_result.reservation.is_reserved() @ AssignmentCheckerError::ReservationActive,
+    )]
+    pub old_check_result: Account<'info, CheckResult>,
+    #[account(zero,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), new_course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        old_check_result.assignment_id.as_ref(),
+    ],
+    seeds::program = result_processor_program,
+    bump)]
+    pub new_check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitCriteria<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority, constraint = !course.frozen @ AssignmentCheckerError::CourseFrozen)]
+    pub course: Account<'info, course_manager::Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(
+        zero,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        CRITERIA_SEED,
+    ],
+    seeds::program = result_processor_program, bump)]
+    pub assignment_criteria: Account<'info, AssignmentCriteria>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateChecker<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority, constraint = !course.frozen @ AssignmentCheckerError::CourseFrozen)]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeCheckResult<'info> {
+    // pays rent of the grown account
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+    // older layouts cannot be deserialized as CheckResult,
+    // pda check is made by upgrade_check_result
+    #[account(mut, signer, owner = ID)]
+    pub check_result: AccountInfo<'info>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16], course_secret: Option<[u8; 32]>)]
+pub struct InitCheckResult<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(zero,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ],
+    seeds::program = result_processor_program,
+    bump)]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStudentCommitment<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(
         seeds=[
         COURSE_DATA_SEED,
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_checker.assignment_id.as_ref(),
     ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    constraint = assignment_checker.per_student_salt @ AssignmentCheckerError::SharedSalt,
     constraint = assignment_checker.assignment_id == check_result.assignment_id
     )]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // student is not required to sign, the account is used to derive check_result PDA
+    pub student: AccountInfo<'info>,
 
     #[account(mut,
         signer,
@@ -175,53 +1261,1430 @@ pub struct Check<'info> {
         ASSIGNMENT_ID_SEED,
         check_result.assignment_id.as_ref(),
     ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.check_passed @ AssignmentCheckerError::AlreadyPassed
     )]
     pub check_result: Account<'info, CheckResult>,
-    // result_processor_program is expected to be called by student
-    // and sign for mutable PDAs
-    // student cannot call check directly
     #[account(executable)]
     pub result_processor_program: AccountInfo<'info>,
 }
 
-#[account]
-pub struct AssignmentCheckerState {
-    /// Assignment ID is unique within a course
-    pub assignment_id: [u8; 16],
-    /// Max number of successful checks possible + 1
-    ///
-    /// at least 1 check per student of the batch + 1 hash
-    /// to keep the ground truth value away of sending to public blockchain
-    pub hash_chain_length: u16,
-    pub to_mint_on_successful_check: u16,
-    pub salt: [u8; 32],
-    /// Result of hash(...(hash(hashv([salt, value]))...)
-    ///
-    /// hash is applied `hash_chain_length` number of times
-    ground_truth_hash_chain_tail: [u8; 32],
-    pub bump_seed: u8,
+#[derive(Accounts)]
+pub struct GrantExtraAttempts<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, course_manager::Course>,
+    // student is not required to sign, the account is used to derive check_result PDA
+    pub student: AccountInfo<'info>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.check_passed @ AssignmentCheckerError::AlreadyPassed
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
 }
 
-impl AssignmentCheckerState {
-    pub const LEN: usize = 16 + 2 + 2 + 32 + 32 + 1;
+#[derive(Accounts)]
+pub struct UpdateRewardLedger<'info> {
+    // student is not required to sign, the account is used to derive check_result PDA
+    pub student: AccountInfo<'info>,
+    pub course: Account<'info, course_manager::Course>,
 
-    pub fn ground_truth_hash_chain_tail(&mut self) -> &mut [u8; 32] {
-        &mut self.ground_truth_hash_chain_tail
-    }
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
 }
 
-#[account]
-pub struct CheckResult {
-    /// Assignment ID is unique within a course
-    pub assignment_id: [u8; 16],
-    pub check_passed: bool,
-    /// This is true only after first successful check
-    pub passed_first_time: bool,
-    pub bump_seed: u8,
+#[derive(Accounts)]
+#[instruction(expected_hash_chain_length: u16, hash_chain_tail_parent: [u8; 32], course_secret: Option<[u8; 32]>, shard: u8)]
+pub struct Check<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    // Anchor rejects default and checker addresses as not owned by course_manager
+    #[account(constraint = !course.forbids_self_check(&student.key()) @ AssignmentCheckerError::AuthoritySelfCheck)]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    has_one = course,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    // seeds bind the address, the stored seed binds the result to the signing student
+    constraint = check_result.belongs_to(&student.key(), course_secret.as_ref()) @ AssignmentCheckerError::ResultOwnershipMismatch,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    // result_processor_program is expected to be called by student
+    // and sign for mutable PDAs
+    // student cannot call check directly
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
 }
 
-impl CheckResult {
-    pub const LEN: usize = 16 + 1 + 1 + 1;
+#[derive(Accounts)]
+pub struct ProctoredCheck<'info> {
+    pub proctor: Signer<'info>,
+    // student is not required to sign, the account is used to derive check_result PDA
+    // and receives its rent when the check result is closed
+    #[account(mut)]
+    pub student: AccountInfo<'info>,
+    #[account(
+        constraint = course.is_authorized_proctor(&proctor.key()) @ AssignmentCheckerError::ProctorNotAuthorized,
+        constraint = !course.forbids_self_check(&student.key()) @ AssignmentCheckerError::AuthoritySelfCheck,
+    )]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    has_one = course,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    constraint = check_result.belongs_to(&student.key(), None) @ AssignmentCheckerError::ResultOwnershipMismatch,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CheckCriterion<'info> {
+    pub student: Signer<'info>,
+    #[account(constraint = !course.forbids_self_check(&student.key()) @ AssignmentCheckerError::AuthoritySelfCheck)]
+    pub course: Account<'info, course_manager::Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    has_one = course,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        CRITERIA_SEED,
+    ], seeds::program = result_processor_program, bump=assignment_criteria.bump_seed,
+    )]
+    pub assignment_criteria: Account<'info, AssignmentCriteria>,
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    constraint = !check_result.check_passed @ AssignmentCheckerError::AlreadyPassed,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(course_secret: Option<[u8; 32]>)]
+pub struct Reserve<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    constraint = !check_result.is_migrated() @ AssignmentCheckerError::ResultMigrated,
+    constraint = !check_result.finalized @ AssignmentCheckerError::ResultFinalized,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseReservation<'info> {
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    // student doesn't sign, abandoned reservations are released by anyone
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result.student_seed.as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[account]
+#[derive(Default)]
+pub struct AssignmentCheckerState {
+    /// Assignment ID is unique within a course
+    pub assignment_id: [u8; 16],
+    /// Max number of successful checks possible + 1
+    ///
+    /// at least 1 check per student of the batch + 1 hash
+    /// to keep the ground truth value away of sending to public blockchain
+    pub hash_chain_length: u16,
+    pub to_mint_on_successful_check: u16,
+    pub salt: [u8; 32],
+    /// Check against `CheckResult::student_commitment` instead of the shared hash chain
+    ///
+    /// `hash_chain_length` still limits number of successful checks
+    pub per_student_salt: bool,
+    /// Number of hashes of each hash chain step, makes brute force of short values expensive
+    pub hash_iterations_per_step: u8,
+    /// Result of hash(...(hash(hashv([salt, course, assignment_id, value]))...)
+    ///
+    /// Keyed `commitment_mode` starts the chain with the salt as the key instead
+    ///
+    /// hash is applied `hash_iterations_per_step` times on each of `hash_chain_length` - 1 steps
+    ground_truth_hash_chain_tail: [u8; 32],
+    /// Hash chain length of `ground_truth_hash_chain_tail`
+    ///
+    /// It's greater than `hash_chain_length` while reserved slots are not checked
+    pub revealed_hash_chain_length: u16,
+    /// Hash chain length on init
+    pub initial_hash_chain_length: u16,
+    /// Total number of slots added by `extend_chain`
+    pub hash_chain_extensions: u16,
+    /// Number of first time passed checks
+    pub successful_checks: u16,
+    /// Number of reserved slots that are neither checked nor released
+    pub reserved_slots: u16,
+    /// Number of released slots that were exposed and can't return to the chain
+    pub discarded_slots: u16,
+    /// Cap of `lifetime_passes`, 0 for uncapped checker
+    pub max_lifetime_passes: u16,
+    /// Number of shards added by `add_shard`, the main chain is shard 0
+    pub added_shards: u8,
+    /// Hash chains of shards 1..=`added_shards`
+    pub shards: [ChainShard; MAX_SHARDS - 1],
+    pub bump_seed: u8,
+    /// Rounding of fractional base units in scaled mint amounts
+    pub reward_rounding: RewardRounding,
+    /// Students who have recently passed the check for the first time
+    pub recent_passers: RecentPassers,
+    /// Number of first time passers by attempts they needed
+    pub attempts_histogram: AttemptsHistogram,
+    /// The checker accepts the same answer without hash chain, see `convert_to_shared`
+    pub shared_answer: bool,
+    /// Number of first time passed checks with shared answer
+    pub shared_answer_passes: u16,
+    /// Wrong answers consume slots of the main chain, see `set_consume_on_failure`
+    pub consume_on_failure: bool,
+    /// New passes are rejected this long after `last_activity_unix_ts`, 0 for no pause
+    pub auto_pause_after_seconds: u32,
+    /// Time of creation or the last `extend_chain`
+    pub last_activity_unix_ts: i64,
+    /// Hash chain tail parents consumed by recent passes of the main chain and shards
+    pub recent_parents: RecentParents,
+    /// Checks are rejected, see `set_archived`
+    pub archived: bool,
+    /// Failed checks that close the check result, 0 for unlimited attempts
+    pub max_attempts: u16,
+    /// Hashing of the salt into chain starts of the main chain and shards
+    pub commitment_mode: CommitmentMode,
+    /// Difficulty of `attempts_histogram` in basis points, see `AttemptsHistogram::difficulty_score`
+    pub difficulty_score: u16,
+    /// `Course::checker_generation` on init, recreated checker of the assignment ID gets a new one
+    pub generation: u16,
+    /// Number of checks of results that hadn't passed before, see `pass_rate_bps`
+    pub attempts_total: u32,
+    /// Number of the counted checks that passed
+    pub passes_total: u16,
+    /// `course_manager::Module` of the assignment, zero for assignments outside of modules
+    pub module_id: [u8; 16],
+    /// `course_manager::Course` of the assignment, checks are bound to it by `has_one`
+    pub course: Pubkey,
+    /// Length of the answer in bytes for plaintext checks, zero when it's not committed
+    ///
+    /// Takes the reserved space of the checker, see `set_answer_len_commitment`.
+    pub answer_len_commitment: u16,
+    /// Space for future fields, zero until they take it
+    pub reserved: CheckerReserve,
+}
+
+impl AssignmentCheckerState {
+    pub const LEN: usize = 16
+        + 2
+        + 2
+        + 32
+        + 1
+        + 1
+        + 32
+        + 2
+        + 2 * 6
+        + 1
+        + ChainShard::LEN * (MAX_SHARDS - 1)
+        + 1
+        + 1
+        + RecentPassers::LEN
+        + AttemptsHistogram::LEN
+        + 1
+        + 2
+        + 1
+        + 4
+        + 8
+        + RecentParents::LEN
+        + 1
+        + 2
+        + 1
+        + 2
+        + 2
+        + 4
+        + 2
+        + 16
+        + PUBKEY_BYTES
+        + 2
+        + CHECKER_RESERVED_LEN;
+
+    pub fn ground_truth_hash_chain_tail(&mut self) -> &mut [u8; 32] {
+        &mut self.ground_truth_hash_chain_tail
+    }
+
+    /// Amount to mint on successful check scaled by `scale_bps` basis points
+    ///
+    /// `BPS_DENOMINATOR` keeps `to_mint_on_successful_check` as is.
+    pub fn reward_amount(&self, scale_bps: u64) -> Result<u64> {
+        self.reward_rounding
+            .div(
+                u128::from(self.to_mint_on_successful_check) * u128::from(scale_bps),
+                u128::from(BPS_DENOMINATOR),
+            )
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or_else(|| error!(AssignmentCheckerError::RewardOverflow))
+    }
+
+    /// Remove the tail slot from the chain for reservation or successful check
+    ///
+    /// Returns hash chain length of the removed tail. The chain start is never removed.
+    pub fn take_slot(&mut self, reserve: bool) -> Result<u16> {
+        let slot = self.hash_chain_length;
+        if slot < MIN_CHECKABLE_HASH_CHAIN_LENGTH {
+            return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
+        }
+        self.hash_chain_length = slot - 1;
+        let counter = if reserve {
+            &mut self.reserved_slots
+        } else {
+            &mut self.successful_checks
+        };
+        *counter = counter
+            .checked_add(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::ChainCounterOverflow))?;
+        self.debug_assert_chain_invariant();
+        Ok(slot)
+    }
+
+    /// Count successful check of the reserved slot
+    pub fn complete_reservation(&mut self) -> Result<()> {
+        self.reserved_slots = self
+            .reserved_slots
+            .checked_sub(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::NoReservation))?;
+        self.successful_checks = self
+            .successful_checks
+            .checked_add(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::ChainCounterOverflow))?;
+        self.debug_assert_chain_invariant();
+        Ok(())
+    }
+
+    /// Count attempts of the first time passer in the histogram and the difficulty score
+    pub fn record_first_time_attempts(&mut self, attempts: u16) {
+        self.attempts_histogram.record(attempts);
+        self.difficulty_score = self.attempts_histogram.difficulty_score();
+    }
+
+    /// Count the check of a result that hasn't passed before in the pass rate gauge
+    pub fn record_check(&mut self, passed: bool) -> Result<()> {
+        self.attempts_total = self
+            .attempts_total
+            .checked_add(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::PassRateOverflow))?;
+        if passed {
+            self.passes_total = self
+                .passes_total
+                .checked_add(1)
+                .ok_or_else(|| error!(AssignmentCheckerError::PassRateOverflow))?;
+        }
+        Ok(())
+    }
+
+    /// The checker belongs to a module of the course
+    pub fn in_module(&self) -> bool {
+        self.module_id != [0; 16]
+    }
+
+    /// Fraction of counted checks that passed in basis points, 0 before the first check
+    pub fn pass_rate_bps(&self) -> u16 {
+        if self.attempts_total == 0 {
+            return 0;
+        }
+        (u64::from(self.passes_total) * BPS_DENOMINATOR / u64::from(self.attempts_total)) as u16
+    }
+
+    /// Whether the next check of the result passes on the main chain with the parent
+    ///
+    /// It's the verification of `check` without changing the chain: the parent of
+    /// the reserved slot or the current tail, the own commitment with `per_student_salt`.
+    pub fn verifies_parent(
+        &self,
+        check_result: &CheckResult,
+        parent: &[u8; 32],
+        now_unix_ts: i64,
+    ) -> bool {
+        if self.recent_parents.contains(parent) {
+            return false;
+        }
+        let reservation = check_result.reservation;
+        let hash_chain_length = if reservation.is_reserved() {
+            if now_unix_ts >= reservation.expires_unix_ts || reservation.is_exposed(self) {
+                return false;
+            }
+            reservation.hash_chain_length
+        } else {
+            self.hash_chain_length
+        };
+        if hash_chain_length < MIN_CHECKABLE_HASH_CHAIN_LENGTH {
+            return false;
+        }
+        if self.per_student_salt {
+            hash_chain::verify_ancestor(
+                parent,
+                self.hash_iterations_per_step.into(),
+                &check_result.student_commitment,
+            )
+        } else {
+            verify_hash_chain_parent(
+                &self.ground_truth_hash_chain_tail,
+                self.revealed_hash_chain_length,
+                parent,
+                hash_chain_length - 1,
+                self.hash_iterations_per_step,
+            )
+        }
+    }
+
+    /// The check result has used all attempts of `max_attempts` and its `extra_attempts`
+    pub fn attempts_exhausted(&self, check_result: &CheckResult) -> bool {
+        self.max_attempts != 0
+            && check_result.attempts
+                >= self
+                    .max_attempts
+                    .saturating_add(check_result.extra_attempts)
+    }
+
+    /// Return the slot of released reservation to the hash chain
+    ///
+    /// Slots above the revealed tail are discarded, their parents can be computed by anyone.
+    /// So are slots released out of order: the chain would hand out the slot of a lower
+    /// reservation twice. Checks of per-student salts don't depend on the slot.
+    pub fn release_reservation(&mut self, reserved_hash_chain_length: u16) {
+        self.reserved_slots = self.reserved_slots.saturating_sub(1);
+        let in_order =
+            self.per_student_salt || reserved_hash_chain_length == self.hash_chain_length + 1;
+        if in_order && self.hash_chain_length < self.revealed_hash_chain_length {
+            self.hash_chain_length += 1;
+        } else {
+            self.discarded_slots = self.discarded_slots.saturating_add(1);
+        }
+        self.debug_assert_chain_invariant();
+    }
+
+    /// Discard the slot of a wrong answer, see `set_consume_on_failure`
+    ///
+    /// Reserved slot of the check is discarded instead of the chain tail.
+    pub fn consume_slot(&mut self, reserved: bool) -> Result<()> {
+        let counter = if reserved {
+            &mut self.reserved_slots
+        } else {
+            &mut self.hash_chain_length
+        };
+        *counter = counter
+            .checked_sub(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::ZeroHashChainLength))?;
+        self.discarded_slots = self
+            .discarded_slots
+            .checked_add(1)
+            .ok_or_else(|| error!(AssignmentCheckerError::ChainCounterOverflow))?;
+        self.debug_assert_chain_invariant();
+        Ok(())
+    }
+
+    /// Add `extension` slots to the chain, the revealed tail starts a new chain
+    pub fn extend(&mut self, extension: u16) -> Result<()> {
+        let overflow = || error!(AssignmentCheckerError::ChainCounterOverflow);
+        self.hash_chain_length = self
+            .hash_chain_length
+            .checked_add(extension)
+            .ok_or_else(overflow)?;
+        self.hash_chain_extensions = self
+            .hash_chain_extensions
+            .checked_add(extension)
+            .ok_or_else(overflow)?;
+        self.revealed_hash_chain_length = self.hash_chain_length;
+        self.debug_assert_chain_invariant();
+        Ok(())
+    }
+
+    /// No more checks can pass and no reservations are pending
+    ///
+    /// The last slot of each chain keeps the ground truth value. Shared answer is never exhausted.
+    pub fn is_exhausted(&self) -> bool {
+        !self.shared_answer
+            && self.reserved_slots == 0
+            && (0..self.shard_count()).all(|shard| self.shard_hash_chain_length(shard) <= 1)
+    }
+
+    /// New passes are rejected at `now_unix_ts`, see `set_auto_pause`
+    pub fn is_paused(&self, now_unix_ts: i64) -> bool {
+        self.pause_unix_ts()
+            .is_some_and(|pause_unix_ts| now_unix_ts >= pause_unix_ts)
+    }
+
+    /// Time when new passes get rejected, `None` without auto pause
+    pub fn pause_unix_ts(&self) -> Option<i64> {
+        (self.auto_pause_after_seconds != 0)
+            .then(|| self.last_activity_unix_ts + i64::from(self.auto_pause_after_seconds))
+    }
+
+    /// Number of hash chain shards including the main chain
+    pub fn shard_count(&self) -> u8 {
+        self.added_shards + 1
+    }
+
+    /// Hash chain length of the shard, 0 for unknown shard
+    pub fn shard_hash_chain_length(&self, shard: u8) -> u16 {
+        match shard {
+            0 => self.hash_chain_length,
+            _ => self
+                .added_shard(shard)
+                .map_or(0, |chain| chain.hash_chain_length),
+        }
+    }
+
+    /// Current settings of `update_config`, every field is `Some`
+    pub fn config(&self) -> CheckerConfigUpdate {
+        CheckerConfigUpdate {
+            to_mint_on_successful_check: Some(self.to_mint_on_successful_check),
+            reward_rounding: Some(self.reward_rounding),
+            max_attempts: Some(self.max_attempts),
+            auto_pause_after_seconds: Some(self.auto_pause_after_seconds),
+            archived: Some(self.archived),
+        }
+    }
+
+    /// Set `Some` fields of the config update
+    pub fn apply_config(&mut self, config: &CheckerConfigUpdate) {
+        if let Some(to_mint_on_successful_check) = config.to_mint_on_successful_check {
+            self.to_mint_on_successful_check = to_mint_on_successful_check;
+        }
+        if let Some(reward_rounding) = config.reward_rounding {
+            self.reward_rounding = reward_rounding;
+        }
+        if let Some(max_attempts) = config.max_attempts {
+            self.max_attempts = max_attempts;
+        }
+        if let Some(auto_pause_after_seconds) = config.auto_pause_after_seconds {
+            self.auto_pause_after_seconds = auto_pause_after_seconds;
+        }
+        if let Some(archived) = config.archived {
+            self.archived = archived;
+        }
+    }
+
+    /// The answer of `len` bytes has the committed length or no length is committed
+    ///
+    /// Plaintext checks reject answers of another length before hashing them,
+    /// tools validate inputs with it.
+    pub fn accepts_answer_len(&self, len: usize) -> bool {
+        self.answer_len_commitment == 0 || usize::from(self.answer_len_commitment) == len
+    }
+
+    /// Parent of the shard tail for the answer `value`, computed on-chain by plaintext checks
+    ///
+    /// `None` for checkers with per student salt, unknown or used up shards and chains
+    /// that need more than `MAX_PLAINTEXT_HASHES` hashes. Reserved slots are not considered.
+    pub fn plaintext_parent(&self, course: &Pubkey, shard: u8, value: &[u8]) -> Option<[u8; 32]> {
+        if self.per_student_salt {
+            return None;
+        }
+        let (salt, hash_chain_length) = match (self.shared_answer, shard) {
+            (true, _) => (self.salt, SHARED_ANSWER_HASH_CHAIN_LENGTH),
+            (false, 0) => (self.salt, self.hash_chain_length),
+            (false, _) => self
+                .added_shard(shard)
+                .map(|chain| (chain.salt, chain.hash_chain_length))?,
+        };
+        let parent_hash_chain_length = hash_chain_length.checked_sub(1).filter(|len| *len > 0)?;
+        // the chain start and the steps up to the parent
+        let hashes =
+            1 + u32::from(parent_hash_chain_length - 1) * u32::from(self.hash_iterations_per_step);
+        if hashes > MAX_PLAINTEXT_HASHES {
+            return None;
+        }
+        Some(self.commitment_mode.build_iterated_tail(
+            &salt,
+            &course.to_bytes(),
+            &self.assignment_id,
+            value,
+            parent_hash_chain_length,
+            self.hash_iterations_per_step,
+        ))
+    }
+
+    /// Number of students who still can pass on the main chain
+    ///
+    /// It's one less than `hash_chain_length`, the last hash keeps the ground truth value.
+    /// Shards and shared answer have capacity of their own.
+    pub fn remaining_capacity(&self) -> u16 {
+        self.hash_chain_length.saturating_sub(1)
+    }
+
+    /// Remaining capacity of the shard, 0 for unknown shard
+    pub fn shard_remaining_capacity(&self, shard: u8) -> u16 {
+        self.shard_hash_chain_length(shard).saturating_sub(1)
+    }
+
+    fn added_shard(&self, shard: u8) -> Option<&ChainShard> {
+        if shard == 0 || shard > self.added_shards {
+            return None;
+        }
+        self.shards.get(usize::from(shard) - 1)
+    }
+
+    /// Check `hash_chain_tail_parent` against the tail of added shard
+    ///
+    /// The tail slot of the shard is taken on success.
+    pub fn check_shard(
+        &mut self,
+        shard: u8,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: &[u8; 32],
+    ) -> Result<bool> {
+        if self.added_shard(shard).is_none() {
+            return Err(error!(AssignmentCheckerError::UnknownShard));
+        }
+        let hash_iterations_per_step = self.hash_iterations_per_step;
+        let passed = self.shards[usize::from(shard) - 1].check(
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            hash_iterations_per_step,
+        )?;
+        if passed {
+            self.successful_checks = self
+                .successful_checks
+                .checked_add(1)
+                .ok_or_else(|| error!(AssignmentCheckerError::ChainCounterOverflow))?;
+            self.debug_assert_chain_invariant();
+        }
+        Ok(passed)
+    }
+
+    /// Number of first time passes with and without hash chain
+    pub fn first_time_passes(&self) -> u32 {
+        u32::from(self.successful_checks) + u32::from(self.shared_answer_passes)
+    }
+
+    /// Max number of successful checks over the checker lifetime
+    ///
+    /// The last slot of each chain keeps the ground truth value and can't be checked.
+    pub fn lifetime_passes(&self) -> u32 {
+        let shard_passes: u32 = self
+            .shards
+            .iter()
+            .map(|chain| u32::from(chain.initial_hash_chain_length.saturating_sub(1)))
+            .sum();
+        (u32::from(self.initial_hash_chain_length) + u32::from(self.hash_chain_extensions))
+            .saturating_sub(1)
+            + shard_passes
+    }
+
+    pub fn lifetime_passes_within_cap(&self) -> bool {
+        self.max_lifetime_passes == 0
+            || self.lifetime_passes() <= u32::from(self.max_lifetime_passes)
+    }
+
+    /// Every slot of the chain is either remaining, reserved, checked or discarded
+    pub fn chain_invariant_holds(&self) -> bool {
+        let (shard_initial, shard_remaining) =
+            self.shards
+                .iter()
+                .fold((0, 0), |(initial, remaining), chain| {
+                    (
+                        initial + u32::from(chain.initial_hash_chain_length),
+                        remaining + u32::from(chain.hash_chain_length),
+                    )
+                });
+        u32::from(self.initial_hash_chain_length)
+            + u32::from(self.hash_chain_extensions)
+            + shard_initial
+            == u32::from(self.successful_checks)
+                + u32::from(self.hash_chain_length)
+                + u32::from(self.reserved_slots)
+                + u32::from(self.discarded_slots)
+                + shard_remaining
+    }
+
+    fn debug_assert_chain_invariant(&self) {
+        debug_assert!(self.chain_invariant_holds(), "hash chain slots are lost");
+    }
+}
+
+/// Reserved space of the checker, see `CHECKER_RESERVED_LEN`
+///
+/// `Default` of arrays stops at 32 items, so the checker derives it through this one.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckerReserve {
+    pub bytes: [u8; CHECKER_RESERVED_LEN],
+}
+
+impl Default for CheckerReserve {
+    fn default() -> Self {
+        Self {
+            bytes: [0; CHECKER_RESERVED_LEN],
+        }
+    }
+}
+
+/// Hash chain slot reserved by the student
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reservation {
+    /// Reserved hash chain tail length, the student submits the parent of this tail
+    ///
+    /// Zero when there is no reservation
+    pub hash_chain_length: u16,
+    /// The reservation can be released after this time
+    pub expires_unix_ts: i64,
+}
+
+impl Reservation {
+    pub const LEN: usize = 2 + 8;
+
+    pub fn is_reserved(&self) -> bool {
+        self.hash_chain_length > 0
+    }
+
+    /// A lower slot was checked first and revealed the parent of the reserved tail
+    pub fn is_exposed(&self, checker: &AssignmentCheckerState) -> bool {
+        !checker.per_student_salt && self.hash_chain_length > checker.revealed_hash_chain_length
+    }
+
+    fn is_releasable(&self, checker: &AssignmentCheckerState, now_unix_ts: i64) -> bool {
+        now_unix_ts >= self.expires_unix_ts || self.is_exposed(checker)
+    }
+}
+
+/// Whether `parent` of length `parent_hash_chain_length` leads to the `tail`
+///
+/// Parents of reserved slots are several steps away from the revealed tail.
+pub fn verify_hash_chain_parent(
+    tail: &[u8; 32],
+    tail_hash_chain_length: u16,
+    parent: &[u8; 32],
+    parent_hash_chain_length: u16,
+    hash_iterations_per_step: u8,
+) -> bool {
+    tail_hash_chain_length
+        .checked_sub(parent_hash_chain_length)
+        .is_some_and(|steps| {
+            hash_chain::verify_ancestor(
+                parent,
+                u32::from(steps) * u32::from(hash_iterations_per_step),
+                tail,
+            )
+        })
+}
+
+/// Hash chain of the shard added by `add_shard`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainShard {
+    pub salt: [u8; 32],
+    ground_truth_hash_chain_tail: [u8; 32],
+    pub hash_chain_length: u16,
+    /// Hash chain length when the shard was added
+    pub initial_hash_chain_length: u16,
+}
+
+impl ChainShard {
+    pub const LEN: usize = 32 + 32 + 2 + 2;
+
+    /// Check `hash_chain_tail_parent` against the tail and take the tail slot on success
+    pub fn check(
+        &mut self,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: &[u8; 32],
+        hash_iterations_per_step: u8,
+    ) -> Result<bool> {
+        if self.hash_chain_length < MIN_CHECKABLE_HASH_CHAIN_LENGTH {
+            return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
+        }
+        if self.hash_chain_length != expected_hash_chain_length {
+            return Err(error!(AssignmentCheckerError::ExpectedHashLengthDiffers));
+        }
+        let passed = verify_hash_chain_parent(
+            &self.ground_truth_hash_chain_tail,
+            self.hash_chain_length,
+            hash_chain_tail_parent,
+            self.hash_chain_length - 1,
+            hash_iterations_per_step,
+        );
+        if passed {
+            self.ground_truth_hash_chain_tail = *hash_chain_tail_parent;
+            self.hash_chain_length -= 1;
+        }
+        Ok(passed)
+    }
+}
+
+/// Grading criteria of the assignment, see `init_criteria`
+#[account]
+#[derive(Default)]
+pub struct AssignmentCriteria {
+    pub assignment_id: [u8; 16],
+    /// Hash chains of answers to criteria 0..`criteria_count`
+    pub chains: [ChainShard; MAX_CRITERIA],
+    /// Relative weights of criteria in the score
+    pub weights: [u16; MAX_CRITERIA],
+    pub criteria_count: u8,
+    pub bump_seed: u8,
+}
+
+impl AssignmentCriteria {
+    pub const LEN: usize = 16 + ChainShard::LEN * MAX_CRITERIA + 2 * MAX_CRITERIA + 1 + 1;
+
+    /// Weighted fraction of `passed_criteria` bit mask in basis points
+    pub fn weighted_score_bps(&self, passed_criteria: u8) -> u16 {
+        let weights = &self.weights[..usize::from(self.criteria_count)];
+        let total: u64 = weights.iter().copied().map(u64::from).sum();
+        let passed: u64 = weights
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| passed_criteria & (1 << index) != 0)
+            .map(|(_, weight)| u64::from(*weight))
+            .sum();
+        // weights are non-zero, see `init_criteria`, and the score doesn't exceed BPS_DENOMINATOR
+        (passed * BPS_DENOMINATOR / total) as u16
+    }
+}
+
+/// Criterion of `init_criteria`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NewCriterion {
+    pub salt: [u8; 32],
+    pub ground_truth_hash_chain_tail: [u8; 32],
+    pub hash_chain_length: u16,
+    pub weight: u16,
+}
+
+/// Shard of the student among `shard_count` shards of the checker
+///
+/// Clients spread students over shards by their address.
+pub fn student_shard(student_address: &Pubkey, shard_count: u8) -> u8 {
+    match shard_count {
+        0 => 0,
+        _ => blake3::hash(student_address.as_ref()).0[0] % shard_count,
+    }
+}
+
+/// Ring buffer of the last `RecentPassers::CAPACITY` passers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecentPassers {
+    passers: [Pubkey; RecentPassers::CAPACITY],
+    /// Position of the next passer
+    next: u8,
+    /// Number of stored passers
+    len: u8,
+}
+
+impl RecentPassers {
+    pub const CAPACITY: usize = 8;
+    pub const LEN: usize = PUBKEY_BYTES * Self::CAPACITY + 1 + 1;
+
+    /// Add passer replacing the oldest one when the buffer is full
+    pub fn push(&mut self, passer: Pubkey) {
+        self.passers[usize::from(self.next)] = passer;
+        self.next = ((usize::from(self.next) + 1) % Self::CAPACITY) as u8;
+        if usize::from(self.len) < Self::CAPACITY {
+            self.len += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Passers from the most recent to the oldest one
+    pub fn iter(&self) -> impl Iterator<Item = &Pubkey> {
+        let next = usize::from(self.next);
+        (1..=self.len())
+            .map(move |age| &self.passers[(next + Self::CAPACITY - age) % Self::CAPACITY])
+    }
+}
+
+/// Ring buffer of the last `RecentParents::CAPACITY` consumed hash chain tail parents
+///
+/// Consumed parents are public in transactions of their passes. Copies of them are
+/// rejected with a clear error instead of a failed check.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecentParents {
+    parents: [[u8; 32]; RecentParents::CAPACITY],
+    /// Position of the next parent
+    next: u8,
+    /// Number of stored parents
+    len: u8,
+}
+
+impl RecentParents {
+    pub const CAPACITY: usize = 4;
+    pub const LEN: usize = 32 * Self::CAPACITY + 1 + 1;
+
+    /// Add consumed parent replacing the oldest one when the buffer is full
+    pub fn push(&mut self, parent: [u8; 32]) {
+        self.parents[usize::from(self.next)] = parent;
+        self.next = ((usize::from(self.next) + 1) % Self::CAPACITY) as u8;
+        if usize::from(self.len) < Self::CAPACITY {
+            self.len += 1;
+        }
+    }
+
+    pub fn contains(&self, parent: &[u8; 32]) -> bool {
+        self.parents[..usize::from(self.len)].contains(parent)
+    }
+}
+
+/// Histogram of attempts needed to pass with buckets 1, 2-3, 4-7 and 8+
+///
+/// Counters saturate at `u16::MAX`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AttemptsHistogram {
+    pub buckets: [u16; AttemptsHistogram::BUCKETS],
+}
+
+impl AttemptsHistogram {
+    pub const BUCKETS: usize = 4;
+    pub const LEN: usize = 2 * Self::BUCKETS;
+
+    /// Bucket index of the number of attempts
+    pub fn bucket(attempts: u16) -> usize {
+        match attempts {
+            0..=1 => 0,
+            2..=3 => 1,
+            4..=7 => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn record(&mut self, attempts: u16) {
+        let counter = &mut self.buckets[Self::bucket(attempts)];
+        *counter = counter.saturating_add(1);
+    }
+
+    /// Average bucket of passers scaled to basis points of the last bucket
+    ///
+    /// 0 when every passer has passed the first time or nobody has passed yet,
+    /// `BPS_DENOMINATOR` when every passer has needed 8+ attempts.
+    pub fn difficulty_score(&self) -> u16 {
+        let (passers, weighted) = self.buckets.iter().enumerate().fold(
+            (0u64, 0u64),
+            |(passers, weighted), (bucket, count)| {
+                (
+                    passers + u64::from(*count),
+                    weighted + bucket as u64 * u64::from(*count),
+                )
+            },
+        );
+        let last_bucket = (Self::BUCKETS - 1) as u64;
+        // the average bucket is at most the last one
+        (weighted * BPS_DENOMINATOR)
+            .checked_div(passers * last_bucket)
+            .map_or(0, |score| score as u16)
+    }
+}
+
+/// Settings of the checker changed by `update_config`, `None` keeps the setting
+///
+/// `AssignmentCheckerState::config` reads the current settings in the same shape.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CheckerConfigUpdate {
+    pub to_mint_on_successful_check: Option<u16>,
+    pub reward_rounding: Option<RewardRounding>,
+    /// See `set_max_attempts`
+    pub max_attempts: Option<u16>,
+    /// See `set_auto_pause`
+    pub auto_pause_after_seconds: Option<u32>,
+    /// See `set_archived`
+    pub archived: Option<bool>,
+}
+
+/// Settings of a new assignment checker, see `init`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NewAssignmentChecker {
+    pub assignment_id: [u8; 16],
+    pub hash_chain_length: u16,
+    pub to_mint_on_successful_check: u16,
+    pub salt: [u8; 32],
+    /// Students are checked against own commitments set by `set_student_commitment`
+    pub per_student_salt: bool,
+    /// Number of hashes applied on each step of the hash chain
+    pub hash_iterations_per_step: u8,
+    /// Creator of assignment checker is a trusted authority
+    /// It should precompute ground truth hash chain tail
+    /// to save nonfree compute operations of onchain program
+    /// and not to send the ground truth assignment result value to public blockchain
+    pub ground_truth_hash_chain_tail: [u8; 32],
+}
+
+impl NewAssignmentChecker {
+    pub const LEN: usize = 16 + 2 + 2 + 32 + 1 + 1 + 32;
+}
+
+/// Rounding of computed mint amounts that don't divide evenly
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RewardRounding {
+    #[default]
+    Floor,
+    Ceil,
+    /// Round half up
+    Nearest,
+}
+
+impl RewardRounding {
+    /// Divide with rounding, returns `None` on zero denominator or overflow
+    pub fn div(self, numerator: u128, denominator: u128) -> Option<u128> {
+        let quotient = numerator.checked_div(denominator)?;
+        let remainder = numerator % denominator;
+        let round_up = match self {
+            RewardRounding::Floor => false,
+            RewardRounding::Ceil => remainder > 0,
+            RewardRounding::Nearest => remainder >= denominator - remainder,
+        };
+        if round_up {
+            quotient.checked_add(1)
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+/// How the salt is hashed into the chain start, built off-chain by `build_iterated_tail`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitmentMode {
+    /// The salt prefixes the hashed input, see `hash_chain::chain_start`
+    #[default]
+    Prefixed,
+    /// The salt is the key of blake3 keyed hash, see `hash_chain::keyed_chain_start`
+    Keyed,
+}
+
+impl CommitmentMode {
+    /// Tail of the hash chain of length `len` started in this mode
+    ///
+    /// Steps after the start are plain hashes in both modes, so the check doesn't
+    /// depend on the mode.
+    pub fn build_iterated_tail(
+        self,
+        salt: &[u8; 32],
+        course: &[u8; 32],
+        assignment_id: &[u8; 16],
+        value: &[u8],
+        len: u16,
+        iterations_per_step: u8,
+    ) -> [u8; 32] {
+        match self {
+            CommitmentMode::Prefixed => hash_chain::build_iterated_tail(
+                salt,
+                course,
+                assignment_id,
+                value,
+                len,
+                iterations_per_step,
+            ),
+            CommitmentMode::Keyed => hash_chain::build_keyed_iterated_tail(
+                salt,
+                course,
+                assignment_id,
+                value,
+                len,
+                iterations_per_step,
+            ),
+        }
+    }
+}
+
+#[account]
+pub struct CheckResult {
+    /// Assignment ID is unique within a course
+    pub assignment_id: [u8; 16],
+    /// Check has ever passed, it's never reset
+    pub check_passed: bool,
+    /// Set by the first successful check together with `check_passed` and never reset
+    ///
+    /// Repeated checks used to reset it. Whether a call was the first pass is
+    /// `CheckOutcome::first_time` of the result_processor_program now.
+    pub passed_first_time: bool,
+    pub bump_seed: u8,
+    /// Reward bookkeeping of the result_processor_program
+    pub reward: RewardLedger,
+    /// Hash of the student specific hash chain tail parent for checkers with per student salt
+    pub student_commitment: [u8; 32],
+    /// Student part of the PDA seeds
+    pub student_seed: [u8; 32],
+    /// Hash chain slot reserved for the check
+    pub reservation: Reservation,
+    /// Number of checks until the first pass including the passed one
+    pub attempts: u16,
+    /// Layout version, zero for check results created before versioning
+    ///
+    /// New fields are appended after it and are zero in upgraded check results.
+    pub schema_version: u8,
+    /// Reward of the pass that couldn't be paid on check, the student claims it later
+    pub reward_claimable: u64,
+    /// Check result of the new seed scheme, see `migrate_result`
+    ///
+    /// Default pubkey for check results that are in use.
+    pub migrated_to: Pubkey,
+    /// Bit mask of criteria passed by `check_criterion`
+    pub passed_criteria: u8,
+    /// Weighted fraction of passed criteria in basis points
+    pub weighted_score_bps: u16,
+    /// The student has locked the result as the official submission, see `finalize_result`
+    pub finalized: bool,
+    /// Generation of the checker that has checked or reserved the result, see `bind_generation`
+    pub generation: u16,
+    /// Attempts granted to the student above `max_attempts`, see `grant_extra_attempts`
+    pub extra_attempts: u16,
+    /// Proctor who submitted the first pass, default pubkey when the student submitted it
+    pub proctor: Pubkey,
+    /// Space for future fields, zero until they take it
+    pub reserved: [u8; CHECK_RESULT_RESERVED_LEN],
+}
+
+impl CheckResult {
+    /// Length of the layout before versioning
+    pub const LEN_V0: usize = 16 + 1 + 1 + 1 + RewardLedger::LEN + 32 + 32 + Reservation::LEN + 2;
+    pub const LEN: usize = Self::LEN_V0
+        + 1
+        + 8
+        + PUBKEY_BYTES
+        + 1
+        + 2
+        + 1
+        + 2
+        + 2
+        + PUBKEY_BYTES
+        + CHECK_RESULT_RESERVED_LEN;
+
+    /// Deserialize account data of the current or an older layout
+    ///
+    /// Fields missing in older layouts are zero, `schema_version` keeps the version of the data.
+    pub fn from_versioned_data(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(&Self::discriminator()) {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+        }
+        let data = &data[8..];
+        if data.len() < Self::LEN_V0 {
+            return Err(error!(ErrorCode::AccountDidNotDeserialize));
+        }
+        let mut padded = data.to_vec();
+        if padded.len() < Self::LEN {
+            padded.resize(Self::LEN, 0);
+        }
+        Self::deserialize(&mut padded.as_slice())
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+
+    /// Check result was created for the student, see `check_result_student_seed`
+    pub fn belongs_to(&self, student_address: &Pubkey, course_secret: Option<&[u8; 32]>) -> bool {
+        self.student_seed == check_result_student_seed(student_address, course_secret)
+    }
+
+    /// Check result was derived from course secret
+    pub fn is_anonymous(&self, student_address: &Pubkey) -> bool {
+        self.student_seed != student_address.to_bytes()
+    }
+
+    /// Check result was replaced by the check result of another seed scheme
+    pub fn is_migrated(&self) -> bool {
+        self.migrated_to != Pubkey::default()
+    }
+
+    /// The reward is escrowed or claimable, so it's still owed to the student
+    pub fn has_pending_reward(&self) -> bool {
+        self.reward.escrowed != 0 || self.reward_claimable != 0
+    }
+
+    /// A check of the result could still pass
+    ///
+    /// Passed results only repeat the pass, checks of migrated and finalized results
+    /// are rejected. Clients hide the submission of such assignments.
+    pub fn needs_check(&self) -> bool {
+        !self.check_passed && !self.is_migrated() && !self.finalized
+    }
+
+    /// Nothing was checked or reserved, the result can serve any checker generation
+    pub fn is_unused(&self) -> bool {
+        !self.check_passed
+            && self.attempts == 0
+            && self.passed_criteria == 0
+            && !self.reservation.is_reserved()
+    }
+
+    /// The result belongs to a closed checker of the same assignment ID
+    ///
+    /// Passes and attempts of the old checker don't count for the recreated one.
+    pub fn is_stale(&self, checker: &AssignmentCheckerState) -> bool {
+        self.generation != checker.generation && !self.is_unused()
+    }
+
+    /// Stamp the checker generation on the result before it's checked or reserved
+    ///
+    /// Check results are created without the checker, so unused results take
+    /// the generation of the checker that uses them first.
+    pub fn bind_generation(&mut self, generation: u16) -> Result<()> {
+        if self.generation != generation {
+            if !self.is_unused() {
+                return Err(error!(AssignmentCheckerError::StaleCheckResult));
+            }
+            self.generation = generation;
+        }
+        Ok(())
+    }
+}
+
+/// Any account owned by the assignment checker program, see `try_from_data`
+///
+/// Explorers and other tools display accounts of the program without knowing their type.
+#[derive(Clone)]
+pub enum AssignmentCheckerAccount {
+    Checker(Box<AssignmentCheckerState>),
+    Criteria(AssignmentCriteria),
+    Result(CheckResult),
+}
+
+impl AssignmentCheckerAccount {
+    /// Deserialize account data by its discriminator
+    ///
+    /// Check results of older layouts are read by `CheckResult::from_versioned_data`.
+    /// The caller checks that the account is owned by the program.
+    pub fn try_from_data(data: &[u8]) -> Result<Self> {
+        let mut account_data = data;
+        if data.starts_with(&AssignmentCheckerState::discriminator()) {
+            AssignmentCheckerState::try_deserialize(&mut account_data)
+                .map(|checker| Self::Checker(Box::new(checker)))
+        } else if data.starts_with(&AssignmentCriteria::discriminator()) {
+            AssignmentCriteria::try_deserialize(&mut account_data).map(Self::Criteria)
+        } else if data.starts_with(&CheckResult::discriminator()) {
+            CheckResult::from_versioned_data(data).map(Self::Result)
+        } else {
+            Err(error!(ErrorCode::AccountDiscriminatorMismatch))
+        }
+    }
+}
+
+/// Seeds of assignment checker PDA derived from `result_processor_program`
+///
+/// Single definition of the seeds for signing result processor programs and clients.
+/// Account constraints list the same seeds because Anchor expects them inline.
+pub fn assignment_checker_seeds<'a>(
+    course_address: &'a Pubkey,
+    assignment_id: &'a [u8; 16],
+) -> [&'a [u8]; 4] {
+    [
+        COURSE_DATA_SEED,
+        course_address.as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id,
+    ]
+}
+
+/// Seeds of assignment checker PDA with the bump seed for `invoke_signed`
+pub fn assignment_checker_signer_seeds<'a>(
+    course_address: &'a Pubkey,
+    assignment_id: &'a [u8; 16],
+    bump_seed: &'a [u8; 1],
+) -> [&'a [u8]; 5] {
+    let [course_data_seed, course, assignment_id_seed, assignment_id] =
+        assignment_checker_seeds(course_address, assignment_id);
+    [
+        course_data_seed,
+        course,
+        assignment_id_seed,
+        assignment_id,
+        bump_seed,
+    ]
+}
+
+/// Student part of check result PDA seeds
+///
+/// Anonymous check results use `blake3(student || course_secret)` instead of student address.
+/// Their addresses can't be enumerated from list of student addresses without
+/// the secret shared by course authority with students. The secret is
+/// visible in student transactions, so it doesn't hide the results from
+/// observers of these transactions. Student progress of the batch is still derived
+/// from student address. Dispute window escrow and per student commitments
+/// work only with check results derived from student address.
+pub fn check_result_student_seed(
+    student_address: &Pubkey,
+    course_secret: Option<&[u8; 32]>,
+) -> [u8; 32] {
+    match course_secret {
+        Some(course_secret) => blake3::hashv(&[student_address.as_ref(), course_secret]).0,
+        None => student_address.to_bytes(),
+    }
+}
+
+/// Salt is zero or derived from the assignment ID only
+///
+/// Students could precompute hash chains of such salts for likely answers.
+pub fn is_weak_salt(salt: &[u8; 32], assignment_id: &[u8; 16]) -> bool {
+    *salt == [0; 32] || (salt[..16] == assignment_id[..] && salt[16..] == assignment_id[..])
+}
+
+/// Hash chain tail parent that only the given student can submit
+///
+/// Its hash is the student commitment of the checker with per student salt.
+pub fn student_hash_chain_tail_parent(
+    salt: &[u8; 32],
+    student_address: &Pubkey,
+    value: &[u8],
+) -> [u8; 32] {
+    blake3::hashv(&[salt, student_address.as_ref(), value]).0
+}
+
+/// Reward state of a passed check
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardLedger {
+    /// Number of tokens held in escrow during dispute window
+    pub escrowed: u64,
+    /// Token account that holds escrowed tokens
+    pub escrow: Pubkey,
+    /// Escrowed tokens can be released to the student after this time
+    pub escrowed_until_unix_ts: i64,
+    /// Escrowed tokens were revoked by course authority
+    pub revoked: bool,
+    /// Student who referred the owner of the check result and received part of the reward
+    ///
+    /// Default pubkey when the check had no referrer
+    pub referrer: Pubkey,
+    /// Streak multiplier applied to the reward in basis points
+    ///
+    /// Zero when the reward had no streak bonus
+    pub streak_multiplier_bps: u16,
+}
+
+impl RewardLedger {
+    pub const LEN: usize = 8 + PUBKEY_BYTES + 8 + 1 + PUBKEY_BYTES + 2;
 }
 
 #[error_code]
@@ -230,4 +2693,84 @@ pub enum AssignmentCheckerError {
     ZeroHashChainLength,
     #[msg("The hash chain for this checker differs from provided expected hash chain length. Retry with updated expected length.")]
     ExpectedHashLengthDiffers,
+    #[msg("Scaled mint amount overflows")]
+    RewardOverflow,
+    #[msg("The checker uses shared hash chain for all students")]
+    SharedSalt,
+    #[msg("The check has already passed")]
+    AlreadyPassed,
+    #[msg("The check result has an active reservation")]
+    ReservationActive,
+    #[msg("The check result has no reservation")]
+    NoReservation,
+    #[msg("The reservation has expired. Reserve again.")]
+    ReservationExpired,
+    #[msg("The reserved hash chain tail was exposed by a check of a lower slot. Reserve again.")]
+    ReservationExposed,
+    #[msg("Hash chain counter overflows")]
+    ChainCounterOverflow,
+    #[msg("The hash chain has reserved slots")]
+    ReservationsPending,
+    #[msg("The course is frozen")]
+    CourseFrozen,
+    #[msg("Hash chain step should apply at least one hash")]
+    ZeroHashIterations,
+    #[msg("The hash chain allows more passes than max lifetime passes of the checker")]
+    LifetimePassesExceeded,
+    #[msg("The hash chain should have at least 2 hashes")]
+    ShortHashChain,
+    #[msg("The checker has no such hash chain shard")]
+    UnknownShard,
+    #[msg("The checker has max number of hash chain shards")]
+    ShardLimit,
+    #[msg("Reservations and per student salt use the main hash chain of shard 0")]
+    MainShardOnly,
+    #[msg("The checker accepts shared answer without hash chain")]
+    SharedAnswer,
+    #[msg("Only checkers with a single shared salt hash chain can accept shared answer")]
+    NotConvertible,
+    #[msg("Course authority cannot check assignments of the course")]
+    AuthoritySelfCheck,
+    #[msg("Wrong answers consume slots of the main hash chain without shards or shared answer")]
+    ConsumeOnFailure,
+    #[msg("The checker is paused after a long time without extension")]
+    CheckerPaused,
+    #[msg("The account is not a check result of the course")]
+    InvalidCheckResult,
+    #[msg("The hash chain tail parent was already consumed by another student")]
+    DuplicateParentSubmission,
+    #[msg("The salt is zero or derived from the assignment ID")]
+    WeakSalt,
+    #[msg("The checker is archived")]
+    CheckerArchived,
+    #[msg("The check result was migrated to another seed scheme")]
+    ResultMigrated,
+    #[msg("Escrowed or claimable rewards cannot move to anonymous check result")]
+    AnonymousPendingReward,
+    #[msg("Criteria need non-zero weights and hash chains of at least 2 elements")]
+    InvalidCriteria,
+    #[msg("The assignment has no such criterion")]
+    UnknownCriterion,
+    #[msg("The check result is graded by criteria")]
+    CriteriaGraded,
+    #[msg("The check result is finalized by the student")]
+    ResultFinalized,
+    #[msg("The check result has failed max attempts")]
+    MaxAttemptsExceeded,
+    #[msg("Commitment mode can't change after the checker has passes or reservations")]
+    CommitmentModeLocked,
+    #[msg("The check result was used by a closed checker of the assignment")]
+    StaleCheckResult,
+    #[msg("The check result has escrowed or claimable reward")]
+    UnclaimedRewardPresent,
+    #[msg("Pass rate counters of the checker overflow")]
+    PassRateOverflow,
+    #[msg("The student has too many extra attempts")]
+    ExtraAttemptsOverflow,
+    #[msg("The signer is not an authorized proctor of the course")]
+    ProctorNotAuthorized,
+    #[msg("The check result belongs to another student")]
+    ResultOwnershipMismatch,
+    #[msg("The check result has failed attempts")]
+    ResultHasAttempts,
 }
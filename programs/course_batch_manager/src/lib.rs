@@ -1,23 +1,88 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::pubkey::PUBKEY_BYTES;
-use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{mint_to, Mint, MintTo, Token, TokenAccount};
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{blake3, pubkey::PUBKEY_BYTES};
+use anchor_lang::AccountsClose;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken, Create};
+use anchor_spl::token::{
+    burn, freeze_account, initialize_account, initialize_mint, mint_to, set_authority,
+    spl_token::instruction::AuthorityType, thaw_account, transfer, Burn, FreezeAccount,
+    InitializeAccount, InitializeMint, Mint, MintTo, SetAuthority, ThawAccount, Token,
+    TokenAccount, Transfer,
+};
+pub use assignment_checker::{
+    assignment_checker_seeds, assignment_checker_signer_seeds, check_result_student_seed,
+    student_shard, AssignmentCheckerAccount, AssignmentCheckerState, AssignmentCriteria,
+    ChainShard, CheckResult, CheckerConfigUpdate, CommitmentMode, NewAssignmentChecker,
+    NewCriterion, Reservation, RewardLedger, RewardRounding, BPS_DENOMINATOR, CRITERIA_SEED,
+    MAX_CRITERIA, MAX_PLAINTEXT_HASHES, MIN_CHECKABLE_HASH_CHAIN_LENGTH,
+};
 use assignment_checker::{
-    cpi::accounts::{Check, Init, InitCheckResult},
+    cpi::accounts::{
+        Check, CheckCriterion, Close, CloseResult, FinalizeResult, GrantExtraAttempts, Init,
+        InitCheckResult, InitCriteria, MigrateResult, ProctoredCheck, ReleaseReservation, Reserve,
+        SetStudentCommitment, UpdateChecker, UpdateRewardLedger, UpgradeCheckResult,
+    },
     program::AssignmentChecker,
 };
-pub use assignment_checker::{AssignmentCheckerState, CheckResult};
 
-use course_manager::Course;
+use course_manager::{
+    cpi::accounts::{
+        RegisterEnrollment, UnregisterEnrollment, UpdateAssignmentCount,
+        UpdateModuleAssignmentCount,
+    },
+    create_pda_account, decode_event, emit_event,
+    program::CourseManager,
+    Course, Module,
+};
+use std::collections::BTreeMap;
 
 declare_id!("Po3YrSjzp5HM7VRFYszFM23LVJ58HHC9qoionaUgvRy");
 
 pub const COURSE_DATA_SEED: &[u8; 11] = assignment_checker::COURSE_DATA_SEED;
 pub const BATCH_DATA_SEED: &[u8; 10] = b"batch_data";
 pub const BATCH_MINT_SEED: &[u8; 10] = b"batch_mint";
-pub const BATCH_ID_SEED: &[u8; 15] = b"course_batch_id";
+// seed of the batch reward vault, it holds escrowed rewards
+pub const BATCH_ESCROW_SEED: &[u8; 12] = b"batch_escrow";
+pub const STUDENT_PROGRESS_SEED: &[u8; 16] = course_manager::STUDENT_PROGRESS_SEED;
+pub const ANSWER_REVEAL_SEED: &[u8; 13] = b"answer_reveal";
+pub const CERTIFICATE_MINT_SEED: &[u8; 16] = b"certificate_mint";
+pub const ROSTER_ENTRY_SEED: &[u8; 12] = b"roster_entry";
+pub const TIMELOCKED_ACTION_SEED: &[u8; 17] = b"timelocked_action";
+pub const EXHAUSTED_CHECK_RESULT_SEED: &[u8; 22] = b"exhausted_check_result";
+pub const BATCH_ID_SEED: &[u8; 15] = course_manager::BATCH_ID_SEED;
 pub const ASSIGNMENT_ID_SEED: &[u8; 13] = assignment_checker::ASSIGNMENT_ID_SEED;
 pub const STUDENT_ADDRESS_SEED: &[u8; 15] = assignment_checker::STUDENT_ADDRESS_SEED;
+pub const MODULE_ID_SEED: &[u8; 9] = course_manager::MODULE_ID_SEED;
+pub const PRICE_FEED_SEED: &[u8; 10] = b"price_feed";
+pub const LEADERBOARD_SEED: &[u8; 11] = b"leaderboard";
+/// Prices of `PriceFeed` older than this are rejected by claims
+pub const MAX_PRICE_AGE_SECONDS: i64 = 300;
+/// Batch rewards match `to_mint_on_successful_check` of checkers by default
+pub const DEFAULT_REWARD_MULTIPLIER_BPS: u16 = 10_000;
+/// Capacity of the answer revealed after deadline, shorter answers are zero padded
+pub const ANSWER_PLAINTEXT_LEN: usize = 64;
+/// Streak length above which first attempt passes get no extra bonus
+pub const MAX_STREAK_BONUS_STEPS: u16 = 10;
+/// Size limit of serialized transactions, see `checkers_per_transaction`
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+/// Max number of accounts locked by a transaction
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+/// Checkers of `create_many_assignment_checkers` that fit into the default compute budget
+pub const MAX_CHECKERS_PER_TRANSACTION: usize = 4;
+/// Rewards of a pass above this number of whole tokens likely assume wrong mint decimals
+///
+/// Batch mints have zero decimals, so whole tokens are base units, see `max_pass_reward`.
+pub const MAX_REWARD_WHOLE_TOKENS: u64 = 1_000_000;
+pub const GRADEBOOK_LEAF_PREFIX: &[u8; 1] = &[0];
+pub const GRADEBOOK_NODE_PREFIX: &[u8; 1] = &[1];
+/// Test only instructions like `expected_parent` are compiled in, see `test-only` feature
+pub const TEST_ONLY_INSTRUCTIONS: bool = cfg!(feature = "test-only");
+/// Shortest delay of timelocked actions, students get a day to react to a queued action
+pub const MIN_ACTION_TIMELOCK_SECONDS: u32 = 86_400;
+
+#[cfg(all(feature = "test-only", feature = "mainnet"))]
+compile_error!("test-only instructions must not be built for mainnet");
 
 #[program]
 pub mod course_batch_manager {
@@ -26,466 +91,6779 @@ pub mod course_batch_manager {
     /// Create data and mint accounts for course batch
     pub fn create_new_batch(ctx: Context<NewCourseBatch>, batch_id: [u8; 16]) -> Result<()> {
         let course_batch_account = &mut ctx.accounts.course_batch;
-        course_batch_account.id = batch_id;
-        course_batch_account.course = ctx.accounts.course.key();
-        course_batch_account.authority = ctx.accounts.authority.key();
-        course_batch_account.mint = ctx.accounts.mint.key();
-        course_batch_account.bump_seed = *ctx
-            .bumps
-            .get("course_batch")
-            .expect("course_batch pda is present");
-        course_batch_account.mint_bump_seed = *ctx.bumps.get("mint").expect("mint pda is present");
+        **course_batch_account = CourseBatch::new(
+            batch_id,
+            ctx.accounts.course.key(),
+            ctx.accounts.authority.key(),
+            ctx.accounts.mint.key(),
+            *ctx.bumps
+                .get("course_batch")
+                .expect("course_batch pda is present"),
+            *ctx.bumps.get("mint").expect("mint pda is present"),
+            *ctx.bumps.get("escrow").expect("escrow pda is present"),
+        );
+        ctx.accounts.course.emit(BatchCreated {
+            course: course_batch_account.course,
+            course_batch: course_batch_account.key(),
+            batch_id,
+        })?;
+        Ok(())
+    }
+
+    /// Hold rewards in batch escrow for `dispute_window_seconds` after successful check
+    ///
+    /// Zero window mints rewards directly to the student
+    pub fn set_dispute_window(
+        ctx: Context<UpdateCourseBatch>,
+        dispute_window_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts.course_batch.dispute_window_seconds = dispute_window_seconds;
+        ctx.accounts.course.emit(DisputeWindowSet {
+            course_batch: ctx.accounts.course_batch.key(),
+            dispute_window_seconds,
+        })?;
+        Ok(())
+    }
+
+    /// Delay between `queue_action` and execution of sensitive actions of the batch
+    ///
+    /// The delay can only grow, otherwise the authority could drop it right before an action.
+    /// It's never shorter than `MIN_ACTION_TIMELOCK_SECONDS`.
+    pub fn set_action_timelock(
+        ctx: Context<UpdateCourseBatch>,
+        action_timelock_seconds: u32,
+    ) -> Result<()> {
+        let course_batch = &mut ctx.accounts.course_batch;
+        if action_timelock_seconds < course_batch.action_timelock_seconds {
+            return Err(error!(CourseBatchManagerError::ActionTimelockDecreased));
+        }
+        if action_timelock_seconds < MIN_ACTION_TIMELOCK_SECONDS {
+            return Err(error!(CourseBatchManagerError::ActionTimelockTooShort));
+        }
+        course_batch.action_timelock_seconds = action_timelock_seconds;
+        ctx.accounts.course.emit(ActionTimelockSet {
+            course_batch: course_batch.key(),
+            action_timelock_seconds,
+        })?;
+        Ok(())
+    }
+
+    /// Queue the sensitive action of the batch for execution after `action_timelock_seconds`
+    ///
+    /// Students see the queued action before it runs. One action of each kind is
+    /// pending at a time, `cancel_action` drops it.
+    pub fn queue_action(
+        ctx: Context<QueueAction>,
+        action: TimelockedActionKind,
+        destination: Pubkey,
+    ) -> Result<()> {
+        let queue = ctx.accounts;
+        let queued_unix_ts = Clock::get()?.unix_timestamp;
+        // batches created before the minimum have no delay set
+        let action_timelock_seconds = queue
+            .course_batch
+            .action_timelock_seconds
+            .max(MIN_ACTION_TIMELOCK_SECONDS);
+        let execute_after_unix_ts = queued_unix_ts + i64::from(action_timelock_seconds);
+        *queue.timelocked_action = TimelockedAction {
+            course_batch: queue.course_batch.key(),
+            action,
+            destination,
+            queued_unix_ts,
+            execute_after_unix_ts,
+            bump_seed: *ctx
+                .bumps
+                .get("timelocked_action")
+                .expect("timelocked_action pda is present"),
+        };
+        queue.course.emit(ActionQueued {
+            course_batch: queue.course_batch.key(),
+            timelocked_action: queue.timelocked_action.key(),
+            action,
+            destination,
+            execute_after_unix_ts,
+        })?;
+        Ok(())
+    }
+
+    /// Drop the queued action without running it
+    pub fn cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+        ctx.accounts.course.emit(ActionCancelled {
+            course_batch: ctx.accounts.course_batch.key(),
+            timelocked_action: ctx.accounts.timelocked_action.key(),
+            action: ctx.accounts.timelocked_action.action,
+        })?;
+        Ok(())
+    }
+
+    /// Share of rewards in basis points minted to the referrer named by `check_assignment`
+    pub fn set_referral_bps(ctx: Context<UpdateCourseBatch>, referral_bps: u16) -> Result<()> {
+        if u64::from(referral_bps) > BPS_DENOMINATOR {
+            return Err(error!(CourseBatchManagerError::InvalidReferralBps));
+        }
+        ctx.accounts.course_batch.referral_bps = referral_bps;
+        ctx.accounts.course.emit(ReferralBpsSet {
+            course_batch: ctx.accounts.course_batch.key(),
+            referral_bps,
+        })?;
+        Ok(())
+    }
+
+    /// Scale rewards of all assignment checkers for the batch by `reward_multiplier_bps`
+    ///
+    /// `BPS_DENOMINATOR` keeps `to_mint_on_successful_check` of the checkers as is.
+    /// Scales above `MAX_REWARD_WHOLE_TOKENS` need `allow_large_rewards`, see `check_reward_bound`.
+    pub fn set_reward_multiplier(
+        ctx: Context<UpdateCourseBatch>,
+        reward_multiplier_bps: u16,
+        allow_large_rewards: bool,
+    ) -> Result<()> {
+        ctx.accounts.course_batch.reward_multiplier_bps = reward_multiplier_bps;
+        ctx.accounts
+            .course_batch
+            .check_reward_bound(allow_large_rewards)?;
+        ctx.accounts.course.emit(RewardMultiplierSet {
+            course_batch: ctx.accounts.course_batch.key(),
+            reward_multiplier_bps,
+        })?;
+        Ok(())
+    }
+
+    /// Boost rewards of first attempt passes in a row by `streak_bonus_bps` per previous pass
+    ///
+    /// The second pass of a streak gets one bonus step, the streak bonus is capped at
+    /// `MAX_STREAK_BONUS_STEPS` steps. Zero disables streak bonus.
+    /// Bonuses above `MAX_REWARD_WHOLE_TOKENS` need `allow_large_rewards`, see `check_reward_bound`.
+    pub fn set_streak_bonus(
+        ctx: Context<UpdateCourseBatch>,
+        streak_bonus_bps: u16,
+        allow_large_rewards: bool,
+    ) -> Result<()> {
+        let course_batch = &mut ctx.accounts.course_batch;
+        course_batch.streak_bonus_bps = streak_bonus_bps;
+        // the max streak multiplier is recorded in check results
+        if course_batch.streak_multiplier_bps(MAX_STREAK_BONUS_STEPS + 1) > u64::from(u16::MAX) {
+            return Err(error!(CourseBatchManagerError::InvalidStreakBonus));
+        }
+        course_batch.check_reward_bound(allow_large_rewards)?;
+        ctx.accounts.course.emit(StreakBonusSet {
+            course_batch: course_batch.key(),
+            streak_bonus_bps,
+        })?;
+        Ok(())
+    }
+
+    /// Add `first_solver_bonus` tokens to the reward of the first solver of each checker
+    ///
+    /// Checks of a checker write its account, so the cluster processes them one by one even
+    /// within a slot. The first solver is the student whose first time pass is processed
+    /// first, rewards of later passes of the same slot have no bonus. Checkers are shared
+    /// by batches of the course, the bonus of the batch of the first solver applies.
+    /// Bonuses above `MAX_REWARD_WHOLE_TOKENS` need `allow_large_rewards`, see `check_reward_bound`.
+    pub fn set_first_solver_bonus(
+        ctx: Context<UpdateCourseBatch>,
+        first_solver_bonus: u64,
+        allow_large_rewards: bool,
+    ) -> Result<()> {
+        let course_batch = &mut ctx.accounts.course_batch;
+        course_batch.first_solver_bonus = first_solver_bonus;
+        course_batch.check_reward_bound(allow_large_rewards)?;
+        ctx.accounts.course.emit(FirstSolverBonusSet {
+            course_batch: course_batch.key(),
+            first_solver_bonus,
+        })?;
         Ok(())
     }
 
     /// Create Associated Token Account for given student wallet and mint
-    pub fn enroll_batch(_ctx: Context<EnrollBatch>) -> Result<()> {
-        // ATA is inited by Anchor
+    ///
+    /// Student progress account tracks passed assignments of the batch
+    ///
+    /// Existing ATA of the student is reused. The student who cannot pay rent of new ATA
+    /// gets `CannotCreateTokenAccount` before the associated token program call.
+    /// Enrollments into batches of invite-only courses are signed by the course authority.
+    pub fn enroll_batch(ctx: Context<EnrollBatch>) -> Result<()> {
+        let enroll = &ctx.accounts;
+        if enroll.course_batch_token.data_is_empty() {
+            let token_account_rent =
+                Rent::get()?.minimum_balance(anchor_spl::token::spl_token::state::Account::LEN);
+            if enroll.student.lamports() < token_account_rent {
+                return Err(error!(CourseBatchManagerError::CannotCreateTokenAccount));
+            }
+            associated_token::create(enroll.create_token_account_cpi_ctx())?;
+        } else if *enroll.course_batch_token.owner != anchor_spl::token::ID {
+            return Err(error!(CourseBatchManagerError::CannotCreateTokenAccount));
+        }
+        let student_progress = &mut ctx.accounts.student_progress;
+        student_progress.student = ctx.accounts.student.key();
+        student_progress.course_batch = ctx.accounts.course_batch.key();
+        student_progress.bump_seed = *ctx
+            .bumps
+            .get("student_progress")
+            .expect("student_progress pda is present");
+
+        let enroll = &ctx.accounts;
+        let student_key = enroll.student.key();
+        let course_key = enroll.course.key();
+        let student_progress_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            enroll.course_batch.id.as_ref(),
+            STUDENT_PROGRESS_SEED,
+            &[enroll.student_progress.bump_seed],
+        ];
+        course_manager::cpi::register_enrollment(
+            enroll.register_enrollment_cpi_ctx(&[student_progress_seeds.as_slice()]),
+            enroll.course_batch.id,
+        )?;
+        let student_progress = &ctx.accounts.student_progress;
+        enroll.course.emit(StudentEnrolled {
+            course_batch: student_progress.course_batch,
+            student: student_progress.student,
+        })?;
+        Ok(())
+    }
+
+    /// Mark the batch as ended, the course authority may close enrollments afterwards
+    ///
+    /// The batch can't be reopened, checks and claims of its students go on.
+    pub fn end_course_batch(ctx: Context<UpdateCourseBatch>) -> Result<()> {
+        let course_batch = &mut ctx.accounts.course_batch;
+        course_batch.ended = true;
+        ctx.accounts.course.emit(CourseBatchEnded {
+            course_batch: course_batch.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Close enrollment of the student who has dropped the batch and return its rent
+    ///
+    /// The student closes own progress record without escrowed or claimable rewards
+    /// at any time, the course authority after `end_course_batch`. Passes aren't counted again after
+    /// re-enrollment. Rent goes to the student who has paid it, check results and their
+    /// rewards are kept.
+    pub fn close_enrollment(ctx: Context<CloseEnrollment>) -> Result<()> {
+        let close = &ctx.accounts;
+        let student_key = close.student.key();
+        let course_key = close.course.key();
+        let student_progress_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            close.course_batch.id.as_ref(),
+            STUDENT_PROGRESS_SEED,
+            &[close.student_progress.bump_seed],
+        ];
+        course_manager::cpi::unregister_enrollment(
+            close.unregister_enrollment_cpi_ctx(&[student_progress_seeds.as_slice()]),
+            close.course_batch.id,
+        )?;
+        close.course.emit(EnrollmentClosed {
+            course_batch: close.course_batch.key(),
+            student: student_key,
+        })?;
+        // account is closed by Anchor
+        Ok(())
+    }
+
+    /// Link roster number of the course batch to enrolled student
+    ///
+    /// Gradebooks iterate roster indices in order and resolve check results of the students
+    /// with `roster_check_result_canonical_pda`. Each index is assigned once.
+    pub fn assign_roster_index(ctx: Context<AssignRosterIndex>, roster_index: u32) -> Result<()> {
+        let roster_entry = &mut ctx.accounts.roster_entry;
+        roster_entry.course_batch = ctx.accounts.course_batch.key();
+        roster_entry.roster_index = roster_index;
+        roster_entry.student = ctx.accounts.student_progress.student;
+        roster_entry.bump_seed = *ctx
+            .bumps
+            .get("roster_entry")
+            .expect("roster_entry pda is present");
+        ctx.accounts.course.emit(RosterIndexAssigned {
+            course_batch: roster_entry.course_batch,
+            roster_index,
+            student: roster_entry.student,
+        })?;
+        Ok(())
+    }
+
+    /// Create the leaderboard of the course batch
+    ///
+    /// Checks of the batch take the leaderboard as the last of `remaining_accounts`
+    /// and rank students on their first time passes, see `Leaderboard::record`.
+    /// Students are ranked from their first pass after the leaderboard is created.
+    pub fn create_leaderboard(ctx: Context<CreateLeaderboard>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.course_batch = ctx.accounts.course_batch.key();
+        leaderboard.bump_seed = *ctx
+            .bumps
+            .get("leaderboard")
+            .expect("leaderboard pda is present");
+        ctx.accounts.course_batch.has_leaderboard = true;
+        ctx.accounts.course.emit(LeaderboardCreated {
+            course_batch: leaderboard.course_batch,
+            leaderboard: leaderboard.key(),
+        })?;
         Ok(())
     }
 
     /// Create an assignment checker
     pub fn create_assignment_checker(
         ctx: Context<CreateAssignmentChecker>,
-        assignment_id: [u8; 16],
-        hash_chain_length: u16,
-        to_mint_on_successful_check: u16,
-        salt: [u8; 32],
-        // Creator of assignment checker is a trusted authority
-        // It should precompute ground truth hash chain tail
-        // to save nonfree compute operations of onchain program
-        // and not to send the ground truth assignment result value to public blockchain
-        ground_truth_hash_chain_tail: [u8; 32],
+        checker: NewAssignmentChecker,
     ) -> Result<()> {
         // we don't own assignment_checker account
         let create = ctx.accounts;
+        let assignment_id = checker.assignment_id;
 
         let course_key = create.course.key();
-        let assignment_checker_seeds = [
-            COURSE_DATA_SEED,
-            course_key.as_ref(),
-            ASSIGNMENT_ID_SEED,
-            assignment_id.as_ref(),
-            &[*ctx
-                .bumps
-                .get("assignment_checker")
-                .expect("assignment_checker pda is present")],
-        ];
+        let bump_seed = [*ctx
+            .bumps
+            .get("assignment_checker")
+            .expect("assignment_checker pda is present")];
+        let assignment_checker_seeds =
+            assignment_checker_signer_seeds(&course_key, &assignment_id, &bump_seed);
 
         let signer_seeds = [assignment_checker_seeds.as_slice()];
 
-        assignment_checker::cpi::init(
-            create.init_cpi_ctx(signer_seeds.as_slice()),
+        // the checker takes the course generation started by the registration
+        course_manager::cpi::register_assignment(create.update_assignment_count_cpi_ctx())?;
+        assignment_checker::cpi::init(create.init_cpi_ctx(signer_seeds.as_slice()), checker)?;
+        create.course.emit(AssignmentCheckerCreated {
+            course: course_key,
+            assignment_checker: create.assignment_checker.key(),
             assignment_id,
-            hash_chain_length,
-            to_mint_on_successful_check,
-            salt,
-            ground_truth_hash_chain_tail,
-        )?;
+            hash_chain_length: checker.hash_chain_length,
+        })?;
         Ok(())
     }
 
-    /// Start assignment solving
-    ///
-    /// CheckResult account is initialized
+    /// Create an assignment checker together with the reward mint of the batch
     ///
-    /// Called by a student when he/she starts to solve the assignment
-    pub fn create_check_result(
-        ctx: Context<CreateCheckResult>,
-        assignment_id: [u8; 16],
+    /// Course batch, its mint and reward vault are created like in `create_new_batch`
+    /// unless the batch exists, then the mint of the batch is reused.
+    /// Course setup takes one transaction.
+    pub fn create_assignment_checker_with_mint(
+        ctx: Context<CreateAssignmentCheckerWithMint>,
+        batch_id: [u8; 16],
+        checker: NewAssignmentChecker,
     ) -> Result<()> {
         let create = ctx.accounts;
-
-        let student_key = create.student.key();
         let course_key = create.course.key();
-        let check_result_seeds = [
-            STUDENT_ADDRESS_SEED,
-            student_key.as_ref(),
-            COURSE_DATA_SEED,
-            course_key.as_ref(),
-            ASSIGNMENT_ID_SEED,
-            assignment_id.as_ref(),
-            &[*ctx
+        let assignment_id = checker.assignment_id;
+
+        if create.course_batch.data_is_empty() {
+            let bump_seed = *ctx
                 .bumps
-                .get("check_result")
-                .expect("check_result pda is present")],
-        ];
+                .get("course_batch")
+                .expect("course_batch pda is present");
+            let mint_bump_seed = *ctx.bumps.get("mint").expect("mint pda is present");
+            let escrow_bump_seed = *ctx.bumps.get("escrow").expect("escrow pda is present");
+            let owner = create.course_batch_manager_program.key();
+            let token_program = create.token_program.key();
+            for (account, seed, bump, space, owner) in [
+                (
+                    &create.course_batch,
+                    BATCH_DATA_SEED.as_slice(),
+                    bump_seed,
+                    8 + CourseBatch::LEN,
+                    &owner,
+                ),
+                (
+                    &create.mint,
+                    BATCH_MINT_SEED.as_slice(),
+                    mint_bump_seed,
+                    anchor_spl::token::spl_token::state::Mint::LEN,
+                    &token_program,
+                ),
+                (
+                    &create.escrow,
+                    BATCH_ESCROW_SEED.as_slice(),
+                    escrow_bump_seed,
+                    anchor_spl::token::spl_token::state::Account::LEN,
+                    &token_program,
+                ),
+            ] {
+                let bump = [bump];
+                let seeds = [
+                    COURSE_DATA_SEED.as_slice(),
+                    course_key.as_ref(),
+                    BATCH_ID_SEED.as_slice(),
+                    batch_id.as_ref(),
+                    seed,
+                    bump.as_slice(),
+                ];
+                let signer_seeds = [seeds.as_slice()];
+                // lamports sent to the addresses beforehand don't block the batch
+                create_pda_account(
+                    create.authority.to_account_info(),
+                    account.clone(),
+                    create.system_program.to_account_info(),
+                    space,
+                    owner,
+                    signer_seeds.as_slice(),
+                )?;
+            }
+            let course_batch = CourseBatch::new(
+                batch_id,
+                course_key,
+                create.authority.key(),
+                create.mint.key(),
+                bump_seed,
+                mint_bump_seed,
+                escrow_bump_seed,
+            );
+            course_batch.try_serialize(&mut &mut create.course_batch.try_borrow_mut_data()?[..])?;
+            initialize_mint(
+                create.initialize_mint_cpi_ctx(),
+                0,
+                create.course_batch.key,
+                Some(create.course_batch.key),
+            )?;
+            initialize_account(create.initialize_escrow_cpi_ctx())?;
+            create.course.emit(BatchCreated {
+                course: course_key,
+                course_batch: create.course_batch.key(),
+                batch_id,
+            })?;
+        } else {
+            // seeds bind the batch and its mint to the course
+            let course_batch = Account::<CourseBatch>::try_from(&create.course_batch)?;
+            msg!(
+                "reuse mint {} of course batch {}",
+                course_batch.mint,
+                course_batch.key()
+            );
+        }
 
-        let signer_seeds = [check_result_seeds.as_slice()];
+        let bump_seed = [*ctx
+            .bumps
+            .get("assignment_checker")
+            .expect("assignment_checker pda is present")];
+        let assignment_checker_seeds =
+            assignment_checker_signer_seeds(&course_key, &assignment_id, &bump_seed);
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
 
-        assignment_checker::cpi::init_check_result(
-            create.init_check_result_cpi_ctx(signer_seeds.as_slice()),
+        course_manager::cpi::register_assignment(create.update_assignment_count_cpi_ctx())?;
+        assignment_checker::cpi::init(create.init_cpi_ctx(signer_seeds.as_slice()), checker)?;
+        create.course.emit(AssignmentCheckerCreated {
+            course: course_key,
+            assignment_checker: create.assignment_checker.key(),
             assignment_id,
-        )?;
+            hash_chain_length: checker.hash_chain_length,
+        })?;
         Ok(())
     }
 
-    /// Check assignment solution and mint `assignment_checker.to_mint_on_successful_check` tokens when the check is succeded
-    pub fn check_assignment(
-        ctx: Context<CheckAssignment>,
-        expected_hash_chain_length: u16,
-        hash_chain_tail_parent: [u8; 32],
+    /// Create several assignment checkers of the course
+    ///
+    /// `remaining_accounts` are writable checker PDAs of `checkers` in the same order.
+    /// Clients split larger courses into transactions of `checkers_per_transaction` checkers.
+    /// Sets the number of created checkers as return data.
+    pub fn create_many_assignment_checkers<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateManyAssignmentCheckers<'info>>,
+        checkers: Vec<NewAssignmentChecker>,
     ) -> Result<()> {
-        let check = ctx.accounts;
+        if checkers.len() != ctx.remaining_accounts.len() {
+            return Err(error!(CourseBatchManagerError::CheckerAccountsMismatch));
+        }
+        let create = ctx.accounts;
+        let course_key = create.course.key();
+        let space = 8 + AssignmentCheckerState::LEN;
 
-        let course_key = check.course.key();
-        let assignment_checker_seeds = [
-            COURSE_DATA_SEED,
-            course_key.as_ref(),
-            ASSIGNMENT_ID_SEED,
-            check.assignment_checker.assignment_id.as_ref(),
-            &[check.assignment_checker.bump_seed],
-        ];
+        for (checker, checker_info) in checkers.iter().zip(ctx.remaining_accounts) {
+            let (checker_address, bump_seed) = Pubkey::find_program_address(
+                &assignment_checker_seeds(&course_key, &checker.assignment_id),
+                &ID,
+            );
+            if checker_address != checker_info.key() {
+                return Err(error!(CourseBatchManagerError::CheckerAccountsMismatch));
+            }
+            let bump_seed = [bump_seed];
+            let checker_signer_seeds =
+                assignment_checker_signer_seeds(&course_key, &checker.assignment_id, &bump_seed);
+            let signer_seeds = [checker_signer_seeds.as_slice()];
 
-        let student_key = check.student.key();
+            // lamports sent to the checker address beforehand don't block the checker
+            create_pda_account(
+                create.authority.to_account_info(),
+                checker_info.clone(),
+                create.system_program.to_account_info(),
+                space,
+                create.assignment_checker_program.key,
+                signer_seeds.as_slice(),
+            )?;
+            course_manager::cpi::register_assignment(create.update_assignment_count_cpi_ctx())?;
+            assignment_checker::cpi::init(
+                create.init_cpi_ctx(checker_info.clone(), signer_seeds.as_slice()),
+                *checker,
+            )?;
+            create.course.emit(AssignmentCheckerCreated {
+                course: course_key,
+                assignment_checker: checker_address,
+                assignment_id: checker.assignment_id,
+                hash_chain_length: checker.hash_chain_length,
+            })?;
+        }
+        set_return_data(&(checkers.len() as u16).try_to_vec()?);
+        Ok(())
+    }
 
-        let check_result_seeds = [
-            assignment_checker::STUDENT_ADDRESS_SEED,
-            student_key.as_ref(),
-            assignment_checker::COURSE_DATA_SEED,
-            course_key.as_ref(),
-            assignment_checker::ASSIGNMENT_ID_SEED,
-            check.check_result.assignment_id.as_ref(),
-            &[check.check_result.bump_seed],
-        ];
-        let signer_seeds = [
-            assignment_checker_seeds.as_slice(),
-            check_result_seeds.as_slice(),
-        ];
+    /// Create assignment checker of another course with the configuration of existing one
+    ///
+    /// Checkers are derived from course, so a rerun of the course under new course account
+    /// gets its checkers with the same assignment IDs. Hash chain starts with the initial
+    /// length of the source checker, `ground_truth_hash_chain_tail` is provided for fresh `salt`.
+    /// Shards of the source checker need own tails and are added separately.
+    pub fn clone_assignment_checker(
+        ctx: Context<CloneAssignmentChecker>,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let clone = ctx.accounts;
+        let source = &clone.source_assignment_checker;
 
-        assignment_checker::cpi::check(
-            check.check_cpi_ctx(signer_seeds.as_slice()),
-            expected_hash_chain_length,
-            hash_chain_tail_parent,
+        let course_key = clone.course.key();
+        let bump_seed = [*ctx
+            .bumps
+            .get("assignment_checker")
+            .expect("assignment_checker pda is present")];
+        let assignment_checker_seeds =
+            assignment_checker_signer_seeds(&course_key, &source.assignment_id, &bump_seed);
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        course_manager::cpi::register_assignment(clone.update_assignment_count_cpi_ctx())?;
+        assignment_checker::cpi::init(
+            clone.init_cpi_ctx(signer_seeds.as_slice()),
+            NewAssignmentChecker {
+                assignment_id: source.assignment_id,
+                hash_chain_length: source.initial_hash_chain_length,
+                to_mint_on_successful_check: source.to_mint_on_successful_check,
+                salt,
+                per_student_salt: source.per_student_salt,
+                hash_iterations_per_step: source.hash_iterations_per_step,
+                ground_truth_hash_chain_tail,
+            },
         )?;
+        if source.reward_rounding != RewardRounding::default() {
+            assignment_checker::cpi::set_reward_rounding(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                source.reward_rounding,
+            )?;
+        }
+        if source.max_lifetime_passes != 0 {
+            assignment_checker::cpi::set_max_lifetime_passes(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                source.max_lifetime_passes,
+            )?;
+        }
+        if source.consume_on_failure {
+            assignment_checker::cpi::set_consume_on_failure(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                true,
+            )?;
+        }
+        if source.max_attempts != 0 {
+            assignment_checker::cpi::set_max_attempts(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                source.max_attempts,
+            )?;
+        }
+        if source.commitment_mode != CommitmentMode::default() {
+            assignment_checker::cpi::set_commitment_mode(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                source.commitment_mode,
+            )?;
+        }
+        if source.auto_pause_after_seconds != 0 {
+            assignment_checker::cpi::set_auto_pause(
+                clone.update_cpi_ctx(signer_seeds.as_slice()),
+                source.auto_pause_after_seconds,
+            )?;
+        }
+        msg!(
+            "cloned assignment checker {} to {}",
+            source.key(),
+            clone.assignment_checker.key()
+        );
+        clone.course.emit(AssignmentCheckerCloned {
+            source_assignment_checker: source.key(),
+            assignment_checker: clone.assignment_checker.key(),
+        })?;
+        Ok(())
+    }
 
-        // deserialize check_result again after assignment checker has changed the account
-        check.check_result.reload()?;
+    /// Close assignment checker and return rent to course authority
+    ///
+    /// Checkers of modules are removed from them first, see `remove_checker_module`.
+    pub fn close_assignment_checker(ctx: Context<CloseAssignmentChecker>) -> Result<()> {
+        let close = ctx.accounts;
 
-        let check_result = &check.check_result;
-        msg!(
-            "check_passed: {}, passed_first_time: {}",
-            check_result.check_passed,
-            check_result.passed_first_time
+        let course_key = close.course.key();
+        let bump_seed = [close.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &close.assignment_checker.assignment_id,
+            &bump_seed,
         );
-        if check_result.check_passed && check_result.passed_first_time {
-            let mint_seeds = [
-                COURSE_DATA_SEED,
-                course_key.as_ref(),
-                BATCH_ID_SEED,
-                check.course_batch.id.as_ref(),
-                BATCH_MINT_SEED,
-                &[check.course_batch.mint_bump_seed],
-            ];
-            let course_batch_seeds = [
-                COURSE_DATA_SEED,
-                course_key.as_ref(),
-                BATCH_ID_SEED,
-                check.course_batch.id.as_ref(),
-                BATCH_DATA_SEED,
-                &[check.course_batch.bump_seed],
-            ];
-            let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
-            let amount = check.assignment_checker.to_mint_on_successful_check.into();
-            mint_to(check.mint_to_cpi_ctx(signer_seeds.as_slice()), amount)?;
-            msg!("minted {} tokens to {}", amount, check.student.key());
-        }
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::close(close.close_cpi_ctx(signer_seeds.as_slice()))?;
+        course_manager::cpi::unregister_assignment(close.update_assignment_count_cpi_ctx())?;
+        close.course.emit(AssignmentCheckerClosed {
+            course: course_key,
+            assignment_checker: close.assignment_checker.key(),
+        })?;
         Ok(())
     }
-}
 
-pub fn batch_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
-    Pubkey::find_program_address(
-        &[
-            COURSE_DATA_SEED,
-            course_address.as_ref(),
-            BATCH_ID_SEED,
-            batch_id,
-            BATCH_DATA_SEED,
-        ],
-        &ID,
-    )
-    .0
-}
+    /// Close exhausted assignment checkers of the course and return rent to course authority
+    ///
+    /// Checkers are shared by batches of the course, so the sweep is run after the batch
+    /// that exhausted them. `remaining_accounts` are writable assignment checkers.
+    /// Checkers that are not exhausted, belong to a module or are not derived from the course
+    /// are skipped and logged.
+    /// Sets the number of closed checkers as return data.
+    pub fn close_batch_checkers<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBatchCheckers<'info>>,
+    ) -> Result<()> {
+        let sweep = ctx.accounts;
+        let course_key = sweep.course.key();
 
-pub fn batch_mint_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
-    Pubkey::find_program_address(
-        &[
-            COURSE_DATA_SEED,
-            course_address.as_ref(),
-            BATCH_ID_SEED,
-            batch_id,
-            BATCH_MINT_SEED,
-        ],
-        &ID,
-    )
-    .0
-}
+        let mut closed: u16 = 0;
+        for checker_info in ctx.remaining_accounts {
+            let checker = match sweep.exhausted_checker(checker_info) {
+                Some(checker) => checker,
+                None => {
+                    msg!("skip assignment checker {}", checker_info.key());
+                    continue;
+                }
+            };
+            let bump_seed = [checker.bump_seed];
+            let assignment_checker_seeds =
+                assignment_checker_signer_seeds(&course_key, &checker.assignment_id, &bump_seed);
+            assignment_checker::cpi::close(
+                sweep.close_cpi_ctx(checker_info.clone(), &[assignment_checker_seeds.as_slice()]),
+            )?;
+            course_manager::cpi::unregister_assignment(sweep.update_assignment_count_cpi_ctx())?;
+            msg!("closed assignment checker {}", checker_info.key());
+            sweep.course.emit(AssignmentCheckerClosed {
+                course: course_key,
+                assignment_checker: checker_info.key(),
+            })?;
+            closed += 1;
+        }
+        set_return_data(&closed.try_to_vec()?);
+        Ok(())
+    }
 
-pub fn assignment_checker_canonical_pda(
-    course_address: Pubkey,
-    assignment_id: &[u8; 16],
-) -> Pubkey {
-    Pubkey::find_program_address(
-        &[
-            COURSE_DATA_SEED,
-            course_address.as_ref(),
-            ASSIGNMENT_ID_SEED,
-            assignment_id,
-        ],
-        &ID,
-    )
-    .0
-}
+    /// Set rounding mode of fractional mint amounts for the assignment checker
+    pub fn set_reward_rounding(
+        ctx: Context<UpdateAssignmentChecker>,
+        reward_rounding: RewardRounding,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
 
-pub fn check_result_canonical_pda(
-    student_address: Pubkey,
-    course_data: Pubkey,
-    assignment_id: &[u8; 16],
-) -> Pubkey {
-    Pubkey::find_program_address(
-        &[
+        assignment_checker::cpi::set_reward_rounding(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            reward_rounding,
+        )?;
+        update.course.emit(RewardRoundingSet {
+            assignment_checker: update.assignment_checker.key(),
+            reward_rounding,
+        })?;
+        Ok(())
+    }
+
+    /// Cap number of successful checks of the assignment checker over its lifetime
+    pub fn set_max_lifetime_passes(
+        ctx: Context<UpdateAssignmentChecker>,
+        max_lifetime_passes: u16,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_max_lifetime_passes(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            max_lifetime_passes,
+        )?;
+        update.course.emit(MaxLifetimePassesSet {
+            assignment_checker: update.assignment_checker.key(),
+            max_lifetime_passes,
+        })?;
+        Ok(())
+    }
+
+    /// Consume a hash chain slot of the assignment checker on each wrong answer
+    pub fn set_consume_on_failure(
+        ctx: Context<UpdateAssignmentChecker>,
+        consume_on_failure: bool,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_consume_on_failure(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            consume_on_failure,
+        )?;
+        update.course.emit(ConsumeOnFailureSet {
+            assignment_checker: update.assignment_checker.key(),
+            consume_on_failure,
+        })?;
+        Ok(())
+    }
+
+    /// Close check results after `max_attempts` failed checks and return their rent
+    pub fn set_max_attempts(
+        ctx: Context<UpdateAssignmentChecker>,
+        max_attempts: u16,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_max_attempts(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            max_attempts,
+        )?;
+        update.course.emit(MaxAttemptsSet {
+            assignment_checker: update.assignment_checker.key(),
+            max_attempts,
+        })?;
+        Ok(())
+    }
+
+    /// Start hash chains of the assignment checker with blake3 keyed by the salt or prefixed salt
+    ///
+    /// The ground truth tail and shards have to be built in the same mode, see
+    /// `CommitmentMode::build_iterated_tail`.
+    pub fn set_commitment_mode(
+        ctx: Context<UpdateAssignmentChecker>,
+        commitment_mode: CommitmentMode,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_commitment_mode(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            commitment_mode,
+        )?;
+        update.course.emit(CommitmentModeSet {
+            assignment_checker: update.assignment_checker.key(),
+            commitment_mode,
+        })?;
+        Ok(())
+    }
+
+    /// Commit to the length of the answer for plaintext checks, zero removes the commitment
+    ///
+    /// `check_plaintext` rejects answers of another length with `WrongAnswerLength`.
+    pub fn set_answer_len_commitment(
+        ctx: Context<UpdateAssignmentChecker>,
+        answer_len_commitment: u16,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_answer_len_commitment(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            answer_len_commitment,
+        )?;
+        update.course.emit(AnswerLenCommitmentSet {
+            assignment_checker: update.assignment_checker.key(),
+            answer_len_commitment,
+        })?;
+        Ok(())
+    }
+
+    /// Pause the assignment checker `auto_pause_after_seconds` after creation or last extension
+    pub fn set_auto_pause(
+        ctx: Context<UpdateAssignmentChecker>,
+        auto_pause_after_seconds: u32,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_auto_pause(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            auto_pause_after_seconds,
+        )?;
+        update.course.emit(AutoPauseSet {
+            assignment_checker: update.assignment_checker.key(),
+            auto_pause_after_seconds,
+        })?;
+        Ok(())
+    }
+
+    /// Update several settings of the assignment checker in one instruction
+    ///
+    /// `Some` fields of the update are applied at once, `None` fields keep their settings.
+    pub fn update_config(
+        ctx: Context<UpdateAssignmentChecker>,
+        config: CheckerConfigUpdate,
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::update_config(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            config,
+        )?;
+        update.course.emit(CheckerConfigUpdated {
+            assignment_checker: update.assignment_checker.key(),
+            config,
+        })?;
+        Ok(())
+    }
+
+    /// Archive the assignment checker or return it to active ones
+    ///
+    /// Unlike `close_assignment_checker` archiving keeps the rent, the hash chain and
+    /// check results of the checker.
+    pub fn set_archived(ctx: Context<UpdateAssignmentChecker>, archived: bool) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_archived(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            archived,
+        )?;
+        update.course.emit(ArchivedSet {
+            assignment_checker: update.assignment_checker.key(),
+            archived,
+        })?;
+        Ok(())
+    }
+
+    /// Add `extension` slots to the hash chain of the assignment checker
+    ///
+    /// `ground_truth_hash_chain_tail` is the tail of a new chain for the `salt`,
+    /// its length is `hash_chain_length + extension`
+    pub fn extend_chain(
+        ctx: Context<UpdateAssignmentChecker>,
+        extension: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::extend_chain(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            extension,
+            salt,
+            ground_truth_hash_chain_tail,
+        )?;
+        update.course.emit(ChainExtended {
+            assignment_checker: update.assignment_checker.key(),
+            extension,
+        })?;
+        Ok(())
+    }
+
+    /// Let the assignment checker accept the same answer from any number of students
+    ///
+    /// `ground_truth_hash` is the tail of `SHARED_ANSWER_HASH_CHAIN_LENGTH` for the `salt`
+    pub fn convert_to_shared(
+        ctx: Context<UpdateAssignmentChecker>,
+        salt: [u8; 32],
+        ground_truth_hash: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::convert_to_shared(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            salt,
+            ground_truth_hash,
+        )?;
+        update.course.emit(ConvertedToShared {
+            assignment_checker: update.assignment_checker.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Add independent hash chain shard to the assignment checker
+    ///
+    /// `ground_truth_hash_chain_tail` is the tail of the shard chain for the `salt`
+    pub fn add_assignment_checker_shard(
+        ctx: Context<UpdateAssignmentChecker>,
+        hash_chain_length: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::add_shard(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            hash_chain_length,
+            salt,
+            ground_truth_hash_chain_tail,
+        )?;
+        update.assignment_checker.reload()?;
+        update.course.emit(ShardAdded {
+            assignment_checker: update.assignment_checker.key(),
+            shard: update.assignment_checker.added_shards,
+            hash_chain_length,
+        })?;
+        Ok(())
+    }
+
+    /// Grade the assignment by weighted criteria with partial credit
+    ///
+    /// Students pass the criteria with `check_criterion`.
+    pub fn create_assignment_criteria(
+        ctx: Context<CreateAssignmentCriteria>,
+        criteria: Vec<NewCriterion>,
+    ) -> Result<()> {
+        let create = ctx.accounts;
+        let course_key = create.course.key();
+        let assignment_id = create.assignment_checker.assignment_id;
+        let bump_seed = [*ctx
+            .bumps
+            .get("assignment_criteria")
+            .expect("assignment_criteria pda is present")];
+        let assignment_criteria_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            CRITERIA_SEED,
+            &bump_seed,
+        ];
+        let signer_seeds = [assignment_criteria_seeds.as_slice()];
+
+        assignment_checker::cpi::init_criteria(
+            create.init_criteria_cpi_ctx(signer_seeds.as_slice()),
+            criteria.clone(),
+        )?;
+        create.course.emit(AssignmentCriteriaCreated {
+            assignment_checker: create.assignment_checker.key(),
+            assignment_criteria: create.assignment_criteria.key(),
+            weights: criteria.iter().map(|criterion| criterion.weight).collect(),
+        })?;
+        Ok(())
+    }
+
+    /// Set student commitment for the assignment checker with per student salt
+    pub fn set_student_commitment(
+        ctx: Context<RegisterStudentCommitment>,
+        student_commitment: [u8; 32],
+    ) -> Result<()> {
+        let set = ctx.accounts;
+        let student_key = set.student.key();
+        let course_key = set.course.key();
+        let check_result_seeds = [
             STUDENT_ADDRESS_SEED,
-            student_address.as_ref(),
+            student_key.as_ref(),
             COURSE_DATA_SEED,
-            course_data.as_ref(),
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            set.check_result.assignment_id.as_ref(),
+            &[set.check_result.bump_seed],
+        ];
+        let signer_seeds = [check_result_seeds.as_slice()];
+
+        assignment_checker::cpi::set_student_commitment(
+            set.set_student_commitment_cpi_ctx(signer_seeds.as_slice()),
+            student_commitment,
+        )?;
+        set.course.emit(StudentCommitmentSet {
+            check_result: set.check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Grant extra attempts to the student as an accommodation, see `set_max_attempts`
+    pub fn grant_extra_attempts(
+        ctx: Context<GrantStudentExtraAttempts>,
+        extra_attempts: u16,
+    ) -> Result<()> {
+        let grant = ctx.accounts;
+        let student_key = grant.student.key();
+        let course_key = grant.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            grant.check_result.assignment_id.as_ref(),
+            &[grant.check_result.bump_seed],
+        ];
+        let signer_seeds = [check_result_seeds.as_slice()];
+
+        assignment_checker::cpi::grant_extra_attempts(
+            grant.grant_extra_attempts_cpi_ctx(signer_seeds.as_slice()),
+            extra_attempts,
+        )?;
+        grant.check_result.reload()?;
+        grant.course.emit(ExtraAttemptsGranted {
+            check_result: grant.check_result.key(),
+            extra_attempts: grant.check_result.extra_attempts,
+        })?;
+        Ok(())
+    }
+
+    /// Start assignment solving
+    ///
+    /// CheckResult account is initialized
+    ///
+    /// Called by a student when he/she starts to solve the assignment
+    ///
+    /// Check result is anonymous when `course_secret` is provided, see `check_result_student_seed`
+    ///
+    /// Check result closed by the last failed attempt is recreated after the checker is
+    /// extended, the checker is the first of `remaining_accounts` then, see `ExhaustedCheckResult`.
+    pub fn create_check_result(
+        ctx: Context<CreateCheckResult>,
+        assignment_id: [u8; 16],
+        course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let create = ctx.accounts;
+
+        let student_seed = check_result_student_seed(&create.student.key(), course_secret.as_ref());
+        let course_key = create.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
             ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[*ctx
+                .bumps
+                .get("check_result")
+                .expect("check_result pda is present")],
+        ];
+
+        let signer_seeds = [check_result_seeds.as_slice()];
+
+        // check result closed by the last failed attempt waits for the extended checker
+        let mut extra_attempts = 0;
+        if create.exhausted_check_result.owner == &ID {
+            let exhausted =
+                Account::<ExhaustedCheckResult>::try_from(&create.exhausted_check_result)?;
+            let checker_info = ctx
+                .remaining_accounts
+                .first()
+                .filter(|checker_info| checker_info.key() == exhausted.assignment_checker)
+                .ok_or_else(|| error!(CourseBatchManagerError::CheckResultExhausted))?;
+            let checker = Account::<AssignmentCheckerState>::try_from(checker_info)?;
+            if !exhausted.is_stale(&checker) {
+                return Err(error!(CourseBatchManagerError::CheckResultExhausted));
+            }
+            extra_attempts = exhausted.extra_attempts;
+            exhausted.close(create.student.to_account_info())?;
+        }
+
+        assignment_checker::cpi::init_check_result(
+            create.init_check_result_cpi_ctx(signer_seeds.as_slice()),
             assignment_id,
-        ],
-        &ID,
-    )
-    .0
+            course_secret,
+            extra_attempts,
+        )?;
+        create.course.emit(CheckResultCreated {
+            check_result: create.check_result.key(),
+            assignment_id,
+        })?;
+        Ok(())
+    }
+
+    /// Upgrade check result of an older layout before the check
+    ///
+    /// Clients send it in the check transaction for results created by older versions.
+    pub fn upgrade_student_check_result(ctx: Context<UpgradeStudentCheckResult>) -> Result<()> {
+        let upgrade = ctx.accounts;
+        let check_result =
+            CheckResult::from_versioned_data(&upgrade.check_result.try_borrow_data()?)?;
+
+        let course_key = upgrade.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            check_result.assignment_id.as_ref(),
+            &[check_result.bump_seed],
+        ];
+
+        assignment_checker::cpi::upgrade_check_result(
+            upgrade.upgrade_cpi_ctx(&[check_result_seeds.as_slice()]),
+        )?;
+        upgrade.course.emit(CheckResultUpgraded {
+            check_result: upgrade.check_result.key(),
+            schema_version: assignment_checker::CHECK_RESULT_SCHEMA_VERSION,
+        })?;
+        Ok(())
+    }
+
+    /// Move check result of the student to the PDA of another seed scheme
+    ///
+    /// Old and new check results are derived from the student, the course, `assignment_id`
+    /// and an optional course secret, see `check_result_student_seed`. The student pays
+    /// rent of the new check result, the old one is kept to block its recreation.
+    pub fn migrate_check_result(
+        ctx: Context<MigrateCheckResult>,
+        assignment_id: [u8; 16],
+        old_course_secret: Option<[u8; 32]>,
+        new_course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let migrate = ctx.accounts;
+
+        let course_key = migrate.course.key();
+        let old_student_seed =
+            check_result_student_seed(&migrate.student.key(), old_course_secret.as_ref());
+        let old_check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            old_student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[migrate.old_check_result.bump_seed],
+        ];
+        let new_student_seed =
+            check_result_student_seed(&migrate.student.key(), new_course_secret.as_ref());
+        let new_check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            new_student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[*ctx
+                .bumps
+                .get("new_check_result")
+                .expect("new_check_result pda is present")],
+        ];
+        let signer_seeds = [
+            old_check_result_seeds.as_slice(),
+            new_check_result_seeds.as_slice(),
+        ];
+
+        assignment_checker::cpi::migrate_result(
+            migrate.migrate_result_cpi_ctx(signer_seeds.as_slice()),
+            old_course_secret,
+            new_course_secret,
+        )?;
+        migrate.course.emit(CheckResultMigrated {
+            old_check_result: migrate.old_check_result.key(),
+            new_check_result: migrate.new_check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Close check result of the student that hasn't passed and return its rent
+    ///
+    /// Fails with `UnclaimedRewardPresent` while a reward of the result is pending.
+    pub fn close_check_result(
+        ctx: Context<CloseCheckResult>,
+        // provided for anonymous check result
+        course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let close = ctx.accounts;
+
+        let course_key = close.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            close.check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            close.check_result.assignment_id.as_ref(),
+            &[close.check_result.bump_seed],
+        ];
+        let check_result_key = close.check_result.key();
+
+        assignment_checker::cpi::close_result(
+            close.close_result_cpi_ctx(&[check_result_seeds.as_slice()]),
+            course_secret,
+        )?;
+        close.course.emit(CheckResultClosed {
+            check_result: check_result_key,
+        })?;
+        Ok(())
+    }
+
+    /// Lock check result as the official submission of the student
+    ///
+    /// `gradebook_snapshot` includes only finalized check results, so snapshots
+    /// don't capture results of students who are still solving the assignment.
+    pub fn finalize_check_result(
+        ctx: Context<FinalizeCheckResult>,
+        // provided for anonymous check result
+        course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let finalize = ctx.accounts;
+
+        let course_key = finalize.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            finalize.check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            finalize.check_result.assignment_id.as_ref(),
+            &[finalize.check_result.bump_seed],
+        ];
+
+        assignment_checker::cpi::finalize_result(
+            finalize.finalize_result_cpi_ctx(&[check_result_seeds.as_slice()]),
+            course_secret,
+        )?;
+        finalize.course.emit(CheckResultFinalized {
+            check_result: finalize.check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Reserve hash chain slot of the assignment checker before the check
+    ///
+    /// Reserved check doesn't depend on concurrent checks of other students
+    pub fn reserve_check(
+        ctx: Context<ReserveCheck>,
+        // provided for anonymous check result
+        course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let reserve = ctx.accounts;
+
+        let course_key = reserve.course.key();
+        let bump_seed = [reserve.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &reserve.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            reserve.check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            reserve.check_result.assignment_id.as_ref(),
+            &[reserve.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+
+        assignment_checker::cpi::reserve(
+            reserve.reserve_cpi_ctx(signer_seeds.as_slice()),
+            course_secret,
+        )?;
+        reserve.course.emit(CheckReserved {
+            assignment_checker: reserve.assignment_checker.key(),
+            check_result: reserve.check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Give up active reservation of the student when the assignment is not solved
+    pub fn give_up_check_reservation(
+        ctx: Context<ReserveCheck>,
+        // provided for anonymous check result
+        course_secret: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let reserve = ctx.accounts;
+
+        let course_key = reserve.course.key();
+        let bump_seed = [reserve.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &reserve.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            reserve.check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            reserve.check_result.assignment_id.as_ref(),
+            &[reserve.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+
+        assignment_checker::cpi::give_up_reservation(
+            reserve.reserve_cpi_ctx(signer_seeds.as_slice()),
+            course_secret,
+        )?;
+        reserve.course.emit(CheckReservationReleased {
+            assignment_checker: reserve.assignment_checker.key(),
+            check_result: reserve.check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Release expired or exposed reservation of any student
+    pub fn release_check_reservation(ctx: Context<ReleaseCheckReservation>) -> Result<()> {
+        let release = ctx.accounts;
+
+        let course_key = release.course.key();
+        let bump_seed = [release.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &release.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            release.check_result.student_seed.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            release.check_result.assignment_id.as_ref(),
+            &[release.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+
+        assignment_checker::cpi::release_reservation(
+            release.release_reservation_cpi_ctx(signer_seeds.as_slice()),
+        )?;
+        release.course.emit(CheckReservationReleased {
+            assignment_checker: release.assignment_checker.key(),
+            check_result: release.check_result.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Check assignment solution and mint `assignment_checker.to_mint_on_successful_check` tokens when the check is succeded
+    ///
+    /// Rewards are minted to the batch reward vault and transferred to the student at once
+    /// or after the dispute window. `paid_rewards` of the batch tracks the vault outflow.
+    ///
+    /// Optional `remaining_accounts` name the referrer of the student: student progress
+    /// and writable course batch token account of the referrer. The referrer receives
+    /// `referral_bps` of the reward, rewards with referrer are not escrowed or anonymous.
+    /// The writable leaderboard of batches with `has_leaderboard` follows them.
+    ///
+    /// The pass is recorded when the payout would fail, see `payout_block`. Its reward
+    /// becomes claimable with `claim_reward` without referral.
+    ///
+    /// Sets `CheckOutcome` return data
+    pub fn check_assignment<'info>(
+        ctx: Context<'_, '_, '_, 'info, CheckAssignment<'info>>,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+        // provided for anonymous check result
+        course_secret: Option<[u8; 32]>,
+        // hash chain shard picked by `student_shard`
+        shard: u8,
+    ) -> Result<()> {
+        let check = ctx.accounts;
+        // assignment_checker binds the check result to the student, mismatches fail before the CPI
+        if !check
+            .check_result
+            .belongs_to(&check.student.key(), course_secret.as_ref())
+        {
+            return Err(error!(CourseBatchManagerError::ResultOwnershipMismatch));
+        }
+        let (referrer_accounts, mut leaderboard) =
+            batch_leaderboard(&check.course_batch, ctx.remaining_accounts)?;
+        let referrer = check.referrer(referrer_accounts)?;
+
+        let course_key = check.course.key();
+        let bump_seed = [check.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &check.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+
+        let student_seed = check_result_student_seed(&check.student.key(), course_secret.as_ref());
+
+        // check result is reloaded while its seeds are in use
+        let assignment_id = check.check_result.assignment_id;
+        let check_result_seeds = [
+            assignment_checker::STUDENT_ADDRESS_SEED,
+            student_seed.as_ref(),
+            assignment_checker::COURSE_DATA_SEED,
+            course_key.as_ref(),
+            assignment_checker::ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[check.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+        // shared answer passes and repeats of passed checks keep the hash chain
+        let hash_chain_length = check.assignment_checker.shard_hash_chain_length(shard);
+        // only this call tells whether it's the first pass, the check result keeps the pass
+        let passed_before = check.check_result.check_passed;
+        let extra_attempts = check.check_result.extra_attempts;
+
+        assignment_checker::cpi::check(
+            check.check_cpi_ctx(signer_seeds.as_slice()),
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            course_secret,
+            shard,
+        )?;
+
+        // check result of the last failed attempt is closed, see `set_max_attempts`
+        let result_closed = check.check_result.to_account_info().lamports() == 0;
+        // deserialize check_result and assignment_checker again after assignment checker has changed the accounts
+        if !result_closed {
+            check.check_result.reload()?;
+        }
+        check.assignment_checker.reload()?;
+        if result_closed {
+            mark_exhausted_check_result(
+                check.student.to_account_info(),
+                check.exhausted_check_result.clone(),
+                check.system_program.to_account_info(),
+                &check.check_result.key(),
+                &check.assignment_checker,
+                extra_attempts,
+                *ctx.bumps
+                    .get("exhausted_check_result")
+                    .expect("exhausted_check_result pda is present"),
+            )?;
+        }
+
+        let mut outcome = CheckOutcome {
+            passed: !result_closed && check.check_result.check_passed,
+            first_time: !result_closed && !passed_before && check.check_result.check_passed,
+            minted: 0,
+            referral_minted: 0,
+            claimable: 0,
+            payout_block: None,
+            remaining_chain: check.assignment_checker.shard_hash_chain_length(shard),
+            remaining_capacity: check.assignment_checker.shard_remaining_capacity(shard),
+            result_closed,
+            slot_consumed: check.assignment_checker.shard_hash_chain_length(shard)
+                < hash_chain_length,
+        };
+        msg!(
+            "check_passed: {}, first_time: {}",
+            outcome.passed,
+            outcome.first_time
+        );
+        if !outcome.passed {
+            check.student_progress.current_streak = 0;
+        }
+        if outcome.passed && outcome.first_time {
+            check
+                .student_progress
+                .record_pass(check.check_result.attempts);
+            let streak_multiplier_bps = check
+                .course_batch
+                .streak_multiplier_bps(check.student_progress.current_streak);
+            if let Some(leaderboard) = leaderboard.as_mut() {
+                leaderboard.record(&check.student_progress, Clock::get()?.unix_timestamp);
+                leaderboard.exit(&ID)?;
+            }
+
+            let mint_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                check.course_batch.id.as_ref(),
+                BATCH_MINT_SEED,
+                &[check.course_batch.mint_bump_seed],
+            ];
+            let course_batch_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                check.course_batch.id.as_ref(),
+                BATCH_DATA_SEED,
+                &[check.course_batch.bump_seed],
+            ];
+            let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
+            let (amount, first_solver_bonus) = check
+                .course_batch
+                .pass_reward(&check.assignment_checker, streak_multiplier_bps)?;
+            // zero marks rewards without streak bonus
+            let recorded_streak_multiplier_bps = if streak_multiplier_bps == BPS_DENOMINATOR {
+                0
+            } else {
+                // fits by the bound of `set_streak_bonus`
+                streak_multiplier_bps as u16
+            };
+            let dispute_window_seconds = check.course_batch.dispute_window_seconds;
+            if dispute_window_seconds != 0 && course_secret.is_some() {
+                return Err(error!(CourseBatchManagerError::AnonymousEscrow));
+            }
+            if referrer.is_some() && dispute_window_seconds != 0 {
+                return Err(error!(CourseBatchManagerError::ReferralEscrow));
+            }
+            if referrer.is_some() && course_secret.is_some() {
+                return Err(error!(CourseBatchManagerError::AnonymousReferral));
+            }
+            // failed payout would abort the whole check
+            let blocked_payout = payout_block(
+                &check.mint,
+                &check.escrow,
+                &check.course_batch_token,
+                amount,
+            )
+            .filter(|_| dispute_window_seconds == 0 && course_secret.is_none());
+            if let Some(block) = blocked_payout {
+                assignment_checker::cpi::set_reward_claimable(
+                    check.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+                    amount,
+                )?;
+                msg!(
+                    "{} tokens for {} are claimable, payout is blocked by {:?}",
+                    amount,
+                    check.student.key(),
+                    block
+                );
+                check.student_progress.hold_reward(amount);
+                outcome.claimable = amount;
+                outcome.payout_block = Some(block);
+            } else {
+                // the batch reward vault is the single emission point of rewards
+                mint_to(
+                    check.mint_to_escrow_cpi_ctx(signer_seeds.as_slice()),
+                    amount,
+                )?;
+                if dispute_window_seconds == 0 {
+                    outcome.referral_minted = referrer
+                        .as_ref()
+                        .map_or(0, |_| check.course_batch.referral_amount(amount));
+                    outcome.minted = amount - outcome.referral_minted;
+                    transfer(
+                        check.transfer_cpi_ctx(
+                            check.course_batch_token.to_account_info(),
+                            signer_seeds.as_slice(),
+                        ),
+                        outcome.minted,
+                    )?;
+                    msg!(
+                        "minted {} tokens to {}",
+                        outcome.minted,
+                        check.student.key()
+                    );
+                    if let Some((referrer, referrer_token)) = referrer {
+                        transfer(
+                            check.transfer_cpi_ctx(referrer_token, signer_seeds.as_slice()),
+                            outcome.referral_minted,
+                        )?;
+                        let reward = RewardLedger {
+                            referrer,
+                            streak_multiplier_bps: recorded_streak_multiplier_bps,
+                            ..check.check_result.reward
+                        };
+                        assignment_checker::cpi::update_reward_ledger(
+                            check.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+                            reward,
+                        )?;
+                        msg!(
+                            "minted {} tokens to referrer {}",
+                            outcome.referral_minted,
+                            referrer
+                        );
+                    } else if recorded_streak_multiplier_bps != 0 {
+                        let reward = RewardLedger {
+                            streak_multiplier_bps: recorded_streak_multiplier_bps,
+                            ..check.check_result.reward
+                        };
+                        assignment_checker::cpi::update_reward_ledger(
+                            check.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+                            reward,
+                        )?;
+                    }
+                    check.course_batch.record_payout(amount);
+                } else {
+                    // hold the reward until the course authority has a chance to revoke it
+                    check.course_batch.escrowed_rewards = check
+                        .course_batch
+                        .escrowed_rewards
+                        .checked_add(amount)
+                        .ok_or_else(|| error!(CourseBatchManagerError::EscrowedRewardsOverflow))?;
+                    let reward = RewardLedger {
+                        escrowed: amount,
+                        escrow: check.escrow.key(),
+                        escrowed_until_unix_ts: Clock::get()?.unix_timestamp
+                            + i64::from(dispute_window_seconds),
+                        revoked: false,
+                        referrer: Pubkey::default(),
+                        streak_multiplier_bps: recorded_streak_multiplier_bps,
+                    };
+                    assignment_checker::cpi::update_reward_ledger(
+                        check.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+                        reward,
+                    )?;
+                    check.student_progress.hold_reward(amount);
+                    msg!(
+                        "escrowed {} tokens for {} until {}",
+                        amount,
+                        check.student.key(),
+                        reward.escrowed_until_unix_ts
+                    );
+                    outcome.minted = amount;
+                }
+            }
+            if first_solver_bonus != 0 {
+                check.course.emit(FirstSolverRewarded {
+                    assignment_checker: check.assignment_checker.key(),
+                    check_result: check.check_result.key(),
+                    first_solver_bonus,
+                })?;
+            }
+        }
+        if result_closed {
+            check.course.emit(CheckResultClosed {
+                check_result: check.check_result.key(),
+            })?;
+        }
+        let details = if check.course.event_verbosity.is_verbose() {
+            Some(CheckDetails {
+                unix_ts: Clock::get()?.unix_timestamp,
+                outcome,
+            })
+        } else {
+            None
+        };
+        check.course.emit(AssignmentChecked {
+            assignment_checker: check.assignment_checker.key(),
+            check_result: check.check_result.key(),
+            shard,
+            passed: outcome.passed,
+            first_time: outcome.first_time,
+            slot_consumed: outcome.slot_consumed,
+            details,
+        })?;
+        set_return_data(&outcome.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Check the answer in plaintext for practice assignments that need no secrecy
+    ///
+    /// The program builds the hash chain tail parent of the answer instead of the client,
+    /// see `AssignmentCheckerState::plaintext_parent`. The answer is public in the
+    /// transaction, so anybody can pass with it afterwards. Answers of another length than
+    /// `answer_len_commitment` of the checker are rejected before hashing.
+    pub fn check_plaintext<'info>(
+        ctx: Context<'_, '_, '_, 'info, CheckAssignment<'info>>,
+        expected_hash_chain_length: u16,
+        value: Vec<u8>,
+        course_secret: Option<[u8; 32]>,
+        shard: u8,
+    ) -> Result<()> {
+        if !ctx
+            .accounts
+            .assignment_checker
+            .accepts_answer_len(value.len())
+        {
+            return Err(error!(CourseBatchManagerError::WrongAnswerLength));
+        }
+        let hash_chain_tail_parent = ctx
+            .accounts
+            .assignment_checker
+            .plaintext_parent(&ctx.accounts.course.key(), shard, &value)
+            .ok_or_else(|| error!(CourseBatchManagerError::PlaintextCheckUnavailable))?;
+        check_assignment(
+            ctx,
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            course_secret,
+            shard,
+        )
+    }
+
+    /// Check assignment solution on behalf of the student by the proctor of the course
+    ///
+    /// The pass is recorded in the check result and the progress of the student, who
+    /// doesn't sign. The reward of the first pass becomes claimable by the student with
+    /// `claim_reward` instead of being paid to an account the proctor doesn't own.
+    /// Proctored checks are not anonymous and have no referrer. The writable leaderboard
+    /// of batches with `has_leaderboard` is the only one of `remaining_accounts`.
+    ///
+    /// Sets `CheckOutcome` return data
+    pub fn proctored_check<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProctoredCheckAssignment<'info>>,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+        shard: u8,
+    ) -> Result<()> {
+        let check = ctx.accounts;
+        let (_, mut leaderboard) = batch_leaderboard(&check.course_batch, ctx.remaining_accounts)?;
+
+        let course_key = check.course.key();
+        let bump_seed = [check.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &check.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let student_key = check.student.key();
+        let assignment_id = check.check_result.assignment_id;
+        let check_result_seeds = [
+            assignment_checker::STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            assignment_checker::COURSE_DATA_SEED,
+            course_key.as_ref(),
+            assignment_checker::ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[check.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+        let hash_chain_length = check.assignment_checker.shard_hash_chain_length(shard);
+        let passed_before = check.check_result.check_passed;
+        let extra_attempts = check.check_result.extra_attempts;
+
+        assignment_checker::cpi::proctored_check(
+            check.proctored_check_cpi_ctx(signer_seeds.as_slice()),
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            shard,
+        )?;
+
+        let result_closed = check.check_result.to_account_info().lamports() == 0;
+        if !result_closed {
+            check.check_result.reload()?;
+        }
+        check.assignment_checker.reload()?;
+        if result_closed {
+            // the student doesn't sign, the proctor pays rent of the marker
+            mark_exhausted_check_result(
+                check.proctor.to_account_info(),
+                check.exhausted_check_result.clone(),
+                check.system_program.to_account_info(),
+                &check.check_result.key(),
+                &check.assignment_checker,
+                extra_attempts,
+                *ctx.bumps
+                    .get("exhausted_check_result")
+                    .expect("exhausted_check_result pda is present"),
+            )?;
+        }
+
+        let mut outcome = CheckOutcome {
+            passed: !result_closed && check.check_result.check_passed,
+            first_time: !result_closed && !passed_before && check.check_result.check_passed,
+            minted: 0,
+            referral_minted: 0,
+            claimable: 0,
+            payout_block: None,
+            remaining_chain: check.assignment_checker.shard_hash_chain_length(shard),
+            remaining_capacity: check.assignment_checker.shard_remaining_capacity(shard),
+            result_closed,
+            slot_consumed: check.assignment_checker.shard_hash_chain_length(shard)
+                < hash_chain_length,
+        };
+        if !outcome.passed {
+            check.student_progress.current_streak = 0;
+        }
+        if outcome.passed && outcome.first_time {
+            check
+                .student_progress
+                .record_pass(check.check_result.attempts);
+            let streak_multiplier_bps = check
+                .course_batch
+                .streak_multiplier_bps(check.student_progress.current_streak);
+            if let Some(leaderboard) = leaderboard.as_mut() {
+                leaderboard.record(&check.student_progress, Clock::get()?.unix_timestamp);
+                leaderboard.exit(&ID)?;
+            }
+            let (amount, first_solver_bonus) = check
+                .course_batch
+                .pass_reward(&check.assignment_checker, streak_multiplier_bps)?;
+            assignment_checker::cpi::set_reward_claimable(
+                check.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+                amount,
+            )?;
+            check.student_progress.hold_reward(amount);
+            msg!("{} tokens for {} are claimable", amount, student_key);
+            outcome.claimable = amount;
+            if first_solver_bonus != 0 {
+                check.course.emit(FirstSolverRewarded {
+                    assignment_checker: check.assignment_checker.key(),
+                    check_result: check.check_result.key(),
+                    first_solver_bonus,
+                })?;
+            }
+        }
+        if result_closed {
+            check.course.emit(CheckResultClosed {
+                check_result: check.check_result.key(),
+            })?;
+        }
+        check.course.emit(ProctoredCheckSubmitted {
+            check_result: check.check_result.key(),
+            student: student_key,
+            proctor: check.proctor.key(),
+        })?;
+        let details = if check.course.event_verbosity.is_verbose() {
+            Some(CheckDetails {
+                unix_ts: Clock::get()?.unix_timestamp,
+                outcome,
+            })
+        } else {
+            None
+        };
+        check.course.emit(AssignmentChecked {
+            assignment_checker: check.assignment_checker.key(),
+            check_result: check.check_result.key(),
+            shard,
+            passed: outcome.passed,
+            first_time: outcome.first_time,
+            slot_consumed: outcome.slot_consumed,
+            details,
+        })?;
+        set_return_data(&outcome.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Check the answer to a criterion of the assignment
+    ///
+    /// The reward of the check result is the weighted score of passed criteria times
+    /// `to_mint_on_successful_check`, each passed criterion mints the increase of the reward.
+    /// Rewards of partial credit are not escrowed, batches with dispute window reject it.
+    pub fn check_criterion(
+        ctx: Context<CheckAssignmentCriterion>,
+        criterion: u8,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+    ) -> Result<()> {
+        let check = ctx.accounts;
+        if check.course_batch.dispute_window_seconds != 0 {
+            return Err(error!(CourseBatchManagerError::CriteriaEscrow));
+        }
+        let course_key = check.course.key();
+        let student_key = check.student.key();
+        let assignment_id = check.check_result.assignment_id;
+        let assignment_criteria_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            CRITERIA_SEED,
+            &[check.assignment_criteria.bump_seed],
+        ];
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id.as_ref(),
+            &[check.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_criteria_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+        let previous_score_bps = check.check_result.weighted_score_bps;
+
+        assignment_checker::cpi::check_criterion(
+            check.check_criterion_cpi_ctx(signer_seeds.as_slice()),
+            criterion,
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+        )?;
+        check.check_result.reload()?;
+
+        let weighted_score_bps = check.check_result.weighted_score_bps;
+        let reward_multiplier_bps = u64::from(check.course_batch.reward_multiplier_bps);
+        let previous_reward = check.assignment_checker.reward_amount(
+            reward_multiplier_bps * u64::from(previous_score_bps) / BPS_DENOMINATOR,
+        )?;
+        let reward = check.assignment_checker.reward_amount(
+            reward_multiplier_bps * u64::from(weighted_score_bps) / BPS_DENOMINATOR,
+        )?;
+        let minted = reward.saturating_sub(previous_reward);
+        if minted != 0 {
+            let mint_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                check.course_batch.id.as_ref(),
+                BATCH_MINT_SEED,
+                &[check.course_batch.mint_bump_seed],
+            ];
+            let course_batch_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                check.course_batch.id.as_ref(),
+                BATCH_DATA_SEED,
+                &[check.course_batch.bump_seed],
+            ];
+            let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
+            mint_to(
+                check.mint_to_escrow_cpi_ctx(signer_seeds.as_slice()),
+                minted,
+            )?;
+            transfer(check.transfer_cpi_ctx(signer_seeds.as_slice()), minted)?;
+            check.course_batch.record_payout(minted);
+            msg!("minted {} tokens to {}", minted, student_key);
+        }
+        check.course.emit(CriterionChecked {
+            assignment_criteria: check.assignment_criteria.key(),
+            check_result: check.check_result.key(),
+            criterion,
+            weighted_score_bps,
+            minted,
+        })?;
+        Ok(())
+    }
+
+    /// Release escrowed reward to the student after the dispute window
+    pub fn finalize_reward(ctx: Context<FinalizeReward>) -> Result<()> {
+        let finalize = ctx.accounts;
+        let mut reward = finalize.check_result.reward;
+        if reward.escrowed == 0 {
+            return Err(error!(CourseBatchManagerError::NothingEscrowed));
+        }
+        if Clock::get()?.unix_timestamp < reward.escrowed_until_unix_ts {
+            return Err(error!(CourseBatchManagerError::DisputeWindowOpen));
+        }
+
+        let course_key = finalize.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            finalize.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[finalize.course_batch.bump_seed],
+        ];
+        let amount = reward.escrowed;
+        transfer(
+            finalize.transfer_cpi_ctx(&[course_batch_seeds.as_slice()]),
+            amount,
+        )?;
+        msg!("released {} tokens to {}", amount, finalize.student.key());
+        finalize.course_batch.release_escrowed(amount);
+        finalize.course_batch.record_payout(amount);
+        finalize.student_progress.release_reward(amount);
+
+        let student_key = finalize.student.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            finalize.check_result.assignment_id.as_ref(),
+            &[finalize.check_result.bump_seed],
+        ];
+        reward.escrowed = 0;
+        assignment_checker::cpi::update_reward_ledger(
+            finalize.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+            reward,
+        )?;
+        finalize.course.emit(RewardReleased {
+            check_result: finalize.check_result.key(),
+            student: student_key,
+            amount,
+        })?;
+        Ok(())
+    }
+
+    /// Pay the reward that was claimable instead of paid on check
+    ///
+    /// Fails while the payout is still blocked, see `payout_block`.
+    /// Batches with `reward_conversion` take its price feed as the first remaining account
+    /// and pay the claimable reward converted at the price.
+    pub fn claim_reward<'info>(ctx: Context<'_, '_, '_, 'info, ClaimReward<'info>>) -> Result<()> {
+        let claimable = ctx.accounts.check_result.reward_claimable;
+        if claimable == 0 {
+            return Err(error!(CourseBatchManagerError::NothingClaimable));
+        }
+        let conversion = ctx.accounts.course_batch.reward_conversion;
+        let amount = if conversion.is_enabled() {
+            let price_feed_info = ctx
+                .remaining_accounts
+                .first()
+                .filter(|info| info.key() == conversion.price_feed)
+                .ok_or_else(|| error!(CourseBatchManagerError::PriceFeedMissing))?;
+            let price_feed = Account::<PriceFeed>::try_from(price_feed_info)?;
+            if Clock::get()?.unix_timestamp - price_feed.updated_unix_ts > MAX_PRICE_AGE_SECONDS {
+                return Err(error!(CourseBatchManagerError::StalePrice));
+            }
+            let amount = conversion.convert(claimable, price_feed.price);
+            msg!(
+                "converted {} tokens to {} at price {}",
+                claimable,
+                amount,
+                price_feed.price
+            );
+            amount
+        } else {
+            claimable
+        };
+        let claim = ctx.accounts;
+
+        let course_key = claim.course.key();
+        let mint_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            claim.course_batch.id.as_ref(),
+            BATCH_MINT_SEED,
+            &[claim.course_batch.mint_bump_seed],
+        ];
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            claim.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[claim.course_batch.bump_seed],
+        ];
+        let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
+        mint_to(
+            claim.mint_to_escrow_cpi_ctx(signer_seeds.as_slice()),
+            amount,
+        )?;
+        transfer(claim.transfer_cpi_ctx(signer_seeds.as_slice()), amount)?;
+        msg!("claimed {} tokens by {}", amount, claim.student.key());
+        claim.course_batch.record_payout(amount);
+        claim.student_progress.release_reward(claimable);
+
+        let student_key = claim.student.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            claim.check_result.assignment_id.as_ref(),
+            &[claim.check_result.bump_seed],
+        ];
+        assignment_checker::cpi::set_reward_claimable(
+            claim.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+            0,
+        )?;
+        claim.course.emit(RewardClaimed {
+            check_result: claim.check_result.key(),
+            student: student_key,
+            amount,
+        })?;
+        Ok(())
+    }
+
+    /// Create price feed of a token, its authority is the oracle that pushes prices
+    ///
+    /// Price feeds are shared by courses, so their events don't follow `emit_events`.
+    pub fn create_price_feed(
+        ctx: Context<CreatePriceFeed>,
+        feed_id: [u8; 16],
+        price: u64,
+    ) -> Result<()> {
+        if price == 0 {
+            return Err(error!(CourseBatchManagerError::InvalidPrice));
+        }
+        let price_feed = &mut ctx.accounts.price_feed;
+        **price_feed = PriceFeed {
+            authority: ctx.accounts.authority.key(),
+            feed_id,
+            price,
+            updated_unix_ts: Clock::get()?.unix_timestamp,
+            bump_seed: *ctx
+                .bumps
+                .get("price_feed")
+                .expect("price_feed pda is present"),
+        };
+        emit_event(PriceFeedUpdated {
+            price_feed: price_feed.key(),
+            price,
+        })?;
+        Ok(())
+    }
+
+    /// Push the current price to the price feed
+    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>, price: u64) -> Result<()> {
+        if price == 0 {
+            return Err(error!(CourseBatchManagerError::InvalidPrice));
+        }
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.price = price;
+        price_feed.updated_unix_ts = Clock::get()?.unix_timestamp;
+        emit_event(PriceFeedUpdated {
+            price_feed: price_feed.key(),
+            price,
+        })?;
+        Ok(())
+    }
+
+    /// Convert claimed rewards at the price of a price feed, see `RewardConversion`
+    ///
+    /// Default conversion pays claimed rewards as is. Rewards paid on check aren't converted.
+    pub fn set_reward_conversion(
+        ctx: Context<UpdateCourseBatch>,
+        reward_conversion: RewardConversion,
+    ) -> Result<()> {
+        if reward_conversion.is_enabled()
+            && (reward_conversion.reference_price == 0
+                || reward_conversion.min_bps > reward_conversion.max_bps)
+        {
+            return Err(error!(CourseBatchManagerError::InvalidRewardConversion));
+        }
+        let course_batch = &mut ctx.accounts.course_batch;
+        course_batch.reward_conversion = reward_conversion;
+        ctx.accounts.course.emit(RewardConversionSet {
+            course_batch: course_batch.key(),
+            reward_conversion,
+        })?;
+        Ok(())
+    }
+
+    /// Revoke escrowed reward of a fraudulent pass during the dispute window
+    ///
+    /// Escrowed tokens are burned and return to unminted supply of the batch mint.
+    pub fn revoke_reward(ctx: Context<RevokeReward>) -> Result<()> {
+        let revoke = ctx.accounts;
+        let mut reward = revoke.check_result.reward;
+        if reward.escrowed == 0 {
+            return Err(error!(CourseBatchManagerError::NothingEscrowed));
+        }
+        if Clock::get()?.unix_timestamp >= reward.escrowed_until_unix_ts {
+            return Err(error!(CourseBatchManagerError::DisputeWindowClosed));
+        }
+
+        let course_key = revoke.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            revoke.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[revoke.course_batch.bump_seed],
+        ];
+        let amount = reward.escrowed;
+        burn(
+            revoke.burn_cpi_ctx(&[course_batch_seeds.as_slice()]),
+            amount,
+        )?;
+        msg!("revoked {} tokens of {}", amount, revoke.student.key());
+        revoke.course_batch.release_escrowed(amount);
+        revoke.student_progress.release_reward(amount);
+
+        let student_key = revoke.student.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            revoke.check_result.assignment_id.as_ref(),
+            &[revoke.check_result.bump_seed],
+        ];
+        reward.escrowed = 0;
+        reward.revoked = true;
+        assignment_checker::cpi::update_reward_ledger(
+            revoke.update_reward_ledger_cpi_ctx(&[check_result_seeds.as_slice()]),
+            reward,
+        )?;
+        revoke.course.emit(RewardRevoked {
+            check_result: revoke.check_result.key(),
+            student: student_key,
+            amount,
+        })?;
+        Ok(())
+    }
+
+    /// Release escrowed rewards of several students after the dispute window
+    ///
+    /// `remaining_accounts` are quadruples of student, writable check result, writable
+    /// student course batch token account and writable student progress in the batch.
+    /// Invalid quadruples and rewards that cannot be released yet are skipped and logged so that one item doesn't fail the whole airdrop.
+    /// Sets the number of released rewards as return data.
+    pub fn airdrop_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, AirdropRewards<'info>>,
+    ) -> Result<()> {
+        let airdrop = ctx.accounts;
+        let now = Clock::get()?.unix_timestamp;
+        let course_key = airdrop.course.key();
+        // the batch is updated while its seeds are in use
+        let batch_id = airdrop.course_batch.id;
+        let bump_seed = [airdrop.course_batch.bump_seed];
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            batch_id.as_ref(),
+            BATCH_DATA_SEED,
+            &bump_seed,
+        ];
+
+        let mut airdropped: u16 = 0;
+        for item in ctx.remaining_accounts.chunks(4) {
+            let (student, check_result_info, token_info, student_progress_info) = match item {
+                [student, check_result, token, student_progress] => {
+                    (student, check_result, token, student_progress)
+                }
+                _ => {
+                    msg!("skip incomplete airdrop item");
+                    continue;
+                }
+            };
+            let (mut reward, mut student_progress) = match airdrop.releasable_reward(
+                student,
+                check_result_info,
+                token_info,
+                student_progress_info,
+                now,
+            ) {
+                Some(releasable) => releasable,
+                None => {
+                    msg!("skip airdrop to {}", student.key());
+                    continue;
+                }
+            };
+
+            transfer(
+                airdrop.transfer_cpi_ctx(token_info.clone(), &[course_batch_seeds.as_slice()]),
+                reward.escrowed,
+            )?;
+            msg!("released {} tokens to {}", reward.escrowed, student.key());
+            airdrop.course_batch.release_escrowed(reward.escrowed);
+            airdrop.course_batch.record_payout(reward.escrowed);
+            student_progress.release_reward(reward.escrowed);
+            student_progress.exit(&ID)?;
+            airdrop.course.emit(RewardReleased {
+                check_result: check_result_info.key(),
+                student: student.key(),
+                amount: reward.escrowed,
+            })?;
+
+            let check_result = Account::<CheckResult>::try_from(check_result_info)?;
+            let student_key = student.key();
+            let check_result_seeds = [
+                STUDENT_ADDRESS_SEED,
+                student_key.as_ref(),
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                ASSIGNMENT_ID_SEED,
+                check_result.assignment_id.as_ref(),
+                &[check_result.bump_seed],
+            ];
+            reward.escrowed = 0;
+            assignment_checker::cpi::update_reward_ledger(
+                airdrop.update_reward_ledger_cpi_ctx(
+                    student.clone(),
+                    check_result_info.clone(),
+                    &[check_result_seeds.as_slice()],
+                ),
+                reward,
+            )?;
+            airdropped += 1;
+        }
+        set_return_data(&airdropped.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Withdraw tokens stuck in batch escrow to the `destination` token account
+    ///
+    /// Recovery path for escrow balance that isn't owed to any student, e.g. tokens sent
+    /// to the escrow directly or left by a bug. Escrowed rewards of students,
+    /// including the ones ready to be finalized, stay in the escrow.
+    ///
+    /// Executes the withdrawal queued by `queue_action` after its timelock.
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let withdraw = ctx.accounts;
+        if !withdraw
+            .timelocked_action
+            .is_executable(Clock::get()?.unix_timestamp)
+        {
+            return Err(error!(CourseBatchManagerError::ActionTimelocked));
+        }
+        let amount = withdraw
+            .escrow
+            .amount
+            .saturating_sub(withdraw.course_batch.escrowed_rewards);
+        if amount == 0 {
+            return Err(error!(CourseBatchManagerError::NothingToWithdraw));
+        }
+
+        let course_key = withdraw.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            withdraw.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[withdraw.course_batch.bump_seed],
+        ];
+        transfer(
+            withdraw.transfer_cpi_ctx(&[course_batch_seeds.as_slice()]),
+            amount,
+        )?;
+        msg!(
+            "emergency withdrawal of {} tokens from escrow {} to {} by {}",
+            amount,
+            withdraw.escrow.key(),
+            withdraw.destination.key(),
+            withdraw.authority.key()
+        );
+        withdraw.course.emit(EmergencyWithdrawal {
+            course_batch: withdraw.course_batch.key(),
+            destination: withdraw.destination.key(),
+            amount,
+        })?;
+        Ok(())
+    }
+
+    /// Freeze or thaw the reward vault of the batch
+    ///
+    /// Frozen vault pauses reward payouts. Passes are still recorded while it's frozen,
+    /// their rewards are claimable with `claim_reward` after the vault is thawed.
+    pub fn set_rewards_frozen(ctx: Context<SetRewardsFrozen>, frozen: bool) -> Result<()> {
+        let update = ctx.accounts;
+        let course_key = update.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            update.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[update.course_batch.bump_seed],
+        ];
+        if frozen {
+            freeze_account(update.freeze_cpi_ctx(&[course_batch_seeds.as_slice()]))?;
+        } else {
+            thaw_account(update.thaw_cpi_ctx(&[course_batch_seeds.as_slice()]))?;
+        }
+        update.course.emit(RewardsFrozenSet {
+            course_batch: update.course_batch.key(),
+            frozen,
+        })?;
+        Ok(())
+    }
+
+    /// Mint completion certificate NFT to the student who passed all assignments of the course
+    ///
+    /// Certificate mint has zero decimals and its supply is fixed to one token.
+    pub fn mint_certificate(ctx: Context<MintCertificate>) -> Result<()> {
+        let certify = ctx.accounts;
+        let assignment_count = certify.course.assignment_count;
+        if assignment_count == 0 || certify.student_progress.assignments_passed < assignment_count {
+            return Err(error!(CourseBatchManagerError::AssignmentsNotPassed));
+        }
+        if certify.student_progress.certificate_minted {
+            return Err(error!(CourseBatchManagerError::CertificateAlreadyMinted));
+        }
+
+        let course_key = certify.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            certify.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[certify.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        mint_to(certify.mint_to_cpi_ctx(signer_seeds.as_slice()), 1)?;
+        // nobody can mint another certificate token
+        set_authority(
+            certify.set_authority_cpi_ctx(signer_seeds.as_slice()),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+        certify.student_progress.certificate_minted = true;
+        msg!(
+            "minted certificate {} to {}",
+            certify.certificate_mint.key(),
+            certify.student.key()
+        );
+        certify.course.emit(CertificateMinted {
+            course_batch: certify.course_batch.key(),
+            student: certify.student.key(),
+            certificate_mint: certify.certificate_mint.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Add the assignment checker to a module of the course
+    ///
+    /// The checker belongs to one module at a time, `remove_checker_module` takes it out.
+    pub fn set_checker_module(ctx: Context<SetCheckerModule>) -> Result<()> {
+        let update = ctx.accounts;
+        if update.assignment_checker.in_module() {
+            return Err(error!(CourseBatchManagerError::CheckerInModule));
+        }
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        let module_id = update.module.module_id;
+        assignment_checker::cpi::set_module(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            module_id,
+        )?;
+        course_manager::cpi::register_module_assignment(update.update_module_cpi_ctx())?;
+        update.course.emit(CheckerModuleSet {
+            assignment_checker: update.assignment_checker.key(),
+            module: update.module.key(),
+            in_module: true,
+        })?;
+        Ok(())
+    }
+
+    /// Remove the assignment checker from its module
+    pub fn remove_checker_module(ctx: Context<SetCheckerModule>) -> Result<()> {
+        let update = ctx.accounts;
+        if !update.assignment_checker.in_module()
+            || update.assignment_checker.module_id != update.module.module_id
+        {
+            return Err(error!(CourseBatchManagerError::WrongModule));
+        }
+        let course_key = update.course.key();
+        let bump_seed = [update.assignment_checker.bump_seed];
+        let assignment_checker_seeds = assignment_checker_signer_seeds(
+            &course_key,
+            &update.assignment_checker.assignment_id,
+            &bump_seed,
+        );
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_module(
+            update.update_cpi_ctx(signer_seeds.as_slice()),
+            [0; 16],
+        )?;
+        course_manager::cpi::unregister_module_assignment(update.update_module_cpi_ctx())?;
+        update.course.emit(CheckerModuleSet {
+            assignment_checker: update.assignment_checker.key(),
+            module: update.module.key(),
+            in_module: false,
+        })?;
+        Ok(())
+    }
+
+    /// Record completion of the module by the student who passed all of its assignments
+    ///
+    /// `remaining_accounts` are pairs of assignment checker of the module and check result
+    /// of the student for it. Each checker is counted once, invalid pairs and check results
+    /// of closed checkers with the same assignment ID are skipped. The completion is
+    /// counted in `modules_completed` once per batch.
+    pub fn complete_module<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompleteModule<'info>>,
+    ) -> Result<()> {
+        let complete = ctx.accounts;
+        let mut passed_checkers: Vec<Pubkey> = Vec::new();
+        for item in ctx.remaining_accounts.chunks(2) {
+            let passed_checker = match item {
+                [checker_info, check_result_info] => {
+                    complete.passed_module_checker(checker_info, check_result_info)
+                }
+                _ => None,
+            };
+            match passed_checker {
+                Some(checker) if !passed_checkers.contains(&checker) => {
+                    passed_checkers.push(checker)
+                }
+                _ => msg!("skip module checker"),
+            }
+        }
+        let assignment_count = complete.module.assignment_count;
+        if assignment_count == 0 || passed_checkers.len() < usize::from(assignment_count) {
+            return Err(error!(CourseBatchManagerError::ModuleNotPassed));
+        }
+
+        let module_completion = &mut complete.module_completion;
+        **module_completion = ModuleCompletion {
+            student_progress: complete.student_progress.key(),
+            module: complete.module.key(),
+            bump_seed: *ctx
+                .bumps
+                .get("module_completion")
+                .expect("module_completion pda is present"),
+        };
+        complete.student_progress.modules_completed = complete
+            .student_progress
+            .modules_completed
+            .saturating_add(1);
+        complete.course.emit(ModuleCompleted {
+            course_batch: complete.course_batch.key(),
+            student: complete.student.key(),
+            module: complete.module.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Verify that a gradebook entry of the student matches on-chain check result
+    ///
+    /// The gradebook is a Merkle tree of `gradebook_leaf` values published by the course authority.
+    /// Sets `true` return data and logs the outcome when `proof` leads from the leaf
+    /// built from `check_result` to `gradebook_root`.
+    pub fn verify_gradebook_entry(
+        ctx: Context<VerifyGradebookEntry>,
+        gradebook_root: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let verify = ctx.accounts;
+        let leaf = gradebook_leaf(
+            verify.student.key(),
+            &verify.check_result.assignment_id,
+            verify.check_result.check_passed,
+        );
+        let consistent = gradebook_root_from_proof(leaf, &proof) == gradebook_root;
+        msg!("gradebook entry is consistent: {}", consistent);
+        set_return_data(&consistent.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Reward mint of the course batch and the PDA that holds its mint authority
+    ///
+    /// Sets `RewardMintInfo` return data for programs that build CPIs with batch rewards.
+    /// Mints belong to batches, so the batch manager rather than the course manager answers.
+    pub fn get_reward_mint_info(ctx: Context<GetRewardMintInfo>) -> Result<()> {
+        let course_batch = &ctx.accounts.course_batch;
+        let info = RewardMintInfo {
+            reward_mint: course_batch.mint,
+            mint_authority_pda: course_batch.key(),
+            mint_authority_bump: course_batch.bump_seed,
+        };
+        set_return_data(&info.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Non-secret configuration of the assignment checker for auditors and frontends
+    ///
+    /// Sets `CheckerDescription` return data. Hash chain tails are left out, so
+    /// the description can be shown and logged without the commitment.
+    pub fn describe_checker(ctx: Context<DescribeChecker>) -> Result<()> {
+        let checker = &ctx.accounts.assignment_checker;
+        let course_batch = &ctx.accounts.course_batch;
+        let description = CheckerDescription {
+            course: ctx.accounts.course.key(),
+            course_batch: course_batch.key(),
+            assignment_id: checker.assignment_id,
+            hash_chain_length: checker.hash_chain_length,
+            remaining_capacity: checker.remaining_capacity(),
+            added_shards: checker.added_shards,
+            successful_checks: checker.successful_checks,
+            max_lifetime_passes: checker.max_lifetime_passes,
+            to_mint_on_successful_check: checker.to_mint_on_successful_check,
+            reward_amount: checker.reward_amount(u64::from(course_batch.reward_multiplier_bps))?,
+            first_solver_bonus: course_batch.first_solver_bonus,
+            reward_rounding: checker.reward_rounding,
+            hash_iterations_per_step: checker.hash_iterations_per_step,
+            pause_unix_ts: checker.pause_unix_ts(),
+            per_student_salt: checker.per_student_salt,
+            shared_answer: checker.shared_answer,
+            consume_on_failure: checker.consume_on_failure,
+            max_attempts: checker.max_attempts,
+            commitment_mode: checker.commitment_mode,
+            answer_len_commitment: checker.answer_len_commitment,
+            difficulty_score: checker.difficulty_score,
+            generation: checker.generation,
+            attempts_total: checker.attempts_total,
+            passes_total: checker.passes_total,
+            pass_rate_bps: checker.pass_rate_bps(),
+            archived: checker.archived,
+        };
+        set_return_data(&description.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Parent of the main chain tail for the `value`, built with `test-only` feature only
+    ///
+    /// Sets the parent as return data. The value is the secret answer known to tests,
+    /// so builds without the feature reject the instruction.
+    pub fn expected_parent(ctx: Context<ExpectedParent>, value: Vec<u8>) -> Result<()> {
+        if !TEST_ONLY_INSTRUCTIONS {
+            return Err(error!(CourseBatchManagerError::TestOnlyInstruction));
+        }
+        let checker = &ctx.accounts.assignment_checker;
+        if checker.hash_chain_length < MIN_CHECKABLE_HASH_CHAIN_LENGTH {
+            return Err(error!(CourseBatchManagerError::NoExpectedParent));
+        }
+        let parent = checker.commitment_mode.build_iterated_tail(
+            &checker.salt,
+            &ctx.accounts.course.key().to_bytes(),
+            &checker.assignment_id,
+            &value,
+            checker.hash_chain_length - 1,
+            checker.hash_iterations_per_step,
+        );
+        set_return_data(&parent);
+        Ok(())
+    }
+
+    /// Move the queued action `seconds` back in time, built with `test-only` feature only
+    ///
+    /// Tests warp the timelock instead of waiting for `MIN_ACTION_TIMELOCK_SECONDS`.
+    pub fn warp_timelocked_action(ctx: Context<WarpTimelockedAction>, seconds: u32) -> Result<()> {
+        if !TEST_ONLY_INSTRUCTIONS {
+            return Err(error!(CourseBatchManagerError::TestOnlyInstruction));
+        }
+        let timelocked_action = &mut ctx.accounts.timelocked_action;
+        timelocked_action.queued_unix_ts -= i64::from(seconds);
+        timelocked_action.execute_after_unix_ts -= i64::from(seconds);
+        Ok(())
+    }
+
+    /// Verify the candidate parent of the student without consuming a hash chain slot
+    ///
+    /// Sets `bool` return data, clients simulate the instruction before paying for the check.
+    /// Parents of checkers with `per_student_salt` verify against the commitment of the
+    /// student, so the candidate of one student is false for another.
+    pub fn verify_candidate_parent(
+        ctx: Context<VerifyCandidateParent>,
+        _assignment_id: [u8; 16],
+        hash_chain_tail_parent: [u8; 32],
+    ) -> Result<()> {
+        let verified = ctx.accounts.assignment_checker.verifies_parent(
+            &ctx.accounts.check_result,
+            &hash_chain_tail_parent,
+            Clock::get()?.unix_timestamp,
+        );
+        msg!("candidate parent is verified: {}", verified);
+        set_return_data(&verified.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Succeed only if the student has passed the assignment check
+    ///
+    /// Other programs gate their actions on course progress by CPI, all accounts are
+    /// read-only. Failed assertion fails the transaction of the caller as a whole.
+    /// Anonymous check results are not derived from the student and cannot be asserted.
+    pub fn assert_passed(ctx: Context<AssertPassed>, _assignment_id: [u8; 16]) -> Result<()> {
+        if !ctx.accounts.check_result.check_passed {
+            return Err(error!(CourseBatchManagerError::NotPassed));
+        }
+        msg!(
+            "student {} has passed the assignment",
+            ctx.accounts.student.key()
+        );
+        Ok(())
+    }
+
+    /// State of the assignment of the student, the missing check result isn't an error
+    ///
+    /// Sets `AssignmentState` return data for frontends that render grids of assignments.
+    /// Students who have never attempted the assignment have no check result.
+    pub fn get_assignment_state(
+        ctx: Context<GetAssignmentState>,
+        _assignment_id: [u8; 16],
+    ) -> Result<()> {
+        let check_result_info = &ctx.accounts.check_result;
+        let state = if check_result_info.data_is_empty() {
+            AssignmentState::of(None)
+        } else {
+            let check_result = Account::<CheckResult>::try_from(check_result_info)?;
+            AssignmentState::of(Some(&check_result))
+        };
+        msg!("assignment state is {:?}", state);
+        set_return_data(&state.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Publish the answer of the assignment that becomes readable after the deadline
+    ///
+    /// Account data is public, the deadline gates `reveal_answer` only.
+    /// Course authority stores an explanation of the solution rather than the checked value.
+    pub fn create_answer_reveal(
+        ctx: Context<CreateAnswerReveal>,
+        assignment_id: [u8; 16],
+        answer_plaintext: [u8; ANSWER_PLAINTEXT_LEN],
+        deadline_unix_ts: i64,
+    ) -> Result<()> {
+        let answer_reveal = &mut ctx.accounts.answer_reveal;
+        answer_reveal.assignment_id = assignment_id;
+        answer_reveal.answer_plaintext = answer_plaintext;
+        answer_reveal.deadline_unix_ts = deadline_unix_ts;
+        answer_reveal.bump_seed = *ctx
+            .bumps
+            .get("answer_reveal")
+            .expect("answer reveal pda is present");
+        ctx.accounts.course.emit(AnswerRevealCreated {
+            answer_reveal: answer_reveal.key(),
+            assignment_id,
+            deadline_unix_ts,
+        })?;
+        Ok(())
+    }
+
+    /// Reveal the answer of the assignment
+    ///
+    /// Sets `Option<[u8; ANSWER_PLAINTEXT_LEN]>` return data, it's `None` before the deadline.
+    pub fn reveal_answer(ctx: Context<RevealAnswer>) -> Result<()> {
+        let answer = ctx
+            .accounts
+            .answer_reveal
+            .answer(Clock::get()?.unix_timestamp);
+        msg!("answer is revealed: {}", answer.is_some());
+        set_return_data(&answer.try_to_vec()?);
+        Ok(())
+    }
+}
+
+/// Length of compact-u16 encoding of transaction lengths
+fn compact_u16_len(value: usize) -> usize {
+    match value {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// Serialized size of the transaction with `create_many_assignment_checkers` instruction
+///
+/// The transaction has `signers` signers. The program of the instruction is one of
+/// `CreateManyAssignmentCheckers` accounts.
+pub fn create_many_transaction_size(signers: usize, checkers: usize) -> usize {
+    const SIGNATURE_BYTES: usize = 64;
+    const MESSAGE_HEADER_BYTES: usize = 3;
+    const BLOCKHASH_BYTES: usize = 32;
+    let accounts = CreateManyAssignmentCheckers::FIXED_ACCOUNTS + checkers;
+    let data_len = 8 + 4 + NewAssignmentChecker::LEN * checkers;
+    let signatures = compact_u16_len(signers) + SIGNATURE_BYTES * signers;
+    let account_keys = compact_u16_len(accounts) + PUBKEY_BYTES * accounts;
+    let instruction =
+        1 + compact_u16_len(accounts) + accounts + compact_u16_len(data_len) + data_len;
+    signatures + MESSAGE_HEADER_BYTES + account_keys + BLOCKHASH_BYTES + 1 + instruction
+}
+
+/// Number of checkers of `create_many_assignment_checkers` per transaction with `signers`
+///
+/// The transaction stays within size, account and compute limits.
+pub fn checkers_per_transaction(signers: usize) -> usize {
+    let max_checkers = MAX_CHECKERS_PER_TRANSACTION
+        .min(MAX_TRANSACTION_ACCOUNTS - CreateManyAssignmentCheckers::FIXED_ACCOUNTS);
+    (1..=max_checkers)
+        .take_while(|checkers| {
+            create_many_transaction_size(signers, *checkers) <= MAX_TRANSACTION_SIZE
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+/// Warning for the hash chain that has fewer slots than enrolled students of the course
+///
+/// Clients show it before `create_assignment_checker`, see `Course::suggested_hash_chain_length`.
+pub fn short_hash_chain_warning(course: &Course, hash_chain_length: u16) -> Option<String> {
+    let suggested = course.suggested_hash_chain_length();
+    (hash_chain_length < suggested).then(|| {
+        format!(
+            "hash chain length {} is shorter than {} for {} enrolled students",
+            hash_chain_length, suggested, course.enrolled_count
+        )
+    })
+}
+
+/// Hash chain length for a new batch sized by prior batches of the course
+///
+/// Clients fetch student progress of the prior batches. Each batch needs a slot for every
+/// student who has passed an assignment, the length leaves half of the busiest batch as
+/// headroom. Courses without prior passes get `Course::suggested_hash_chain_length`.
+pub fn historical_hash_chain_length(
+    course: &Course,
+    prior_progress: impl IntoIterator<Item = StudentProgress>,
+) -> u16 {
+    let mut batch_passers: BTreeMap<Pubkey, u32> = BTreeMap::new();
+    for progress in prior_progress {
+        if progress.assignments_passed > 0 {
+            *batch_passers.entry(progress.course_batch).or_default() += 1;
+        }
+    }
+    match batch_passers.into_values().max() {
+        // ceil of 1.5 slots per passer plus the chain start without parent
+        Some(passers) => u16::try_from((passers * 3).div_ceil(2) + 1).unwrap_or(u16::MAX),
+        None => course.suggested_hash_chain_length(),
+    }
+}
+
+/// Token program failure of the reward payout, see `payout_block`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoutBlock {
+    /// Reward vault or student token account is frozen
+    Frozen,
+    /// Mint supply or a token account balance would overflow
+    Overflow,
+}
+
+impl PayoutBlock {
+    /// The block is lifted by `set_rewards_frozen` or the mint authority without burning tokens
+    ///
+    /// Overflowing supply or balance needs burned tokens before `claim_reward` succeeds.
+    pub fn is_transient(self) -> bool {
+        self == PayoutBlock::Frozen
+    }
+}
+
+/// Reason the token program would reject minting `amount` to the `vault` and transferring
+/// it to the `student_token` account
+///
+/// Errors of the token program abort the transaction and can't be handled by the caller,
+/// so its checks are repeated before the payout.
+pub fn payout_block(
+    mint: &anchor_spl::token::spl_token::state::Mint,
+    vault: &anchor_spl::token::spl_token::state::Account,
+    student_token: &anchor_spl::token::spl_token::state::Account,
+    amount: u64,
+) -> Option<PayoutBlock> {
+    if vault.is_frozen() || student_token.is_frozen() {
+        return Some(PayoutBlock::Frozen);
+    }
+    let overflows = mint.supply.checked_add(amount).is_none()
+        || vault.amount.checked_add(amount).is_none()
+        || student_token.amount.checked_add(amount).is_none();
+    overflows.then_some(PayoutBlock::Overflow)
+}
+
+/// Reward ledger of the batch compared with its reward vault and mint, see `reconcile_rewards`
+///
+/// Discrepancies are signed, zero for a consistent ledger.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardReconciliation {
+    /// Rewards students claim later, they are not minted yet
+    pub claimable: u64,
+    /// Escrowed rewards of the check results
+    pub escrowed: u64,
+    /// Minted rewards that weren't revoked, `paid_rewards` and `escrowed_rewards` of the batch
+    pub minted: u64,
+    pub vault_balance: u64,
+    pub mint_supply: u64,
+    /// Escrowed rewards of the check results above `escrowed_rewards` of the batch
+    pub escrow_discrepancy: i128,
+    /// Vault balance above `escrowed_rewards`, e.g. tokens sent to the vault directly
+    /// that `emergency_withdraw` recovers. Negative for a vault that can't pay escrowed rewards.
+    pub vault_discrepancy: i128,
+    /// Mint supply above minted rewards, negative when holders have burned tokens
+    pub supply_discrepancy: i128,
+}
+
+impl RewardReconciliation {
+    pub fn is_consistent(&self) -> bool {
+        self.escrow_discrepancy == 0 && self.vault_discrepancy == 0 && self.supply_discrepancy == 0
+    }
+}
+
+/// Reconcile rewards of the batch for auditors
+///
+/// Clients fetch check results of the students enrolled in the batch together with
+/// the balance of the batch reward vault and the supply of the batch mint.
+pub fn reconcile_rewards(
+    course_batch: &CourseBatch,
+    vault_balance: u64,
+    mint_supply: u64,
+    check_results: impl IntoIterator<Item = CheckResult>,
+) -> RewardReconciliation {
+    let (claimable, escrowed) =
+        check_results
+            .into_iter()
+            .fold((0u64, 0u64), |(claimable, escrowed), check_result| {
+                (
+                    claimable.saturating_add(check_result.reward_claimable),
+                    escrowed.saturating_add(check_result.reward.escrowed),
+                )
+            });
+    let minted = course_batch
+        .paid_rewards
+        .saturating_add(course_batch.escrowed_rewards);
+    RewardReconciliation {
+        claimable,
+        escrowed,
+        minted,
+        vault_balance,
+        mint_supply,
+        escrow_discrepancy: i128::from(escrowed) - i128::from(course_batch.escrowed_rewards),
+        vault_discrepancy: i128::from(vault_balance) - i128::from(course_batch.escrowed_rewards),
+        supply_discrepancy: i128::from(mint_supply) - i128::from(minted),
+    }
+}
+
+/// What a confirmed `check_assignment` transaction has done
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckReceipt {
+    pub assignment_checker: Pubkey,
+    pub check_result: Pubkey,
+    /// Check has ever passed
+    pub passed: bool,
+    /// This check has passed for the first time
+    pub first_time: bool,
+    /// The check has cut the hash chain tail of the shard
+    pub slot_consumed: bool,
+}
+
+/// Receipt of the check from a log data entry of its transaction, `None` for other entries
+///
+/// Repeated checks of a passed result are no-ops, the receipt tells them apart from
+/// the first pass with `first_time` and `slot_consumed`.
+pub fn check_receipt(log_data: &[u8]) -> Option<CheckReceipt> {
+    let (_, event) = decode_event::<AssignmentChecked>(log_data)?;
+    Some(CheckReceipt {
+        assignment_checker: event.assignment_checker,
+        check_result: event.check_result,
+        passed: event.passed,
+        first_time: event.first_time,
+        slot_consumed: event.slot_consumed,
+    })
+}
+
+/// Leaf of the gradebook Merkle tree
+pub fn gradebook_leaf(student_address: Pubkey, assignment_id: &[u8; 16], passed: bool) -> [u8; 32] {
+    blake3::hashv(&[
+        GRADEBOOK_LEAF_PREFIX,
+        student_address.as_ref(),
+        assignment_id,
+        &[u8::from(passed)],
+    ])
+    .0
+}
+
+fn gradebook_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    // sorted pair doesn't require leaf position in the proof
+    let (first, second) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    blake3::hashv(&[GRADEBOOK_NODE_PREFIX, first, second]).0
+}
+
+fn gradebook_root_from_proof(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof
+        .iter()
+        .fold(leaf, |hash, sibling| gradebook_node(&hash, sibling))
+}
+
+fn gradebook_levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves.to_vec()];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let level = levels.last().expect("level is present");
+        // the last node without a pair is promoted to the next level
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => gradebook_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of gradebook Merkle tree, zero hash for empty gradebook
+pub fn gradebook_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    gradebook_levels(leaves)
+        .last()
+        .and_then(|level| level.first().copied())
+        .unwrap_or_default()
+}
+
+/// Proof of the leaf at `index` for `verify_gradebook_entry`
+pub fn gradebook_proof(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    let levels = gradebook_levels(leaves);
+    let mut proof = Vec::new();
+    let mut index = index;
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+/// Gradebook leaves of the assignment from fetched check result accounts
+///
+/// Only finalized check results at `check_result_canonical_pda` addresses are included,
+/// see `finalize_check_result`. Leaves are ordered by the student address.
+pub fn gradebook_snapshot(
+    course_address: Pubkey,
+    assignment_id: &[u8; 16],
+    check_results: impl IntoIterator<Item = (Pubkey, CheckResult)>,
+) -> Vec<[u8; 32]> {
+    let mut entries: Vec<(Pubkey, bool)> = check_results
+        .into_iter()
+        .filter(|(_, check_result)| {
+            check_result.finalized && check_result.assignment_id == *assignment_id
+        })
+        .map(|(address, check_result)| {
+            (
+                address,
+                Pubkey::new_from_array(check_result.student_seed),
+                check_result.check_passed,
+            )
+        })
+        .filter(|(address, student, _)| {
+            *address == check_result_canonical_pda(*student, course_address, assignment_id)
+        })
+        .map(|(_, student, passed)| (student, passed))
+        .collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(student, passed)| gradebook_leaf(student, assignment_id, passed))
+        .collect()
+}
+
+/// Check result of the student in a transcript
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    pub assignment_id: [u8; 16],
+    pub check_result: Pubkey,
+    pub check_passed: bool,
+    pub passed_first_time: bool,
+    /// Escrowed reward and its release time
+    pub reward: RewardLedger,
+}
+
+/// State of an assignment of the student, see `get_assignment_state`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssignmentState {
+    /// No check result or no checks of it
+    NotAttempted,
+    /// Checks haven't passed yet
+    Failed,
+    Passed,
+}
+
+impl AssignmentState {
+    /// State of the fetched check result, `None` stands for the missing account
+    pub fn of(check_result: Option<&CheckResult>) -> Self {
+        match check_result {
+            Some(check_result) if check_result.check_passed => AssignmentState::Passed,
+            Some(check_result) if check_result.attempts > 0 => AssignmentState::Failed,
+            _ => AssignmentState::NotAttempted,
+        }
+    }
+}
+
+/// All check results of the student in the course
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+    pub student: Pubkey,
+    pub course: Pubkey,
+    /// Entries ordered by assignment ID
+    pub entries: Vec<TranscriptEntry>,
+}
+
+/// Leaderboard of the batch cohort from fetched student progress accounts
+///
+/// Students are ordered by `assignments_passed`, ties by student address.
+/// Progress of other batches is skipped.
+pub fn cohort_leaderboard(
+    course_batch: Pubkey,
+    student_progress: impl IntoIterator<Item = (Pubkey, StudentProgress)>,
+) -> Vec<(Pubkey, StudentProgress)> {
+    let mut leaderboard: Vec<(Pubkey, StudentProgress)> = student_progress
+        .into_iter()
+        .filter(|(_, progress)| progress.course_batch == course_batch)
+        .collect();
+    leaderboard.sort_by(|(_, a), (_, b)| {
+        b.assignments_passed
+            .cmp(&a.assignments_passed)
+            .then(a.student.cmp(&b.student))
+    });
+    leaderboard
+}
+
+/// Record the check result closed by its last failed attempt, see `create_check_result`
+fn mark_exhausted_check_result<'info>(
+    payer: AccountInfo<'info>,
+    exhausted_check_result: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    check_result: &Pubkey,
+    assignment_checker: &Account<'info, AssignmentCheckerState>,
+    extra_attempts: u16,
+    bump_seed: u8,
+) -> Result<()> {
+    let bump_seed_bytes = [bump_seed];
+    let seeds = [
+        EXHAUSTED_CHECK_RESULT_SEED,
+        check_result.as_ref(),
+        &bump_seed_bytes,
+    ];
+    create_pda_account(
+        payer,
+        exhausted_check_result.clone(),
+        system_program,
+        8 + ExhaustedCheckResult::LEN,
+        &ID,
+        &[seeds.as_slice()],
+    )?;
+    let exhausted = ExhaustedCheckResult {
+        assignment_checker: assignment_checker.key(),
+        generation: assignment_checker.generation,
+        hash_chain_extensions: assignment_checker.hash_chain_extensions,
+        extra_attempts,
+        bump_seed,
+    };
+    exhausted.try_serialize(&mut &mut exhausted_check_result.try_borrow_mut_data()?[..])
+}
+
+/// Leaderboard of the course batch at the end of `remaining_accounts` of checks
+///
+/// Checks of batches with `has_leaderboard` are rejected without it, other remaining
+/// accounts are returned for the instruction.
+fn batch_leaderboard<'a, 'info>(
+    course_batch: &Account<'info, CourseBatch>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(
+    &'a [AccountInfo<'info>],
+    Option<Account<'info, Leaderboard>>,
+)> {
+    if !course_batch.has_leaderboard {
+        return Ok((remaining_accounts, None));
+    }
+    let (leaderboard_info, other_accounts) = remaining_accounts
+        .split_last()
+        .ok_or_else(|| error!(CourseBatchManagerError::InvalidLeaderboard))?;
+    let leaderboard = Account::<Leaderboard>::try_from(leaderboard_info)
+        .map_err(|_| error!(CourseBatchManagerError::InvalidLeaderboard))?;
+    if !leaderboard_info.is_writable || leaderboard.course_batch != course_batch.key() {
+        return Err(error!(CourseBatchManagerError::InvalidLeaderboard));
+    }
+    Ok((other_accounts, Some(leaderboard)))
+}
+
+/// Transcript of the student from fetched check result accounts
+///
+/// Clients fetch check results at `check_result_canonical_pda` addresses
+/// of known course assignment IDs. Results at other addresses are skipped,
+/// so anonymous check results and results of other courses are not included.
+pub fn course_transcript(
+    student_address: Pubkey,
+    course_address: Pubkey,
+    check_results: impl IntoIterator<Item = (Pubkey, CheckResult)>,
+) -> Transcript {
+    let mut entries: Vec<TranscriptEntry> = check_results
+        .into_iter()
+        .filter(|(address, check_result)| {
+            *address
+                == check_result_canonical_pda(
+                    student_address,
+                    course_address,
+                    &check_result.assignment_id,
+                )
+        })
+        .map(|(address, check_result)| TranscriptEntry {
+            assignment_id: check_result.assignment_id,
+            check_result: address,
+            check_passed: check_result.check_passed,
+            passed_first_time: check_result.passed_first_time,
+            reward: check_result.reward,
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.assignment_id);
+    Transcript {
+        student: student_address,
+        course: course_address,
+        entries,
+    }
+}
+
+pub fn batch_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            BATCH_DATA_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Client side derivation of `get_reward_mint_info` return data
+pub fn reward_mint_info(course_address: Pubkey, batch_id: &[u8; 16]) -> RewardMintInfo {
+    let (mint_authority_pda, mint_authority_bump) = Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            BATCH_DATA_SEED,
+        ],
+        &ID,
+    );
+    RewardMintInfo {
+        reward_mint: batch_mint_canonical_pda(course_address, batch_id),
+        mint_authority_pda,
+        mint_authority_bump,
+    }
+}
+
+pub fn batch_mint_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            BATCH_MINT_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn batch_escrow_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            BATCH_ESCROW_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn exhausted_check_result_canonical_pda(check_result: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EXHAUSTED_CHECK_RESULT_SEED, check_result.as_ref()], &ID).0
+}
+
+pub fn price_feed_canonical_pda(authority: Pubkey, feed_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(&[PRICE_FEED_SEED, authority.as_ref(), feed_id], &ID).0
+}
+
+pub fn timelocked_action_canonical_pda(
+    course_address: Pubkey,
+    batch_id: &[u8; 16],
+    action: TimelockedActionKind,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            TIMELOCKED_ACTION_SEED,
+            &[action as u8],
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn student_progress_canonical_pda(
+    student_address: Pubkey,
+    course_address: Pubkey,
+    batch_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            STUDENT_PROGRESS_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn certificate_mint_canonical_pda(
+    student_address: Pubkey,
+    course_address: Pubkey,
+    batch_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            CERTIFICATE_MINT_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn roster_entry_canonical_pda(
+    course_address: Pubkey,
+    batch_id: &[u8; 16],
+    roster_index: u32,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            ROSTER_ENTRY_SEED,
+            &roster_index.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Check result of the student with the roster entry
+pub fn roster_check_result_canonical_pda(
+    roster_entry: &RosterEntry,
+    course_address: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    check_result_canonical_pda(roster_entry.student, course_address, assignment_id)
+}
+
+pub fn leaderboard_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            LEADERBOARD_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn answer_reveal_canonical_pda(course_address: Pubkey, assignment_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+            ANSWER_REVEAL_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn assignment_checker_canonical_pda(
+    course_address: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &assignment_checker_seeds(&course_address, assignment_id),
+        &ID,
+    )
+    .0
+}
+
+pub fn assignment_criteria_canonical_pda(
+    course_address: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+            CRITERIA_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Anonymous check result derived from the student address and course secret
+pub fn anonymous_check_result_canonical_pda(
+    student_address: Pubkey,
+    course_secret: &[u8; 32],
+    course_data: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            &check_result_student_seed(&student_address, Some(course_secret)),
+            COURSE_DATA_SEED,
+            course_data.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn module_completion_canonical_pda(
+    student_address: Pubkey,
+    course_address: Pubkey,
+    batch_id: &[u8; 16],
+    module_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            BATCH_ID_SEED,
+            batch_id,
+            MODULE_ID_SEED,
+            module_id,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn check_result_canonical_pda(
+    student_address: Pubkey,
+    course_data: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            COURSE_DATA_SEED,
+            course_data.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+        ],
+        &ID,
+    )
+    .0
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: [u8; 16])]
+pub struct NewCourseBatch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(init, payer = authority, space = 8 + CourseBatch::LEN, seeds= [
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        BATCH_DATA_SEED,
+    ], bump)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = authority,
+        mint::authority = course_batch,
+        mint::decimals = 0,
+        mint::freeze_authority = course_batch,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &batch_id,
+            BATCH_MINT_SEED,
+    ], bump)]
+    pub mint: Account<'info, Mint>,
+    // Reward vault of the batch, rewards are minted here and held during dispute window
+    #[account(init, payer = authority,
+        token::mint = mint,
+        token::authority = course_batch,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &batch_id,
+            BATCH_ESCROW_SEED,
+    ], bump)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16])]
+pub struct AssertPassed<'info> {
+    // CHECK: the student is a seed of the check result, its signature isn't required
+    pub student: AccountInfo<'info>,
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump=check_result.bump_seed)]
+    pub check_result: Account<'info, CheckResult>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16])]
+pub struct GetAssignmentState<'info> {
+    // CHECK: the student is a seed of the check result, its signature isn't required
+    pub student: AccountInfo<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: the check result is empty until the student creates it
+    #[account(
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump)]
+    pub check_result: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16])]
+pub struct VerifyCandidateParent<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(has_one = course,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump=check_result.bump_seed)]
+    pub check_result: Account<'info, CheckResult>,
+}
+
+#[derive(Accounts)]
+pub struct GetRewardMintInfo<'info> {
+    pub course: Account<'info, Course>,
+    #[account(has_one = course,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+}
+
+#[derive(Accounts)]
+pub struct DescribeChecker<'info> {
+    pub course: Account<'info, Course>,
+    #[account(has_one = course,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+}
+
+#[derive(Accounts)]
+pub struct ExpectedParent<'info> {
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+    ], bump=assignment_checker.bump_seed)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+}
+
+#[derive(Accounts)]
+pub struct WarpTimelockedAction<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut, has_one = course_batch)]
+    pub timelocked_action: Account<'info, TimelockedAction>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCourseBatch<'info> {
+    pub authority: Signer<'info>,
+    // events of the batch follow `emit_events` of the course
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = authority, has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 16])]
+pub struct CreatePriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(init, payer = authority, space = 8 + PriceFeed::LEN, seeds=[
+        PRICE_FEED_SEED,
+        authority.key().as_ref(),
+        &feed_id,
+    ], bump)]
+    pub price_feed: Account<'info, PriceFeed>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub price_feed: Account<'info, PriceFeed>,
+}
+
+#[derive(Accounts)]
+pub struct EnrollBatch<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    // course authority signs enrollments into invite-only courses
+    pub authority: AccountInfo<'info>,
+    // enrolled count is updated by course_manager
+    #[account(mut,
+        constraint = course.self_enroll_allowed || authority.is_signer @ CourseBatchManagerError::SelfEnrollNotAllowed,
+    )]
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority, has_one = course, has_one = mint)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mint::authority = course_batch)]
+    pub mint: Account<'info, Mint>,
+    // CHECK: ATA of the student is created by enroll_batch unless it exists
+    #[account(mut, address = get_associated_token_address(&student.key(), &mint.key()))]
+    pub course_batch_token: AccountInfo<'info>,
+    #[account(init, payer = student, space = 8 + StudentProgress::LEN, seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump)]
+    pub student_progress: Account<'info, StudentProgress>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> EnrollBatch<'info> {
+    pub fn create_token_account_cpi_ctx(&self) -> CpiContext<'a, 'b, 'c, 'info, Create<'info>> {
+        let cpi_program = self.associated_token_program.to_account_info();
+
+        let cpi_accounts = Create {
+            payer: self.student.to_account_info(),
+            associated_token: self.course_batch_token.to_account_info(),
+            authority: self.student.to_account_info(),
+            mint: self.mint.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            rent: self.rent.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn register_enrollment_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, RegisterEnrollment<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = RegisterEnrollment {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            student_progress: self.student_progress.to_account_info(),
+            enrollment_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseEnrollment<'info> {
+    // the student or the course authority
+    pub closer: Signer<'info>,
+    // CHECK: rent recipient bound by has_one of the student progress
+    #[account(mut)]
+    pub student: AccountInfo<'info>,
+    // enrolled count is updated by course_manager
+    #[account(mut)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut, close = student, has_one = student, has_one = course_batch,
+        constraint = closer.key() == student.key()
+            || (closer.key() == course_batch.authority && course_batch.ended)
+            @ CourseBatchManagerError::NotEnrollmentCloser,
+        constraint = student_progress.pending_rewards == 0
+            @ CourseBatchManagerError::EnrollmentHasProgress,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CloseEnrollment<'info> {
+    pub fn unregister_enrollment_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UnregisterEnrollment<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UnregisterEnrollment {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            student_progress: self.student_progress.to_account_info(),
+            enrollment_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(roster_index: u32)]
+pub struct AssignRosterIndex<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority, has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // only enrolled students get roster indices
+    #[account(has_one = course_batch,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student_progress.student.as_ref(),
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    #[account(init, payer = authority, space = 8 + RosterEntry::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        ROSTER_ENTRY_SEED,
+        &roster_index.to_le_bytes(),
+    ], bump)]
+    pub roster_entry: Account<'info, RosterEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLeaderboard<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = authority, has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = authority, space = 8 + Leaderboard::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        LEADERBOARD_SEED,
+    ], bump)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(checker: NewAssignmentChecker)]
+pub struct CreateAssignmentChecker<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // assignment count is updated by course_manager
+    // Anchor rejects default and checker addresses as not owned by course_manager
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, course_manager::Course>,
+    // By default init sets the owner field of the created account to the currently executing program.
+    // We override it to assignment_checker program.
+    // Anchor will find the canonical bump for the assignment checker PDA derived for course_batch_manager program.
+    // Course batch manager can sign for PDA to do mutable cross-program operations.
+    // The PDA is derived from course account and assignment IDs.
+    #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCheckerState::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        checker.assignment_id.as_ref(),
+    ], bump, constraint = checker.hash_chain_length >= 2)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> CreateAssignmentChecker<'info> {
+    pub fn init_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Init<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Init {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: [u8; 16], checker: NewAssignmentChecker)]
+pub struct CreateAssignmentCheckerWithMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // assignment count is updated by course_manager
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, course_manager::Course>,
+    // CHECK: batch accounts are created by the instruction unless the batch exists
+    #[account(mut, seeds= [
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        BATCH_DATA_SEED,
+    ], bump)]
+    pub course_batch: AccountInfo<'info>,
+    // CHECK: mint of the existing batch is reused
+    #[account(mut, seeds= [
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        BATCH_MINT_SEED,
+    ], bump)]
+    pub mint: AccountInfo<'info>,
+    // CHECK: reward vault of the existing batch is reused
+    #[account(mut, seeds= [
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        BATCH_ESCROW_SEED,
+    ], bump)]
+    pub escrow: AccountInfo<'info>,
+    // the checker is created like in `CreateAssignmentChecker`
+    #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCheckerState::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        checker.assignment_id.as_ref(),
+    ], bump, constraint = checker.hash_chain_length >= 2)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'a, 'b, 'c, 'info> CreateAssignmentCheckerWithMint<'info> {
+    pub fn initialize_mint_cpi_ctx(&self) -> CpiContext<'a, 'b, 'c, 'info, InitializeMint<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = InitializeMint {
+            mint: self.mint.clone(),
+            rent: self.rent.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn initialize_escrow_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, InitializeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = InitializeAccount {
+            account: self.escrow.clone(),
+            mint: self.mint.clone(),
+            authority: self.course_batch.clone(),
+            rent: self.rent.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn init_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Init<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Init {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateManyAssignmentCheckers<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // assignment count is updated by course_manager
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> CreateManyAssignmentCheckers<'info> {
+    /// Number of accounts before remaining checker accounts
+    pub const FIXED_ACCOUNTS: usize = 6;
+
+    pub fn init_cpi_ctx(
+        &self,
+        assignment_checker: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Init<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Init {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker,
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloneAssignmentChecker<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub source_course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        source_course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        source_assignment_checker.assignment_id.as_ref(),
+    ], bump=source_assignment_checker.bump_seed)]
+    pub source_assignment_checker: Account<'info, AssignmentCheckerState>,
+    // assignment count is updated by course_manager
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+    // the clone is created like in `CreateAssignmentChecker`
+    #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCheckerState::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        source_assignment_checker.assignment_id.as_ref(),
+    ], bump)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> CloneAssignmentChecker<'info> {
+    pub fn init_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Init<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Init {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateChecker<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateChecker {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseAssignmentChecker<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // assignment count is updated by course_manager
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut, constraint = !assignment_checker.in_module() @ CourseBatchManagerError::CheckerInModule)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CloseAssignmentChecker<'info> {
+    pub fn close_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Close<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Close {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseBatchCheckers<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // assignment count is updated by course_manager
+    #[account(mut, has_one = authority, constraint = !course.frozen @ course_manager::CourseManagerError::CourseFrozen)]
+    pub course: Account<'info, Course>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CloseBatchCheckers<'info> {
+    /// Exhausted assignment checker of the course that can be closed
+    fn exhausted_checker(
+        &self,
+        checker_info: &AccountInfo<'info>,
+    ) -> Option<Account<'info, AssignmentCheckerState>> {
+        if !checker_info.is_writable {
+            return None;
+        }
+        let checker = Account::<AssignmentCheckerState>::try_from(checker_info).ok()?;
+        let course_key = self.course.key();
+        let checker_address = Pubkey::create_program_address(
+            &assignment_checker_signer_seeds(
+                &course_key,
+                &checker.assignment_id,
+                &[checker.bump_seed],
+            ),
+            &ID,
+        )
+        .ok()?;
+        if checker_address == checker_info.key() && checker.is_exhausted() && !checker.in_module() {
+            Some(checker)
+        } else {
+            None
+        }
+    }
+
+    pub fn close_cpi_ctx(
+        &self,
+        assignment_checker: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Close<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Close {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker,
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_assignment_count_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssignmentChecker<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> UpdateAssignmentChecker<'info> {
+    pub fn update_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateChecker<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateChecker {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetCheckerModule<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // assignment count is updated by course_manager
+    #[account(mut)]
+    pub module: Account<'info, Module>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub course_manager_program: Program<'info, CourseManager>,
+}
+
+impl<'a, 'b, 'c, 'info> SetCheckerModule<'info> {
+    pub fn update_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateChecker<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateChecker {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_module_cpi_ctx(
+        &self,
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateModuleAssignmentCount<'info>> {
+        let cpi_program = self.course_manager_program.to_account_info();
+
+        let cpi_accounts = UpdateModuleAssignmentCount {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            module: self.module.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateAssignmentCriteria<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCriteria::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        CRITERIA_SEED,
+    ], bump)]
+    pub assignment_criteria: Account<'info, AssignmentCriteria>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> CreateAssignmentCriteria<'info> {
+    pub fn init_criteria_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, InitCriteria<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = InitCriteria {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            assignment_criteria: self.assignment_criteria.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RegisterStudentCommitment<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub student: AccountInfo<'info>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> RegisterStudentCommitment<'info> {
+    pub fn set_student_commitment_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, SetStudentCommitment<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = SetStudentCommitment {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            student: self.student.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct GrantStudentExtraAttempts<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    pub student: AccountInfo<'info>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> GrantStudentExtraAttempts<'info> {
+    pub fn grant_extra_attempts_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, GrantExtraAttempts<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = GrantExtraAttempts {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            student: self.student.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16], course_secret: Option<[u8; 32]>)]
+pub struct CreateCheckResult<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(init, payer = student, space = 8 + assignment_checker::CheckResult::LEN,
+        owner = assignment_checker::ID,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump)]
+    pub check_result: Account<'info, assignment_checker::CheckResult>,
+    // CHECK: the marker is read by create_check_result when it's owned by the program
+    #[account(mut, seeds=[EXHAUSTED_CHECK_RESULT_SEED, check_result.key().as_ref()], bump)]
+    pub exhausted_check_result: AccountInfo<'info>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> CreateCheckResult<'info> {
+    pub fn init_check_result_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, InitCheckResult<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = InitCheckResult {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16], old_course_secret: Option<[u8; 32]>, new_course_secret: Option<[u8; 32]>)]
+pub struct MigrateCheckResult<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub old_check_result: Account<'info, assignment_checker::CheckResult>,
+    #[account(init, payer = student, space = 8 + assignment_checker::CheckResult::LEN,
+        owner = assignment_checker::ID,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        check_result_student_seed(&student.key(), new_course_secret.as_ref()).as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_id.as_ref(),
+    ], bump)]
+    pub new_check_result: Account<'info, assignment_checker::CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> MigrateCheckResult<'info> {
+    pub fn migrate_result_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MigrateResult<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = MigrateResult {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            old_check_result: self.old_check_result.to_account_info(),
+            new_check_result: self.new_check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpgradeStudentCheckResult<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: older layouts are read by upgrade_student_check_result,
+    // pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: AccountInfo<'info>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'a, 'b, 'c, 'info> UpgradeStudentCheckResult<'info> {
+    pub fn upgrade_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpgradeCheckResult<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpgradeCheckResult {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseCheckResult<'info> {
+    // rent recipient, assignment_checker binds it to the check result seeds
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CloseCheckResult<'info> {
+    pub fn close_result_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, CloseResult<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = CloseResult {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct FinalizeCheckResult<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> FinalizeCheckResult<'info> {
+    pub fn finalize_result_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FinalizeResult<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = FinalizeResult {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReserveCheck<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> ReserveCheck<'info> {
+    pub fn reserve_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Reserve<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Reserve {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReleaseCheckReservation<'info> {
+    pub course: Account<'info, Course>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> ReleaseCheckReservation<'info> {
+    pub fn release_reservation_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ReleaseReservation<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = ReleaseReservation {
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CheckAssignment<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    // checks don't change the course
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint, constraint = course.authority == course_batch.authority,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    // checkers of another course fail clearly before the CPI
+    #[account(mut, has_one = course)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    // CHECK: marker created when the last failed attempt closes check_result
+    #[account(mut, seeds=[EXHAUSTED_CHECK_RESULT_SEED, check_result.key().as_ref()], bump)]
+    pub exhausted_check_result: AccountInfo<'info>,
+    #[account(
+        mut,
+        mint::authority = course_batch,
+        mint::decimals = 0,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_MINT_SEED,
+    ], bump=course_batch.mint_bump_seed)]
+    pub mint: Account<'info, Mint>,
+    // mints are compared before the address, so token accounts of other mints fail clearly
+    #[account(
+        mut,
+        constraint = course_batch_token.mint == mint.key() @ CourseBatchManagerError::WrongRewardMint,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CheckAssignment<'info> {
+    /// Referrer and its token account named by `remaining_accounts` of `check_assignment`
+    fn referrer(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<Option<(Pubkey, AccountInfo<'info>)>> {
+        let (progress_info, token_info) = match remaining_accounts {
+            [] => return Ok(None),
+            [progress, token] => (progress, token),
+            _ => return Err(error!(CourseBatchManagerError::InvalidReferrer)),
+        };
+        let progress = Account::<StudentProgress>::try_from(progress_info)?;
+        let token = Account::<TokenAccount>::try_from(token_info)?;
+        let valid = progress.course_batch == self.course_batch.key()
+            && token_info.is_writable
+            && token.mint == self.mint.key()
+            && token.owner == progress.student;
+        if !valid {
+            return Err(error!(CourseBatchManagerError::InvalidReferrer));
+        }
+        if progress.student == self.student.key() {
+            return Err(error!(CourseBatchManagerError::SelfReferral));
+        }
+        Ok(Some((progress.student, token_info.clone())))
+    }
+
+    pub fn check_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Check<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let course = self.course.to_account_info();
+        let check_result = self.check_result.to_account_info();
+        let cpi_accounts = Check {
+            student: self.student.to_account_info(),
+            course,
+            assignment_checker: self.assignment_checker.to_account_info(),
+            check_result,
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn mint_to_escrow_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: self.mint.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    /// Transfer from the batch reward vault
+    pub fn transfer_cpi_ctx(
+        &self,
+        to: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to,
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ProctoredCheckAssignment<'info> {
+    #[account(mut, constraint = course.is_authorized_proctor(&proctor.key()) @ CourseBatchManagerError::ProctorNotAuthorized)]
+    pub proctor: Signer<'info>,
+    // student is not required to sign, the check result and the progress are derived from it
+    #[account(mut)]
+    pub student: AccountInfo<'info>,
+    pub course: Account<'info, Course>,
+    #[account(constraint = course.authority == course_batch.authority,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(mut, has_one = course)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    // CHECK: marker created when the last failed attempt closes check_result
+    #[account(mut, seeds=[EXHAUSTED_CHECK_RESULT_SEED, check_result.key().as_ref()], bump)]
+    pub exhausted_check_result: AccountInfo<'info>,
+    #[account(mut,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    pub system_program: Program<'info, System>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> ProctoredCheckAssignment<'info> {
+    pub fn proctored_check_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ProctoredCheck<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = ProctoredCheck {
+            proctor: self.proctor.to_account_info(),
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CheckAssignmentCriterion<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint, constraint = course.authority == course_batch.authority,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    #[account(has_one = course)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_criteria: Account<'info, AssignmentCriteria>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_MINT_SEED,
+    ], bump=course_batch.mint_bump_seed)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = course_batch_token.mint == mint.key() @ CourseBatchManagerError::WrongRewardMint,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CheckAssignmentCriterion<'info> {
+    pub fn check_criterion_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, CheckCriterion<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = CheckCriterion {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            assignment_criteria: self.assignment_criteria.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn mint_to_escrow_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: self.mint.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn transfer_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.course_batch_token.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct FinalizeReward<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut, constraint = check_result.reward.escrow == escrow.key())]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(mut, has_one = student, has_one = course_batch,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = course_batch_token.mint == mint.key() @ CourseBatchManagerError::WrongRewardMint,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> FinalizeReward<'info> {
+    pub fn transfer_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.course_batch_token.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(mut, has_one = student, has_one = course_batch,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = course_batch_token.mint == mint.key() @ CourseBatchManagerError::WrongRewardMint,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> ClaimReward<'info> {
+    pub fn mint_to_escrow_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: self.mint.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn transfer_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.course_batch_token.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeReward<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // student whose reward is revoked
+    pub student: AccountInfo<'info>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut, constraint = check_result.reward.escrow == escrow.key())]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(mut, has_one = student, has_one = course_batch,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> RevokeReward<'info> {
+    pub fn burn_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Burn<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Burn {
+            mint: self.mint.to_account_info(),
+            from: self.escrow.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+#[derive(Accounts)]
+pub struct AirdropRewards<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> AirdropRewards<'info> {
+    /// Escrowed reward of the airdrop item that can be released now
+    /// and the student progress that holds it
+    fn releasable_reward(
+        &self,
+        student: &AccountInfo<'info>,
+        check_result_info: &AccountInfo<'info>,
+        token_info: &AccountInfo<'info>,
+        student_progress_info: &AccountInfo<'info>,
+        now_unix_ts: i64,
+    ) -> Option<(RewardLedger, Account<'info, StudentProgress>)> {
+        if !check_result_info.is_writable
+            || !token_info.is_writable
+            || !student_progress_info.is_writable
+        {
+            return None;
+        }
+        let check_result = Account::<CheckResult>::try_from(check_result_info).ok()?;
+        let check_result_address = Pubkey::create_program_address(
+            &[
+                STUDENT_ADDRESS_SEED,
+                student.key().as_ref(),
+                COURSE_DATA_SEED,
+                self.course.key().as_ref(),
+                ASSIGNMENT_ID_SEED,
+                check_result.assignment_id.as_ref(),
+                &[check_result.bump_seed],
+            ],
+            &ID,
+        )
+        .ok()?;
+        let token = Account::<TokenAccount>::try_from(token_info).ok()?;
+        let student_progress = Account::<StudentProgress>::try_from(student_progress_info).ok()?;
+        let student_progress_address = Pubkey::create_program_address(
+            &[
+                STUDENT_ADDRESS_SEED,
+                student.key().as_ref(),
+                COURSE_DATA_SEED,
+                self.course.key().as_ref(),
+                BATCH_ID_SEED,
+                self.course_batch.id.as_ref(),
+                STUDENT_PROGRESS_SEED,
+                &[student_progress.bump_seed],
+            ],
+            &ID,
+        )
+        .ok()?;
+        let reward = check_result.reward;
+        let releasable = check_result_address == check_result_info.key()
+            && student_progress_address == student_progress_info.key()
+            && token.mint == self.mint.key()
+            && token.owner == student.key()
+            && reward.escrow == self.escrow.key()
+            && reward.escrowed > 0
+            && now_unix_ts >= reward.escrowed_until_unix_ts;
+        if releasable {
+            Some((reward, student_progress))
+        } else {
+            None
+        }
+    }
+
+    pub fn transfer_cpi_ctx(
+        &self,
+        to: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to,
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn update_reward_ledger_cpi_ctx(
+        &self,
+        student: AccountInfo<'info>,
+        check_result: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateRewardLedger<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateRewardLedger {
+            student,
+            course: self.course.to_account_info(),
+            check_result,
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(action: TimelockedActionKind)]
+pub struct QueueAction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = authority, space = 8 + TimelockedAction::LEN,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        TIMELOCKED_ACTION_SEED,
+        &[action as u8],
+    ], bump)]
+    pub timelocked_action: Account<'info, TimelockedAction>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority, has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut, close = authority, has_one = course_batch)]
+    pub timelocked_action: Account<'info, TimelockedAction>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    // the executed action is closed, so it runs once
+    #[account(mut, close = authority, has_one = course_batch, has_one = destination,
+        constraint = timelocked_action.action == TimelockedActionKind::EmergencyWithdraw
+            @ CourseBatchManagerError::WrongTimelockedAction,
+    )]
+    pub timelocked_action: Account<'info, TimelockedAction>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'a, 'b, 'c, 'info> EmergencyWithdraw<'info> {
+    pub fn transfer_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.destination.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetRewardsFrozen<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = authority, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(
+        mut,
+        seeds= [
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            BATCH_ESCROW_SEED,
+    ], bump=course_batch.escrow_bump_seed)]
+    pub escrow: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'a, 'b, 'c, 'info> SetRewardsFrozen<'info> {
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.escrow.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn thaw_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ThawAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = ThawAccount {
+            account: self.escrow.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct MintCertificate<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    #[account(init, payer = student,
+        mint::authority = course_batch,
+        mint::decimals = 0,
+        seeds= [
+            STUDENT_ADDRESS_SEED,
+            student.key().as_ref(),
+            COURSE_DATA_SEED,
+            course.key().as_ref(),
+            BATCH_ID_SEED,
+            &course_batch.id,
+            CERTIFICATE_MINT_SEED,
+    ], bump)]
+    pub certificate_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = student,
+        associated_token::mint = certificate_mint,
+        associated_token::authority = student,
+    )]
+    pub certificate_token: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'a, 'b, 'c, 'info> MintCertificate<'info> {
+    pub fn mint_to_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: self.certificate_mint.to_account_info(),
+            to: self.certificate_token.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn set_authority_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, SetAuthority<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = SetAuthority {
+            current_authority: self.course_batch.to_account_info(),
+            account_or_mint: self.certificate_mint.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CompleteModule<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(has_one = course)]
+    pub module: Account<'info, Module>,
+    #[account(mut,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        STUDENT_PROGRESS_SEED,
+    ], bump=student_progress.bump_seed)]
+    pub student_progress: Account<'info, StudentProgress>,
+    // the module is completed once per batch
+    #[account(init, payer = student, space = 8 + ModuleCompletion::LEN,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        MODULE_ID_SEED,
+        &module.module_id,
+    ], bump)]
+    pub module_completion: Account<'info, ModuleCompletion>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CompleteModule<'info> {
+    /// Checker of the module passed by the student
+    fn passed_module_checker(
+        &self,
+        checker_info: &AccountInfo<'info>,
+        check_result_info: &AccountInfo<'info>,
+    ) -> Option<Pubkey> {
+        let checker = Account::<AssignmentCheckerState>::try_from(checker_info).ok()?;
+        let course_key = self.course.key();
+        let checker_address = Pubkey::create_program_address(
+            &assignment_checker_signer_seeds(
+                &course_key,
+                &checker.assignment_id,
+                &[checker.bump_seed],
+            ),
+            &ID,
+        )
+        .ok()?;
+        if checker_address != checker_info.key() || checker.module_id != self.module.module_id {
+            return None;
+        }
+        let check_result = Account::<CheckResult>::try_from(check_result_info).ok()?;
+        let student_key = self.student.key();
+        let check_result_address = Pubkey::create_program_address(
+            &[
+                STUDENT_ADDRESS_SEED,
+                student_key.as_ref(),
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                ASSIGNMENT_ID_SEED,
+                checker.assignment_id.as_ref(),
+                &[check_result.bump_seed],
+            ],
+            &ID,
+        )
+        .ok()?;
+        if check_result_address == check_result_info.key()
+            && check_result.check_passed
+            && !check_result.is_stale(&checker)
+        {
+            Some(checker_address)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct VerifyGradebookEntry<'info> {
+    // student of the gradebook entry
+    pub student: AccountInfo<'info>,
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+    ], bump=check_result.bump_seed)]
+    pub check_result: Account<'info, CheckResult>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16])]
+pub struct CreateAnswerReveal<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(init, payer = authority, space = 8 + AnswerReveal::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        &assignment_id,
+        ANSWER_REVEAL_SEED,
+    ], bump)]
+    pub answer_reveal: Account<'info, AnswerReveal>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealAnswer<'info> {
+    pub course: Account<'info, Course>,
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        &answer_reveal.assignment_id,
+        ANSWER_REVEAL_SEED,
+    ], bump=answer_reveal.bump_seed)]
+    pub answer_reveal: Account<'info, AnswerReveal>,
+}
+
+/// Each batch of any course has unique id
+///
+/// Course authority creates course batch account on each batch of course.
+#[account]
+pub struct CourseBatch {
+    /// Course batch identifier like UUID or ULID
+    pub id: [u8; 16],
+    pub course: Pubkey,
+    /// Course batch organizer
+    pub authority: Pubkey,
+    /// Mint account
+    pub mint: Pubkey,
+    pub bump_seed: u8,
+    pub mint_bump_seed: u8,
+    pub escrow_bump_seed: u8,
+    /// Rewards are held in escrow during this period after successful check
+    pub dispute_window_seconds: u32,
+    /// Tokens in escrow owed to students, they can't be withdrawn by `emergency_withdraw`
+    pub escrowed_rewards: u64,
+    /// Scale of checker rewards in basis points, see `set_reward_multiplier`
+    pub reward_multiplier_bps: u16,
+    /// Share of rewards minted to referrers in basis points, see `set_referral_bps`
+    pub referral_bps: u16,
+    /// Tokens transferred from the reward vault to students and referrers
+    pub paid_rewards: u64,
+    /// Reward bonus per previous pass of the streak in basis points, see `set_streak_bonus`
+    pub streak_bonus_bps: u16,
+    /// Tokens added to the reward of the first solver of a checker
+    pub first_solver_bonus: u64,
+    /// Delay of actions queued by `queue_action`, see `set_action_timelock`
+    pub action_timelock_seconds: u32,
+    /// Conversion of claimed rewards, see `set_reward_conversion`
+    pub reward_conversion: RewardConversion,
+    /// Checks rank students on the leaderboard, see `create_leaderboard`
+    pub has_leaderboard: bool,
+    /// The course authority may close enrollments, see `end_course_batch`
+    pub ended: bool,
+}
+
+impl CourseBatch {
+    pub const LEN: usize = 16
+        + PUBKEY_BYTES * 3
+        + 1
+        + 1
+        + 1
+        + 4
+        + 8
+        + 2
+        + 2
+        + 8
+        + 2
+        + 8
+        + 4
+        + RewardConversion::LEN
+        + 1
+        + 1;
+
+    /// New batch with default settings
+    pub fn new(
+        id: [u8; 16],
+        course: Pubkey,
+        authority: Pubkey,
+        mint: Pubkey,
+        bump_seed: u8,
+        mint_bump_seed: u8,
+        escrow_bump_seed: u8,
+    ) -> Self {
+        Self {
+            id,
+            course,
+            authority,
+            mint,
+            bump_seed,
+            mint_bump_seed,
+            escrow_bump_seed,
+            dispute_window_seconds: 0,
+            escrowed_rewards: 0,
+            reward_multiplier_bps: DEFAULT_REWARD_MULTIPLIER_BPS,
+            referral_bps: 0,
+            paid_rewards: 0,
+            streak_bonus_bps: 0,
+            first_solver_bonus: 0,
+            action_timelock_seconds: MIN_ACTION_TIMELOCK_SECONDS,
+            reward_conversion: RewardConversion::default(),
+            has_leaderboard: false,
+            ended: false,
+        }
+    }
+
+    /// Largest reward of a pass of the checker with `to_mint_on_successful_check`
+    ///
+    /// The first solver with the longest streak gets it, rounding up is assumed.
+    pub fn max_pass_reward(&self, to_mint_on_successful_check: u16) -> u64 {
+        let scale_bps = u128::from(self.reward_multiplier_bps)
+            * u128::from(self.streak_multiplier_bps(MAX_STREAK_BONUS_STEPS + 1));
+        let amount = (u128::from(to_mint_on_successful_check) * scale_bps)
+            .div_ceil(u128::from(BPS_DENOMINATOR) * u128::from(BPS_DENOMINATOR));
+        // the scale is below u16::MAX squared, so the amount fits into u64
+        (amount as u64).saturating_add(self.first_solver_bonus)
+    }
+
+    /// Reject settings that reward a pass above `MAX_REWARD_WHOLE_TOKENS`
+    ///
+    /// Checkers are shared by batches of the course, so the largest
+    /// `to_mint_on_successful_check` is assumed. `allow_large_rewards` skips the check.
+    pub fn check_reward_bound(&self, allow_large_rewards: bool) -> Result<()> {
+        if !allow_large_rewards && self.max_pass_reward(u16::MAX) > MAX_REWARD_WHOLE_TOKENS {
+            return Err(error!(CourseBatchManagerError::SuspiciousRewardAmount));
+        }
+        Ok(())
+    }
+
+    /// Reward scale in basis points for the student with `current_streak` first attempt passes
+    pub fn streak_multiplier_bps(&self, current_streak: u16) -> u64 {
+        let steps = current_streak.saturating_sub(1).min(MAX_STREAK_BONUS_STEPS);
+        BPS_DENOMINATOR + u64::from(self.streak_bonus_bps) * u64::from(steps)
+    }
+
+    /// Reward of the first pass of the checker with its first solver bonus
+    ///
+    /// Returns the total and the bonus part, see `set_first_solver_bonus` for the order of solvers.
+    pub fn pass_reward(
+        &self,
+        assignment_checker: &AssignmentCheckerState,
+        streak_multiplier_bps: u64,
+    ) -> Result<(u64, u64)> {
+        let amount = assignment_checker.reward_amount(
+            u64::from(self.reward_multiplier_bps) * streak_multiplier_bps / BPS_DENOMINATOR,
+        )?;
+        let first_solver = assignment_checker.first_time_passes() == 1;
+        let first_solver_bonus = if first_solver {
+            self.first_solver_bonus
+        } else {
+            0
+        };
+        let amount = amount
+            .checked_add(first_solver_bonus)
+            .ok_or_else(|| error!(CourseBatchManagerError::RewardOverflow))?;
+        Ok((amount, first_solver_bonus))
+    }
+
+    /// Part of the reward `amount` minted to the referrer, rounded down
+    pub fn referral_amount(&self, amount: u64) -> u64 {
+        // referral_bps doesn't exceed BPS_DENOMINATOR, so the share fits into amount
+        (u128::from(amount) * u128::from(self.referral_bps) / u128::from(BPS_DENOMINATOR)) as u64
+    }
+
+    /// Escrowed reward has left the escrow
+    pub fn release_escrowed(&mut self, amount: u64) {
+        self.escrowed_rewards = self.escrowed_rewards.saturating_sub(amount);
+    }
+
+    /// Reward has been transferred from the reward vault
+    pub fn record_payout(&mut self, amount: u64) {
+        self.paid_rewards = self.paid_rewards.saturating_add(amount);
+    }
+}
+
+/// Conversion of claimed rewards into tokens at the price of a `PriceFeed`
+///
+/// Claimed reward keeps its value at `reference_price`: the price twice as high pays half
+/// of the tokens. The paid amount is rounded down and bounded by `min_bps` and `max_bps`
+/// of the claimed reward.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewardConversion {
+    /// Price account read by `claim_reward`, default pubkey disables the conversion
+    pub price_feed: Pubkey,
+    /// Token price at which claimed rewards are paid as is
+    pub reference_price: u64,
+    pub min_bps: u32,
+    pub max_bps: u32,
+}
+
+impl RewardConversion {
+    pub const LEN: usize = PUBKEY_BYTES + 8 + 4 + 4;
+
+    pub fn is_enabled(&self) -> bool {
+        self.price_feed != Pubkey::default()
+    }
+
+    /// Tokens paid for the claimed `amount` at non-zero `price`
+    pub fn convert(&self, amount: u64, price: u64) -> u64 {
+        let amount = u128::from(amount);
+        let value = amount * u128::from(self.reference_price) / u128::from(price);
+        let min = amount * u128::from(self.min_bps) / u128::from(BPS_DENOMINATOR);
+        let max = amount * u128::from(self.max_bps) / u128::from(BPS_DENOMINATOR);
+        // `set_reward_conversion` keeps min bound below max bound
+        value.clamp(min, max).min(u128::from(u64::MAX)) as u64
+    }
+}
+
+/// Price of a token pushed by an off-chain oracle
+///
+/// It's the interface of price accounts of `RewardConversion`. Prices are in any fixed
+/// unit like micro USD per token, `reference_price` of the conversion uses the same unit.
+#[account]
+pub struct PriceFeed {
+    /// Oracle that updates the price
+    pub authority: Pubkey,
+    pub feed_id: [u8; 16],
+    pub price: u64,
+    pub updated_unix_ts: i64,
+    pub bump_seed: u8,
+}
+
+impl PriceFeed {
+    pub const LEN: usize = PUBKEY_BYTES + 16 + 8 + 8 + 1;
+}
+
+/// Return data of `check_assignment`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// Check has ever passed
+    pub passed: bool,
+    /// This check has passed for the first time
+    pub first_time: bool,
+    /// Number of tokens paid to the student or held in batch escrow
+    pub minted: u64,
+    /// Number of tokens paid to the referrer
+    pub referral_minted: u64,
+    /// Number of tokens the student claims later with `claim_reward`
+    pub claimable: u64,
+    /// Why the claimable reward was not paid
+    pub payout_block: Option<PayoutBlock>,
+    /// Hash chain length of the checked shard after the check
+    pub remaining_chain: u16,
+    /// Number of students who still can pass on the checked shard
+    pub remaining_capacity: u16,
+    /// The check has failed max attempts and closed the check result
+    pub result_closed: bool,
+    /// The check has cut the hash chain tail of the shard
+    pub slot_consumed: bool,
+}
+
+#[event]
+pub struct BatchCreated {
+    pub course: Pubkey,
+    pub course_batch: Pubkey,
+    pub batch_id: [u8; 16],
+}
+
+#[event]
+pub struct DisputeWindowSet {
+    pub course_batch: Pubkey,
+    pub dispute_window_seconds: u32,
+}
+
+#[event]
+pub struct RewardMultiplierSet {
+    pub course_batch: Pubkey,
+    pub reward_multiplier_bps: u16,
+}
+
+#[event]
+pub struct ReferralBpsSet {
+    pub course_batch: Pubkey,
+    pub referral_bps: u16,
+}
+
+#[event]
+pub struct StreakBonusSet {
+    pub course_batch: Pubkey,
+    pub streak_bonus_bps: u16,
+}
+
+#[event]
+pub struct FirstSolverBonusSet {
+    pub course_batch: Pubkey,
+    pub first_solver_bonus: u64,
+}
+
+#[event]
+pub struct StudentEnrolled {
+    pub course_batch: Pubkey,
+    pub student: Pubkey,
+}
+
+#[event]
+pub struct EnrollmentClosed {
+    pub course_batch: Pubkey,
+    pub student: Pubkey,
+}
+
+#[event]
+pub struct CourseBatchEnded {
+    pub course_batch: Pubkey,
+}
+
+#[event]
+pub struct RosterIndexAssigned {
+    pub course_batch: Pubkey,
+    pub roster_index: u32,
+    pub student: Pubkey,
+}
+
+#[event]
+pub struct LeaderboardCreated {
+    pub course_batch: Pubkey,
+    pub leaderboard: Pubkey,
+}
+
+#[event]
+pub struct AssignmentCheckerCreated {
+    pub course: Pubkey,
+    pub assignment_checker: Pubkey,
+    pub assignment_id: [u8; 16],
+    pub hash_chain_length: u16,
+}
+
+#[event]
+pub struct AssignmentCheckerCloned {
+    pub source_assignment_checker: Pubkey,
+    pub assignment_checker: Pubkey,
+}
+
+#[event]
+pub struct AssignmentCheckerClosed {
+    pub course: Pubkey,
+    pub assignment_checker: Pubkey,
+}
+
+#[event]
+pub struct RewardRoundingSet {
+    pub assignment_checker: Pubkey,
+    pub reward_rounding: RewardRounding,
+}
+
+#[event]
+pub struct MaxLifetimePassesSet {
+    pub assignment_checker: Pubkey,
+    pub max_lifetime_passes: u16,
+}
+
+#[event]
+pub struct ConsumeOnFailureSet {
+    pub assignment_checker: Pubkey,
+    pub consume_on_failure: bool,
+}
+
+#[event]
+pub struct MaxAttemptsSet {
+    pub assignment_checker: Pubkey,
+    pub max_attempts: u16,
+}
+
+#[event]
+pub struct CommitmentModeSet {
+    pub assignment_checker: Pubkey,
+    pub commitment_mode: CommitmentMode,
+}
+
+#[event]
+pub struct AnswerLenCommitmentSet {
+    pub assignment_checker: Pubkey,
+    pub answer_len_commitment: u16,
+}
+
+#[event]
+pub struct ArchivedSet {
+    pub assignment_checker: Pubkey,
+    pub archived: bool,
+}
+
+#[event]
+pub struct AutoPauseSet {
+    pub assignment_checker: Pubkey,
+    pub auto_pause_after_seconds: u32,
+}
+
+#[event]
+pub struct CheckerConfigUpdated {
+    pub assignment_checker: Pubkey,
+    pub config: CheckerConfigUpdate,
+}
+
+#[event]
+pub struct ChainExtended {
+    pub assignment_checker: Pubkey,
+    pub extension: u16,
+}
+
+#[event]
+pub struct ConvertedToShared {
+    pub assignment_checker: Pubkey,
+}
+
+#[event]
+pub struct ShardAdded {
+    pub assignment_checker: Pubkey,
+    pub shard: u8,
+    pub hash_chain_length: u16,
+}
+
+#[event]
+pub struct AssignmentCriteriaCreated {
+    pub assignment_checker: Pubkey,
+    pub assignment_criteria: Pubkey,
+    pub weights: Vec<u16>,
+}
+
+#[event]
+pub struct StudentCommitmentSet {
+    pub check_result: Pubkey,
+}
+
+#[event]
+pub struct ExtraAttemptsGranted {
+    pub check_result: Pubkey,
+    /// Total extra attempts of the student
+    pub extra_attempts: u16,
+}
+
+// student is not logged to keep anonymous check results anonymous
+#[event]
+pub struct CheckResultCreated {
+    pub check_result: Pubkey,
+    pub assignment_id: [u8; 16],
+}
+
+#[event]
+pub struct CheckResultUpgraded {
+    pub check_result: Pubkey,
+    pub schema_version: u8,
+}
+
+// student is not logged to keep anonymous check results anonymous
+#[event]
+pub struct CheckResultClosed {
+    pub check_result: Pubkey,
+}
+
+#[event]
+pub struct CheckResultMigrated {
+    pub old_check_result: Pubkey,
+    pub new_check_result: Pubkey,
+}
+
+// student is not logged to keep anonymous check results anonymous
+#[event]
+pub struct CheckResultFinalized {
+    pub check_result: Pubkey,
+}
+
+#[event]
+pub struct CheckReserved {
+    pub assignment_checker: Pubkey,
+    pub check_result: Pubkey,
+}
+
+#[event]
+pub struct CheckReservationReleased {
+    pub assignment_checker: Pubkey,
+    pub check_result: Pubkey,
+}
+
+#[event]
+pub struct AssignmentChecked {
+    pub assignment_checker: Pubkey,
+    pub check_result: Pubkey,
+    pub shard: u8,
+    pub passed: bool,
+    pub first_time: bool,
+    pub slot_consumed: bool,
+    /// `None` for courses with compact `event_verbosity`
+    pub details: Option<CheckDetails>,
+}
+
+// `AssignmentChecked` logged next tells the outcome of the proctored check
+#[event]
+pub struct ProctoredCheckSubmitted {
+    pub check_result: Pubkey,
+    pub student: Pubkey,
+    pub proctor: Pubkey,
+}
+
+/// Context of the check logged by verbose `AssignmentChecked` events
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckDetails {
+    pub unix_ts: i64,
+    /// Remaining capacity and rewards of the check
+    pub outcome: CheckOutcome,
+}
+
+// student is not logged to keep the event in line with `AssignmentChecked`
+#[event]
+pub struct CriterionChecked {
+    pub assignment_criteria: Pubkey,
+    pub check_result: Pubkey,
+    pub criterion: u8,
+    pub weighted_score_bps: u16,
+    pub minted: u64,
+}
+
+/// The first processed first time pass of the checker gets the bonus of its batch
+///
+/// Ties within a slot are broken by the processing order of the cluster, see
+/// `set_first_solver_bonus`. The student isn't logged to keep anonymous check results so.
+#[event]
+pub struct FirstSolverRewarded {
+    pub assignment_checker: Pubkey,
+    pub check_result: Pubkey,
+    pub first_solver_bonus: u64,
+}
+
+/// Escrowed reward is transferred to the student by `finalize_reward` or `airdrop_rewards`
+#[event]
+pub struct RewardReleased {
+    pub check_result: Pubkey,
+    pub student: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RewardRevoked {
+    pub check_result: Pubkey,
+    pub student: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ActionTimelockSet {
+    pub course_batch: Pubkey,
+    pub action_timelock_seconds: u32,
+}
+
+#[event]
+pub struct ActionQueued {
+    pub course_batch: Pubkey,
+    pub timelocked_action: Pubkey,
+    pub action: TimelockedActionKind,
+    pub destination: Pubkey,
+    pub execute_after_unix_ts: i64,
+}
+
+#[event]
+pub struct ActionCancelled {
+    pub course_batch: Pubkey,
+    pub timelocked_action: Pubkey,
+    pub action: TimelockedActionKind,
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub course_batch: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub price_feed: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct RewardConversionSet {
+    pub course_batch: Pubkey,
+    pub reward_conversion: RewardConversion,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub check_result: Pubkey,
+    pub student: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(batch_id: [u8; 16])]
-pub struct NewCourseBatch<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(has_one = authority)]
-    pub course: Account<'info, Course>,
-    #[account(init, payer = authority, space = 8 + CourseBatch::LEN, seeds= [
-        COURSE_DATA_SEED,
-        course.key().as_ref(),
-        BATCH_ID_SEED,
-        &batch_id,
-        BATCH_DATA_SEED,
-    ], bump)]
-    pub course_batch: Account<'info, CourseBatch>,
-    #[account(init, payer = authority,
-        mint::authority = course_batch,
-        mint::decimals = 0,
-        mint::freeze_authority = course_batch,
-        seeds= [
-            COURSE_DATA_SEED,
-            course.key().as_ref(),
-            BATCH_ID_SEED,
-            &batch_id,
-            BATCH_MINT_SEED,
-    ], bump)]
-    pub mint: Account<'info, Mint>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct RewardsFrozenSet {
+    pub course_batch: Pubkey,
+    pub frozen: bool,
 }
 
-#[derive(Accounts)]
-pub struct EnrollBatch<'info> {
-    #[account(mut)]
-    pub student: Signer<'info>,
-    // course authority
-    pub authority: AccountInfo<'info>,
-    #[account(has_one = authority, has_one = mint)]
-    pub course_batch: Account<'info, CourseBatch>,
-    #[account(mint::authority = course_batch)]
-    pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = student,
-        associated_token::mint = mint,
-        associated_token::authority = student,
-    )]
-    pub course_batch_token: Account<'info, TokenAccount>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct CheckerModuleSet {
+    pub assignment_checker: Pubkey,
+    pub module: Pubkey,
+    /// The checker was added to the module, otherwise removed from it
+    pub in_module: bool,
 }
 
-#[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16], hash_chain_length: u16)]
-pub struct CreateAssignmentChecker<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(has_one = authority)]
-    pub course: Account<'info, course_manager::Course>,
-    // By default init sets the owner field of the created account to the currently executing program.
-    // We override it to assignment_checker program.
-    // Anchor will find the canonical bump for the assignment checker PDA derived for course_batch_manager program.
-    // Course batch manager can sign for PDA to do mutable cross-program operations.
-    // The PDA is derived from course account and assignment IDs.
-    #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCheckerState::LEN, seeds=[
-        COURSE_DATA_SEED,
-        course.key().as_ref(),
-        ASSIGNMENT_ID_SEED,
-        assignment_id.as_ref(),
-    ], bump, constraint = hash_chain_length >= 2)]
-    pub assignment_checker: Account<'info, AssignmentCheckerState>,
-    pub assignment_checker_program: Program<'info, AssignmentChecker>,
-    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct ModuleCompleted {
+    pub course_batch: Pubkey,
+    pub student: Pubkey,
+    pub module: Pubkey,
 }
 
-impl<'a, 'b, 'c, 'info> CreateAssignmentChecker<'info> {
-    pub fn init_cpi_ctx(
-        &self,
-        signer_seeds: &'a [&'b [&'c [u8]]],
-    ) -> CpiContext<'a, 'b, 'c, 'info, Init<'info>> {
-        let cpi_program = self.assignment_checker_program.to_account_info();
+#[event]
+pub struct CertificateMinted {
+    pub course_batch: Pubkey,
+    pub student: Pubkey,
+    pub certificate_mint: Pubkey,
+}
 
-        let cpi_accounts = Init {
-            authority: self.authority.to_account_info(),
-            course: self.course.to_account_info(),
-            assignment_checker: self.assignment_checker.to_account_info(),
-            result_processor_program: self.course_batch_manager_program.to_account_info(),
-        };
-        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+#[event]
+pub struct AnswerRevealCreated {
+    pub answer_reveal: Pubkey,
+    pub assignment_id: [u8; 16],
+    pub deadline_unix_ts: i64,
+}
+
+/// Answer of the assignment revealed after the deadline, see `create_answer_reveal`
+#[account]
+pub struct AnswerReveal {
+    pub assignment_id: [u8; 16],
+    pub answer_plaintext: [u8; ANSWER_PLAINTEXT_LEN],
+    /// `reveal_answer` returns the answer from this moment
+    pub deadline_unix_ts: i64,
+    pub bump_seed: u8,
+}
+
+impl AnswerReveal {
+    pub const LEN: usize = 16 + ANSWER_PLAINTEXT_LEN + 8 + 1;
+
+    /// The answer if the deadline has passed at `now_unix_ts`
+    pub fn answer(&self, now_unix_ts: i64) -> Option<[u8; ANSWER_PLAINTEXT_LEN]> {
+        if now_unix_ts >= self.deadline_unix_ts {
+            Some(self.answer_plaintext)
+        } else {
+            None
+        }
     }
 }
 
-#[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16])]
-pub struct CreateCheckResult<'info> {
-    #[account(mut)]
-    pub student: Signer<'info>,
-    pub course: Account<'info, course_manager::Course>,
+/// Return data of `get_reward_mint_info`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardMintInfo {
+    pub reward_mint: Pubkey,
+    /// Course batch account is the mint authority of its reward mint
+    pub mint_authority_pda: Pubkey,
+    pub mint_authority_bump: u8,
+}
 
-    #[account(init, payer = student, space = 8 + assignment_checker::CheckResult::LEN,
-        owner = assignment_checker::ID,
-        seeds=[
-        STUDENT_ADDRESS_SEED,
-        student.key().as_ref(),
-        COURSE_DATA_SEED,
-        course.key().as_ref(),
-        ASSIGNMENT_ID_SEED,
-        assignment_id.as_ref(),
-    ], bump)]
-    pub check_result: Account<'info, assignment_checker::CheckResult>,
-    pub assignment_checker_program: Program<'info, AssignmentChecker>,
-    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
-    pub system_program: Program<'info, System>,
+/// Return data of `describe_checker`
+///
+/// Hash chain tails of the checker are not described.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckerDescription {
+    pub course: Pubkey,
+    pub course_batch: Pubkey,
+    pub assignment_id: [u8; 16],
+    /// Remaining hash chain length of the main chain
+    pub hash_chain_length: u16,
+    /// Number of students who still can pass on the main chain
+    pub remaining_capacity: u16,
+    pub added_shards: u8,
+    pub successful_checks: u16,
+    pub max_lifetime_passes: u16,
+    pub to_mint_on_successful_check: u16,
+    /// Reward of the batch before streak and first solver bonuses
+    pub reward_amount: u64,
+    pub first_solver_bonus: u64,
+    pub reward_rounding: RewardRounding,
+    /// Blake3 applications of each hash chain step
+    pub hash_iterations_per_step: u8,
+    /// Time when new passes get rejected, see `set_auto_pause`
+    pub pause_unix_ts: Option<i64>,
+    pub per_student_salt: bool,
+    pub shared_answer: bool,
+    pub consume_on_failure: bool,
+    /// Failed checks that close the check result, see `set_max_attempts`
+    pub max_attempts: u16,
+    pub commitment_mode: CommitmentMode,
+    /// Length of plaintext answers, zero when it's not committed
+    pub answer_len_commitment: u16,
+    /// Frontends sort assignments by it, see `AttemptsHistogram::difficulty_score`
+    pub difficulty_score: u16,
+    /// Check results of other generations belong to closed checkers of the assignment ID
+    pub generation: u16,
+    /// Checks counted by the pass rate, repeats of passed checks are left out
+    pub attempts_total: u32,
+    pub passes_total: u16,
+    pub pass_rate_bps: u16,
+    pub archived: bool,
 }
 
-impl<'a, 'b, 'c, 'info> CreateCheckResult<'info> {
-    pub fn init_check_result_cpi_ctx(
-        &self,
-        signer_seeds: &'a [&'b [&'c [u8]]],
-    ) -> CpiContext<'a, 'b, 'c, 'info, InitCheckResult<'info>> {
-        let cpi_program = self.assignment_checker_program.to_account_info();
+/// Progress of the student in the course batch
+///
+/// The account is created on enrollment.
+#[account]
+pub struct StudentProgress {
+    pub student: Pubkey,
+    pub course_batch: Pubkey,
+    /// Number of assignments passed for the first time
+    pub assignments_passed: u16,
+    /// Completion certificate is minted once
+    pub certificate_minted: bool,
+    pub bump_seed: u8,
+    /// Number of assignments passed on the first attempt in a row
+    pub current_streak: u16,
+    /// Number of modules completed by `complete_module`
+    pub modules_completed: u16,
+    /// Escrowed and claimable reward tokens of passes in the batch
+    pub pending_rewards: u64,
+}
 
-        let cpi_accounts = InitCheckResult {
-            student: self.student.to_account_info(),
-            course: self.course.to_account_info(),
-            check_result: self.check_result.to_account_info(),
-            result_processor_program: self.course_batch_manager_program.to_account_info(),
+impl StudentProgress {
+    pub const LEN: usize = PUBKEY_BYTES * 2 + 2 + 1 + 1 + 2 + 2 + 8;
+    /// Offset of `course_batch` in account data for filters of program account scans
+    pub const COURSE_BATCH_OFFSET: usize = 8 + PUBKEY_BYTES;
+
+    /// Count the first time pass that has taken `attempts` checks
+    ///
+    /// Pass after failed attempts breaks the streak.
+    pub fn record_pass(&mut self, attempts: u16) {
+        self.assignments_passed = self.assignments_passed.saturating_add(1);
+        self.current_streak = if attempts <= 1 {
+            self.current_streak.saturating_add(1)
+        } else {
+            0
         };
-        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    /// Count the reward that is escrowed or claimable by the student
+    pub fn hold_reward(&mut self, amount: u64) {
+        self.pending_rewards = self.pending_rewards.saturating_add(amount);
+    }
+
+    /// Discount the held reward that is paid out or revoked
+    pub fn release_reward(&mut self, amount: u64) {
+        self.pending_rewards = self.pending_rewards.saturating_sub(amount);
     }
 }
-#[derive(Accounts)]
-pub struct CheckAssignment<'info> {
-    #[account(mut)]
-    pub student: Signer<'info>,
-    pub course: Account<'info, Course>,
-    #[account(has_one = mint, constraint = course.authority == course_batch.authority,
-        seeds=[
-        COURSE_DATA_SEED,
-        course.key().as_ref(),
-        BATCH_ID_SEED,
-        &course_batch.id,
-        BATCH_DATA_SEED,
-    ], bump=course_batch.bump_seed)]
-    pub course_batch: Account<'info, CourseBatch>,
-    // CHECK: pda check and assignment_id equality will be made by assignment_checker
-    #[account(mut)]
-    pub assignment_checker: Account<'info, AssignmentCheckerState>,
 
-    // CHECK: pda check and assignment_id equality will be made by assignment_checker
-    #[account(mut)]
-    pub check_result: Account<'info, CheckResult>,
-    #[account(
-        mut,
-        mint::authority = course_batch,
-        mint::decimals = 0,
-        seeds= [
-            COURSE_DATA_SEED,
-            course.key().as_ref(),
-            BATCH_ID_SEED,
-            &course_batch.id,
-            BATCH_MINT_SEED,
-    ], bump=course_batch.mint_bump_seed)]
-    pub mint: Account<'info, Mint>,
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = student,
-    )]
-    pub course_batch_token: Account<'info, TokenAccount>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub assignment_checker_program: Program<'info, AssignmentChecker>,
-    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+/// Roster number of the student in the course batch
+///
+/// Course authority assigns roster indices to enrolled students.
+#[account]
+pub struct RosterEntry {
+    pub course_batch: Pubkey,
+    pub roster_index: u32,
+    pub student: Pubkey,
+    pub bump_seed: u8,
 }
 
-impl<'a, 'b, 'c, 'info> CheckAssignment<'info> {
-    pub fn check_cpi_ctx(
-        &self,
-        signer_seeds: &'a [&'b [&'c [u8]]],
-    ) -> CpiContext<'a, 'b, 'c, 'info, Check<'info>> {
-        let cpi_program = self.assignment_checker_program.to_account_info();
+impl RosterEntry {
+    pub const LEN: usize = PUBKEY_BYTES + 4 + PUBKEY_BYTES + 1;
+}
 
-        let course = self.course.to_account_info();
-        let check_result = self.check_result.to_account_info();
-        let cpi_accounts = Check {
-            student: self.student.to_account_info(),
-            course,
-            assignment_checker: self.assignment_checker.to_account_info(),
-            check_result,
-            result_processor_program: self.course_batch_manager_program.to_account_info(),
-        };
-        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+/// Marker of the check result closed by its last failed attempt
+///
+/// The check result address is in the seeds. `create_check_result` recreates
+/// the check result after the checker is extended or gets a new generation.
+#[account]
+pub struct ExhaustedCheckResult {
+    pub assignment_checker: Pubkey,
+    /// Generation of the checker at the last failed attempt
+    pub generation: u16,
+    /// `hash_chain_extensions` of the checker at the last failed attempt
+    pub hash_chain_extensions: u16,
+    /// Attempts granted to the student, the recreated check result keeps them
+    pub extra_attempts: u16,
+    pub bump_seed: u8,
+}
+
+impl ExhaustedCheckResult {
+    pub const LEN: usize = PUBKEY_BYTES + 2 + 2 + 2 + 1;
+
+    /// The checker has new slots or generation since the last failed attempt
+    pub fn is_stale(&self, assignment_checker: &AssignmentCheckerState) -> bool {
+        self.generation != assignment_checker.generation
+            || self.hash_chain_extensions != assignment_checker.hash_chain_extensions
     }
+}
 
-    pub fn mint_to_cpi_ctx(
-        &self,
-        signer_seeds: &'a [&'b [&'c [u8]]],
-    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
-        let cpi_program = self.token_program.to_account_info();
+/// Top students of the course batch, see `create_leaderboard`
+///
+/// Students are ranked by `assignments_passed`, ties by the earliest time of reaching
+/// the number, unlike `cohort_leaderboard` of fetched progress records. Frontends read
+/// the top `Leaderboard::CAPACITY` students with one account fetch.
+#[account]
+#[derive(Default)]
+pub struct Leaderboard {
+    pub course_batch: Pubkey,
+    pub bump_seed: u8,
+    entries: [LeaderboardEntry; Leaderboard::CAPACITY],
+    /// Number of ranked students
+    len: u8,
+}
 
-        let cpi_accounts = MintTo {
-            mint: self.mint.to_account_info(),
-            to: self.course_batch_token.to_account_info(),
-            authority: self.course_batch.to_account_info(),
+impl Leaderboard {
+    pub const CAPACITY: usize = 10;
+    pub const LEN: usize = PUBKEY_BYTES + 1 + LeaderboardEntry::LEN * Self::CAPACITY + 1;
+
+    /// Ranked students from the first place
+    pub fn entries(&self) -> &[LeaderboardEntry] {
+        &self.entries[..usize::from(self.len)]
+    }
+
+    /// Rank the student after a first time pass
+    ///
+    /// The previous entry of the student is replaced. The last student of the full
+    /// leaderboard is evicted by students who outrank it, others are not added.
+    pub fn record(&mut self, progress: &StudentProgress, reached_unix_ts: i64) {
+        let entry = LeaderboardEntry {
+            student: progress.student,
+            assignments_passed: progress.assignments_passed,
+            reached_unix_ts,
         };
-        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+        let mut len = usize::from(self.len);
+        if let Some(position) = self
+            .entries()
+            .iter()
+            .position(|ranked| ranked.student == entry.student)
+        {
+            self.entries.copy_within(position + 1..len, position);
+            len -= 1;
+        }
+        let rank = self.entries[..len]
+            .iter()
+            .position(|ranked| entry.outranks(ranked))
+            .unwrap_or(len);
+        if rank == Self::CAPACITY {
+            return;
+        }
+        let last = len.min(Self::CAPACITY - 1);
+        self.entries.copy_within(rank..last, rank + 1);
+        self.entries[rank] = entry;
+        self.len = (last + 1) as u8;
     }
 }
-/// Each batch of any course has unique id
+
+/// Place of the student on the `Leaderboard`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub student: Pubkey,
+    pub assignments_passed: u16,
+    /// When the student has passed the last of `assignments_passed`
+    pub reached_unix_ts: i64,
+}
+
+impl LeaderboardEntry {
+    pub const LEN: usize = PUBKEY_BYTES + 2 + 8;
+
+    /// More passed assignments or the same number passed earlier
+    pub fn outranks(&self, other: &LeaderboardEntry) -> bool {
+        self.assignments_passed > other.assignments_passed
+            || (self.assignments_passed == other.assignments_passed
+                && self.reached_unix_ts < other.reached_unix_ts)
+    }
+}
+
+/// Sensitive action of the course authority that runs after the timelock
 ///
-/// Course authority creates course batch account on each batch of course.
+/// Courses have no authority transfer and salts only change with a new hash chain of
+/// `extend_chain`, so withdrawal from the escrow is the only kind. Kinds are PDA seeds of
+/// `TimelockedAction`, new ones are appended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimelockedActionKind {
+    /// `emergency_withdraw` to `destination`
+    EmergencyWithdraw,
+}
+
+/// Completion of the module by the student in the course batch
 #[account]
-pub struct CourseBatch {
-    /// Course batch identifier like UUID or ULID
-    pub id: [u8; 16],
-    pub course: Pubkey,
-    /// Course batch organizer
-    pub authority: Pubkey,
-    /// Mint account
-    pub mint: Pubkey,
+pub struct ModuleCompletion {
+    pub student_progress: Pubkey,
+    pub module: Pubkey,
     pub bump_seed: u8,
-    pub mint_bump_seed: u8,
 }
 
-impl CourseBatch {
-    pub const LEN: usize = 16 + PUBKEY_BYTES * 3 + 1 + 1;
+impl ModuleCompletion {
+    pub const LEN: usize = PUBKEY_BYTES + PUBKEY_BYTES + 1;
+}
+
+/// Action queued by `queue_action`
+///
+/// The account is closed on execution or cancellation.
+#[account]
+pub struct TimelockedAction {
+    pub course_batch: Pubkey,
+    pub action: TimelockedActionKind,
+    /// Token account receiving withdrawn tokens
+    pub destination: Pubkey,
+    pub queued_unix_ts: i64,
+    /// The action is rejected before this time
+    pub execute_after_unix_ts: i64,
+    pub bump_seed: u8,
+}
+
+impl TimelockedAction {
+    pub const LEN: usize = PUBKEY_BYTES + 1 + PUBKEY_BYTES + 8 + 8 + 1;
+
+    /// The timelock of the action has passed
+    pub fn is_executable(&self, now_unix_ts: i64) -> bool {
+        now_unix_ts >= self.execute_after_unix_ts
+    }
+}
+
+#[error_code]
+pub enum CourseBatchManagerError {
+    #[msg("There are no escrowed tokens for this check result")]
+    NothingEscrowed,
+    #[msg("Escrowed tokens cannot be released during dispute window")]
+    DisputeWindowOpen,
+    #[msg("Escrowed tokens cannot be revoked after dispute window")]
+    DisputeWindowClosed,
+    #[msg("The student hasn't passed all assignments of the course")]
+    AssignmentsNotPassed,
+    #[msg("Completion certificate is already minted")]
+    CertificateAlreadyMinted,
+    #[msg("Rewards of anonymous check results cannot be escrowed")]
+    AnonymousEscrow,
+    #[msg("Escrowed rewards of the batch overflow")]
+    EscrowedRewardsOverflow,
+    #[msg("Escrow has no tokens that are not owed to students")]
+    NothingToWithdraw,
+    #[msg("Referral share exceeds the reward")]
+    InvalidReferralBps,
+    #[msg("Referrer is not enrolled in the batch or its token account is invalid")]
+    InvalidReferrer,
+    #[msg("The student cannot refer themselves")]
+    SelfReferral,
+    #[msg("Rewards with referrer cannot be escrowed")]
+    ReferralEscrow,
+    #[msg("Rewards of anonymous check results cannot be shared with referrer")]
+    AnonymousReferral,
+    #[msg("Max streak multiplier exceeds u16 basis points")]
+    InvalidStreakBonus,
+    #[msg("The student cannot pay rent of the course batch token account")]
+    CannotCreateTokenAccount,
+    #[msg("Remaining accounts are not the checkers to create")]
+    CheckerAccountsMismatch,
+    #[msg("The student hasn't passed the assignment")]
+    NotPassed,
+    #[msg("The reward with first solver bonus overflows")]
+    RewardOverflow,
+    #[msg("The check result has no claimable reward")]
+    NothingClaimable,
+    #[msg("Partial credit rewards cannot be escrowed")]
+    CriteriaEscrow,
+    #[msg("The instruction is built with test-only feature only")]
+    TestOnlyInstruction,
+    #[msg("The hash chain has no tail parent")]
+    NoExpectedParent,
+    #[msg("Action timelock can't decrease")]
+    ActionTimelockDecreased,
+    #[msg("The queued action is still timelocked")]
+    ActionTimelocked,
+    #[msg("The queued action is of another kind")]
+    WrongTimelockedAction,
+    #[msg("The checker needs a hash chain tail parent built by the client")]
+    PlaintextCheckUnavailable,
+    #[msg("The assignment checker belongs to a module")]
+    CheckerInModule,
+    #[msg("The assignment checker doesn't belong to the module")]
+    WrongModule,
+    #[msg("The student hasn't passed all assignments of the module")]
+    ModuleNotPassed,
+    #[msg("The token account is of another mint than the batch rewards")]
+    WrongRewardMint,
+    #[msg("The price is zero")]
+    InvalidPrice,
+    #[msg("The reference price is zero or min bound exceeds max bound")]
+    InvalidRewardConversion,
+    #[msg("The price feed of the reward conversion is missing")]
+    PriceFeedMissing,
+    #[msg("The price of the price feed is outdated")]
+    StalePrice,
+    #[msg("Students of the course are enrolled by the course authority")]
+    SelfEnrollNotAllowed,
+    #[msg("Only the student or the course authority of the ended batch can close the enrollment")]
+    NotEnrollmentCloser,
+    #[msg("The enrollment has escrowed or claimable rewards")]
+    EnrollmentHasProgress,
+    #[msg("The signer is not an authorized proctor of the course")]
+    ProctorNotAuthorized,
+    #[msg("The answer has another length than committed for the assignment")]
+    WrongAnswerLength,
+    #[msg("The leaderboard of the course batch is missing or wrong")]
+    InvalidLeaderboard,
+    #[msg("The check result belongs to another student")]
+    ResultOwnershipMismatch,
+    #[msg("Action timelock is shorter than the minimum")]
+    ActionTimelockTooShort,
+    #[msg("The check result has failed max attempts until the checker is extended")]
+    CheckResultExhausted,
+    #[msg("The reward of a pass is above the human scale bound")]
+    SuspiciousRewardAmount,
 }
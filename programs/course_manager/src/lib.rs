@@ -1,22 +1,483 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::pubkey::PUBKEY_BYTES;
+use anchor_lang::solana_program::{log::sol_log_data, pubkey::PUBKEY_BYTES};
+use anchor_lang::system_program::{
+    allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+};
+use anchor_lang::Discriminator;
+use anchor_spl::token::Mint;
 
 declare_id!("Po2hjSPEQmN9e1YLiZwwL3tCkqMCo2wYyLqAkF7ZmQn");
 
 pub const COURSE_AUTHORITY_SEED: &[u8; 16] = b"course_authority";
 pub const COURSE_ID_SEED: &[u8; 9] = b"course_id";
+pub const STUDENT_ADDRESS_SEED: &[u8; 15] = b"student_address";
+pub const COURSE_DATA_SEED: &[u8; 11] = b"course_data";
+pub const BATCH_ID_SEED: &[u8; 15] = b"course_batch_id";
+pub const STUDENT_PROGRESS_SEED: &[u8; 16] = b"student_progress";
+pub const MODULE_ID_SEED: &[u8; 9] = b"module_id";
+/// The program that enrolls students into course batches, see `register_enrollment`
+///
+/// Course batch manager depends on this program, so its ID
+/// `Po3YrSjzp5HM7VRFYszFM23LVJ58HHC9qoionaUgvRy` is repeated here.
+pub const COURSE_BATCH_MANAGER_ID: Pubkey = Pubkey::new_from_array([
+    5, 214, 215, 1, 41, 54, 230, 115, 153, 67, 189, 136, 246, 187, 201, 55, 37, 168, 236, 76, 147,
+    174, 37, 128, 234, 92, 119, 171, 79, 75, 69, 196,
+]);
+/// Version of the course programs stamped on events, bumped by upgrades that change events
+pub const PROGRAM_VERSION: u16 = 1;
+/// Layout version of new courses, see `Course::from_versioned_data`
+///
+/// Version 1 adds `schema_version` and `reserved` bytes. Courses created before it
+/// have version 0 and any layout between `Course::LEN_V0` and `Course::LEN`.
+pub const COURSE_SCHEMA_VERSION: u8 = 1;
+/// Zeroed bytes at the end of new courses that later fields take
+///
+/// Fields added after version 1 come out of it, so courses don't need `upgrade_course`.
+pub const COURSE_RESERVED_LEN: usize = 64;
 
 #[program]
 pub mod course_manager {
     use super::*;
 
-    pub fn create_new_course(ctx: Context<NewCourse>, course_id: [u8; 16]) -> Result<()> {
+    /// Create course account of the course authority
+    ///
+    /// The course address is derived from the authority and the course ID, so IDs are
+    /// unique per authority. Reused IDs fail with `CourseAlreadyExists` instead of
+    /// the system program error, lamports sent to the address beforehand don't block it.
+    ///
+    /// Courses with `reward_mint` take the mint as the first remaining account.
+    pub fn create_new_course<'info>(
+        ctx: Context<'_, '_, '_, 'info, NewCourse<'info>>,
+        course_id: [u8; 16],
+        reward_mint: Pubkey,
+    ) -> Result<()> {
+        if reward_mint != Pubkey::default() {
+            let reward_mint_info = ctx
+                .remaining_accounts
+                .first()
+                .filter(|info| info.key() == reward_mint)
+                .ok_or_else(|| error!(CourseManagerError::InvalidRewardMint))?;
+            Account::<Mint>::try_from(reward_mint_info)
+                .map_err(|_| error!(CourseManagerError::InvalidRewardMint))?;
+        }
+        let create = ctx.accounts;
+        let bump_seed = *ctx.bumps.get("course").expect("course pda is present");
+        let course_authority = create.course_authority.key();
+        let bump = [bump_seed];
+        let seeds = [
+            COURSE_AUTHORITY_SEED.as_slice(),
+            course_authority.as_ref(),
+            COURSE_ID_SEED.as_slice(),
+            course_id.as_ref(),
+            bump.as_slice(),
+        ];
+        let signer_seeds = [seeds.as_slice()];
+        create_pda_account(
+            create.course_authority.to_account_info(),
+            create.course.clone(),
+            create.system_program.to_account_info(),
+            8 + Course::LEN,
+            &ID,
+            signer_seeds.as_slice(),
+        )?;
+        let course = Course {
+            id: course_id,
+            authority: course_authority,
+            bump_seed,
+            assignment_count: 0,
+            frozen: false,
+            forbid_authority_self_check: false,
+            enrolled_count: 0,
+            checker_generation: 0,
+            event_verbosity: EventVerbosity::default(),
+            created_at_unix_ts: Clock::get()?.unix_timestamp,
+            self_enroll_allowed: true,
+            master_secret_hash: [0; 32],
+            proctor: Pubkey::default(),
+            proctored_checks_allowed: false,
+            emit_events: true,
+            reward_mint,
+            schema_version: COURSE_SCHEMA_VERSION,
+            reserved: [0; COURSE_RESERVED_LEN],
+        };
+        course.try_serialize(&mut &mut create.course.try_borrow_mut_data()?[..])?;
+        emit_event(CourseCreated {
+            course: create.course.key(),
+            course_id,
+        })?;
+        Ok(())
+    }
+
+    /// Upgrade course of an older layout to `COURSE_SCHEMA_VERSION`
+    ///
+    /// The account grows to the current layout, the course authority pays the rent difference.
+    /// Counters and settings of the course are kept. Current courses are left as is.
+    pub fn upgrade_course(ctx: Context<UpgradeCourse>) -> Result<()> {
+        let course_info = ctx.accounts.course.to_account_info();
+        let course = Course::from_versioned_data(&course_info.try_borrow_data()?)?;
+        if course.authority != ctx.accounts.authority.key() {
+            return Err(error!(ErrorCode::ConstraintHasOne));
+        }
+        if course.schema_version == COURSE_SCHEMA_VERSION
+            && course_info.data_len() == 8 + Course::LEN
+        {
+            msg!("course {} is up to date", course_info.key());
+            return Ok(());
+        }
+
+        let rent = Rent::get()?.minimum_balance(8 + Course::LEN);
+        let rent_difference = rent.saturating_sub(course_info.lamports());
+        if rent_difference > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: course_info.clone(),
+                    },
+                ),
+                rent_difference,
+            )?;
+        }
+        course_info.realloc(8 + Course::LEN, false)?;
+        let upgraded = Course {
+            schema_version: COURSE_SCHEMA_VERSION,
+            ..course
+        };
+        upgraded.try_serialize(&mut &mut course_info.try_borrow_mut_data()?[..])?;
+        upgraded.emit(CourseUpgraded {
+            course: course_info.key(),
+            schema_version: COURSE_SCHEMA_VERSION,
+        })?;
+        Ok(())
+    }
+
+    /// Count new assignment of the course
+    ///
+    /// Called by course programs when assignment checker is created. Each registration
+    /// starts a new `checker_generation`, closing the checker doesn't return it.
+    pub fn register_assignment(ctx: Context<UpdateAssignmentCount>) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        course_account.assignment_count = course_account
+            .assignment_count
+            .checked_add(1)
+            .ok_or_else(|| error!(CourseManagerError::AssignmentCountOverflow))?;
+        course_account.checker_generation = course_account
+            .checker_generation
+            .checked_add(1)
+            .ok_or_else(|| error!(CourseManagerError::CheckerGenerationOverflow))?;
+        course_account.emit(AssignmentCountChanged {
+            course: course_account.key(),
+            assignment_count: course_account.assignment_count,
+        })?;
+        Ok(())
+    }
+
+    /// Stop counting closed assignment of the course
+    ///
+    /// Called by course programs when assignment checker is closed
+    pub fn unregister_assignment(ctx: Context<UpdateAssignmentCount>) -> Result<()> {
         let course_account = &mut ctx.accounts.course;
-        course_account.id = course_id;
-        course_account.authority = ctx.accounts.course_authority.key();
-        course_account.bump_seed = *ctx.bumps.get("course").expect("course pda is present");
+        course_account.assignment_count = course_account
+            .assignment_count
+            .checked_sub(1)
+            .ok_or_else(|| error!(CourseManagerError::NoAssignments))?;
+        course_account.emit(AssignmentCountChanged {
+            course: course_account.key(),
+            assignment_count: course_account.assignment_count,
+        })?;
+        Ok(())
+    }
+
+    /// Count new enrollment of the student into a batch of the course
+    ///
+    /// Called by course batch manager when the student enrolls. The progress record of
+    /// the student is created once per batch, so each enrollment is counted once.
+    pub fn register_enrollment(
+        ctx: Context<RegisterEnrollment>,
+        _batch_id: [u8; 16],
+    ) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        course_account.enrolled_count = course_account
+            .enrolled_count
+            .checked_add(1)
+            .ok_or_else(|| error!(CourseManagerError::EnrolledCountOverflow))?;
+        course_account.emit(EnrolledCountChanged {
+            course: course_account.key(),
+            enrolled_count: course_account.enrolled_count,
+        })?;
+        Ok(())
+    }
+
+    /// Stop counting enrollment of the student who has dropped the batch
+    ///
+    /// Called by course batch manager when the progress record of the student is closed.
+    pub fn unregister_enrollment(
+        ctx: Context<UnregisterEnrollment>,
+        _batch_id: [u8; 16],
+    ) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        course_account.enrolled_count = course_account
+            .enrolled_count
+            .checked_sub(1)
+            .ok_or_else(|| error!(CourseManagerError::NoEnrollments))?;
+        course_account.emit(EnrolledCountChanged {
+            course: course_account.key(),
+            enrolled_count: course_account.enrolled_count,
+        })?;
+        Ok(())
+    }
+
+    /// Lock course structure for the term
+    ///
+    /// Assignments of frozen course cannot be created, closed or reconfigured.
+    /// Students still can check them. The course cannot be unfrozen.
+    pub fn freeze_course(ctx: Context<FreezeCourse>) -> Result<()> {
+        ctx.accounts.course.frozen = true;
+        msg!("course {} is frozen", ctx.accounts.course.key());
+        ctx.accounts.course.emit(CourseFrozen {
+            course: ctx.accounts.course.key(),
+        })?;
+        Ok(())
+    }
+
+    /// Reject assignment checks made by the course authority
+    ///
+    /// Prevents minting rewards of the course to its authority.
+    pub fn set_forbid_authority_self_check(
+        ctx: Context<UpdateCourse>,
+        forbid_authority_self_check: bool,
+    ) -> Result<()> {
+        ctx.accounts.course.forbid_authority_self_check = forbid_authority_self_check;
+        ctx.accounts.course.emit(AuthoritySelfCheckForbidden {
+            course: ctx.accounts.course.key(),
+            forbid_authority_self_check,
+        })?;
+        Ok(())
+    }
+
+    /// Make the course open or invite-only
+    ///
+    /// Students of invite-only courses are enrolled into batches with a signature of
+    /// the course authority. New courses are open.
+    pub fn set_self_enroll_allowed(
+        ctx: Context<UpdateCourse>,
+        self_enroll_allowed: bool,
+    ) -> Result<()> {
+        ctx.accounts.course.self_enroll_allowed = self_enroll_allowed;
+        ctx.accounts.course.emit(SelfEnrollAllowedSet {
+            course: ctx.accounts.course.key(),
+            self_enroll_allowed,
+        })?;
+        Ok(())
+    }
+
+    /// Derive salts of new assignment checkers from a master secret of the course
+    ///
+    /// Checkers created with zero salt take `hash_chain::derive_salt` of the hash.
+    /// The hash is public like the salts, it saves managing a salt per assignment
+    /// rather than hiding them. Zero hash turns the derivation off.
+    pub fn set_master_secret_hash(
+        ctx: Context<UpdateCourse>,
+        master_secret_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.course.master_secret_hash = master_secret_hash;
+        ctx.accounts.course.emit(MasterSecretHashSet {
+            course: ctx.accounts.course.key(),
+            master_secret_hash,
+        })?;
+        Ok(())
+    }
+
+    /// Authorize a proctor to submit checks on behalf of students of the course
+    ///
+    /// Proctored checks are rejected unless they are allowed, e.g. the proctor
+    /// of an exam hall keeps the key while students have no wallets at hand.
+    pub fn set_proctor(
+        ctx: Context<UpdateCourse>,
+        proctor: Pubkey,
+        proctored_checks_allowed: bool,
+    ) -> Result<()> {
+        let course = &mut ctx.accounts.course;
+        course.proctor = proctor;
+        course.proctored_checks_allowed = proctored_checks_allowed;
+        course.emit(ProctorSet {
+            course: course.key(),
+            proctor,
+            proctored_checks_allowed,
+        })?;
+        Ok(())
+    }
+
+    /// Create a module that groups assignments of the course, e.g. a week of the term
+    ///
+    /// Course programs add assignment checkers to the module, see `register_module_assignment`.
+    pub fn create_module(ctx: Context<NewModule>, module_id: [u8; 16]) -> Result<()> {
+        let module = &mut ctx.accounts.module;
+        module.course = ctx.accounts.course.key();
+        module.module_id = module_id;
+        module.bump_seed = *ctx.bumps.get("module").expect("module pda is present");
+        ctx.accounts.course.emit(ModuleCreated {
+            course: module.course,
+            module: module.key(),
+            module_id,
+        })?;
+        Ok(())
+    }
+
+    /// Count assignment added to the module
+    ///
+    /// Called by course programs when assignment checker joins the module
+    pub fn register_module_assignment(ctx: Context<UpdateModuleAssignmentCount>) -> Result<()> {
+        let module = &mut ctx.accounts.module;
+        module.assignment_count = module
+            .assignment_count
+            .checked_add(1)
+            .ok_or_else(|| error!(CourseManagerError::ModuleAssignmentCountOverflow))?;
+        ctx.accounts.course.emit(ModuleAssignmentCountChanged {
+            module: module.key(),
+            assignment_count: module.assignment_count,
+        })?;
         Ok(())
     }
+
+    /// Stop counting assignment removed from the module
+    ///
+    /// Called by course programs when assignment checker leaves the module
+    pub fn unregister_module_assignment(ctx: Context<UpdateModuleAssignmentCount>) -> Result<()> {
+        let module = &mut ctx.accounts.module;
+        module.assignment_count = module
+            .assignment_count
+            .checked_sub(1)
+            .ok_or_else(|| error!(CourseManagerError::NoModuleAssignments))?;
+        ctx.accounts.course.emit(ModuleAssignmentCountChanged {
+            module: module.key(),
+            assignment_count: module.assignment_count,
+        })?;
+        Ok(())
+    }
+
+    /// Choose between compact and verbose events of high-volume course instructions
+    ///
+    /// Compact events leave out the context that consumers can read from accounts,
+    /// so busy courses spend less on logs.
+    pub fn set_event_verbosity(
+        ctx: Context<UpdateCourse>,
+        event_verbosity: EventVerbosity,
+    ) -> Result<()> {
+        ctx.accounts.course.event_verbosity = event_verbosity;
+        ctx.accounts.course.emit(EventVerbositySet {
+            course: ctx.accounts.course.key(),
+            event_verbosity,
+        })?;
+        Ok(())
+    }
+
+    /// Turn events of the course programs on or off
+    ///
+    /// Courses that index their accounts directly save the log compute of each
+    /// instruction, state changes are the same. The switch itself is always logged.
+    /// New courses emit events.
+    pub fn set_emit_events(ctx: Context<UpdateCourse>, emit_events: bool) -> Result<()> {
+        ctx.accounts.course.emit_events = emit_events;
+        emit_event(EmitEventsSet {
+            course: ctx.accounts.course.key(),
+            emit_events,
+        })?;
+        Ok(())
+    }
+}
+
+/// Common header of events emitted by the course programs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventHeader {
+    pub program_version: u16,
+    /// Slot of the emitting transaction
+    pub slot: u64,
+}
+
+/// Log the event with common header
+///
+/// Log data is the event discriminator followed by serialized `EventHeader` and the event,
+/// see `decode_event`. Assignment checker instructions are invoked by result processor
+/// programs, which emit events of their own. Instructions with the course account log
+/// through `Course::emit`, which follows `emit_events` of the course.
+pub fn emit_event<T: AnchorSerialize + Discriminator>(event: T) -> Result<()> {
+    let header = EventHeader {
+        program_version: PROGRAM_VERSION,
+        slot: Clock::get()?.slot,
+    };
+    let mut data = T::discriminator().to_vec();
+    header.serialize(&mut data)?;
+    event.serialize(&mut data)?;
+    sol_log_data(&[&data]);
+    Ok(())
+}
+
+/// Decode log data of `emit_event`, `None` for other events
+pub fn decode_event<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<(EventHeader, T)> {
+    let discriminator = T::discriminator();
+    if !data.starts_with(&discriminator) {
+        return None;
+    }
+    let mut data = &data[discriminator.len()..];
+    let header = EventHeader::deserialize(&mut data).ok()?;
+    let event = T::deserialize(&mut data).ok()?;
+    Some((header, event))
+}
+
+/// Create the PDA of `signer_seeds` with `space` bytes owned by `owner` like `init` does
+///
+/// `create_account` fails for addresses that hold lamports, so anybody could block the PDA
+/// by sending lamports to it. Such accounts get the rest of the rent from the payer and are
+/// allocated and assigned instead.
+pub fn create_pda_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+    if lamports == 0 {
+        let cpi_accounts = CreateAccount {
+            from: payer,
+            to: account,
+        };
+        return create_account(
+            CpiContext::new_with_signer(system_program, cpi_accounts, signer_seeds),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+    let missing_rent = rent.saturating_sub(lamports);
+    if missing_rent > 0 {
+        let cpi_accounts = Transfer {
+            from: payer,
+            to: account.clone(),
+        };
+        transfer(
+            CpiContext::new(system_program.clone(), cpi_accounts),
+            missing_rent,
+        )?;
+    }
+    let cpi_accounts = Allocate {
+        account_to_allocate: account.clone(),
+    };
+    allocate(
+        CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+        space as u64,
+    )?;
+    let cpi_accounts = Assign {
+        account_to_assign: account,
+    };
+    assign(
+        CpiContext::new_with_signer(system_program, cpi_accounts, signer_seeds),
+        owner,
+    )
 }
 
 pub fn course_canonical_pda(course_authority: Pubkey, course_id: &[u8; 16]) -> Pubkey {
@@ -37,20 +498,137 @@ pub fn course_canonical_pda(course_authority: Pubkey, course_id: &[u8; 16]) -> P
 pub struct NewCourse<'info> {
     #[account(mut)]
     pub course_authority: Signer<'info>,
-    // By default init sets the owner field of the created account to the currently executing program.
-    // Anchor will find the canonical bump for the assignment checker PDA.
+    // CHECK: the course account is created by the instruction
+    // Anchor will find the canonical bump for the course PDA.
     // The PDA is derived from course_authority account and course name.
-    // #[account(init, payer = course_authority, space = 8 + Course::LEN)]
-    #[account(init, payer = course_authority, space = 8 + Course::LEN, seeds=[
+    #[account(mut, seeds=[
         COURSE_AUTHORITY_SEED,
         course_authority.key().as_ref(),
         COURSE_ID_SEED,
         &course_id
-    ], bump)]
+    ], bump,
+    constraint = course_id != [0; 16] @ CourseManagerError::InvalidCourseId,
+    constraint = course.data_is_empty() @ CourseManagerError::CourseAlreadyExists)]
+    pub course: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn module_canonical_pda(course: Pubkey, module_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[COURSE_DATA_SEED, course.as_ref(), MODULE_ID_SEED, module_id],
+        &ID,
+    )
+    .0
+}
+
+#[derive(Accounts)]
+pub struct UpgradeCourse<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // CHECK: older layouts don't deserialize as `Course`, the authority is checked
+    // by `upgrade_course`
+    #[account(mut, owner = ID)]
+    pub course: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssignmentCount<'info> {
+    pub authority: Signer<'info>,
+    // Anchor persists changes only for mutable accounts.
+    // Callers have to pass the course writable.
+    #[account(mut, has_one = authority, constraint = !course.frozen @ CourseManagerError::CourseFrozen)]
     pub course: Account<'info, Course>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: [u8; 16])]
+pub struct RegisterEnrollment<'info> {
+    pub student: Signer<'info>,
+    #[account(mut)]
+    pub course: Account<'info, Course>,
+    // only the enrolling program signs for the progress record of the student
+    #[account(signer, seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        STUDENT_PROGRESS_SEED,
+    ], seeds::program = enrollment_program, bump)]
+    pub student_progress: AccountInfo<'info>,
+    // any other program could sign for made up progress records
+    #[account(executable,
+        address = COURSE_BATCH_MANAGER_ID @ CourseManagerError::UnknownEnrollmentProgram)]
+    pub enrollment_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: [u8; 16])]
+pub struct UnregisterEnrollment<'info> {
+    // CHECK: the student is a seed of the progress record, the course authority
+    // may close the record without the student
+    pub student: AccountInfo<'info>,
+    #[account(mut)]
+    pub course: Account<'info, Course>,
+    // only the enrolling program signs for the progress record of the student
+    #[account(signer, seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &batch_id,
+        STUDENT_PROGRESS_SEED,
+    ], seeds::program = enrollment_program, bump)]
+    pub student_progress: AccountInfo<'info>,
+    // any other program could sign for made up progress records
+    #[account(executable,
+        address = COURSE_BATCH_MANAGER_ID @ CourseManagerError::UnknownEnrollmentProgram)]
+    pub enrollment_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(module_id: [u8; 16])]
+pub struct NewModule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority, constraint = !course.frozen @ CourseManagerError::CourseFrozen)]
+    pub course: Account<'info, Course>,
+    #[account(init, payer = authority, space = 8 + Module::LEN, seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        MODULE_ID_SEED,
+        &module_id
+    ], bump)]
+    pub module: Account<'info, Module>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateModuleAssignmentCount<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority, constraint = !course.frozen @ CourseManagerError::CourseFrozen)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = course)]
+    pub module: Account<'info, Module>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeCourse<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCourse<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+}
+
 /// Each course has unique id
 ///
 /// Course authority creates course account before the first batch of new course.
@@ -61,8 +639,254 @@ pub struct Course {
     /// Course organizer
     pub authority: Pubkey,
     pub bump_seed: u8,
+    /// Number of assignment checkers of the course
+    pub assignment_count: u16,
+    /// Course structure is locked, see `freeze_course`
+    pub frozen: bool,
+    /// Course authority cannot check assignments of the course
+    pub forbid_authority_self_check: bool,
+    /// Number of student enrollments into batches of the course
+    pub enrolled_count: u16,
+    /// Number of assignment checkers ever created for the course
+    ///
+    /// Generation of the latest checker, checkers recreated after a close get new generations.
+    pub checker_generation: u16,
+    /// Fields of events emitted by checks of the course, see `set_event_verbosity`
+    pub event_verbosity: EventVerbosity,
+    pub created_at_unix_ts: i64,
+    /// Students enroll into batches without the course authority, see `set_self_enroll_allowed`
+    pub self_enroll_allowed: bool,
+    /// Salts of checkers created with zero salt are derived from it, see `set_master_secret_hash`
+    pub master_secret_hash: [u8; 32],
+    /// Submits checks on behalf of students, see `set_proctor`
+    pub proctor: Pubkey,
+    pub proctored_checks_allowed: bool,
+    /// Instructions of the course log events, see `set_emit_events`
+    pub emit_events: bool,
+    /// Mint of course rewards set at creation, default when batches mint their own
+    pub reward_mint: Pubkey,
+    /// Layout version of the course, see `COURSE_SCHEMA_VERSION`
+    pub schema_version: u8,
+    /// Space of future fields, see `COURSE_RESERVED_LEN`
+    pub reserved: [u8; COURSE_RESERVED_LEN],
 }
 
 impl Course {
-    pub const LEN: usize = 16 + PUBKEY_BYTES + 1;
+    /// Layout of the course before versioning was added to the course manager
+    pub const LEN_V0: usize = 16 + PUBKEY_BYTES + 1;
+    pub const LEN: usize = Self::LEN_V0
+        + 2
+        + 1
+        + 1
+        + 2
+        + 2
+        + 1
+        + 8
+        + 1
+        + 32
+        + PUBKEY_BYTES
+        + 1
+        + 1
+        + PUBKEY_BYTES
+        + 1
+        + COURSE_RESERVED_LEN;
+    const EVENT_VERBOSITY_OFFSET: usize = Self::LEN_V0 + 2 + 1 + 1 + 2 + 2;
+    const SELF_ENROLL_ALLOWED_OFFSET: usize = Self::EVENT_VERBOSITY_OFFSET + 1 + 8;
+    const EMIT_EVENTS_OFFSET: usize = Self::SELF_ENROLL_ALLOWED_OFFSET + 1 + 32 + PUBKEY_BYTES + 1;
+
+    /// Deserialize course data of any layout version
+    ///
+    /// Fields missing in older layouts take the values of `create_new_course`,
+    /// `schema_version` keeps the version of the data.
+    pub fn from_versioned_data(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(&Self::discriminator()) {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+        }
+        let data = &data[8..];
+        if data.len() < Self::LEN_V0 {
+            return Err(error!(ErrorCode::AccountDidNotDeserialize));
+        }
+        let mut padded = data.to_vec();
+        if padded.len() < Self::LEN {
+            padded.resize(Self::LEN, 0);
+        }
+        let mut course = Self::deserialize(&mut padded.as_slice())
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))?;
+        if data.len() <= Self::EVENT_VERBOSITY_OFFSET {
+            course.event_verbosity = EventVerbosity::default();
+        }
+        if data.len() <= Self::SELF_ENROLL_ALLOWED_OFFSET {
+            course.self_enroll_allowed = true;
+        }
+        if data.len() <= Self::EMIT_EVENTS_OFFSET {
+            course.emit_events = true;
+        }
+        Ok(course)
+    }
+
+    /// Shortest hash chain that has a slot for each enrolled student
+    ///
+    /// The chain start has no parent, so a chain of `n` hashes has `n - 1` slots.
+    pub fn suggested_hash_chain_length(&self) -> u16 {
+        self.enrolled_count.saturating_add(1)
+    }
+
+    /// The student is the course authority and self checks are forbidden
+    pub fn forbids_self_check(&self, student: &Pubkey) -> bool {
+        self.forbid_authority_self_check && *student == self.authority
+    }
+
+    /// The signer may submit checks on behalf of students
+    pub fn is_authorized_proctor(&self, proctor: &Pubkey) -> bool {
+        self.proctored_checks_allowed && *proctor == self.proctor
+    }
+
+    /// Log the event with `emit_event` unless events of the course are off
+    pub fn emit<T: AnchorSerialize + Discriminator>(&self, event: T) -> Result<()> {
+        if self.emit_events {
+            emit_event(event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Context carried by events of the course
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventVerbosity {
+    /// Minimal fields that identify what happened
+    Compact,
+    /// Minimal fields with timestamps, remaining capacity and rewards
+    #[default]
+    Verbose,
+}
+
+impl EventVerbosity {
+    pub fn is_verbose(self) -> bool {
+        self == EventVerbosity::Verbose
+    }
+}
+
+/// Group of assignments of the course
+///
+/// Assignment checkers carry `module_id` of their module, students complete
+/// the module by passing all of its assignments.
+#[account]
+pub struct Module {
+    pub course: Pubkey,
+    /// Module identifier unique within the course
+    pub module_id: [u8; 16],
+    /// Number of assignment checkers of the module
+    pub assignment_count: u16,
+    pub bump_seed: u8,
+}
+
+impl Module {
+    pub const LEN: usize = PUBKEY_BYTES + 16 + 2 + 1;
+}
+
+#[event]
+pub struct CourseCreated {
+    pub course: Pubkey,
+    pub course_id: [u8; 16],
+}
+
+#[event]
+pub struct CourseUpgraded {
+    pub course: Pubkey,
+    pub schema_version: u8,
+}
+
+#[event]
+pub struct AssignmentCountChanged {
+    pub course: Pubkey,
+    pub assignment_count: u16,
+}
+
+#[event]
+pub struct EnrolledCountChanged {
+    pub course: Pubkey,
+    pub enrolled_count: u16,
+}
+
+#[event]
+pub struct CourseFrozen {
+    pub course: Pubkey,
+}
+
+#[event]
+pub struct AuthoritySelfCheckForbidden {
+    pub course: Pubkey,
+    pub forbid_authority_self_check: bool,
+}
+
+#[event]
+pub struct ModuleCreated {
+    pub course: Pubkey,
+    pub module: Pubkey,
+    pub module_id: [u8; 16],
+}
+
+#[event]
+pub struct ModuleAssignmentCountChanged {
+    pub module: Pubkey,
+    pub assignment_count: u16,
+}
+
+#[event]
+pub struct EventVerbositySet {
+    pub course: Pubkey,
+    pub event_verbosity: EventVerbosity,
+}
+
+#[event]
+pub struct SelfEnrollAllowedSet {
+    pub course: Pubkey,
+    pub self_enroll_allowed: bool,
+}
+
+#[event]
+pub struct MasterSecretHashSet {
+    pub course: Pubkey,
+    pub master_secret_hash: [u8; 32],
+}
+
+#[event]
+pub struct ProctorSet {
+    pub course: Pubkey,
+    pub proctor: Pubkey,
+    pub proctored_checks_allowed: bool,
+}
+
+#[event]
+pub struct EmitEventsSet {
+    pub course: Pubkey,
+    pub emit_events: bool,
+}
+
+#[error_code]
+pub enum CourseManagerError {
+    #[msg("The course has too many assignments")]
+    AssignmentCountOverflow,
+    #[msg("The course has no assignments")]
+    NoAssignments,
+    #[msg("The course is frozen")]
+    CourseFrozen,
+    #[msg("The course has too many enrollments")]
+    EnrolledCountOverflow,
+    #[msg("The course has too many checker generations")]
+    CheckerGenerationOverflow,
+    #[msg("The module has too many assignments")]
+    ModuleAssignmentCountOverflow,
+    #[msg("The module has no assignments")]
+    NoModuleAssignments,
+    #[msg("The course ID is all zeros")]
+    InvalidCourseId,
+    #[msg("The course authority has a course with the same ID")]
+    CourseAlreadyExists,
+    #[msg("The course has no enrollments")]
+    NoEnrollments,
+    #[msg("Enrollments are registered by course batch manager only")]
+    UnknownEnrollmentProgram,
+    #[msg("The reward mint is not passed as a token mint")]
+    InvalidRewardMint,
 }
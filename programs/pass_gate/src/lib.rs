@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use course_batch_manager::{cpi::accounts::AssertPassed, program::CourseBatchManager};
+
+declare_id!("PoCuGFKxkyWLZYZ7YGJL7QEKZY4ThGBYC9s8EyfrSbs");
+
+// Example consumer of `assert_passed` of course_batch_manager
+#[program]
+pub mod pass_gate {
+    use super::*;
+
+    /// Let the student in if the assignment check has passed
+    ///
+    /// Failed assertion fails the whole transaction, the student stays outside.
+    pub fn enter(ctx: Context<Enter>, assignment_id: [u8; 16]) -> Result<()> {
+        course_batch_manager::cpi::assert_passed(
+            ctx.accounts.assert_passed_cpi_ctx(),
+            assignment_id,
+        )?;
+        msg!("access granted to {}", ctx.accounts.student.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Enter<'info> {
+    pub student: Signer<'info>,
+    // CHECK: the course is validated by course_batch_manager
+    pub course: AccountInfo<'info>,
+    // CHECK: the check result is validated by course_batch_manager
+    pub check_result: AccountInfo<'info>,
+    pub course_batch_manager_program: Program<'info, CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> Enter<'info> {
+    pub fn assert_passed_cpi_ctx(&self) -> CpiContext<'a, 'b, 'c, 'info, AssertPassed<'info>> {
+        let cpi_program = self.course_batch_manager_program.to_account_info();
+
+        let cpi_accounts = AssertPassed {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
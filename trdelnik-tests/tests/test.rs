@@ -1,12 +1,37 @@
-use anchor_lang::solana_program::{blake3, sysvar::rent};
-use anchor_lang::system_program;
+use anchor_client::solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use anchor_client::{Client as AnchorClient, Cluster};
+use anchor_lang::solana_program::{
+    blake3, instruction::AccountMeta, program_pack::Pack, sysvar::rent,
+};
+use anchor_lang::{
+    system_program, AccountDeserialize, AccountSerialize, AnchorDeserialize, AnchorSerialize,
+    Discriminator, InstructionData, ToAccountMetas,
+};
 use anchor_spl::associated_token::{self, get_associated_token_address};
 use anchor_spl::token;
 use fehler::throws;
 use program_client::course_batch_manager_instruction;
 use program_client::course_manager_instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::rc::Rc;
 use trdelnik_client::{anyhow::Result, *};
 
+/// Compute unit ceilings of the hot instructions
+///
+/// Raise them only together with changes that are expected to cost more.
+const CREATE_ASSIGNMENT_CHECKER_MAX_UNITS: u64 = 60_000;
+const CHECK_ASSIGNMENT_MAX_UNITS: u64 = 100_000;
+/// Base delay of `Fixture::submit_check_with_retry`, doubled on each retry up to the max shift
+const RETRY_BACKOFF_MILLIS: u64 = 50;
+const RETRY_BACKOFF_MAX_SHIFT: u16 = 4;
+/// Deploy keypair of the example consumer program `pass_gate`
+const PASS_GATE_PROGRAM_KEYPAIR: [u8; 64] = [
+    107, 237, 75, 26, 125, 41, 225, 142, 76, 140, 53, 157, 217, 204, 164, 127, 251, 3, 75, 233,
+    168, 56, 4, 127, 52, 18, 41, 18, 151, 78, 226, 24, 5, 215, 5, 173, 80, 13, 10, 249, 14, 131,
+    227, 126, 235, 154, 70, 161, 111, 140, 58, 53, 236, 141, 96, 182, 241, 138, 138, 56, 175, 105,
+    94, 50,
+];
+
 #[throws]
 #[fixture]
 async fn start_course_batch() -> Fixture {
@@ -27,6 +52,7 @@ async fn start_course_batch() -> Fixture {
     course_manager_instruction::create_new_course(
         &f.client,
         f.course_id,
+        Pubkey::default(),
         f.course_authority.pubkey(),
         f.course_pda,
         system_program::ID,
@@ -39,6 +65,9 @@ async fn start_course_batch() -> Fixture {
     // Mint address for the course batch token
     f.course_batch_mint_pda =
         course_batch_manager::batch_mint_canonical_pda(f.course_pda, &f.batch_id);
+    // Escrow for rewards during dispute window
+    f.course_batch_escrow_pda =
+        course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &f.batch_id);
 
     // create new course batch data and mint accounts
     course_batch_manager_instruction::create_new_batch(
@@ -48,6 +77,7 @@ async fn start_course_batch() -> Fixture {
         f.course_pda,
         f.course_batch_pda,
         f.course_batch_mint_pda,
+        f.course_batch_escrow_pda,
         system_program::ID,
         rent::id(),
         token::ID,
@@ -62,13 +92,21 @@ async fn start_course_batch() -> Fixture {
         &f.client,
         f.student_a.pubkey(),
         f.course_authority.pubkey(),
+        f.course_pda,
         f.course_batch_pda,
         f.course_batch_mint_pda,
         f.student_a_token_account,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
         system_program::ID,
         token::ID,
         associated_token::ID,
         rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
         [f.student_a.clone()],
     )
     .await?;
@@ -80,79 +118,33 @@ async fn start_course_batch() -> Fixture {
         &f.client,
         f.student_b.pubkey(),
         f.course_authority.pubkey(),
+        f.course_pda,
         f.course_batch_pda,
         f.course_batch_mint_pda,
         f.student_b_token_account,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
         system_program::ID,
         token::ID,
         associated_token::ID,
         rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
         [f.student_b.clone()],
     )
     .await?;
 
     // Prepare assignment checker capable to check 10 - 1 students
-
-    let ground_truth_hash_chain_tail = Fixture::hash(
-        f.hash_chain_length,
-        &f.salt,
-        f.ground_truth_value.as_bytes(),
-    );
-
-    f.assignment_checker_pda =
-        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &f.assignment_id);
-
-    // create assignment checker
-    course_batch_manager_instruction::create_assignment_checker(
-        &f.client,
-        f.assignment_id,
-        f.hash_chain_length,
-        100,
-        f.salt.clone(),
-        ground_truth_hash_chain_tail,
-        f.course_authority.pubkey(),
-        f.course_pda,
-        f.assignment_checker_pda,
-        assignment_checker::ID,
-        course_batch_manager::ID,
-        system_program::ID,
-        [f.course_authority.clone()],
-    )
-    .await?;
+    f.assignment_checker_pda = f.create_assignment_checker(f.assignment_id, false).await?;
 
     // init check result accounts for students A and B
-    course_batch_manager_instruction::create_check_result(
-        &f.client,
-        f.assignment_id,
-        f.student_a.pubkey(),
-        f.course_pda,
-        course_batch_manager::check_result_canonical_pda(
-            f.student_a.pubkey(),
-            f.course_pda,
-            &f.assignment_id,
-        ),
-        assignment_checker::ID,
-        course_batch_manager::ID,
-        system_program::ID,
-        [f.student_a.clone()],
-    )
-    .await?;
-    course_batch_manager_instruction::create_check_result(
-        &f.client,
-        f.assignment_id,
-        f.student_b.pubkey(),
-        f.course_pda,
-        course_batch_manager::check_result_canonical_pda(
-            f.student_b.pubkey(),
-            f.course_pda,
-            &f.assignment_id,
-        ),
-        assignment_checker::ID,
-        course_batch_manager::ID,
-        system_program::ID,
-        [f.student_b.clone()],
-    )
-    .await?;
+    f.create_check_result(f.student_a.clone(), f.assignment_id)
+        .await?;
+    f.create_check_result(f.student_b.clone(), f.assignment_id)
+        .await?;
 
     f
 }
@@ -233,161 +225,9402 @@ async fn test_check_assignment(#[future] start_course_batch: Result<Fixture>) {
         )
         .await?;
 
+    // the repeat keeps persisted flags of the pass
     assert_eq!(check_result.check_passed, true);
-    assert_eq!(check_result.passed_first_time, false);
+    assert_eq!(check_result.passed_first_time, true);
     // balance is not changed
     let balance_a = f
         .client
         .get_token_balance(f.student_a_token_account)
         .await?;
     assert_eq!(balance_a.amount.as_str(), "100");
-}
 
-/// Input keypairs / pubkeys / programs and data to configure tests
-struct Fixture {
-    client: Client,
-    assignment_checker_program: Keypair,
-    course_program: Keypair,
-    course_batch_program: Keypair,
+    // assignment count updated on checker creation persists across checks
+    let course_account = f.get_course_account().await?;
+    assert_eq!(course_account.assignment_count, 1);
+}
 
-    course_authority: Keypair,
-    course_id: [u8; 16],
-    course_pda: Pubkey,
+/// Test if student can locate and read own anonymous check result
+#[trdelnik_test]
+async fn test_anonymous_check_result(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
 
-    batch_id: [u8; 16],
-    course_batch_pda: Pubkey,
-    course_batch_mint_pda: Pubkey,
+    let assignment_id = *b"space_hero_anon_";
+    let assignment_checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    let course_secret = [7; 32];
+    let check_result_pda = course_batch_manager::anonymous_check_result_canonical_pda(
+        f.student_a.pubkey(),
+        &course_secret,
+        f.course_pda,
+        &assignment_id,
+    );
+    course_batch_manager_instruction::create_check_result(
+        &f.client,
+        assignment_id,
+        Some(course_secret),
+        f.student_a.pubkey(),
+        f.course_pda,
+        check_result_pda,
+        course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
 
-    assignment_id: [u8; 16],
-    ground_truth_value: String,
-    hash_chain_length: u16,
-    salt: [u8; 32],
-    assignment_checker_pda: Pubkey,
+    let (_, check_result) = f
+        .check_assignment_with_course_secret(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+            Some(course_secret),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert!(check_result.is_anonymous(&f.student_a.pubkey()));
 
-    // can be related to course_authority
-    student_a: Keypair,
-    student_a_token_account: Pubkey,
-    student_b: Keypair,
-    student_b_token_account: Pubkey,
+    // result can't be found by student address
+    let address_derived_result = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &assignment_id,
+    );
+    assert!(f
+        .client
+        .get_account(address_derived_result)
+        .await?
+        .is_none());
+    // passer is listed by the anonymous seed
+    let checker = f.get_checker_account(assignment_checker_pda).await?;
+    assert_eq!(
+        checker.recent_passers.iter().next(),
+        Some(&Pubkey::new_from_array(check_result.student_seed))
+    );
 }
 
-impl Fixture {
-    fn new() -> Self {
-        Fixture {
-            client: Client::new(system_keypair(0)),
-            assignment_checker_program: program_keypair(1),
-            course_program: program_keypair(2),
-            course_batch_program: program_keypair(3),
-            course_authority: keypair(0),
-            course_id: *b"web2_to_web3____",
-            course_pda: Pubkey::default(),
-            batch_id: *b"the_first_batch_",
-            course_batch_pda: Pubkey::default(),
-            course_batch_mint_pda: Pubkey::default(),
-            assignment_id: *b"space_hero______",
-            // Assignment: "Surname of the first man in space"
-            ground_truth_value: "Gagarin".to_string(),
-            // support up to 10 - 1 students
-            hash_chain_length: 10,
-            // good enough for test
-            salt: [0; 32],
-            assignment_checker_pda: Pubkey::default(),
+/// Test if check result migrated to another seed scheme keeps its pass and isn't rewarded again
+#[trdelnik_test]
+async fn test_migrate_check_result(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.check_passed);
 
-            student_a: keypair(1),
-            student_a_token_account: Pubkey::default(),
-            student_b: keypair(2),
-            student_b_token_account: Pubkey::default(),
-        }
-    }
+    let old_check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let migrate = |old_course_secret: Option<[u8; 32]>, new_course_secret: [u8; 32]| {
+        let old_check_result = match old_course_secret {
+            Some(old_course_secret) => course_batch_manager::anonymous_check_result_canonical_pda(
+                f.student_a.pubkey(),
+                &old_course_secret,
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            None => old_check_result_pda,
+        };
+        course_batch_manager_instruction::migrate_check_result(
+            &f.client,
+            f.assignment_id,
+            old_course_secret,
+            Some(new_course_secret),
+            f.student_a.pubkey(),
+            f.course_pda,
+            old_check_result,
+            course_batch_manager::anonymous_check_result_canonical_pda(
+                f.student_a.pubkey(),
+                &new_course_secret,
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            system_program::ID,
+            [f.student_a.clone()],
+        )
+    };
 
-    #[throws]
-    async fn deploy(&mut self) {
-        self.client
-            .deploy_by_name(&self.course_program, "course_manager")
-            .await?;
-        self.client
-            .deploy_by_name(&self.course_batch_program, "course_batch_manager")
-            .await?;
-        self.client
-            .deploy_by_name(&self.assignment_checker_program, "assignment_checker")
-            .await?;
-    }
+    // address derived result moves to the seeds with course secret
+    let course_secret = [9; 32];
+    let tx = migrate(None, course_secret).await?;
+    let new_check_result_pda = course_batch_manager::anonymous_check_result_canonical_pda(
+        f.student_a.pubkey(),
+        &course_secret,
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let migrated = Fixture::events::<course_batch_manager::CheckResultMigrated>(&tx);
+    assert_eq!(migrated.len(), 1);
+    assert_eq!(migrated[0].1.old_check_result, old_check_result_pda);
+    assert_eq!(migrated[0].1.new_check_result, new_check_result_pda);
+    let new_check_result = f.get_check_result_account(new_check_result_pda).await?;
+    assert!(new_check_result.check_passed && new_check_result.passed_first_time);
+    assert_eq!(new_check_result.attempts, check_result.attempts);
+    assert!(new_check_result.is_anonymous(&f.student_a.pubkey()));
+    assert!(!new_check_result.is_migrated());
+    let old_check_result = f.get_check_result_account(old_check_result_pda).await?;
+    assert!(old_check_result.check_passed);
+    assert_eq!(old_check_result.migrated_to, new_check_result_pda);
 
-    #[throws]
-    async fn get_course_account(&self) -> course_manager::Course {
-        self.client
-            .account_data::<course_manager::Course>(self.course_pda)
-            .await?
-    }
+    // neither result is rewarded again
+    let old_checked = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await;
+    assert!(old_checked.is_err());
+    let (_, new_check_result) = f
+        .check_assignment_with_course_secret(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+            Some(course_secret),
+        )
+        .await?;
+    assert!(new_check_result.check_passed && new_check_result.passed_first_time);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
 
-    #[throws]
-    async fn get_course_batch_account(&self) -> course_batch_manager::CourseBatch {
-        self.client
-            .account_data::<course_batch_manager::CourseBatch>(self.course_batch_pda)
-            .await?
-    }
+    // migrated result cannot be migrated again
+    let migrated_again = migrate(None, [10; 32]).await;
+    assert!(Fixture::is_program_error(&migrated_again, "ResultMigrated"));
+}
 
-    #[throws]
-    async fn get_checker_account(
-        &self,
-        checker_pda: Pubkey,
-    ) -> assignment_checker::AssignmentCheckerState {
-        self.client
-            .account_data::<course_batch_manager::AssignmentCheckerState>(checker_pda)
-            .await?
-    }
+/// Test if partial credit of weighted criteria rewards the high weight criterion more
+#[trdelnik_test]
+async fn test_weighted_criteria(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_crit_";
+    let checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    let criteria: [(u16, &[u8]); 2] = [(1, b"Vostok"), (3, b"Gagarin")];
+    let criteria_pda = f.create_assignment_criteria(checker_pda, &criteria).await?;
+    let assignment_criteria = f
+        .client
+        .account_data::<course_batch_manager::AssignmentCriteria>(criteria_pda)
+        .await?;
+    assert_eq!(assignment_criteria.criteria_count, 2);
+    assert_eq!(assignment_criteria.weights[..2], [1, 3]);
+    f.create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id)
+        .await?;
 
-    #[throws]
-    async fn get_check_result_account(
-        &self,
-        check_result_pda: Pubkey,
-    ) -> assignment_checker::CheckResult {
-        self.client
-            .account_data::<course_batch_manager::CheckResult>(check_result_pda)
-            .await?
-    }
+    let client = &f.client;
+    let balance = |token_account: Pubkey| async move {
+        client
+            .get_token_balance(token_account)
+            .await
+            .map(|balance| balance.amount.parse::<u64>().unwrap())
+    };
+    // the high weight criterion is worth more than the low weight one
+    let check_result_a = f
+        .check_criterion(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            1,
+            criteria[1].1,
+        )
+        .await?;
+    assert_eq!(check_result_a.weighted_score_bps, 7_500);
+    assert!(!check_result_a.check_passed);
+    let check_result_b = f
+        .check_criterion(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            0,
+            criteria[0].1,
+        )
+        .await?;
+    assert_eq!(check_result_b.weighted_score_bps, 2_500);
+    let balance_a = balance(f.student_a_token_account).await?;
+    let balance_b = balance(f.student_b_token_account).await?;
+    assert_eq!(balance_a, 75);
+    assert_eq!(balance_b, 25);
+    assert!(balance_a > balance_b);
 
-    /// Checks assignment and returns the hashed value_to_check and the result of the check
-    #[throws]
-    async fn check_assignment(
-        &self,
-        student_keypair: Keypair,
-        student_token_address: Pubkey,
-        checker_data_address: Pubkey,
-        course_data_address: Pubkey,
-        course_batch_address: Pubkey,
+    // wrong answer to a criterion keeps the score
+    let check_result_b = f
+        .check_criterion(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            1,
+            b"Leonov",
+        )
+        .await?;
+    assert_eq!(check_result_b.weighted_score_bps, 2_500);
+    let unknown = f
+        .check_criterion(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            2,
+            criteria[0].1,
+        )
+        .await;
+    assert!(unknown.is_err());
+
+    // passing all criteria passes the assignment with the full reward
+    let check_result_a = f
+        .check_criterion(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            0,
+            criteria[0].1,
+        )
+        .await?;
+    assert_eq!(check_result_a.weighted_score_bps, 10_000);
+    assert!(check_result_a.check_passed && check_result_a.passed_first_time);
+    assert_eq!(check_result_a.passed_criteria, 0b11);
+    assert_eq!(balance(f.student_a_token_account).await?, 100);
+
+    // criteria graded result is not checked with the whole answer
+    let checked = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await;
+    assert!(checked.is_err());
+}
+
+/// Test if the last failed attempt closes the check result until the chain is extended
+#[trdelnik_test]
+async fn test_max_attempts(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_max__";
+    let checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    let check_result_pda = f
+        .create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    let tx = course_batch_manager_instruction::set_max_attempts(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let max_attempts_set = Fixture::events::<course_batch_manager::MaxAttemptsSet>(&tx);
+    assert_eq!(max_attempts_set.len(), 1);
+    assert_eq!(f.get_checker_account(checker_pda).await?.max_attempts, 2);
+    let client = &f.client;
+    let lamports = |address: Pubkey| async move {
+        client
+            .get_account(address)
+            .await
+            .map(|account| account.map_or(0, |account| account.lamports))
+    };
+
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            b"Leonov",
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.attempts, 1);
+    // closing the failed check result doesn't reset the attempts
+    let closed = course_batch_manager_instruction::close_check_result(
+        &f.client,
+        None,
+        f.student_a.pubkey(),
+        f.course_pda,
+        check_result_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(&closed, "ResultHasAttempts"));
+
+    // the last attempt fails and refunds the rent less the rent of the marker
+    let exhausted_check_result_pda =
+        course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda);
+    let rent = lamports(check_result_pda).await?;
+    let student_lamports = lamports(f.student_a.pubkey()).await?;
+    let checker = f.get_checker_account(checker_pda).await?;
+    let tx = course_batch_manager_instruction::check_assignment(
+        &f.client,
+        checker.hash_chain_length,
+        Fixture::hash(
+            checker.hash_chain_length - 1,
+            &checker.salt,
+            &f.course_pda,
+            &assignment_id,
+            b"Leonov",
+        ),
+        None,
+        0,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        checker_pda,
+        check_result_pda,
+        exhausted_check_result_pda,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        f.course_batch_escrow_pda,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+    assert!(outcome.result_closed && !outcome.passed);
+    let closed = Fixture::events::<course_batch_manager::CheckResultClosed>(&tx);
+    assert_eq!(closed.len(), 1);
+    assert!(f.client.get_account(check_result_pda).await?.is_none());
+    let exhausted_rent = lamports(exhausted_check_result_pda).await?;
+    assert!(exhausted_rent > 0);
+    assert_eq!(
+        lamports(f.student_a.pubkey()).await?,
+        student_lamports + rent - exhausted_rent
+    );
+    let create_check_result = |remaining_accounts: Vec<AccountMeta>| {
+        f.client.send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::CreateCheckResult {
+                assignment_id,
+                course_secret: None,
+            },
+            WithRemainingAccounts {
+                accounts: course_batch_manager::accounts::CreateCheckResult {
+                    student: f.student_a.pubkey(),
+                    course: f.course_pda,
+                    check_result: check_result_pda,
+                    exhausted_check_result: exhausted_check_result_pda,
+                    assignment_checker_program: assignment_checker::ID,
+                    course_batch_manager_program: course_batch_manager::ID,
+                    system_program: system_program::ID,
+                },
+                remaining_accounts,
+            },
+            [f.student_a.clone()],
+        )
+    };
+
+    // the exhausted check result isn't created again before the extension
+    let recreated = create_check_result(vec![]).await;
+    assert!(Fixture::is_program_error(
+        &recreated,
+        "CheckResultExhausted"
+    ));
+    let recreated = create_check_result(vec![AccountMeta::new_readonly(checker_pda, false)]).await;
+    assert!(Fixture::is_program_error(
+        &recreated,
+        "CheckResultExhausted"
+    ));
+    assert!(f.client.get_account(check_result_pda).await?.is_none());
+
+    // the check result is created again to retry after the extension
+    let extension = 5;
+    course_batch_manager_instruction::extend_chain(
+        &f.client,
+        extension,
+        [1; 32],
+        Fixture::hash(
+            checker.hash_chain_length + extension,
+            &[1; 32],
+            &f.course_pda,
+            &assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let student_lamports = lamports(f.student_a.pubkey()).await?;
+    create_check_result(vec![AccountMeta::new_readonly(checker_pda, false)]).await?;
+    assert_eq!(lamports(exhausted_check_result_pda).await?, 0);
+    assert_eq!(
+        lamports(f.student_a.pubkey()).await?,
+        student_lamports + exhausted_rent - rent
+    );
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    assert_eq!(check_result.attempts, 1);
+}
+
+/// Test if the expected parent of programs built with `test-only` feature passes the check
+#[trdelnik_test]
+async fn test_expected_parent(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let expected_parent = course_batch_manager_instruction::expected_parent(
+        &f.client,
+        f.ground_truth_value.as_bytes().to_vec(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        Vec::<Keypair>::new(),
+    )
+    .await;
+    if Fixture::is_program_error(&expected_parent, "TestOnlyInstruction") {
+        // default builds don't answer with the parent
+        assert!(!course_batch_manager::TEST_ONLY_INSTRUCTIONS);
+    } else {
+        let parent: [u8; 32] = Fixture::return_data(&expected_parent?, course_batch_manager::ID)
+            .expect("parent is returned");
+        let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+        assert_eq!(
+            parent,
+            Fixture::hash(
+                checker.hash_chain_length - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            )
+        );
+        let (_, check_result) = f
+            .check_assignment(
+                f.student_a.clone(),
+                f.student_a_token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                Some(parent),
+            )
+            .await?;
+        assert!(check_result.check_passed);
+    }
+}
+
+/// Test if the course counts enrollments and suggests a hash chain with a slot for each
+#[trdelnik_test]
+async fn test_enrolled_count(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    // students A and B are enrolled by the fixture
+    let course = f.get_course_account().await?;
+    assert_eq!(course.enrolled_count, 2);
+    assert_eq!(course.suggested_hash_chain_length(), 3);
+
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    f.enroll_student(student_c).await?;
+    let course = f.get_course_account().await?;
+    assert_eq!(course.enrolled_count, 3);
+    assert_eq!(course.suggested_hash_chain_length(), 4);
+    assert!(course_batch_manager::short_hash_chain_warning(&course, 3).is_some());
+    assert_eq!(
+        course_batch_manager::short_hash_chain_warning(&course, 4),
+        None
+    );
+}
+
+/// Test if keyed commitment mode accepts keyed parents and rejects parents with prefixed salt
+#[trdelnik_test]
+async fn test_keyed_commitment_mode(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_keyed";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    let keyed = course_batch_manager::CommitmentMode::Keyed;
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        course_batch_manager::NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt,
+            per_student_salt: false,
+            hash_iterations_per_step: 1,
+            ground_truth_hash_chain_tail: keyed.build_iterated_tail(
+                &f.salt,
+                &f.course_pda.to_bytes(),
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+                f.hash_chain_length,
+                1,
+            ),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let set_mode = |commitment_mode| {
+        course_batch_manager_instruction::set_commitment_mode(
+            &f.client,
+            commitment_mode,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    set_mode(keyed).await?;
+    let checker = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker.commitment_mode, keyed);
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        f.create_check_result(student, assignment_id).await?;
+    }
+
+    // parent with prefixed salt doesn't lead to the keyed tail
+    let prefixed_parent = Fixture::hash(
+        f.hash_chain_length - 1,
+        &f.salt,
+        &f.course_pda,
+        &assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(prefixed_parent),
+        )
+        .await?;
+    assert!(!check_result.check_passed);
+
+    // the fixture builds the parent in the mode of the checker
+    let (keyed_parent, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    assert_ne!(keyed_parent, prefixed_parent);
+
+    // students already build parents in keyed mode
+    let changed = set_mode(course_batch_manager::CommitmentMode::Prefixed).await;
+    assert!(Fixture::is_program_error(&changed, "CommitmentModeLocked"));
+    let checker = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker.commitment_mode, keyed);
+}
+
+/// Test if accounts of the assignment checker program are detected by their discriminator
+#[trdelnik_test]
+async fn test_assignment_checker_account_type(#[future] start_course_batch: Result<Fixture>) {
+    use course_batch_manager::AssignmentCheckerAccount;
+
+    let f = start_course_batch.await?;
+    match f
+        .get_assignment_checker_account(f.assignment_checker_pda)
+        .await?
+    {
+        Some(AssignmentCheckerAccount::Checker(checker)) => {
+            assert_eq!(checker.assignment_id, f.assignment_id);
+            assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+        }
+        _ => panic!("assignment checker is expected"),
+    }
+
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    match f.get_assignment_checker_account(check_result_pda).await? {
+        Some(AssignmentCheckerAccount::Result(check_result)) => {
+            assert_eq!(check_result.assignment_id, f.assignment_id);
+            assert_eq!(check_result.student_seed, f.student_a.pubkey().to_bytes());
+        }
+        _ => panic!("check result is expected"),
+    }
+
+    let criteria_pda = f
+        .create_assignment_criteria(f.assignment_checker_pda, &[(1, b"Vostok".as_ref())])
+        .await?;
+    assert!(matches!(
+        f.get_assignment_checker_account(criteria_pda).await?,
+        Some(AssignmentCheckerAccount::Criteria(_))
+    ));
+
+    // the course belongs to another program
+    assert!(f
+        .get_assignment_checker_account(f.course_pda)
+        .await?
+        .is_none());
+    let checker_data = f
+        .client
+        .get_account(f.assignment_checker_pda)
+        .await?
+        .unwrap()
+        .data;
+    let mut unknown_data = checker_data.clone();
+    unknown_data[..8].copy_from_slice(&[0; 8]);
+    assert!(AssignmentCheckerAccount::try_from_data(&unknown_data).is_err());
+}
+
+/// Test if the pass is recorded with claimable reward when minting it would overflow the supply
+#[trdelnik_test]
+async fn test_overflowing_reward_mint(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    course_batch_manager_instruction::set_first_solver_bonus(
+        &f.client,
+        u64::MAX - 100,
+        true,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let check = |student: Keypair, token_account: Pubkey| {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+    };
+    // the first solver takes the whole supply
+    check(f.student_a.clone(), f.student_a_token_account).await?;
+    let mint = f
+        .client
+        .account_data::<token::Mint>(f.course_batch_mint_pda)
+        .await?;
+    assert_eq!(mint.supply, u64::MAX);
+
+    let (_, check_result) = check(f.student_b.clone(), f.student_b_token_account).await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    assert_eq!(check_result.reward_claimable, 100);
+    let balance = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "0");
+
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_b.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let claim = || {
+        course_batch_manager_instruction::claim_reward(
+            &f.client,
+            f.student_b.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            check_result_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                f.student_b.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            f.student_b_token_account,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.student_b.clone()],
+        )
+    };
+    assert!(claim().await.is_err());
+
+    // burned tokens make room for the claimable reward
+    f.client
+        .send_transaction(
+            &[token::spl_token::instruction::burn(
+                &token::ID,
+                &f.student_a_token_account,
+                &f.course_batch_mint_pda,
+                &f.student_a.pubkey(),
+                &[],
+                100,
+            )?],
+            [&f.student_a],
+        )
+        .await?;
+    claim().await?;
+    let balance = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+}
+
+/// Test if batch settings rewarding a pass above the human scale bound need the override
+#[trdelnik_test]
+async fn test_suspicious_reward_amount(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_first_solver_bonus = |first_solver_bonus: u64, allow_large_rewards: bool| {
+        course_batch_manager_instruction::set_first_solver_bonus(
+            &f.client,
+            first_solver_bonus,
+            allow_large_rewards,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    let set_streak_bonus = |streak_bonus_bps: u16, allow_large_rewards: bool| {
+        course_batch_manager_instruction::set_streak_bonus(
+            &f.client,
+            streak_bonus_bps,
+            allow_large_rewards,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    // the largest checker reward is u16::MAX tokens
+    let bound_bonus = course_batch_manager::MAX_REWARD_WHOLE_TOKENS - u64::from(u16::MAX);
+    set_first_solver_bonus(bound_bonus, false).await?;
+    assert_eq!(
+        f.get_course_batch_account().await?.first_solver_bonus,
+        bound_bonus
+    );
+    let suspicious = set_first_solver_bonus(bound_bonus + 1, false).await;
+    assert!(Fixture::is_program_error(
+        &suspicious,
+        "SuspiciousRewardAmount"
+    ));
+    assert_eq!(
+        f.get_course_batch_account().await?.first_solver_bonus,
+        bound_bonus
+    );
+    set_first_solver_bonus(bound_bonus + 1, true).await?;
+    assert_eq!(
+        f.get_course_batch_account().await?.first_solver_bonus,
+        bound_bonus + 1
+    );
+
+    // the multiplier with the longest streak is bounded too
+    set_first_solver_bonus(0, false).await?;
+    course_batch_manager_instruction::set_reward_multiplier(
+        &f.client,
+        u16::MAX,
+        false,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let suspicious = set_streak_bonus(5_000, false).await;
+    assert!(Fixture::is_program_error(
+        &suspicious,
+        "SuspiciousRewardAmount"
+    ));
+    set_streak_bonus(5_000, true).await?;
+    assert_eq!(f.get_course_batch_account().await?.streak_bonus_bps, 5_000);
+}
+
+/// Test if emergency withdrawal runs only after the timelock of the queued action
+#[trdelnik_test]
+async fn test_timelocked_action(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_action_timelock = |action_timelock_seconds: u32| {
+        course_batch_manager_instruction::set_action_timelock(
+            &f.client,
+            action_timelock_seconds,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    // new batches start at the minimum delay
+    let min_timelock = f.get_course_batch_account().await?.action_timelock_seconds;
+    assert_eq!(
+        min_timelock,
+        course_batch_manager::MIN_ACTION_TIMELOCK_SECONDS
+    );
+    set_action_timelock(min_timelock + 1).await?;
+    let decreased = set_action_timelock(min_timelock).await;
+    assert!(Fixture::is_program_error(
+        &decreased,
+        "ActionTimelockDecreased"
+    ));
+    assert_eq!(
+        f.get_course_batch_account().await?.action_timelock_seconds,
+        min_timelock + 1
+    );
+
+    // tokens sent to the escrow by mistake
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.client
+        .send_transaction(
+            &[token::spl_token::instruction::transfer(
+                &token::ID,
+                &f.student_a_token_account,
+                &f.course_batch_escrow_pda,
+                &f.student_a.pubkey(),
+                &[],
+                30,
+            )?],
+            [&f.student_a],
+        )
+        .await?;
+
+    let timelocked_action = f
+        .queue_emergency_withdraw(f.student_a_token_account)
+        .await?;
+    let action = f
+        .client
+        .account_data::<course_batch_manager::TimelockedAction>(timelocked_action)
+        .await?;
+    assert_eq!(
+        action.execute_after_unix_ts,
+        action.queued_unix_ts + i64::from(min_timelock + 1)
+    );
+    assert_eq!(action.destination, f.student_a_token_account);
+    // one action of the kind is pending at a time
+    assert!(f
+        .queue_emergency_withdraw(f.student_a_token_account)
+        .await
+        .is_err());
+
+    let emergency_withdraw = || {
+        course_batch_manager_instruction::emergency_withdraw(
+            &f.client,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            timelocked_action,
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            f.student_a_token_account,
+            token::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let early = emergency_withdraw().await;
+    assert!(Fixture::is_program_error(&early, "ActionTimelocked"));
+    let escrow_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(escrow_balance.amount.as_str(), "30");
+
+    // builds without test-only instructions can't warp the day of the timelock
+    if f.warp_to_action(timelocked_action).await? {
+        emergency_withdraw().await?;
+        let escrow_balance = f
+            .client
+            .get_token_balance(f.course_batch_escrow_pda)
+            .await?;
+        assert_eq!(escrow_balance.amount.as_str(), "0");
+        assert!(f.client.get_account(timelocked_action).await?.is_none());
+        f.queue_emergency_withdraw(f.student_a_token_account)
+            .await?;
+    }
+
+    // cancelled action doesn't run
+    course_batch_manager_instruction::cancel_action(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        timelocked_action,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(f.client.get_account(timelocked_action).await?.is_none());
+}
+
+/// Test if cohort progress of the batch is ordered by passed assignments
+#[trdelnik_test]
+async fn test_cohort_progress(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    f.enroll_student(student_c.clone()).await?;
+
+    // student_b passes both assignments, student_a passes one and student_c none
+    let assignment_id = *b"space_hero_2____";
+    let checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    for (student, token_account, checkers) in [
+        (
+            f.student_a.clone(),
+            f.student_a_token_account,
+            vec![f.assignment_checker_pda],
+        ),
+        (
+            f.student_b.clone(),
+            f.student_b_token_account,
+            vec![f.assignment_checker_pda, checker_pda],
+        ),
+    ] {
+        f.create_check_result(student.clone(), assignment_id)
+            .await?;
+        for checker in checkers {
+            let (_, check_result) = f
+                .check_assignment(
+                    student.clone(),
+                    token_account,
+                    checker,
+                    f.course_pda,
+                    f.course_batch_pda,
+                    f.ground_truth_value.as_bytes(),
+                    None,
+                )
+                .await?;
+            assert!(check_result.passed_first_time);
+        }
+    }
+
+    let leaderboard = f.fetch_cohort_progress(f.course_batch_pda).await?;
+    let ranking: Vec<(Pubkey, u16)> = leaderboard
+        .iter()
+        .map(|(_, progress)| (progress.student, progress.assignments_passed))
+        .collect();
+    assert_eq!(
+        ranking,
+        [
+            (f.student_b.pubkey(), 2),
+            (f.student_a.pubkey(), 1),
+            (student_c.pubkey(), 0)
+        ]
+    );
+    for (address, progress) in &leaderboard {
+        assert_eq!(
+            *address,
+            course_batch_manager::student_progress_canonical_pda(
+                progress.student,
+                f.course_pda,
+                &f.batch_id
+            )
+        );
+    }
+    // progress of other batches is filtered out
+    assert!(f
+        .fetch_cohort_progress(Pubkey::new_unique())
+        .await?
+        .is_empty());
+}
+
+/// Test if plaintext answers are hashed on-chain and checked against the hash chain
+#[trdelnik_test]
+async fn test_check_plaintext(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result = f
+        .check_plaintext(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            b"Titov",
+        )
+        .await?;
+    assert!(!check_result.check_passed);
+    assert_eq!(check_result.attempts, 1);
+
+    let check_result = f
+        .check_plaintext(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.ground_truth_value.as_bytes(),
+        )
+        .await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 1);
+
+    // chains of many iterations are walked by the client
+    let assignment_id = *b"space_hero_slow_";
+    let checker_pda = f
+        .create_assignment_checker_with_iterations(assignment_id, false, 100)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id)
+        .await?;
+    let unavailable = f
+        .check_plaintext(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            f.ground_truth_value.as_bytes(),
+        )
+        .await;
+    assert!(unavailable.is_err());
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.attempts, 0);
+}
+
+/// Test if results of a closed checker don't count for the recreated one
+#[trdelnik_test]
+async fn test_checker_generation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(
+        f.get_course_account().await?.checker_generation,
+        checker.generation
+    );
+
+    let (_, old_check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(old_check_result.check_passed);
+    assert_eq!(old_check_result.generation, checker.generation);
+
+    course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    // the same address gets a fresh hash chain
+    f.create_assignment_checker(f.assignment_id, false).await?;
+    let recreated = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(recreated.generation, checker.generation + 1);
+    assert_eq!(
+        f.get_course_account().await?.checker_generation,
+        recreated.generation
+    );
+
+    // the pass of the closed checker is distinguishable and can't be checked again
+    assert!(old_check_result.is_stale(&recreated));
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+
+    // unused result of student_b takes the generation of the recreated checker
+    let (_, new_check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(new_check_result.check_passed);
+    assert_eq!(new_check_result.generation, recreated.generation);
+    assert!(!new_check_result.is_stale(&recreated));
+}
+
+/// Test if check events carry the context configured by event verbosity of the course
+#[trdelnik_test]
+async fn test_event_verbosity(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    assert_eq!(
+        f.get_course_account().await?.event_verbosity,
+        course_manager::EventVerbosity::Verbose
+    );
+    let value = f.ground_truth_value.as_bytes();
+    let length = f.hash_chain_length;
+
+    let tx = f
+        .client
+        .send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_a.pubkey(),
+                f.student_a_token_account,
+                length,
+                Fixture::hash(length - 1, &f.salt, &f.course_pda, &f.assignment_id, value),
+            )],
+            [&f.student_a],
+        )
+        .await?;
+    let checked = Fixture::events::<course_batch_manager::AssignmentChecked>(&tx);
+    assert_eq!(checked.len(), 1);
+    let (_, event) = &checked[0];
+    assert!(event.passed && event.first_time);
+    let details = event.details.expect("verbose event has details");
+    assert!(details.unix_ts > 0);
+    assert_eq!(details.outcome.minted, 100);
+    assert_eq!(details.outcome.remaining_capacity, length - 2);
+
+    let tx = course_manager_instruction::set_event_verbosity(
+        &f.client,
+        course_manager::EventVerbosity::Compact,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let set = Fixture::events::<course_manager::EventVerbositySet>(&tx);
+    assert_eq!(set.len(), 1);
+    assert_eq!(
+        set[0].1.event_verbosity,
+        course_manager::EventVerbosity::Compact
+    );
+
+    let tx = f
+        .client
+        .send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_b.pubkey(),
+                f.student_b_token_account,
+                length - 1,
+                Fixture::hash(length - 2, &f.salt, &f.course_pda, &f.assignment_id, value),
+            )],
+            [&f.student_b],
+        )
+        .await?;
+    let checked = Fixture::events::<course_batch_manager::AssignmentChecked>(&tx);
+    assert_eq!(checked.len(), 1);
+    let (_, event) = &checked[0];
+    assert!(event.passed && event.first_time);
+    assert!(event.details.is_none());
+}
+
+/// Test if courses with events off change state without logging events
+#[trdelnik_test]
+async fn test_emit_events(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_emit_events = |emit_events: bool| {
+        course_manager_instruction::set_emit_events(
+            &f.client,
+            emit_events,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    let check_instruction = |student: Pubkey, token_account: Pubkey, chain_position: u16| {
+        f.check_assignment_instruction(
+            student,
+            token_account,
+            chain_position,
+            Fixture::hash(
+                chain_position - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+        )
+    };
+    assert!(f.get_course_account().await?.emit_events);
+    let length = f.hash_chain_length;
+
+    // the switch itself is logged
+    let tx = set_emit_events(false).await?;
+    let set = Fixture::events::<course_manager::EmitEventsSet>(&tx);
+    assert_eq!(set.len(), 1);
+    assert!(!set[0].1.emit_events);
+    assert!(!f.get_course_account().await?.emit_events);
+
+    let tx = f
+        .client
+        .send_transaction(
+            &[check_instruction(
+                f.student_a.pubkey(),
+                f.student_a_token_account,
+                length,
+            )],
+            [&f.student_a],
+        )
+        .await?;
+    assert!(Fixture::events::<course_batch_manager::AssignmentChecked>(&tx).is_empty());
+    let outcome =
+        Fixture::return_data::<course_batch_manager::CheckOutcome>(&tx, course_batch_manager::ID)
+            .expect("check outcome is returned");
+    assert!(outcome.passed && outcome.first_time);
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert!(check_result.check_passed);
+    let progress = f.get_student_progress_account(f.student_a.pubkey()).await?;
+    assert_eq!(progress.assignments_passed, 1);
+    // batch settings follow the course too
+    let tx = course_batch_manager_instruction::set_referral_bps(
+        &f.client,
+        500,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(Fixture::events::<course_batch_manager::ReferralBpsSet>(&tx).is_empty());
+    assert_eq!(f.get_course_batch_account().await?.referral_bps, 500);
+
+    let tx = set_emit_events(true).await?;
+    assert_eq!(
+        Fixture::events::<course_manager::EmitEventsSet>(&tx).len(),
+        1
+    );
+    let tx = f
+        .client
+        .send_transaction(
+            &[check_instruction(
+                f.student_b.pubkey(),
+                f.student_b_token_account,
+                length - 1,
+            )],
+            [&f.student_b],
+        )
+        .await?;
+    let checked = Fixture::events::<course_batch_manager::AssignmentChecked>(&tx);
+    assert_eq!(checked.len(), 1);
+    assert!(checked[0].1.passed && checked[0].1.first_time);
+}
+
+/// Test if the pass rate gauge counts passes and failures but not repeats of passed checks
+#[trdelnik_test]
+async fn test_pass_rate(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check = |student: Keypair, token_account: Pubkey, parent: Option<[u8; 32]>| {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            parent,
+        )
+    };
+
+    let (_, check_result) = check(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        Some([7; 32]),
+    )
+    .await?;
+    assert!(!check_result.check_passed);
+    check(f.student_a.clone(), f.student_a_token_account, None).await?;
+    check(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        Some([8; 32]),
+    )
+    .await?;
+    check(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        Some([9; 32]),
+    )
+    .await?;
+    // the repeat leaves the gauge as is
+    let (_, check_result) = check(f.student_a.clone(), f.student_a_token_account, None).await?;
+    assert!(check_result.check_passed);
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.attempts_total, 4);
+    assert_eq!(checker.passes_total, 1);
+    assert_eq!(checker.pass_rate_bps(), 2_500);
+
+    let tx = course_batch_manager_instruction::describe_checker(
+        &f.client,
+        f.course_pda,
+        f.course_batch_pda,
+        f.assignment_checker_pda,
+        [f.student_b.clone()],
+    )
+    .await?;
+    let description: course_batch_manager::CheckerDescription =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("description is returned");
+    assert_eq!(description.attempts_total, 4);
+    assert_eq!(description.passes_total, 1);
+    assert_eq!(description.pass_rate_bps, 2_500);
+}
+
+/// Test if passing all assignments of a module marks it complete
+#[trdelnik_test]
+async fn test_module_completion(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_2____";
+    let second_checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    let checkers = [f.assignment_checker_pda, second_checker_pda];
+    let module_pda = f.create_module(*b"week_1__________", &checkers).await?;
+    let module = f
+        .client
+        .account_data::<course_manager::Module>(module_pda)
+        .await?;
+    assert_eq!(module.assignment_count, 2);
+    let checker = f.get_checker_account(second_checker_pda).await?;
+    assert_eq!(checker.module_id, module.module_id);
+
+    // the checker of the module stays until it's removed from the module
+    let closed = course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        second_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(&closed, "CheckerInModule"));
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    // one passed assignment doesn't complete the module, even if it's repeated
+    let repeated = [f.assignment_checker_pda, f.assignment_checker_pda];
+    assert!(f
+        .complete_module(f.student_a.clone(), module_pda, &repeated)
+        .await
+        .is_err());
+    assert!(f
+        .complete_module(f.student_a.clone(), module_pda, &checkers)
+        .await
+        .is_err());
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        second_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let tx = f
+        .complete_module(f.student_a.clone(), module_pda, &checkers)
+        .await?;
+    let completed = Fixture::events::<course_batch_manager::ModuleCompleted>(&tx);
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].1.module, module_pda);
+    let student_progress = f.get_student_progress_account(f.student_a.pubkey()).await?;
+    assert_eq!(student_progress.modules_completed, 1);
+    assert_eq!(student_progress.assignments_passed, 2);
+
+    // the module is completed once
+    assert!(f
+        .complete_module(f.student_a.clone(), module_pda, &checkers)
+        .await
+        .is_err());
+    assert_eq!(
+        f.get_student_progress_account(f.student_a.pubkey())
+            .await?
+            .modules_completed,
+        1
+    );
+}
+
+/// Test if the check receipt tells the first pass from its no-op repeat
+#[trdelnik_test]
+async fn test_check_receipt(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let value = f.ground_truth_value.as_bytes();
+    let length = f.hash_chain_length;
+    let check = |parent: [u8; 32]| {
+        f.client.send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_a.pubkey(),
+                f.student_a_token_account,
+                length,
+                parent,
+            )],
+            [&f.student_a],
+        )
+    };
+
+    let tx = check([0; 32]).await?;
+    let receipt = Fixture::check_receipt(&tx).expect("check has a receipt");
+    assert_eq!(receipt.assignment_checker, f.assignment_checker_pda);
+    assert!(!receipt.passed && !receipt.first_time && !receipt.slot_consumed);
+
+    let parent = Fixture::hash(length - 1, &f.salt, &f.course_pda, &f.assignment_id, value);
+    let tx = check(parent).await?;
+    let receipt = Fixture::check_receipt(&tx).expect("check has a receipt");
+    assert!(receipt.passed && receipt.first_time && receipt.slot_consumed);
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check returns its outcome");
+    assert!(outcome.slot_consumed);
+    assert_eq!(outcome.remaining_chain, length - 1);
+
+    // the repeat keeps the hash chain
+    let tx = check(parent).await?;
+    let receipt = Fixture::check_receipt(&tx).expect("check has a receipt");
+    assert!(receipt.passed && !receipt.first_time && !receipt.slot_consumed);
+    assert_eq!(
+        f.get_checker_account(f.assignment_checker_pda)
+            .await?
+            .hash_chain_length,
+        length - 1
+    );
+}
+
+/// Test if courses are created once per course ID of the authority
+#[trdelnik_test]
+async fn test_course_creation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let course = f.get_course_account().await?;
+    assert_eq!(course.id, f.course_id);
+    assert_eq!(course.authority, f.course_authority.pubkey());
+    assert!(course.created_at_unix_ts > 0);
+    assert_eq!(course.reward_mint, Pubkey::default());
+    assert_eq!(course.schema_version, course_manager::COURSE_SCHEMA_VERSION);
+
+    let create = |course_id: [u8; 16]| {
+        course_manager_instruction::create_new_course(
+            &f.client,
+            course_id,
+            Pubkey::default(),
+            f.course_authority.pubkey(),
+            course_manager::course_canonical_pda(f.course_authority.pubkey(), &course_id),
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    assert!(Fixture::is_program_error(
+        &create(f.course_id).await,
+        "CourseAlreadyExists"
+    ));
+    assert!(Fixture::is_program_error(
+        &create([0; 16]).await,
+        "InvalidCourseId"
+    ));
+
+    // the same ID of another authority is another course
+    let other_authority = keypair(3);
+    f.client
+        .airdrop(other_authority.pubkey(), 5_000_000)
+        .await?;
+    let other_course_pda =
+        course_manager::course_canonical_pda(other_authority.pubkey(), &f.course_id);
+    // lamports sent to the course address beforehand don't block the creation
+    let squatter = keypair(4);
+    f.client.airdrop(squatter.pubkey(), 5_000_000).await?;
+    f.client
+        .send_transaction(
+            &[solana_sdk::system_instruction::transfer(
+                &squatter.pubkey(),
+                &other_course_pda,
+                1_000_000,
+            )],
+            [&squatter],
+        )
+        .await?;
+    let create_with_reward_mint = |reward_mint: Pubkey, passed_mint: Option<Pubkey>| {
+        f.client.send_instruction(
+            course_manager::ID,
+            course_manager::instruction::CreateNewCourse {
+                course_id: f.course_id,
+                reward_mint,
+            },
+            WithRemainingAccounts {
+                accounts: course_manager::accounts::NewCourse {
+                    course_authority: other_authority.pubkey(),
+                    course: other_course_pda,
+                    system_program: system_program::ID,
+                },
+                remaining_accounts: passed_mint
+                    .into_iter()
+                    .map(|mint| AccountMeta::new_readonly(mint, false))
+                    .collect(),
+            },
+            [other_authority.clone()],
+        )
+    };
+    let reward_mint = f.course_batch_mint_pda;
+    // the reward mint is passed and is a token mint
+    assert!(Fixture::is_program_error(
+        &create_with_reward_mint(reward_mint, None).await,
+        "InvalidRewardMint"
+    ));
+    assert!(Fixture::is_program_error(
+        &create_with_reward_mint(f.course_pda, Some(f.course_pda)).await,
+        "InvalidRewardMint"
+    ));
+    create_with_reward_mint(reward_mint, Some(reward_mint)).await?;
+    let other_course = f
+        .client
+        .account_data::<course_manager::Course>(other_course_pda)
+        .await?;
+    assert_eq!(other_course.authority, other_authority.pubkey());
+    assert_eq!(other_course.assignment_count, 0);
+    assert_eq!(other_course.reward_mint, reward_mint);
+
+    // current courses are left as is by the upgrade
+    course_manager_instruction::upgrade_course(
+        &f.client,
+        other_authority.pubkey(),
+        other_course_pda,
+        system_program::ID,
+        [other_authority.clone()],
+    )
+    .await?;
+    assert_eq!(
+        f.client
+            .get_account(other_course_pda)
+            .await?
+            .unwrap()
+            .data
+            .len(),
+        8 + course_manager::Course::LEN
+    );
+    // only the course authority upgrades the course
+    assert!(course_manager_instruction::upgrade_course(
+        &f.client,
+        f.course_authority.pubkey(),
+        other_course_pda,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+}
+
+/// Test if only students who haven't passed the assignment need to check it
+#[trdelnik_test]
+async fn test_needs_check(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    assert!(f.needs_check(f.student_a.pubkey(), f.assignment_id).await?);
+    // student without check result
+    assert!(f.needs_check(keypair(3).pubkey(), f.assignment_id).await?);
+
+    let value = f.ground_truth_value.as_bytes();
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        value,
+        Some([0; 32]),
+    )
+    .await?;
+    assert!(f.needs_check(f.student_a.pubkey(), f.assignment_id).await?);
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        value,
+        None,
+    )
+    .await?;
+    assert!(!f.needs_check(f.student_a.pubkey(), f.assignment_id).await?);
+    assert!(f.needs_check(f.student_b.pubkey(), f.assignment_id).await?);
+}
+
+/// Test if a student with extra attempts can fail more checks than `max_attempts`
+#[trdelnik_test]
+async fn test_extra_attempts(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    course_batch_manager_instruction::set_max_attempts(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let check_result_pda = |student: Pubkey| {
+        course_batch_manager::check_result_canonical_pda(student, f.course_pda, &f.assignment_id)
+    };
+    let grant = |extra_attempts: u16| {
+        course_batch_manager_instruction::grant_extra_attempts(
+            &f.client,
+            extra_attempts,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.student_a.pubkey(),
+            check_result_pda(f.student_a.pubkey()),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    grant(1).await?;
+    let tx = grant(1).await?;
+    let granted = Fixture::events::<course_batch_manager::ExtraAttemptsGranted>(&tx);
+    assert_eq!(granted.len(), 1);
+    assert_eq!(granted[0].1.extra_attempts, 2);
+
+    let length = f.hash_chain_length;
+    // wrong parents differ, so transactions of the attempts differ too
+    let fail = |student: &Keypair, token_account: Pubkey, attempt: u8| {
+        f.client.send_transaction(
+            &[f.check_assignment_instruction(
+                student.pubkey(),
+                token_account,
+                length,
+                [attempt; 32],
+            )],
+            [student],
+        )
+    };
+    let result_closed = |tx: &EncodedConfirmedTransactionWithStatusMeta| {
+        let outcome: course_batch_manager::CheckOutcome =
+            Fixture::return_data(tx, course_batch_manager::ID).expect("check outcome is returned");
+        outcome.result_closed
+    };
+
+    // the student without extra attempts is limited by the checker
+    assert!(!result_closed(
+        &fail(&f.student_b, f.student_b_token_account, 1).await?
+    ));
+    assert!(result_closed(
+        &fail(&f.student_b, f.student_b_token_account, 2).await?
+    ));
+    assert!(f
+        .client
+        .get_account(check_result_pda(f.student_b.pubkey()))
+        .await?
+        .is_none());
+
+    for attempt in 1..=3 {
+        assert!(!result_closed(
+            &fail(&f.student_a, f.student_a_token_account, attempt).await?
+        ));
+    }
+    let check_result = f
+        .get_check_result_account(check_result_pda(f.student_a.pubkey()))
+        .await?;
+    assert_eq!(check_result.attempts, 3);
+    assert_eq!(check_result.extra_attempts, 2);
+    assert!(result_closed(
+        &fail(&f.student_a, f.student_a_token_account, 4).await?
+    ));
+    assert!(f
+        .client
+        .get_account(check_result_pda(f.student_a.pubkey()))
+        .await?
+        .is_none());
+    // the check result recreated after the extension keeps the extra attempts
+    let exhausted = f
+        .client
+        .account_data::<course_batch_manager::ExhaustedCheckResult>(
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda(
+                f.student_a.pubkey(),
+            )),
+        )
+        .await?;
+    assert_eq!(exhausted.extra_attempts, 2);
+    assert_eq!(exhausted.assignment_checker, f.assignment_checker_pda);
+}
+
+/// Test if checks paying rewards to token accounts of another mint fail clearly
+#[trdelnik_test]
+async fn test_wrong_reward_mint(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    // student A has a token account of another batch of the course
+    let batch_id = *b"other_batch_____";
+    let course_batch_pda = course_batch_manager::batch_canonical_pda(f.course_pda, &batch_id);
+    let mint_pda = course_batch_manager::batch_mint_canonical_pda(f.course_pda, &batch_id);
+    course_batch_manager_instruction::create_new_batch(
+        &f.client,
+        batch_id,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        mint_pda,
+        course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &batch_id),
+        system_program::ID,
+        rent::id(),
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let other_token_account = get_associated_token_address(&f.student_a.pubkey(), &mint_pda);
+    course_batch_manager_instruction::enroll_batch(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        mint_pda,
+        other_token_account,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        associated_token::ID,
+        rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+
+    let length = f.hash_chain_length;
+    let parent = Fixture::hash(
+        length - 1,
+        &f.salt,
+        &f.course_pda,
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    let check = |token_account: Pubkey| {
+        f.client.send_transaction(
+            &[f.check_assignment_instruction(f.student_a.pubkey(), token_account, length, parent)],
+            [&f.student_a],
+        )
+    };
+    assert!(Fixture::is_program_error(
+        &check(other_token_account).await,
+        "WrongRewardMint"
+    ));
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    assert!(
+        !f.get_check_result_account(check_result_pda)
+            .await?
+            .check_passed
+    );
+
+    check(f.student_a_token_account).await?;
+    assert!(
+        f.get_check_result_account(check_result_pda)
+            .await?
+            .check_passed
+    );
+}
+
+/// Test if only the call that passes first is first time while the pass stays persisted
+#[trdelnik_test]
+async fn test_first_time_signal(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let value = f.ground_truth_value.as_bytes();
+    let length = f.hash_chain_length;
+    let parent = Fixture::hash(length - 1, &f.salt, &f.course_pda, &f.assignment_id, value);
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    // repeats ignore the expected length, it makes their transactions differ
+    let check = |expected_hash_chain_length: u16| {
+        f.client.send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_a.pubkey(),
+                f.student_a_token_account,
+                expected_hash_chain_length,
+                parent,
+            )],
+            [&f.student_a],
+        )
+    };
+    let outcome = |tx: &EncodedConfirmedTransactionWithStatusMeta| {
+        Fixture::return_data::<course_batch_manager::CheckOutcome>(tx, course_batch_manager::ID)
+            .expect("check outcome is returned")
+    };
+
+    let first = outcome(&check(length).await?);
+    assert!(first.passed && first.first_time);
+    for expected_hash_chain_length in [length - 1, length - 2] {
+        let repeat = outcome(&check(expected_hash_chain_length).await?);
+        assert!(repeat.passed && !repeat.first_time);
+        assert_eq!(repeat.minted, 0);
+        let check_result = f.get_check_result_account(check_result_pda).await?;
+        assert!(check_result.check_passed && check_result.passed_first_time);
+    }
+}
+
+/// Test if claimed rewards scale with the price of the price feed of the batch
+#[trdelnik_test]
+async fn test_reward_price_conversion(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_rewards_frozen = |frozen: bool| {
+        course_batch_manager_instruction::set_rewards_frozen(
+            &f.client,
+            frozen,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            token::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    // both students have claimable rewards
+    set_rewards_frozen(true).await?;
+    for (student, token_account) in [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+    ] {
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.reward_claimable, 100);
+    }
+    set_rewards_frozen(false).await?;
+
+    let oracle = keypair(3);
+    f.client.airdrop(oracle.pubkey(), 5_000_000).await?;
+    let feed_id = *b"token_usd_______";
+    let price_feed_pda = course_batch_manager::price_feed_canonical_pda(oracle.pubkey(), &feed_id);
+    course_batch_manager_instruction::create_price_feed(
+        &f.client,
+        feed_id,
+        2_000_000,
+        oracle.pubkey(),
+        price_feed_pda,
+        system_program::ID,
+        [oracle.clone()],
+    )
+    .await?;
+    let reward_conversion = course_batch_manager::RewardConversion {
+        price_feed: price_feed_pda,
+        reference_price: 1_000_000,
+        min_bps: 2_500,
+        max_bps: 15_000,
+    };
+    let invalid = course_batch_manager_instruction::set_reward_conversion(
+        &f.client,
+        course_batch_manager::RewardConversion {
+            min_bps: 20_000,
+            ..reward_conversion
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(
+        &invalid,
+        "InvalidRewardConversion"
+    ));
+    course_batch_manager_instruction::set_reward_conversion(
+        &f.client,
+        reward_conversion,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let claim = |student: &Keypair, token_account: Pubkey, price_feed: Option<Pubkey>| {
+        f.client.send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::ClaimReward {},
+            WithRemainingAccounts {
+                accounts: course_batch_manager::accounts::ClaimReward {
+                    student: student.pubkey(),
+                    course: f.course_pda,
+                    course_batch: f.course_batch_pda,
+                    check_result: course_batch_manager::check_result_canonical_pda(
+                        student.pubkey(),
+                        f.course_pda,
+                        &f.assignment_id,
+                    ),
+                    student_progress: course_batch_manager::student_progress_canonical_pda(
+                        student.pubkey(),
+                        f.course_pda,
+                        &f.batch_id,
+                    ),
+                    escrow: f.course_batch_escrow_pda,
+                    mint: f.course_batch_mint_pda,
+                    course_batch_token: token_account,
+                    token_program: token::ID,
+                    assignment_checker_program: assignment_checker::ID,
+                    course_batch_manager_program: course_batch_manager::ID,
+                },
+                remaining_accounts: price_feed
+                    .into_iter()
+                    .map(|price_feed| AccountMeta::new_readonly(price_feed, false))
+                    .collect(),
+            },
+            [student.clone()],
+        )
+    };
+    assert!(Fixture::is_program_error(
+        &claim(&f.student_a, f.student_a_token_account, None).await,
+        "PriceFeedMissing"
+    ));
+
+    // the token is twice as expensive as the reference price
+    let tx = claim(
+        &f.student_a,
+        f.student_a_token_account,
+        Some(price_feed_pda),
+    )
+    .await?;
+    let claimed = Fixture::events::<course_batch_manager::RewardClaimed>(&tx);
+    assert_eq!(claimed[0].1.amount, 50);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "50");
+
+    // the cheap token pays more up to the max bound
+    let not_oracle = course_batch_manager_instruction::update_price_feed(
+        &f.client,
+        100_000,
+        f.student_b.pubkey(),
+        price_feed_pda,
+        [f.student_b.clone()],
+    )
+    .await;
+    assert!(not_oracle.is_err());
+    course_batch_manager_instruction::update_price_feed(
+        &f.client,
+        100_000,
+        oracle.pubkey(),
+        price_feed_pda,
+        [oracle.clone()],
+    )
+    .await?;
+    claim(
+        &f.student_b,
+        f.student_b_token_account,
+        Some(price_feed_pda),
+    )
+    .await?;
+    let balance = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "150");
+    assert_eq!(f.get_course_batch_account().await?.paid_rewards, 200);
+}
+
+/// Test if checks of a checker with another course fail with a clear has_one error
+#[trdelnik_test]
+async fn test_checker_course_binding(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.course, f.course_pda);
+
+    // another course of the authority with a batch the student is enrolled into
+    let other_course_id = *b"other_course_id_";
+    let other_course_pda =
+        course_manager::course_canonical_pda(f.course_authority.pubkey(), &other_course_id);
+    course_manager_instruction::create_new_course(
+        &f.client,
+        other_course_id,
+        Pubkey::default(),
+        f.course_authority.pubkey(),
+        other_course_pda,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let other_batch_pda = course_batch_manager::batch_canonical_pda(other_course_pda, &f.batch_id);
+    let other_mint_pda =
+        course_batch_manager::batch_mint_canonical_pda(other_course_pda, &f.batch_id);
+    let other_escrow_pda =
+        course_batch_manager::batch_escrow_canonical_pda(other_course_pda, &f.batch_id);
+    course_batch_manager_instruction::create_new_batch(
+        &f.client,
+        f.batch_id,
+        f.course_authority.pubkey(),
+        other_course_pda,
+        other_batch_pda,
+        other_mint_pda,
+        other_escrow_pda,
+        system_program::ID,
+        rent::id(),
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let other_token_account = get_associated_token_address(&f.student_a.pubkey(), &other_mint_pda);
+    let other_progress_pda = course_batch_manager::student_progress_canonical_pda(
+        f.student_a.pubkey(),
+        other_course_pda,
+        &f.batch_id,
+    );
+    course_batch_manager_instruction::enroll_batch(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_authority.pubkey(),
+        other_course_pda,
+        other_batch_pda,
+        other_mint_pda,
+        other_token_account,
+        other_progress_pda,
+        system_program::ID,
+        token::ID,
+        associated_token::ID,
+        rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+
+    let mut instruction = f.check_assignment_instruction(
+        f.student_a.pubkey(),
+        other_token_account,
+        f.hash_chain_length,
+        [0; 32],
+    );
+    let check_result = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    instruction.accounts = course_batch_manager::accounts::CheckAssignment {
+        student: f.student_a.pubkey(),
+        course: other_course_pda,
+        course_batch: other_batch_pda,
+        assignment_checker: f.assignment_checker_pda,
+        check_result,
+        exhausted_check_result: course_batch_manager::exhausted_check_result_canonical_pda(
+            check_result,
+        ),
+        mint: other_mint_pda,
+        course_batch_token: other_token_account,
+        escrow: other_escrow_pda,
+        student_progress: other_progress_pda,
+        system_program: system_program::ID,
+        token_program: token::ID,
+        assignment_checker_program: assignment_checker::ID,
+        course_batch_manager_program: course_batch_manager::ID,
+    }
+    .to_account_metas(None);
+    let res = f
+        .client
+        .send_transaction(&[instruction], [&f.student_a])
+        .await;
+    assert!(Fixture::is_program_error(&res, "ConstraintHasOne"));
+    let unchanged = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(unchanged.hash_chain_length, checker.hash_chain_length);
+}
+
+/// Test if hash chain length of a new batch is sized by passers of prior batches
+#[trdelnik_test]
+async fn test_historical_hash_chain_length(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let course = f.get_course_account().await?;
+    // no passes yet
+    let cohort = f.fetch_cohort_progress(f.course_batch_pda).await?;
+    assert_eq!(
+        course_batch_manager::historical_hash_chain_length(
+            &course,
+            cohort.into_iter().map(|(_, progress)| progress)
+        ),
+        course.suggested_hash_chain_length()
+    );
+
+    for (student, token_account) in [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+    ] {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    }
+    let cohort: Vec<_> = f
+        .fetch_cohort_progress(f.course_batch_pda)
+        .await?
+        .into_iter()
+        .map(|(_, progress)| progress)
+        .collect();
+    // 2 passers need 3 slots and the chain start
+    assert_eq!(
+        course_batch_manager::historical_hash_chain_length(&course, cohort.clone()),
+        4
+    );
+
+    // busier prior batch with 3 passers and a student without passes
+    let prior_batch = Pubkey::new_unique();
+    let prior_cohort = (0..4).map(|i| course_batch_manager::StudentProgress {
+        student: Pubkey::new_unique(),
+        course_batch: prior_batch,
+        assignments_passed: if i < 3 { 2 } else { 0 },
+        certificate_minted: false,
+        bump_seed: 0,
+        current_streak: 0,
+        modules_completed: 0,
+        pending_rewards: 0,
+    });
+    assert_eq!(
+        course_batch_manager::historical_hash_chain_length(
+            &course,
+            cohort.into_iter().chain(prior_cohort)
+        ),
+        6
+    );
+}
+
+/// Test if students verify candidate parents without consuming slots of the chain
+#[trdelnik_test]
+async fn test_verify_candidate_parent(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let verify = |student: &Keypair,
+                  assignment_id: [u8; 16],
+                  assignment_checker_pda: Pubkey,
+                  parent: [u8; 32]| {
+        course_batch_manager_instruction::verify_candidate_parent(
+            &f.client,
+            assignment_id,
+            parent,
+            student.pubkey(),
+            f.course_pda,
+            assignment_checker_pda,
+            course_batch_manager::check_result_canonical_pda(
+                student.pubkey(),
+                f.course_pda,
+                &assignment_id,
+            ),
+            [student.clone()],
+        )
+    };
+    let verified = |tx: EncodedConfirmedTransactionWithStatusMeta| {
+        Fixture::return_data::<bool>(&tx, course_batch_manager::ID)
+            .expect("verification is returned")
+    };
+
+    // shared chain
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    let parent = checker.commitment_mode.build_iterated_tail(
+        &checker.salt,
+        &f.course_pda.to_bytes(),
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+        checker.hash_chain_length - 1,
+        1,
+    );
+    assert!(verified(
+        verify(
+            &f.student_a,
+            f.assignment_id,
+            f.assignment_checker_pda,
+            parent
+        )
+        .await?
+    ));
+    assert!(!verified(
+        verify(
+            &f.student_a,
+            f.assignment_id,
+            f.assignment_checker_pda,
+            [0; 32]
+        )
+        .await?
+    ));
+    let unchanged = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(unchanged.hash_chain_length, checker.hash_chain_length);
+
+    // per student salt
+    let assignment_id = *b"space_hero_solo_";
+    let assignment_checker_pda = f.create_assignment_checker(assignment_id, true).await?;
+    let mut student_parents = Vec::new();
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        let check_result_pda = f
+            .create_check_result(student.clone(), assignment_id)
+            .await?;
+        let parent = assignment_checker::student_hash_chain_tail_parent(
+            &f.salt,
+            &student.pubkey(),
+            f.ground_truth_value.as_bytes(),
+        );
+        course_batch_manager_instruction::set_student_commitment(
+            &f.client,
+            blake3::hash(&parent).0,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            assignment_checker_pda,
+            student.pubkey(),
+            check_result_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        student_parents.push(parent);
+    }
+    assert!(verified(
+        verify(
+            &f.student_a,
+            assignment_id,
+            assignment_checker_pda,
+            student_parents[0]
+        )
+        .await?
+    ));
+    // the candidate of student_a doesn't verify for student_b
+    assert!(!verified(
+        verify(
+            &f.student_b,
+            assignment_id,
+            assignment_checker_pda,
+            student_parents[0]
+        )
+        .await?
+    ));
+    assert!(verified(
+        verify(
+            &f.student_b,
+            assignment_id,
+            assignment_checker_pda,
+            student_parents[1]
+        )
+        .await?
+    ));
+
+    // the verified candidate passes the check
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(student_parents[0]),
+        )
+        .await?;
+    assert!(check_result.check_passed);
+}
+
+/// Test if students self-enroll only into open courses
+#[trdelnik_test]
+async fn test_self_enroll_allowed(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    assert!(f.get_course_account().await?.self_enroll_allowed);
+    let set_self_enroll_allowed = |self_enroll_allowed: bool| {
+        course_manager_instruction::set_self_enroll_allowed(
+            &f.client,
+            self_enroll_allowed,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    set_self_enroll_allowed(false).await?;
+    assert!(!f.get_course_account().await?.self_enroll_allowed);
+
+    let enroll_instruction = |student: Pubkey, authority_signs: bool| {
+        let mut accounts = course_batch_manager::accounts::EnrollBatch {
+            student,
+            authority: f.course_authority.pubkey(),
+            course: f.course_pda,
+            course_batch: f.course_batch_pda,
+            mint: f.course_batch_mint_pda,
+            course_batch_token: get_associated_token_address(&student, &f.course_batch_mint_pda),
+            student_progress: course_batch_manager::student_progress_canonical_pda(
+                student,
+                f.course_pda,
+                &f.batch_id,
+            ),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            rent: rent::id(),
+            course_manager_program: course_manager::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+        }
+        .to_account_metas(None);
+        accounts[1].is_signer = authority_signs;
+        solana_sdk::instruction::Instruction {
+            program_id: course_batch_manager::ID,
+            accounts,
+            data: course_batch_manager::instruction::EnrollBatch {}.data(),
+        }
+    };
+    let student = keypair(3);
+    f.client.airdrop(student.pubkey(), 5_000_000).await?;
+    let res = f
+        .client
+        .send_transaction(&[enroll_instruction(student.pubkey(), false)], [&student])
+        .await;
+    assert!(Fixture::is_program_error(&res, "SelfEnrollNotAllowed"));
+
+    // the course authority invites the student
+    f.client
+        .send_transaction(
+            &[enroll_instruction(student.pubkey(), true)],
+            [&student, &f.course_authority],
+        )
+        .await?;
+    let progress = f.get_student_progress_account(student.pubkey()).await?;
+    assert_eq!(progress.course_batch, f.course_batch_pda);
+
+    // open course
+    set_self_enroll_allowed(true).await?;
+    let open_student = keypair(4);
+    f.client.airdrop(open_student.pubkey(), 5_000_000).await?;
+    f.enroll_student(open_student).await?;
+    assert_eq!(f.get_course_account().await?.enrolled_count, 4);
+}
+
+/// Test if students and authorities of ended batches close enrollments without pending rewards
+#[trdelnik_test]
+async fn test_close_enrollment(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let client = &f.client;
+    let lamports = |address: Pubkey| async move {
+        client
+            .get_account(address)
+            .await
+            .map(|account| account.map_or(0, |account| account.lamports))
+    };
+    let student_progress_pda = |student: Pubkey| {
+        course_batch_manager::student_progress_canonical_pda(student, f.course_pda, &f.batch_id)
+    };
+    let close = |closer: &Keypair, student: Pubkey| {
+        course_batch_manager_instruction::close_enrollment(
+            &f.client,
+            closer.pubkey(),
+            student,
+            f.course_pda,
+            f.course_batch_pda,
+            student_progress_pda(student),
+            course_manager::ID,
+            course_batch_manager::ID,
+            [closer.clone()],
+        )
+    };
+
+    // passes paid on check don't keep the enrollment
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    // students with escrowed rewards keep their enrollment
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        60,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let student_progress = f.get_student_progress_account(f.student_b.pubkey()).await?;
+    assert_eq!(student_progress.pending_rewards, 100);
+    assert!(Fixture::is_program_error(
+        &close(&f.student_b, f.student_b.pubkey()).await,
+        "EnrollmentHasProgress"
+    ));
+    let stranger = keypair(3);
+    f.client.airdrop(stranger.pubkey(), 5_000_000).await?;
+    assert!(Fixture::is_program_error(
+        &close(&stranger, f.student_a.pubkey()).await,
+        "NotEnrollmentCloser"
+    ));
+    // course authority waits for the end of the batch
+    assert!(Fixture::is_program_error(
+        &close(&f.course_authority, f.student_a.pubkey()).await,
+        "NotEnrollmentCloser"
+    ));
+
+    // the dropped student closes own enrollment
+    let rent = lamports(student_progress_pda(f.student_a.pubkey())).await?;
+    let student_lamports = lamports(f.student_a.pubkey()).await?;
+    let tx = close(&f.student_a, f.student_a.pubkey()).await?;
+    let closed = Fixture::events::<course_batch_manager::EnrollmentClosed>(&tx);
+    assert_eq!(closed[0].1.student, f.student_a.pubkey());
+    assert!(f
+        .client
+        .get_account(student_progress_pda(f.student_a.pubkey()))
+        .await?
+        .is_none());
+    assert_eq!(
+        lamports(f.student_a.pubkey()).await?,
+        student_lamports + rent
+    );
+    assert_eq!(f.get_course_account().await?.enrolled_count, 1);
+
+    // the student re-enrolls with the existing token account
+    let token_account = f.enroll_student(f.student_a.clone()).await?;
+    assert_eq!(token_account, f.student_a_token_account);
+    assert_eq!(f.get_course_account().await?.enrolled_count, 2);
+
+    // course authority closes the enrollment of the student after the end of the batch
+    let tx = course_batch_manager_instruction::end_course_batch(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let ended = Fixture::events::<course_batch_manager::CourseBatchEnded>(&tx);
+    assert_eq!(ended[0].1.course_batch, f.course_batch_pda);
+    assert!(f.get_course_batch_account().await?.ended);
+    let student_lamports = lamports(f.student_a.pubkey()).await?;
+    close(&f.course_authority, f.student_a.pubkey()).await?;
+    assert_eq!(
+        lamports(f.student_a.pubkey()).await?,
+        student_lamports + rent
+    );
+    assert_eq!(f.get_course_account().await?.enrolled_count, 1);
+}
+
+/// Test if checkers created with zero salt derive salts from the master secret of the course
+#[trdelnik_test]
+async fn test_derived_salt(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let master_secret_hash = hash_chain::master_secret_hash(b"one secret of the course");
+    course_manager_instruction::set_master_secret_hash(
+        &f.client,
+        master_secret_hash,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert_eq!(
+        f.get_course_account().await?.master_secret_hash,
+        master_secret_hash
+    );
+
+    let mut salts = Vec::new();
+    for assignment_id in [*b"derived_salt_1st", *b"derived_salt_2nd"] {
+        // the course authority derives the salt off-chain to build the tail
+        let salt = hash_chain::derive_salt(
+            &master_secret_hash,
+            &f.course_pda.to_bytes(),
+            &assignment_id,
+        );
+        let assignment_checker_pda =
+            course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+        course_batch_manager_instruction::create_assignment_checker(
+            &f.client,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length: f.hash_chain_length,
+                to_mint_on_successful_check: 100,
+                salt: [0; 32],
+                per_student_salt: false,
+                hash_iterations_per_step: 1,
+                ground_truth_hash_chain_tail: hash_chain::build_tail(
+                    &salt,
+                    &f.course_pda.to_bytes(),
+                    &assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                    f.hash_chain_length,
+                ),
+            },
+            f.course_authority.pubkey(),
+            f.course_pda,
+            assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        let checker = f.get_checker_account(assignment_checker_pda).await?;
+        assert_eq!(checker.salt, salt);
+        salts.push(salt);
+
+        f.create_check_result(f.student_a.clone(), assignment_id)
+            .await?;
+        let (_, check_result) = f
+            .check_assignment(
+                f.student_a.clone(),
+                f.student_a_token_account,
+                assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert!(check_result.check_passed);
+    }
+    assert_ne!(salts[0], salts[1]);
+}
+
+/// Test if assignment states of students are read without check results
+#[trdelnik_test]
+async fn test_assignment_state(#[future] start_course_batch: Result<Fixture>) {
+    use course_batch_manager::AssignmentState::*;
+    let f = start_course_batch.await?;
+    let probe = |student: Pubkey, payer: &Keypair| {
+        course_batch_manager_instruction::get_assignment_state(
+            &f.client,
+            f.assignment_id,
+            student,
+            f.course_pda,
+            course_batch_manager::check_result_canonical_pda(
+                student,
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            [payer.clone()],
+        )
+    };
+    let state = |tx: EncodedConfirmedTransactionWithStatusMeta| {
+        Fixture::return_data::<course_batch_manager::AssignmentState>(&tx, course_batch_manager::ID)
+            .expect("assignment state is returned")
+    };
+
+    // never attempted, with and without check result
+    let stranger = keypair(3).pubkey();
+    assert_eq!(
+        f.assignment_state(stranger, f.assignment_id).await?,
+        NotAttempted
+    );
+    assert_eq!(state(probe(stranger, &f.student_b).await?), NotAttempted);
+    assert_eq!(
+        f.assignment_state(f.student_a.pubkey(), f.assignment_id)
+            .await?,
+        NotAttempted
+    );
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        Some([0; 32]),
+    )
+    .await?;
+    assert_eq!(
+        f.assignment_state(f.student_a.pubkey(), f.assignment_id)
+            .await?,
+        Failed
+    );
+    assert_eq!(
+        state(probe(f.student_a.pubkey(), &f.student_a).await?),
+        Failed
+    );
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    assert_eq!(
+        f.assignment_state(f.student_a.pubkey(), f.assignment_id)
+            .await?,
+        Passed
+    );
+    assert_eq!(
+        state(probe(f.student_a.pubkey(), &f.student_b).await?),
+        Passed
+    );
+}
+
+/// Test if passes stop when only the reserved chain start of length 1 is left
+#[trdelnik_test]
+async fn test_hash_chain_length_boundary(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let create_checker = |assignment_id: [u8; 16], hash_chain_length: u16| {
+        course_batch_manager_instruction::create_assignment_checker(
+            &f.client,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length,
+                to_mint_on_successful_check: 100,
+                salt: f.salt,
+                per_student_salt: false,
+                hash_iterations_per_step: 1,
+                ground_truth_hash_chain_tail: hash_chain::build_tail(
+                    &f.salt,
+                    &f.course_pda.to_bytes(),
+                    &assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                    hash_chain_length,
+                ),
+            },
+            f.course_authority.pubkey(),
+            f.course_pda,
+            course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    // parent of the given length, length 0 stands for the chain start
+    let check = |student: &Keypair,
+                 token_account: Pubkey,
+                 assignment_id: [u8; 16],
+                 expected_hash_chain_length: u16| {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student.pubkey(),
+            f.course_pda,
+            &assignment_id,
+        );
+        course_batch_manager_instruction::check_assignment(
+            &f.client,
+            expected_hash_chain_length,
+            hash_chain::build_tail(
+                &f.salt,
+                &f.course_pda.to_bytes(),
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+                expected_hash_chain_length.saturating_sub(1),
+            ),
+            None,
+            0,
+            student.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id),
+            check_result_pda,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+            f.course_batch_mint_pda,
+            token_account,
+            f.course_batch_escrow_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                student.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student.clone()],
+        )
+    };
+
+    // length 2 has one slot
+    let assignment_id = *b"boundary_chain_2";
+    create_checker(assignment_id, 2).await?;
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        f.create_check_result(student, assignment_id).await?;
+    }
+    check(&f.student_a, f.student_a_token_account, assignment_id, 2).await?;
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    let checker = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, 1);
+    assert_eq!(checker.remaining_capacity(), 0);
+    assert!(
+        f.get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &assignment_id,
+        ))
+        .await?
+        .check_passed
+    );
+
+    // length 1 keeps the reserved chain start
+    assert!(Fixture::is_program_error(
+        &check(&f.student_b, f.student_b_token_account, assignment_id, 1).await,
+        "ZeroHashChainLength"
+    ));
+    assert_eq!(
+        f.get_checker_account(checker_pda).await?.hash_chain_length,
+        1
+    );
+
+    // length 0 has nothing to check
+    let assignment_id = *b"boundary_chain_0";
+    create_checker(assignment_id, 0).await?;
+    f.create_check_result(f.student_b.clone(), assignment_id)
+        .await?;
+    assert!(Fixture::is_program_error(
+        &check(&f.student_b, f.student_b_token_account, assignment_id, 0).await,
+        "ZeroHashChainLength"
+    ));
+}
+
+/// Test if only authorized proctors check on behalf of students and passes are the students'
+#[trdelnik_test]
+async fn test_proctored_check(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_proctor = |proctor: Pubkey, proctored_checks_allowed: bool| {
+        course_manager_instruction::set_proctor(
+            &f.client,
+            proctor,
+            proctored_checks_allowed,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    let parent = checker.commitment_mode.build_iterated_tail(
+        &checker.salt,
+        &f.course_pda.to_bytes(),
+        &checker.assignment_id,
+        f.ground_truth_value.as_bytes(),
+        checker.hash_chain_length - 1,
+        1,
+    );
+    let proctored_check = |proctor: &Keypair, student: Pubkey| {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student,
+            f.course_pda,
+            &f.assignment_id,
+        );
+        course_batch_manager_instruction::proctored_check(
+            &f.client,
+            checker.hash_chain_length,
+            parent,
+            0,
+            proctor.pubkey(),
+            student,
+            f.course_pda,
+            f.course_batch_pda,
+            f.assignment_checker_pda,
+            check_result_pda,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+            course_batch_manager::student_progress_canonical_pda(
+                student,
+                f.course_pda,
+                &f.batch_id,
+            ),
+            system_program::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [proctor.clone()],
+        )
+    };
+    let proctor = keypair(3);
+    let stranger = keypair(4);
+    f.client.airdrop(proctor.pubkey(), 5_000_000).await?;
+    f.client.airdrop(stranger.pubkey(), 5_000_000).await?;
+
+    // proctored checks are off for new courses
+    assert!(Fixture::is_program_error(
+        &proctored_check(&proctor, f.student_a.pubkey()).await,
+        "ProctorNotAuthorized"
+    ));
+    set_proctor(proctor.pubkey(), true).await?;
+    let course = f.get_course_account().await?;
+    assert_eq!(course.proctor, proctor.pubkey());
+    assert!(course.proctored_checks_allowed);
+    assert!(Fixture::is_program_error(
+        &proctored_check(&stranger, f.student_a.pubkey()).await,
+        "ProctorNotAuthorized"
+    ));
+
+    let tx = proctored_check(&proctor, f.student_a.pubkey()).await?;
+    let outcome =
+        Fixture::return_data::<course_batch_manager::CheckOutcome>(&tx, course_batch_manager::ID)
+            .expect("check outcome is returned");
+    assert!(outcome.passed && outcome.first_time);
+    assert_eq!(outcome.minted, 0);
+    let submitted = Fixture::events::<course_batch_manager::ProctoredCheckSubmitted>(&tx);
+    assert_eq!(submitted[0].1.student, f.student_a.pubkey());
+    assert_eq!(submitted[0].1.proctor, proctor.pubkey());
+
+    // the pass and its reward belong to the student
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let check_result = f.get_check_result_account(check_result_pda).await?;
+    assert!(check_result.check_passed);
+    assert_eq!(check_result.proctor, proctor.pubkey());
+    assert_eq!(check_result.reward_claimable, outcome.claimable);
+    let progress = f.get_student_progress_account(f.student_a.pubkey()).await?;
+    assert_eq!(progress.assignments_passed, 1);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker
+        .recent_passers
+        .iter()
+        .any(|passer| *passer == f.student_a.pubkey()));
+    course_batch_manager_instruction::claim_reward(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        check_result_pda,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        f.course_batch_escrow_pda,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount, outcome.claimable.to_string());
+
+    // checks submitted by the student record no proctor
+    set_proctor(proctor.pubkey(), false).await?;
+    assert!(Fixture::is_program_error(
+        &proctored_check(&proctor, f.student_b.pubkey()).await,
+        "ProctorNotAuthorized"
+    ));
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert!(check_result.check_passed);
+    assert_eq!(check_result.proctor, Pubkey::default());
+}
+
+/// Test if rewards reconcile with the vault and the mint until tokens are sent to the vault
+#[trdelnik_test]
+async fn test_reward_reconciliation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    assert!(f.reward_reconciliation().await?.is_consistent());
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let reconciliation = f.reward_reconciliation().await?;
+    assert!(reconciliation.is_consistent());
+    assert_eq!(reconciliation.minted, 100);
+    assert_eq!(reconciliation.mint_supply, 100);
+    assert_eq!(reconciliation.vault_balance, 0);
+
+    // tokens sent to the vault directly aren't owed to anybody
+    f.client
+        .send_transaction(
+            &[token::spl_token::instruction::transfer(
+                &token::ID,
+                &f.student_a_token_account,
+                &f.course_batch_escrow_pda,
+                &f.student_a.pubkey(),
+                &[],
+                10,
+            )?],
+            [&f.student_a],
+        )
+        .await?;
+    let reconciliation = f.reward_reconciliation().await?;
+    assert!(!reconciliation.is_consistent());
+    assert_eq!(reconciliation.vault_discrepancy, 10);
+    assert_eq!(reconciliation.supply_discrepancy, 0);
+    assert_eq!(reconciliation.escrow_discrepancy, 0);
+}
+
+/// Test if plaintext answers of another length than committed are rejected before hashing
+#[trdelnik_test]
+async fn test_answer_len_commitment(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let commit_answer_len = |checker_pda: Pubkey, answer_len_commitment: u16| {
+        course_batch_manager_instruction::set_answer_len_commitment(
+            &f.client,
+            answer_len_commitment,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let answer_len = f.ground_truth_value.len() as u16;
+    let tx = commit_answer_len(f.assignment_checker_pda, answer_len).await?;
+    let committed = Fixture::events::<course_batch_manager::AnswerLenCommitmentSet>(&tx);
+    assert_eq!(committed[0].1.answer_len_commitment, answer_len);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.accepts_answer_len(f.ground_truth_value.len()));
+    assert!(!checker.accepts_answer_len(f.ground_truth_value.len() + 1));
+
+    let wrong_len = f
+        .check_plaintext(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            format!("{}!", f.ground_truth_value).as_bytes(),
+        )
+        .await;
+    assert!(Fixture::is_program_error(&wrong_len, "WrongAnswerLength"));
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.attempts, 0);
+
+    let check_result = f
+        .check_plaintext(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.ground_truth_value.as_bytes(),
+        )
+        .await?;
+    assert!(check_result.check_passed);
+
+    // the length is checked before the chain too long for plaintext checks is walked
+    let assignment_id = *b"space_hero_slow_";
+    let checker_pda = f
+        .create_assignment_checker_with_iterations(assignment_id, false, 100)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id)
+        .await?;
+    commit_answer_len(checker_pda, answer_len).await?;
+    let wrong_len = f
+        .check_plaintext(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            b"Titov",
+        )
+        .await;
+    assert!(Fixture::is_program_error(&wrong_len, "WrongAnswerLength"));
+    let unavailable = f
+        .check_plaintext(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            checker_pda,
+            f.ground_truth_value.as_bytes(),
+        )
+        .await;
+    assert!(Fixture::is_program_error(
+        &unavailable,
+        "PlaintextCheckUnavailable"
+    ));
+}
+
+/// Test if one config update changes several checker settings and keeps the others
+#[trdelnik_test]
+async fn test_update_config(#[future] start_course_batch: Result<Fixture>) {
+    use course_batch_manager::CheckerConfigUpdate;
+    let f = start_course_batch.await?;
+    let update_config = |config: CheckerConfigUpdate| {
+        course_batch_manager_instruction::update_config(
+            &f.client,
+            config,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let before = f.get_checker_account(f.assignment_checker_pda).await?;
+
+    let config = CheckerConfigUpdate {
+        to_mint_on_successful_check: Some(150),
+        max_attempts: Some(3),
+        auto_pause_after_seconds: Some(3_600),
+        ..Default::default()
+    };
+    let tx = update_config(config).await?;
+    let updated = Fixture::events::<course_batch_manager::CheckerConfigUpdated>(&tx);
+    assert_eq!(updated[0].1.config, config);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.to_mint_on_successful_check, 150);
+    assert_eq!(checker.max_attempts, 3);
+    assert_eq!(checker.auto_pause_after_seconds, 3_600);
+    assert_eq!(checker.reward_rounding, before.reward_rounding);
+    assert_eq!(checker.archived, before.archived);
+    assert_eq!(checker.hash_chain_length, before.hash_chain_length);
+
+    // archiving alone keeps the settings of the previous update
+    update_config(CheckerConfigUpdate {
+        archived: Some(true),
+        ..Default::default()
+    })
+    .await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.archived);
+    assert_eq!(
+        checker.config(),
+        CheckerConfigUpdate {
+            archived: Some(true),
+            reward_rounding: Some(before.reward_rounding),
+            ..config
+        }
+    );
+    let archived = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await;
+    assert!(Fixture::is_program_error(&archived, "CheckerArchived"));
+}
+
+/// Test if the leaderboard ranks students by passed assignments after first time passes
+#[trdelnik_test]
+async fn test_leaderboard(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let leaderboard_pda =
+        course_batch_manager::leaderboard_canonical_pda(f.course_pda, &f.batch_id);
+    let tx = course_batch_manager_instruction::create_leaderboard(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        leaderboard_pda,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let created = Fixture::events::<course_batch_manager::LeaderboardCreated>(&tx);
+    assert_eq!(created[0].1.leaderboard, leaderboard_pda);
+    assert!(f.get_course_batch_account().await?.has_leaderboard);
+    let ranking = |leaderboard: course_batch_manager::Leaderboard| {
+        leaderboard
+            .entries()
+            .iter()
+            .map(|entry| (entry.student, entry.assignments_passed))
+            .collect::<Vec<_>>()
+    };
+    assert!(ranking(f.get_leaderboard_account().await?).is_empty());
+
+    // checks of the batch take its leaderboard
+    let missing = f
+        .client
+        .send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_a.pubkey(),
+                f.student_a_token_account,
+                f.hash_chain_length,
+                Fixture::hash(
+                    f.hash_chain_length - 1,
+                    &f.salt,
+                    &f.course_pda,
+                    &f.assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                ),
+            )],
+            [&f.student_a],
+        )
+        .await;
+    assert!(Fixture::is_program_error(&missing, "InvalidLeaderboard"));
+
+    f.check_with_leaderboard(
+        &f.student_a,
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+    )
+    .await?;
+    f.check_with_leaderboard(
+        &f.student_b,
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+    )
+    .await?;
+    // the earlier pass ranks first among students with the same number of passes
+    assert_eq!(
+        ranking(f.get_leaderboard_account().await?),
+        vec![(f.student_a.pubkey(), 1), (f.student_b.pubkey(), 1)]
+    );
+
+    // repeats of passed checks don't change the ranking
+    f.check_with_leaderboard(
+        &f.student_a,
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+    )
+    .await?;
+    assert_eq!(
+        ranking(f.get_leaderboard_account().await?),
+        vec![(f.student_a.pubkey(), 1), (f.student_b.pubkey(), 1)]
+    );
+
+    let assignment_id = *b"space_hero_2____";
+    let second_checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    f.create_check_result(f.student_b.clone(), assignment_id)
+        .await?;
+    f.check_with_leaderboard(&f.student_b, f.student_b_token_account, second_checker_pda)
+        .await?;
+    // the student moves up without a second entry
+    assert_eq!(
+        ranking(f.get_leaderboard_account().await?),
+        vec![(f.student_b.pubkey(), 2), (f.student_a.pubkey(), 1)]
+    );
+}
+
+/// Test if a student cannot write to the check result of another student
+#[trdelnik_test]
+async fn test_cross_student_check_result(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let course_secret = [7; 32];
+    let anonymous_check_result_pda = course_batch_manager::anonymous_check_result_canonical_pda(
+        f.student_a.pubkey(),
+        &course_secret,
+        f.course_pda,
+        &f.assignment_id,
+    );
+    course_batch_manager_instruction::create_check_result(
+        &f.client,
+        f.assignment_id,
+        Some(course_secret),
+        f.student_a.pubkey(),
+        f.course_pda,
+        anonymous_check_result_pda,
+        course_batch_manager::exhausted_check_result_canonical_pda(anonymous_check_result_pda),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let hash_chain_tail_parent = Fixture::hash(
+        f.hash_chain_length - 1,
+        &f.salt,
+        &f.course_pda,
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    // student B signs the check with a check result of student A
+    let check_instruction = |check_result: Pubkey, course_secret: Option<[u8; 32]>| {
+        let mut instruction = f.check_assignment_instruction(
+            f.student_b.pubkey(),
+            f.student_b_token_account,
+            f.hash_chain_length,
+            hash_chain_tail_parent,
+        );
+        // accounts follow fields of `CheckAssignment`
+        instruction.accounts[4].pubkey = check_result;
+        instruction.data = course_batch_manager::instruction::CheckAssignment {
+            expected_hash_chain_length: f.hash_chain_length,
+            hash_chain_tail_parent,
+            course_secret,
+            shard: 0,
+        }
+        .data();
+        instruction
+    };
+
+    for (check_result, course_secret) in [
+        (check_result_pda, None),
+        (check_result_pda, Some(course_secret)),
+        (anonymous_check_result_pda, None),
+        (anonymous_check_result_pda, Some(course_secret)),
+    ] {
+        let res = f
+            .client
+            .send_transaction(
+                &[check_instruction(check_result, course_secret)],
+                [&f.student_b],
+            )
+            .await;
+        assert!(Fixture::is_program_error(&res, "ResultOwnershipMismatch"));
+    }
+    for check_result in [check_result_pda, anonymous_check_result_pda] {
+        let check_result = f.get_check_result_account(check_result).await?;
+        assert!(!check_result.check_passed);
+        assert_eq!(check_result.attempts, 0);
+    }
+    assert_eq!(
+        f.get_checker_account(f.assignment_checker_pda)
+            .await?
+            .hash_chain_length,
+        f.hash_chain_length
+    );
+
+    // the answer of student B still passes with the check result of student B
+    f.client
+        .send_transaction(
+            &[f.check_assignment_instruction(
+                f.student_b.pubkey(),
+                f.student_b_token_account,
+                f.hash_chain_length,
+                hash_chain_tail_parent,
+            )],
+            [&f.student_b],
+        )
+        .await?;
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert!(check_result.check_passed);
+}
+
+/// Test if completion certificate is minted once after all assignments are passed
+#[trdelnik_test]
+async fn test_mint_certificate(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let certificate_mint_pda = course_batch_manager::certificate_mint_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.batch_id,
+    );
+    let certificate_token =
+        get_associated_token_address(&f.student_a.pubkey(), &certificate_mint_pda);
+    let mint_certificate = || {
+        course_batch_manager_instruction::mint_certificate(
+            &f.client,
+            f.student_a.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                f.student_a.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            certificate_mint_pda,
+            certificate_token,
+            system_program::ID,
+            token::ID,
+            associated_token::ID,
+            rent::id(),
+            [f.student_a.clone()],
+        )
+    };
+
+    // the only assignment of the course hasn't passed yet
+    assert!(mint_certificate().await.is_err());
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let student_progress = f.get_student_progress_account(f.student_a.pubkey()).await?;
+    assert_eq!(student_progress.assignments_passed, 1);
+
+    mint_certificate().await?;
+    let balance = f.client.get_token_balance(certificate_token).await?;
+    assert_eq!(balance.amount.as_str(), "1");
+    let student_progress = f.get_student_progress_account(f.student_a.pubkey()).await?;
+    assert_eq!(student_progress.certificate_minted, true);
+
+    // certificate is minted only once
+    assert!(mint_certificate().await.is_err());
+}
+
+/// Test if course assignment count tracks created and closed assignment checkers
+#[trdelnik_test]
+async fn test_assignment_count(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    assert_eq!(f.get_course_account().await?.assignment_count, 1);
+
+    let assignment_id = *b"space_hero_2____";
+    let assignment_checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    assert_eq!(f.get_course_account().await?.assignment_count, 2);
+
+    for checker_pda in [assignment_checker_pda, f.assignment_checker_pda] {
+        course_batch_manager_instruction::close_assignment_checker(
+            &f.client,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        assert!(f.client.get_account(checker_pda).await?.is_none());
+    }
+    assert_eq!(f.get_course_account().await?.assignment_count, 0);
+
+    // closed checker can be created again
+    f.create_assignment_checker(assignment_id, false).await?;
+    assert_eq!(f.get_course_account().await?.assignment_count, 1);
+}
+
+/// Test if hash chain tail parent of one student fails for another in per student salt mode
+#[trdelnik_test]
+async fn test_per_student_salt(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"space_hero_solo_";
+    let assignment_checker_pda = f.create_assignment_checker(assignment_id, true).await?;
+
+    let mut student_parents = Vec::new();
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        let check_result_pda = f
+            .create_check_result(student.clone(), assignment_id)
+            .await?;
+        let parent = assignment_checker::student_hash_chain_tail_parent(
+            &f.salt,
+            &student.pubkey(),
+            f.ground_truth_value.as_bytes(),
+        );
+        course_batch_manager_instruction::set_student_commitment(
+            &f.client,
+            blake3::hash(&parent).0,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            assignment_checker_pda,
+            student.pubkey(),
+            check_result_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        student_parents.push(parent);
+    }
+
+    // student_b copies the parent of student_a
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(student_parents[0]),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+
+    // both students pass with own parents
+    for (student, token_account, parent) in [
+        (
+            f.student_a.clone(),
+            f.student_a_token_account,
+            student_parents[0],
+        ),
+        (
+            f.student_b.clone(),
+            f.student_b_token_account,
+            student_parents[1],
+        ),
+    ] {
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                &[],
+                Some(parent),
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+    }
+    let checker = f.get_checker_account(assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 2);
+}
+
+/// Test if students check reserved slots in any order without retries
+#[trdelnik_test]
+async fn test_check_reservations(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // both students reserve before checking
+    let reservation_a = f
+        .reserve_check(f.student_a.clone(), f.assignment_checker_pda)
+        .await?;
+    let reservation_b = f
+        .reserve_check(f.student_b.clone(), f.assignment_checker_pda)
+        .await?;
+    assert_eq!(reservation_a.hash_chain_length, f.hash_chain_length);
+    assert_eq!(reservation_b.hash_chain_length, f.hash_chain_length - 1);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 2);
+    assert_eq!(checker.revealed_hash_chain_length, f.hash_chain_length);
+
+    // active reservation cannot be released
+    let released = course_batch_manager_instruction::release_check_reservation(
+        &f.client,
+        f.course_pda,
+        f.assignment_checker_pda,
+        course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        Vec::<Keypair>::new(),
+    )
+    .await;
+    assert!(released.is_err());
+
+    // the second reserved slot is checked first
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(Fixture::hash(
+                reservation_b.hash_chain_length - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            )),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // the check of lower slot has revealed the parent reserved by student_a
+    let exposed_check = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(Fixture::hash(
+                reservation_a.hash_chain_length - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            )),
+        )
+        .await;
+    assert!(exposed_check.is_err());
+
+    // student_a reserves again and passes
+    let reservation_a = f
+        .reserve_check(f.student_a.clone(), f.assignment_checker_pda)
+        .await?;
+    assert_eq!(reservation_a.hash_chain_length, f.hash_chain_length - 2);
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(Fixture::hash(
+                reservation_a.hash_chain_length - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            )),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert!(!check_result.reservation.is_reserved());
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 3);
+    assert_eq!(checker.revealed_hash_chain_length, f.hash_chain_length - 3);
+}
+
+/// Test if scans of assignment checker accounts never mix checkers and check results
+#[trdelnik_test]
+async fn test_program_accounts_scan(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let checkers = f
+        .program_accounts::<assignment_checker::AssignmentCheckerState>(assignment_checker::ID)
+        .await?;
+    let check_results = f
+        .program_accounts::<assignment_checker::CheckResult>(assignment_checker::ID)
+        .await?;
+    let checker_addresses: Vec<_> = checkers.iter().map(|(address, _)| *address).collect();
+    assert_eq!(checker_addresses, vec![f.assignment_checker_pda]);
+    let mut check_result_addresses: Vec<_> =
+        check_results.iter().map(|(address, _)| *address).collect();
+    check_result_addresses.sort();
+    let mut expected_check_results: Vec<_> = [f.student_a.pubkey(), f.student_b.pubkey()]
+        .iter()
+        .map(|student| {
+            course_batch_manager::check_result_canonical_pda(
+                *student,
+                f.course_pda,
+                &f.assignment_id,
+            )
+        })
+        .collect();
+    expected_check_results.sort();
+    assert_eq!(check_result_addresses, expected_check_results);
+
+    // raw account data carries the discriminator of its own type only
+    for address in checker_addresses {
+        let account = f.client.get_account(address).await?.unwrap();
+        assert!(Fixture::has_discriminator::<
+            assignment_checker::AssignmentCheckerState,
+        >(&account.data));
+        assert!(!Fixture::has_discriminator::<assignment_checker::CheckResult>(&account.data));
+    }
+    for address in check_result_addresses {
+        let account = f.client.get_account(address).await?.unwrap();
+        assert!(Fixture::has_discriminator::<assignment_checker::CheckResult>(&account.data));
+        assert!(!Fixture::has_discriminator::<
+            assignment_checker::AssignmentCheckerState,
+        >(&account.data));
+    }
+}
+
+/// Test if first attempt passes in a row boost rewards until a retry breaks the streak
+#[trdelnik_test]
+async fn test_streak_bonus(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // the max streak multiplier doesn't fit u16 basis points
+    let invalid = course_batch_manager_instruction::set_streak_bonus(
+        &f.client,
+        6_000,
+        false,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(&invalid, "InvalidStreakBonus"));
+    course_batch_manager_instruction::set_streak_bonus(
+        &f.client,
+        1_000,
+        false,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let student_progress_pda = course_batch_manager::student_progress_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.batch_id,
+    );
+    let mut balance = 0;
+    // (assignment, failed attempts, streak after the pass, reward, recorded multiplier)
+    for (assignment_id, failed_attempts, streak, reward, multiplier_bps) in [
+        (f.assignment_id, 0, 1, 100, 0),
+        (*b"space_hero_2____", 0, 2, 110, 11_000),
+        (*b"space_hero_3____", 0, 3, 120, 12_000),
+        (*b"space_hero_4____", 1, 0, 100, 0),
+        (*b"space_hero_5____", 0, 1, 100, 0),
+    ] {
+        let checker_pda = if assignment_id == f.assignment_id {
+            f.assignment_checker_pda
+        } else {
+            let checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+            f.create_check_result(f.student_a.clone(), assignment_id)
+                .await?;
+            checker_pda
+        };
+        for _ in 0..failed_attempts {
+            let (_, check_result) = f
+                .check_assignment(
+                    f.student_a.clone(),
+                    f.student_a_token_account,
+                    checker_pda,
+                    f.course_pda,
+                    f.course_batch_pda,
+                    b"Titov",
+                    None,
+                )
+                .await?;
+            assert_eq!(check_result.check_passed, false);
+            let student_progress = f
+                .client
+                .account_data::<course_batch_manager::StudentProgress>(student_progress_pda)
+                .await?;
+            assert_eq!(student_progress.current_streak, 0);
+        }
+        let (_, check_result) = f
+            .check_assignment(
+                f.student_a.clone(),
+                f.student_a_token_account,
+                checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+        assert_eq!(check_result.reward.streak_multiplier_bps, multiplier_bps);
+        let student_progress = f
+            .client
+            .account_data::<course_batch_manager::StudentProgress>(student_progress_pda)
+            .await?;
+        assert_eq!(student_progress.current_streak, streak);
+        balance += reward;
+        let token_balance = f
+            .client
+            .get_token_balance(f.student_a_token_account)
+            .await?;
+        assert_eq!(token_balance.amount, balance.to_string());
+    }
+}
+
+/// Test if a parent copied from the pass of another student is rejected
+#[trdelnik_test]
+async fn test_duplicate_parent_submission(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let (consumed_parent, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // student_b copies the parent from the transaction of student_a
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_b.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let copied = course_batch_manager_instruction::check_assignment(
+        &f.client,
+        checker.hash_chain_length,
+        consumed_parent,
+        None,
+        0,
+        f.student_b.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        f.assignment_checker_pda,
+        check_result_pda,
+        course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+        f.course_batch_mint_pda,
+        f.student_b_token_account,
+        f.course_batch_escrow_pda,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_b.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(
+        &copied,
+        "DuplicateParentSubmission"
+    ));
+
+    // the genuine parent of the next slot passes
+    let (parent, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_ne!(parent, consumed_parent);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.recent_parents.contains(&consumed_parent));
+    assert!(checker.recent_parents.contains(&parent));
+}
+
+/// Test if check with retry policy passes after concurrent checks make its observation stale
+#[trdelnik_test]
+async fn test_submit_check_with_retry(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let mut jitter = Interleaving::new(144);
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = f.enroll_student(student_c.clone()).await?;
+    f.create_check_result(student_c.clone(), f.assignment_id)
+        .await?;
+
+    // student_c observes the chain, then students A and B pass first
+    let observed = f
+        .get_checker_account(f.assignment_checker_pda)
+        .await?
+        .hash_chain_length;
+    for (student, token_account) in [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+    ] {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    }
+
+    // without retries the stale observation fails
+    let no_retries = f
+        .submit_check_with_retry(
+            student_c.clone(),
+            student_c_token_account,
+            f.assignment_checker_pda,
+            f.ground_truth_value.as_bytes(),
+            observed,
+            0,
+            &mut jitter,
+        )
+        .await;
+    assert!(no_retries.is_err());
+
+    let retries = f
+        .submit_check_with_retry(
+            student_c.clone(),
+            student_c_token_account,
+            f.assignment_checker_pda,
+            f.ground_truth_value.as_bytes(),
+            observed,
+            3,
+            &mut jitter,
+        )
+        .await?;
+    assert_eq!(retries, 1);
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            student_c.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, observed - 3);
+}
+
+/// Test if upgrade of current check result keeps it and the next check doesn't mint again
+#[trdelnik_test]
+async fn test_upgrade_check_result(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let check_result = f.get_check_result_account(check_result_pda).await?;
+    assert_eq!(
+        check_result.schema_version,
+        assignment_checker::CHECK_RESULT_SCHEMA_VERSION
+    );
+
+    let check = || {
+        f.check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+    };
+    let (_, check_result) = check().await?;
+    assert_eq!(check_result.check_passed, true);
+
+    let upgrade = || {
+        course_batch_manager_instruction::upgrade_student_check_result(
+            &f.client,
+            f.student_a.pubkey(),
+            f.course_pda,
+            check_result_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            system_program::ID,
+            [f.student_a.clone()],
+        )
+    };
+    upgrade().await?;
+    let upgraded = f.get_check_result_account(check_result_pda).await?;
+    assert!(upgraded.check_passed && upgraded.passed_first_time);
+    assert_eq!(upgraded.reward, check_result.reward);
+    assert_eq!(
+        f.client
+            .get_account(check_result_pda)
+            .await?
+            .unwrap()
+            .data
+            .len(),
+        8 + assignment_checker::CheckResult::LEN
+    );
+
+    // upgraded check result isn't rewarded again
+    let (_, check_result) = check().await?;
+    assert!(check_result.check_passed);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+
+    // checker cannot be upgraded as check result
+    let substituted = course_batch_manager_instruction::upgrade_student_check_result(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.student_a.clone()],
+    )
+    .await;
+    assert!(substituted.is_err());
+}
+
+/// Test if checker and check result are created with zeroed reserved space
+#[trdelnik_test]
+async fn test_reserved_space(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let checker_data_len = f
+        .client
+        .get_account(f.assignment_checker_pda)
+        .await?
+        .unwrap()
+        .data
+        .len();
+    assert_eq!(
+        checker_data_len,
+        8 + assignment_checker::AssignmentCheckerState::LEN
+    );
+    let check_result_data_len = f
+        .client
+        .get_account(check_result_pda)
+        .await?
+        .unwrap()
+        .data
+        .len();
+    assert_eq!(
+        check_result_data_len,
+        8 + assignment_checker::CheckResult::LEN
+    );
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.reserved.bytes.iter().all(|byte| *byte == 0));
+    let check_result = f.get_check_result_account(check_result_pda).await?;
+    assert!(check_result.reserved.iter().all(|byte| *byte == 0));
+
+    // reserved space doesn't change the check
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    assert!(check_result.reserved.iter().all(|byte| *byte == 0));
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.reserved.bytes.iter().all(|byte| *byte == 0));
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+}
+
+/// Test if checker and reward mint of new batch are created in one call and the mint of existing batch is reused
+#[trdelnik_test]
+async fn test_create_checker_with_mint(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let create = |batch_id: [u8; 16], assignment_id: [u8; 16]| {
+        let ground_truth_hash_chain_tail = hash_chain::build_iterated_tail(
+            &f.salt,
+            &f.course_pda.to_bytes(),
+            &assignment_id,
+            f.ground_truth_value.as_bytes(),
+            f.hash_chain_length,
+            1,
+        );
+        course_batch_manager_instruction::create_assignment_checker_with_mint(
+            &f.client,
+            batch_id,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length: f.hash_chain_length,
+                to_mint_on_successful_check: 100,
+                salt: f.salt,
+                per_student_salt: false,
+                hash_iterations_per_step: 1,
+                ground_truth_hash_chain_tail,
+            },
+            f.course_authority.pubkey(),
+            f.course_pda,
+            course_batch_manager::batch_canonical_pda(f.course_pda, &batch_id),
+            course_batch_manager::batch_mint_canonical_pda(f.course_pda, &batch_id),
+            course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &batch_id),
+            course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            rent::id(),
+            token::ID,
+            [f.course_authority.clone()],
+        )
+    };
+
+    // new batch gets its mint and reward vault with the checker
+    let batch_id = *b"one_call_batch__";
+    let assignment_id = *b"space_hero_2____";
+    // lamports sent to the batch addresses beforehand don't block the creation
+    let squatter = keypair(4);
+    f.client.airdrop(squatter.pubkey(), 5_000_000).await?;
+    let prefund = [
+        course_batch_manager::batch_canonical_pda(f.course_pda, &batch_id),
+        course_batch_manager::batch_mint_canonical_pda(f.course_pda, &batch_id),
+        course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &batch_id),
+    ]
+    .map(|address| {
+        solana_sdk::system_instruction::transfer(&squatter.pubkey(), &address, 1_000_000)
+    });
+    f.client.send_transaction(&prefund, [&squatter]).await?;
+    create(batch_id, assignment_id).await?;
+    let course_batch_pda = course_batch_manager::batch_canonical_pda(f.course_pda, &batch_id);
+    let mint_pda = course_batch_manager::batch_mint_canonical_pda(f.course_pda, &batch_id);
+    let checker = f
+        .get_checker_account(course_batch_manager::assignment_checker_canonical_pda(
+            f.course_pda,
+            &assignment_id,
+        ))
+        .await?;
+    assert_eq!(checker.assignment_id, assignment_id);
+    let course_batch: course_batch_manager::CourseBatch =
+        f.client.account_data(course_batch_pda).await?;
+    assert_eq!(course_batch.mint, mint_pda);
+    assert_eq!(course_batch.authority, f.course_authority.pubkey());
+    assert_eq!(
+        course_batch.reward_multiplier_bps,
+        course_batch_manager::DEFAULT_REWARD_MULTIPLIER_BPS
+    );
+    let mint = token::spl_token::state::Mint::unpack(
+        &f.client.get_account(mint_pda).await?.unwrap().data,
+    )?;
+    assert_eq!(mint.mint_authority, Some(course_batch_pda).into());
+    assert_eq!(mint.decimals, 0);
+    let escrow = token::spl_token::state::Account::unpack(
+        &f.client
+            .get_account(course_batch_manager::batch_escrow_canonical_pda(
+                f.course_pda,
+                &batch_id,
+            ))
+            .await?
+            .unwrap()
+            .data,
+    )?;
+    assert_eq!(escrow.mint, mint_pda);
+    assert_eq!(escrow.owner, course_batch_pda);
+
+    // the mint is usable for enrollment
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = get_associated_token_address(&student_c.pubkey(), &mint_pda);
+    course_batch_manager_instruction::enroll_batch(
+        &f.client,
+        student_c.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        mint_pda,
+        student_c_token_account,
+        course_batch_manager::student_progress_canonical_pda(
+            student_c.pubkey(),
+            f.course_pda,
+            &batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        associated_token::ID,
+        rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
+        [student_c.clone()],
+    )
+    .await?;
+    let balance = f.client.get_token_balance(student_c_token_account).await?;
+    assert_eq!(balance.amount.as_str(), "0");
+
+    // existing batch keeps its mint and rewards checks of the new checker
+    let assignment_id = *b"space_hero_3____";
+    create(f.batch_id, assignment_id).await?;
+    let course_batch = f.get_course_batch_account().await?;
+    assert_eq!(course_batch.mint, f.course_batch_mint_pda);
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    f.create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.passed_first_time);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+    let course = f.get_course_account().await?;
+    assert_eq!(course.assignment_count, 3);
+}
+
+/// Test if checkers with zero or assignment derived salt are rejected
+#[trdelnik_test]
+async fn test_weak_salt(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_2____";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    let create = |salt: [u8; 32]| {
+        course_batch_manager_instruction::create_assignment_checker(
+            &f.client,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length: f.hash_chain_length,
+                to_mint_on_successful_check: 100,
+                salt,
+                per_student_salt: false,
+                hash_iterations_per_step: 1,
+                ground_truth_hash_chain_tail: hash_chain::build_tail(
+                    &salt,
+                    &f.course_pda.to_bytes(),
+                    &assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                    f.hash_chain_length,
+                ),
+            },
+            f.course_authority.pubkey(),
+            f.course_pda,
+            checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+    };
+
+    let zero_salt = create([0; 32]).await;
+    assert!(Fixture::is_program_error(&zero_salt, "WeakSalt"));
+    let mut derived_salt = [0; 32];
+    derived_salt[..16].copy_from_slice(&assignment_id);
+    derived_salt[16..].copy_from_slice(&assignment_id);
+    let derived_salt = create(derived_salt).await;
+    assert!(Fixture::is_program_error(&derived_salt, "WeakSalt"));
+    assert!(f.client.get_account(checker_pda).await?.is_none());
+
+    let random_salt = Keypair::new().pubkey().to_bytes();
+    create(random_salt).await?;
+    let checker = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker.salt, random_salt);
+}
+
+/// Test if parent of one assignment doesn't pass another assignment with the same salt and value
+#[trdelnik_test]
+async fn test_assignment_bound_parent(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assignment_id = *b"space_hero_2____";
+    let checker_pda = f.create_assignment_checker(assignment_id, false).await?;
+    let checker_a = f.get_checker_account(f.assignment_checker_pda).await?;
+    let checker_b = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker_a.salt, checker_b.salt);
+    assert_eq!(checker_a.hash_chain_length, checker_b.hash_chain_length);
+
+    let parent_a = Fixture::hash(
+        checker_a.hash_chain_length - 1,
+        &f.salt,
+        &f.course_pda,
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    f.create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            Some(parent_a),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+    let checker_b = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker_b.successful_checks, 0);
+
+    // the same parent is valid for its own assignment
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            Some(parent_a),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // parent built for the assignment passes it
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+}
+
+/// Test if gradebook iterates check results of the roster in index order
+#[trdelnik_test]
+async fn test_roster_index(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let assign = |roster_index: u32, student: Pubkey| {
+        course_batch_manager_instruction::assign_roster_index(
+            &f.client,
+            roster_index,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                student,
+                f.course_pda,
+                &f.batch_id,
+            ),
+            course_batch_manager::roster_entry_canonical_pda(
+                f.course_pda,
+                &f.batch_id,
+                roster_index,
+            ),
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    // roster order differs from enrollment order
+    assign(1, f.student_b.pubkey()).await?;
+    assign(2, f.student_a.pubkey()).await?;
+    // each index is assigned once
+    assert!(assign(2, f.student_b.pubkey()).await.is_err());
+    // students who aren't enrolled don't get indices
+    assert!(assign(3, keypair(3).pubkey()).await.is_err());
+
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+
+    let mut gradebook = Vec::new();
+    for roster_index in 1..=2 {
+        let roster_entry: course_batch_manager::RosterEntry = f
+            .client
+            .account_data(course_batch_manager::roster_entry_canonical_pda(
+                f.course_pda,
+                &f.batch_id,
+                roster_index,
+            ))
+            .await?;
+        assert_eq!(roster_entry.roster_index, roster_index);
+        assert_eq!(roster_entry.course_batch, f.course_batch_pda);
+        let check_result = f
+            .get_check_result_account(course_batch_manager::roster_check_result_canonical_pda(
+                &roster_entry,
+                f.course_pda,
+                &f.assignment_id,
+            ))
+            .await?;
+        gradebook.push((roster_entry.student, check_result.check_passed));
+    }
+    assert_eq!(
+        gradebook,
+        vec![(f.student_b.pubkey(), true), (f.student_a.pubkey(), false)]
+    );
+}
+
+/// Test if checkers that don't fit into one transaction are created in several ones
+#[trdelnik_test]
+async fn test_create_many_assignment_checkers(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let per_transaction = course_batch_manager::checkers_per_transaction(1);
+    assert!(per_transaction > 0);
+    let assignment_ids: Vec<[u8; 16]> = (0..2 * per_transaction + 1)
+        .map(|i| {
+            let mut assignment_id = [0; 16];
+            assignment_id.copy_from_slice(format!("checker_{:08}", i).as_bytes());
+            assignment_id
+        })
+        .collect();
+
+    // lamports sent to a checker address beforehand don't block the checker
+    let squatter = keypair(4);
+    f.client.airdrop(squatter.pubkey(), 5_000_000).await?;
+    f.client
+        .send_transaction(
+            &[solana_sdk::system_instruction::transfer(
+                &squatter.pubkey(),
+                &course_batch_manager::assignment_checker_canonical_pda(
+                    f.course_pda,
+                    &assignment_ids[0],
+                ),
+                1_000_000,
+            )],
+            [&squatter],
+        )
+        .await?;
+    let checker_pdas = f.create_many_assignment_checkers(&assignment_ids).await?;
+    assert_eq!(checker_pdas.len(), assignment_ids.len());
+    for (assignment_id, checker_pda) in assignment_ids.iter().zip(&checker_pdas) {
+        let checker = f.get_checker_account(*checker_pda).await?;
+        assert_eq!(checker.assignment_id, *assignment_id);
+        assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+    }
+    let course = f.get_course_account().await?;
+    assert_eq!(
+        usize::from(course.assignment_count),
+        1 + assignment_ids.len()
+    );
+
+    // checker accounts should match the checkers
+    let (checkers, mut remaining_accounts) = f.new_assignment_checkers(&[*b"space_hero_2____"]);
+    remaining_accounts[0].pubkey = f.assignment_checker_pda;
+    let mismatch = f
+        .client
+        .send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::CreateManyAssignmentCheckers { checkers },
+            WithRemainingAccounts {
+                accounts: f.create_many_accounts(),
+                remaining_accounts,
+            },
+            [f.course_authority.clone()],
+        )
+        .await;
+    assert!(Fixture::is_program_error(
+        &mismatch,
+        "CheckerAccountsMismatch"
+    ));
+}
+
+/// Test if archived checker rejects checks and keeps the hash chain and check results
+#[trdelnik_test]
+async fn test_archived_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check = |student: Keypair, token_account: Pubkey| {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+    };
+    let set_archived = |archived: bool| {
+        course_batch_manager_instruction::set_archived(
+            &f.client,
+            archived,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let (_, check_result_b) = check(f.student_b.clone(), f.student_b_token_account).await?;
+    assert!(check_result_b.passed_first_time);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(!checker.archived);
+
+    set_archived(true).await?;
+    assert!(check(f.student_a.clone(), f.student_a_token_account)
+        .await
+        .is_err());
+    let archived = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(archived.archived);
+    assert_eq!(archived.hash_chain_length, checker.hash_chain_length);
+    assert_eq!(archived.successful_checks, checker.successful_checks);
+    let check_result_pda = |student: Pubkey| {
+        course_batch_manager::check_result_canonical_pda(student, f.course_pda, &f.assignment_id)
+    };
+    let check_result = f
+        .get_check_result_account(check_result_pda(f.student_b.pubkey()))
+        .await?;
+    assert!(check_result.check_passed);
+    let check_result = f
+        .get_check_result_account(check_result_pda(f.student_a.pubkey()))
+        .await?;
+    assert!(!check_result.check_passed);
+
+    // only the course authority archives checkers
+    let not_authority = course_batch_manager_instruction::set_archived(
+        &f.client,
+        false,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await;
+    assert!(not_authority.is_err());
+
+    set_archived(false).await?;
+    let (_, check_result_a) = check(f.student_a.clone(), f.student_a_token_account).await?;
+    assert!(check_result_a.passed_first_time);
+}
+
+/// Test if a consumer program lets in only students who have passed the assignment
+#[trdelnik_test]
+async fn test_assert_passed_by_cpi(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let pass_gate_program = Keypair::from_bytes(&PASS_GATE_PROGRAM_KEYPAIR)?;
+    assert_eq!(pass_gate_program.pubkey(), pass_gate::ID);
+    f.client
+        .deploy_by_name(&pass_gate_program, "pass_gate")
+        .await?;
+    let enter = |student: Keypair| {
+        let check_result = course_batch_manager::check_result_canonical_pda(
+            student.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        );
+        f.client.send_instruction(
+            pass_gate::ID,
+            pass_gate::instruction::Enter {
+                assignment_id: f.assignment_id,
+            },
+            pass_gate::accounts::Enter {
+                student: student.pubkey(),
+                course: f.course_pda,
+                check_result,
+                course_batch_manager_program: course_batch_manager::ID,
+            },
+            [student],
+        )
+    };
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let tx = enter(f.student_a.clone()).await?;
+    assert!(Fixture::has_log(
+        &tx,
+        &format!("access granted to {}", f.student_a.pubkey())
+    ));
+
+    // student B hasn't passed, the gate stays closed
+    let denied = enter(f.student_b.clone()).await;
+    assert!(Fixture::is_program_error(&denied, "NotPassed"));
+}
+
+/// Test if the first solver bonus goes to the first processed pass of the same transaction
+#[trdelnik_test]
+async fn test_first_solver_bonus(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    course_batch_manager_instruction::set_first_solver_bonus(
+        &f.client,
+        50,
+        false,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    // both passes land in one slot, student B is processed first
+    let value = f.ground_truth_value.as_bytes();
+    let length = f.hash_chain_length;
+    let tx = f
+        .client
+        .send_transaction(
+            &[
+                f.check_assignment_instruction(
+                    f.student_b.pubkey(),
+                    f.student_b_token_account,
+                    length,
+                    Fixture::hash(length - 1, &f.salt, &f.course_pda, &f.assignment_id, value),
+                ),
+                f.check_assignment_instruction(
+                    f.student_a.pubkey(),
+                    f.student_a_token_account,
+                    length - 1,
+                    Fixture::hash(length - 2, &f.salt, &f.course_pda, &f.assignment_id, value),
+                ),
+            ],
+            [&f.student_b, &f.student_a],
+        )
+        .await?;
+    let rewarded = Fixture::events::<course_batch_manager::FirstSolverRewarded>(&tx);
+    assert_eq!(rewarded.len(), 1);
+    let (_, event) = &rewarded[0];
+    assert_eq!(
+        event.check_result,
+        course_batch_manager::check_result_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.assignment_id
+        )
+    );
+    assert_eq!(event.first_solver_bonus, 50);
+    for (token_account, balance) in [
+        (f.student_b_token_account, "150"),
+        (f.student_a_token_account, "100"),
+    ] {
+        let token_balance = f.client.get_token_balance(token_account).await?;
+        assert_eq!(token_balance.amount.as_str(), balance);
+    }
+
+    // later solvers of the checker get no bonus
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = f.enroll_student(student_c.clone()).await?;
+    f.create_check_result(student_c.clone(), f.assignment_id)
+        .await?;
+    f.check_assignment(
+        student_c,
+        student_c_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        value,
+        None,
+    )
+    .await?;
+    let balance = f.client.get_token_balance(student_c_token_account).await?;
+    assert_eq!(balance.amount.as_str(), "100");
+}
+
+/// Test if underfunded student gets a clean error before the token account creation
+#[trdelnik_test]
+async fn test_underfunded_enrollment(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let student_c = keypair(3);
+    let student_c_token_account =
+        get_associated_token_address(&student_c.pubkey(), &f.course_batch_mint_pda);
+    let enroll = || {
+        course_batch_manager_instruction::enroll_batch(
+            &f.client,
+            student_c.pubkey(),
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            f.course_batch_mint_pda,
+            student_c_token_account,
+            course_batch_manager::student_progress_canonical_pda(
+                student_c.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            system_program::ID,
+            token::ID,
+            associated_token::ID,
+            rent::id(),
+            course_manager::ID,
+            course_batch_manager::ID,
+            [student_c.clone()],
+        )
+    };
+
+    // the student pays rent of the student progress but not of the token account
+    let rent = rent::Rent::default();
+    let progress_rent = rent.minimum_balance(8 + course_batch_manager::StudentProgress::LEN);
+    let token_account_rent = rent.minimum_balance(token::spl_token::state::Account::LEN);
+    f.client
+        .airdrop(student_c.pubkey(), progress_rent + token_account_rent - 1)
+        .await?;
+    let underfunded = enroll().await;
+    assert!(Fixture::is_program_error(
+        &underfunded,
+        "CannotCreateTokenAccount"
+    ));
+    assert!(f
+        .client
+        .get_account(student_c_token_account)
+        .await?
+        .is_none());
+
+    f.client.airdrop(student_c.pubkey(), 1).await?;
+    enroll().await?;
+    let balance = f.client.get_token_balance(student_c_token_account).await?;
+    assert_eq!(balance.amount.as_str(), "0");
+}
+
+/// Test if close instructions return rent only to the owner of the closed account
+#[trdelnik_test]
+async fn test_close_rent_recipient(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result_a = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let close_check_result = |student: Keypair| {
+        course_batch_manager_instruction::close_check_result(
+            &f.client,
+            None,
+            student.pubkey(),
+            f.course_pda,
+            check_result_a,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student],
+        )
+    };
+
+    // student_b cannot take rent of the check result of student_a
+    let substituted = close_check_result(f.student_b.clone()).await;
+    assert!(Fixture::is_account_error(substituted, "check_result"));
+    // an attacker cannot take rent of the checker
+    let attacker = keypair(4);
+    f.client.airdrop(attacker.pubkey(), 5_000_000).await?;
+    let substituted = course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        attacker.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        [attacker],
+    )
+    .await;
+    assert!(Fixture::is_account_error(substituted, "course"));
+    assert!(f
+        .client
+        .get_account(f.assignment_checker_pda)
+        .await?
+        .is_some());
+
+    // the student closes own check result
+    let rent = f
+        .client
+        .get_account(check_result_a)
+        .await?
+        .unwrap()
+        .lamports;
+    let student_lamports = f
+        .client
+        .get_account(f.student_a.pubkey())
+        .await?
+        .unwrap()
+        .lamports;
+    close_check_result(f.student_a.clone()).await?;
+    assert!(f.client.get_account(check_result_a).await?.is_none());
+    assert_eq!(
+        f.client
+            .get_account(f.student_a.pubkey())
+            .await?
+            .unwrap()
+            .lamports,
+        student_lamports + rent
+    );
+
+    // passed check result keeps the record of the reward
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let passed = course_batch_manager_instruction::close_check_result(
+        &f.client,
+        None,
+        f.student_b.pubkey(),
+        f.course_pda,
+        course_batch_manager::check_result_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_b.clone()],
+    )
+    .await;
+    assert!(Fixture::is_program_error(&passed, "AlreadyPassed"));
+}
+
+/// Test if a given up reservation returns its slot to another student
+#[trdelnik_test]
+async fn test_give_up_reservation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result_a = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let give_up = || {
+        course_batch_manager_instruction::give_up_check_reservation(
+            &f.client,
+            None,
+            f.student_a.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            check_result_a,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.student_a.clone()],
+        )
+    };
+
+    let reservation = f
+        .reserve_check(f.student_a.clone(), f.assignment_checker_pda)
+        .await?;
+    assert_eq!(reservation.hash_chain_length, f.hash_chain_length);
+    give_up().await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+    assert_eq!(checker.reserved_slots, 0);
+    let check_result = f.get_check_result_account(check_result_a).await?;
+    assert!(!check_result.reservation.is_reserved());
+
+    // nothing left to give up
+    let repeated = give_up().await;
+    assert!(Fixture::is_program_error(&repeated, "NoReservation"));
+
+    // the released slot is checked by another student
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 1);
+
+    // committed slot cannot be given up
+    let reservation = f
+        .reserve_check(f.student_a.clone(), f.assignment_checker_pda)
+        .await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            &[],
+            Some(Fixture::hash(
+                reservation.hash_chain_length - 1,
+                &f.salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            )),
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let committed = give_up().await;
+    assert!(Fixture::is_program_error(&committed, "AlreadyPassed"));
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 2);
+}
+
+/// Test if frozen course rejects structural changes but allows checks
+#[trdelnik_test]
+async fn test_freeze_course(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    course_manager_instruction::freeze_course(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(f.get_course_account().await?.frozen);
+
+    // new assignments cannot be created
+    let created = f
+        .create_assignment_checker(*b"space_hero_late_", false)
+        .await;
+    assert!(created.is_err());
+
+    // existing assignments cannot be reconfigured
+    let updated = course_batch_manager_instruction::set_reward_rounding(
+        &f.client,
+        course_batch_manager::RewardRounding::Ceil,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(updated.is_err());
+    let extended = course_batch_manager_instruction::extend_chain(
+        &f.client,
+        5,
+        [1; 32],
+        Fixture::hash(
+            f.hash_chain_length + 5,
+            &[1; 32],
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(extended.is_err());
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+    assert_eq!(
+        checker.reward_rounding,
+        course_batch_manager::RewardRounding::Floor
+    );
+
+    // students still pass the checks
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+}
+
+/// Test if each hash chain step requires several hash iterations
+#[trdelnik_test]
+async fn test_hash_iterations_per_step(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"space_hero_slow_";
+    let hash_iterations_per_step = 3;
+    let assignment_checker_pda = f
+        .create_assignment_checker_with_iterations(assignment_id, false, hash_iterations_per_step)
+        .await?;
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        f.create_check_result(student, assignment_id).await?;
+    }
+
+    // parent of the chain with single iteration is incorrect
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+
+    for (student, token_account) in [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+    ] {
+        let checker = f.get_checker_account(assignment_checker_pda).await?;
+        let parent = hash_chain::build_iterated_tail(
+            &f.salt,
+            &f.course_pda.to_bytes(),
+            &assignment_id,
+            f.ground_truth_value.as_bytes(),
+            checker.hash_chain_length - 1,
+            hash_iterations_per_step,
+        );
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                &[],
+                Some(parent),
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+    }
+    let checker = f.get_checker_account(assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 2);
+}
+
+/// Test if cloned assignment checker of the course rerun keeps config but not state
+#[trdelnik_test]
+async fn test_clone_assignment_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    course_batch_manager_instruction::set_reward_rounding(
+        &f.client,
+        course_batch_manager::RewardRounding::Ceil,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    // course authority reruns the course
+    let rerun_course_id = *b"web2_to_web3_2nd";
+    let rerun_course_pda =
+        course_manager::course_canonical_pda(f.course_authority.pubkey(), &rerun_course_id);
+    course_manager_instruction::create_new_course(
+        &f.client,
+        rerun_course_id,
+        Pubkey::default(),
+        f.course_authority.pubkey(),
+        rerun_course_pda,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let rerun_salt = [5; 32];
+    let rerun_checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(rerun_course_pda, &f.assignment_id);
+    course_batch_manager_instruction::clone_assignment_checker(
+        &f.client,
+        rerun_salt,
+        hash_chain::build_tail(
+            &rerun_salt,
+            &rerun_course_pda.to_bytes(),
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+            f.hash_chain_length,
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        rerun_course_pda,
+        rerun_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let source = f.get_checker_account(f.assignment_checker_pda).await?;
+    let rerun = f.get_checker_account(rerun_checker_pda).await?;
+    assert_eq!(rerun.assignment_id, source.assignment_id);
+    assert_eq!(
+        rerun.to_mint_on_successful_check,
+        source.to_mint_on_successful_check
+    );
+    assert_eq!(rerun.per_student_salt, source.per_student_salt);
+    assert_eq!(
+        rerun.hash_iterations_per_step,
+        source.hash_iterations_per_step
+    );
+    assert_eq!(
+        rerun.reward_rounding,
+        course_batch_manager::RewardRounding::Ceil
+    );
+    assert_eq!(rerun.salt, rerun_salt);
+    let rerun_course = f
+        .client
+        .account_data::<course_manager::Course>(rerun_course_pda)
+        .await?;
+    assert_eq!(rerun_course.assignment_count, 1);
+
+    // checks of the source checker don't change the clone
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let source = f.get_checker_account(f.assignment_checker_pda).await?;
+    let rerun = f.get_checker_account(rerun_checker_pda).await?;
+    assert_eq!(source.hash_chain_length, f.hash_chain_length - 1);
+    assert_eq!(rerun.hash_chain_length, f.hash_chain_length);
+    assert!(rerun.recent_passers.is_empty());
+}
+
+/// Test if students checking on different shards don't invalidate expected lengths of each other
+#[trdelnik_test]
+async fn test_chain_shards(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let shard_salt = [1; 32];
+    course_batch_manager_instruction::add_assignment_checker_shard(
+        &f.client,
+        f.hash_chain_length,
+        shard_salt,
+        Fixture::hash(
+            f.hash_chain_length,
+            &shard_salt,
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.shard_count(), 2);
+    assert_eq!(
+        checker.lifetime_passes(),
+        2 * u32::from(f.hash_chain_length - 1)
+    );
+    for student in [f.student_a.pubkey(), f.student_b.pubkey()] {
+        assert!(course_batch_manager::student_shard(&student, checker.shard_count()) < 2);
+    }
+
+    let check_assignment = |student: Keypair,
+                            token_account: Pubkey,
+                            salt: [u8; 32],
+                            expected_hash_chain_length: u16,
+                            shard: u8| {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        );
+        course_batch_manager_instruction::check_assignment(
+            &f.client,
+            expected_hash_chain_length,
+            Fixture::hash(
+                f.hash_chain_length - 1,
+                &salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+            None,
+            shard,
+            student.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            f.assignment_checker_pda,
+            check_result_pda,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+            f.course_batch_mint_pda,
+            token_account,
+            f.course_batch_escrow_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                student.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student],
+        )
+    };
+
+    // unknown shard is rejected
+    assert!(check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        shard_salt,
+        f.hash_chain_length,
+        2,
+    )
+    .await
+    .is_err());
+
+    // both students expect the initial length of their shards
+    check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.salt,
+        f.hash_chain_length,
+        0,
+    )
+    .await?;
+    let tx = check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        shard_salt,
+        f.hash_chain_length,
+        1,
+    )
+    .await?;
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+    assert!(outcome.passed && outcome.first_time);
+    assert_eq!(outcome.remaining_chain, f.hash_chain_length - 1);
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 1);
+    assert_eq!(checker.shard_hash_chain_length(1), f.hash_chain_length - 1);
+    assert_eq!(checker.successful_checks, 2);
+    assert!(checker.chain_invariant_holds());
+    for (student, token_account) in [
+        (f.student_a.pubkey(), f.student_a_token_account),
+        (f.student_b.pubkey(), f.student_b_token_account),
+    ] {
+        let check_result = f
+            .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+                student,
+                f.course_pda,
+                &f.assignment_id,
+            ))
+            .await?;
+        assert!(check_result.check_passed);
+        let balance = f.client.get_token_balance(token_account).await?;
+        assert_eq!(balance.amount.as_str(), "100");
+    }
+}
+
+/// Test if first time passers are counted by attempts they needed
+#[trdelnik_test]
+async fn test_attempts_histogram(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // student_a fails twice before passing, student_b passes the first time
+    let mut difficulty_scores = Vec::new();
+    for (student, token_account, failures) in [
+        (f.student_a.clone(), f.student_a_token_account, 2),
+        (f.student_b.clone(), f.student_b_token_account, 0),
+    ] {
+        for _ in 0..failures {
+            let (_, check_result) = f
+                .check_assignment(
+                    student.clone(),
+                    token_account,
+                    f.assignment_checker_pda,
+                    f.course_pda,
+                    f.course_batch_pda,
+                    b"Titov",
+                    None,
+                )
+                .await?;
+            assert_eq!(check_result.check_passed, false);
+        }
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+        assert_eq!(check_result.attempts, failures + 1);
+        let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+        difficulty_scores.push(checker.difficulty_score);
+    }
+    // the first time pass makes the assignment easier
+    assert_eq!(difficulty_scores, [3333, 1666]);
+
+    // repeated check of passed assignment doesn't count
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.attempts, 1);
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.attempts_histogram.buckets, [1, 1, 0, 0]);
+    assert_eq!(checker.difficulty_score, 1666);
+}
+
+/// Test if converted checker accepts the same answer from any number of students
+#[trdelnik_test]
+async fn test_convert_to_shared(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let shared_salt = [2; 32];
+    let convert_to_shared = || {
+        course_batch_manager_instruction::convert_to_shared(
+            &f.client,
+            shared_salt,
+            Fixture::hash(
+                assignment_checker::SHARED_ANSWER_HASH_CHAIN_LENGTH,
+                &shared_salt,
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let set_max_lifetime_passes = |max_lifetime_passes: u16| {
+        course_batch_manager_instruction::set_max_lifetime_passes(
+            &f.client,
+            max_lifetime_passes,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+
+    // student_a passes on the hash chain
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+
+    // capped checker can't accept unlimited passes
+    set_max_lifetime_passes(f.hash_chain_length).await?;
+    assert!(convert_to_shared().await.is_err());
+    set_max_lifetime_passes(0).await?;
+    convert_to_shared().await?;
+    assert!(convert_to_shared().await.is_err());
+    assert!(set_max_lifetime_passes(f.hash_chain_length).await.is_err());
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker.shared_answer);
+    assert_eq!(checker.hash_chain_length, 0);
+    assert_eq!(checker.successful_checks, 1);
+    assert!(checker.chain_invariant_holds());
+
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = f.enroll_student(student_c.clone()).await?;
+    f.create_check_result(student_c.clone(), f.assignment_id)
+        .await?;
+
+    // students send the same answer
+    let shared_answer = Fixture::hash(
+        assignment_checker::SHARED_ANSWER_HASH_CHAIN_LENGTH - 1,
+        &shared_salt,
+        &f.course_pda,
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    for (student, token_account) in [
+        (f.student_b.clone(), f.student_b_token_account),
+        (student_c, student_c_token_account),
+    ] {
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                Some(shared_answer),
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+        assert_eq!(check_result.passed_first_time, true);
+        let balance = f.client.get_token_balance(token_account).await?;
+        assert_eq!(balance.amount.as_str(), "100");
+    }
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.shared_answer_passes, 2);
+    assert_eq!(checker.successful_checks, 1);
+
+    // shared answer has no hash chain to extend
+    let extended = course_batch_manager_instruction::extend_chain(
+        &f.client,
+        5,
+        [1; 32],
+        Fixture::hash(
+            5,
+            &[1; 32],
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(extended.is_err());
+}
+
+/// Test if creation and check of assignment checker stay within compute unit ceilings
+#[trdelnik_test]
+async fn test_compute_units(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"space_hero_cu___";
+    let assignment_checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    let tx = course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        course_batch_manager::NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt,
+            per_student_salt: false,
+            hash_iterations_per_step: 1,
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                &f.course_pda,
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let create_units = Fixture::compute_units(&tx, course_batch_manager::ID)
+        .expect("create_assignment_checker consumption is logged");
+    assert!(
+        create_units <= CREATE_ASSIGNMENT_CHECKER_MAX_UNITS,
+        "create_assignment_checker consumed {create_units} units"
+    );
+
+    let check_result_pda = f
+        .create_check_result(f.student_a.clone(), assignment_id)
+        .await?;
+    let tx = course_batch_manager_instruction::check_assignment(
+        &f.client,
+        f.hash_chain_length,
+        Fixture::hash(
+            f.hash_chain_length - 1,
+            &f.salt,
+            &f.course_pda,
+            &assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        None,
+        0,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        assignment_checker_pda,
+        check_result_pda,
+        course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        f.course_batch_escrow_pda,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+    assert!(outcome.passed && outcome.first_time);
+    let check_units = Fixture::compute_units(&tx, course_batch_manager::ID)
+        .expect("check_assignment consumption is logged");
+    assert!(
+        check_units <= CHECK_ASSIGNMENT_MAX_UNITS,
+        "check_assignment consumed {check_units} units"
+    );
+}
+
+/// Test if the same checker rewards students of batches with different multipliers differently
+#[trdelnik_test]
+async fn test_reward_multiplier(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let course_batch = f.get_course_batch_account().await?;
+    assert_eq!(
+        course_batch.reward_multiplier_bps,
+        course_batch_manager::DEFAULT_REWARD_MULTIPLIER_BPS
+    );
+
+    // the next term of the course grants half of the reward
+    let batch_id = *b"next_term_batch_";
+    let course_batch_pda = course_batch_manager::batch_canonical_pda(f.course_pda, &batch_id);
+    let mint_pda = course_batch_manager::batch_mint_canonical_pda(f.course_pda, &batch_id);
+    course_batch_manager_instruction::create_new_batch(
+        &f.client,
+        batch_id,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        mint_pda,
+        course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &batch_id),
+        system_program::ID,
+        rent::id(),
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    for (batch, reward_multiplier_bps) in [(f.course_batch_pda, 15_000), (course_batch_pda, 5_000)]
+    {
+        course_batch_manager_instruction::set_reward_multiplier(
+            &f.client,
+            reward_multiplier_bps,
+            false,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            batch,
+            [f.course_authority.clone()],
+        )
+        .await?;
+    }
+
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = get_associated_token_address(&student_c.pubkey(), &mint_pda);
+    let student_c_progress = course_batch_manager::student_progress_canonical_pda(
+        student_c.pubkey(),
+        f.course_pda,
+        &batch_id,
+    );
+    course_batch_manager_instruction::enroll_batch(
+        &f.client,
+        student_c.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        mint_pda,
+        student_c_token_account,
+        student_c_progress,
+        system_program::ID,
+        token::ID,
+        associated_token::ID,
+        rent::id(),
+        course_manager::ID,
+        course_batch_manager::ID,
+        [student_c.clone()],
+    )
+    .await?;
+    let student_c_check_result = f
+        .create_check_result(student_c.clone(), f.assignment_id)
+        .await?;
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "150");
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    let tx = course_batch_manager_instruction::check_assignment(
+        &f.client,
+        checker.hash_chain_length,
+        Fixture::hash(
+            checker.hash_chain_length - 1,
+            &f.salt,
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        None,
+        0,
+        student_c.pubkey(),
+        f.course_pda,
+        course_batch_pda,
+        f.assignment_checker_pda,
+        student_c_check_result,
+        course_batch_manager::exhausted_check_result_canonical_pda(student_c_check_result),
+        mint_pda,
+        student_c_token_account,
+        course_batch_manager::batch_escrow_canonical_pda(f.course_pda, &batch_id),
+        student_c_progress,
+        system_program::ID,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [student_c.clone()],
+    )
+    .await?;
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+    assert_eq!(outcome.minted, 50);
+    let balance_c = f.client.get_token_balance(student_c_token_account).await?;
+    assert_eq!(balance_c.amount.as_str(), "50");
+}
+
+/// Test if course authority self check is rejected only when it's forbidden
+#[trdelnik_test]
+async fn test_forbid_authority_self_check(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_forbid_authority_self_check = |forbid: bool| {
+        course_manager_instruction::set_forbid_authority_self_check(
+            &f.client,
+            forbid,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            [f.course_authority.clone()],
+        )
+    };
+    let authority_token_account = f.enroll_student(f.course_authority.clone()).await?;
+    f.create_check_result(f.course_authority.clone(), f.assignment_id)
+        .await?;
+    let self_check = || {
+        f.check_assignment(
+            f.course_authority.clone(),
+            authority_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+    };
+
+    set_forbid_authority_self_check(true).await?;
+    assert!(self_check().await.is_err());
+    // students still can check
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    set_forbid_authority_self_check(false).await?;
+    let (_, check_result) = self_check().await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance = f.client.get_token_balance(authority_token_account).await?;
+    assert_eq!(balance.amount.as_str(), "100");
+}
+
+/// Test if checker rejects passes after auto pause and extension resumes it
+#[trdelnik_test]
+async fn test_auto_pause(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    course_batch_manager_instruction::set_auto_pause(
+        &f.client,
+        4,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let check = |student: Keypair, token_account: Pubkey| {
+        f.check_assignment(
+            student,
+            token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+    };
+
+    // within the window
+    let (_, check_result) = check(f.student_a.clone(), f.student_a_token_account).await?;
+    assert_eq!(check_result.check_passed, true);
+
+    std::thread::sleep(std::time::Duration::from_secs(5));
+    let paused = check(f.student_b.clone(), f.student_b_token_account).await;
+    assert!(paused.is_err());
+
+    // extension resets the timer
+    let extension = 5;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    course_batch_manager_instruction::extend_chain(
+        &f.client,
+        extension,
+        [1; 32],
+        Fixture::hash(
+            checker.hash_chain_length + extension,
+            &[1; 32],
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let extended = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(extended.last_activity_unix_ts > checker.last_activity_unix_ts);
+    let (_, check_result) = check(f.student_b.clone(), f.student_b_token_account).await?;
+    assert_eq!(check_result.check_passed, true);
+}
+
+/// Test if reward mint info matches derivation and on-chain mint authority
+#[trdelnik_test]
+async fn test_reward_mint_info(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let tx = course_batch_manager_instruction::get_reward_mint_info(
+        &f.client,
+        f.course_pda,
+        f.course_batch_pda,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let info: course_batch_manager::RewardMintInfo =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("reward mint info is returned");
+    assert_eq!(
+        info,
+        course_batch_manager::reward_mint_info(f.course_pda, &f.batch_id)
+    );
+    assert_eq!(info.reward_mint, f.course_batch_mint_pda);
+    assert_eq!(info.mint_authority_pda, f.course_batch_pda);
+    assert_eq!(
+        info.mint_authority_bump,
+        f.get_course_batch_account().await?.bump_seed
+    );
+
+    let mint = f
+        .client
+        .account_data::<token::Mint>(f.course_batch_mint_pda)
+        .await?;
+    assert_eq!(
+        Option::<Pubkey>::from(mint.mint_authority),
+        Some(info.mint_authority_pda)
+    );
+}
+
+/// Test if passes are recorded with claimable reward while the reward vault is frozen
+#[trdelnik_test]
+async fn test_frozen_reward_vault(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let set_rewards_frozen = |frozen: bool| {
+        course_batch_manager_instruction::set_rewards_frozen(
+            &f.client,
+            frozen,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            token::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let claim = || {
+        course_batch_manager_instruction::claim_reward(
+            &f.client,
+            f.student_a.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            check_result_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                f.student_a.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            f.student_a_token_account,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.student_a.clone()],
+        )
+    };
+
+    set_rewards_frozen(true).await?;
+    let escrow = f
+        .client
+        .account_data::<token::TokenAccount>(f.course_batch_escrow_pda)
+        .await?;
+    assert!(escrow.is_frozen());
+
+    // the pass is recorded instead of failing on the payout
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert!(check_result.check_passed && check_result.passed_first_time);
+    assert_eq!(check_result.reward_claimable, 100);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "0");
+    assert_eq!(f.get_course_batch_account().await?.paid_rewards, 0);
+    assert!(claim().await.is_err());
+    // the pending reward isn't forfeited by closing the check result
+    let close = || {
+        course_batch_manager_instruction::close_check_result(
+            &f.client,
+            None,
+            f.student_a.pubkey(),
+            f.course_pda,
+            check_result_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.student_a.clone()],
+        )
+    };
+    let closed = close().await;
+    assert!(Fixture::is_program_error(&closed, "UnclaimedRewardPresent"));
+
+    set_rewards_frozen(false).await?;
+    let tx = claim().await?;
+    let claimed = Fixture::events::<course_batch_manager::RewardClaimed>(&tx);
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].1.amount, 100);
+    let balance = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance.amount.as_str(), "100");
+    assert_eq!(f.get_course_batch_account().await?.paid_rewards, 100);
+    let check_result = f.get_check_result_account(check_result_pda).await?;
+    assert!(check_result.check_passed);
+    assert_eq!(check_result.reward_claimable, 0);
+
+    // claimed reward is paid once
+    let claimed_again = claim().await;
+    assert!(Fixture::is_program_error(
+        &claimed_again,
+        "NothingClaimable"
+    ));
+    // the passed check result still keeps the record of the reward
+    let closed = close().await;
+    assert!(Fixture::is_program_error(&closed, "AlreadyPassed"));
+}
+
+/// Test if checker description has the configuration of the checker and never its tail
+#[trdelnik_test]
+async fn test_describe_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+
+    let tx = course_batch_manager_instruction::describe_checker(
+        &f.client,
+        f.course_pda,
+        f.course_batch_pda,
+        f.assignment_checker_pda,
+        [f.student_b.clone()],
+    )
+    .await?;
+    let description: course_batch_manager::CheckerDescription =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("description is returned");
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(description.course, f.course_pda);
+    assert_eq!(description.course_batch, f.course_batch_pda);
+    assert_eq!(description.assignment_id, f.assignment_id);
+    assert_eq!(description.hash_chain_length, checker.hash_chain_length);
+    assert_eq!(description.hash_chain_length, f.hash_chain_length - 1);
+    assert_eq!(description.remaining_capacity, f.hash_chain_length - 2);
+    assert_eq!(description.successful_checks, 1);
+    assert_eq!(description.to_mint_on_successful_check, 100);
+    assert_eq!(description.reward_amount, 100);
+    assert_eq!(
+        description.hash_iterations_per_step,
+        checker.hash_iterations_per_step
+    );
+    assert_eq!(description.pause_unix_ts, None);
+    assert!(!description.per_student_salt);
+    assert!(!description.archived);
+
+    // the account keeps the tail, the description doesn't
+    let tail = hash_chain::build_tail(
+        &f.salt,
+        &f.course_pda.to_bytes(),
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+        checker.hash_chain_length,
+    );
+    let contains_tail = |data: &[u8]| data.windows(tail.len()).any(|window| window == tail);
+    let account = f
+        .client
+        .get_account(f.assignment_checker_pda)
+        .await?
+        .expect("checker exists");
+    assert!(contains_tail(&account.data));
+    assert!(!contains_tail(&description.try_to_vec()?));
+}
+
+/// Test if wrong answers consume hash chain slots only when the checker is configured so
+#[trdelnik_test]
+async fn test_consume_on_failure(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let set_consume_on_failure = |consume_on_failure: bool| {
+        course_batch_manager_instruction::set_consume_on_failure(
+            &f.client,
+            consume_on_failure,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let wrong_check = || {
+        f.check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            b"Armstrong",
+            None,
+        )
+    };
+
+    // wrong answer keeps the capacity by default
+    let (_, check_result) = wrong_check().await?;
+    assert_eq!(check_result.check_passed, false);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+    assert_eq!(checker.discarded_slots, 0);
+
+    set_consume_on_failure(true).await?;
+    for consumed in 1..=2 {
+        let (_, check_result) = wrong_check().await?;
+        assert_eq!(check_result.check_passed, false);
+        let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+        assert_eq!(checker.hash_chain_length, f.hash_chain_length - consumed);
+        assert_eq!(checker.discarded_slots, consumed);
+        assert!(checker.chain_invariant_holds());
+    }
+
+    // the right answer passes on the remaining chain
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 3);
+
+    // wrong answers of student_b don't consume slots after the mode is off
+    set_consume_on_failure(false).await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        b"Armstrong",
+        None,
+    )
+    .await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length - 3);
+}
+
+/// Test if many students checking one chain in interleaved order all pass
+///
+/// Each round every pending student reads the chain, then students submit in shuffled
+/// order. Some of them read the chain again before the submission, the others submit
+/// the stale `expected_hash_chain_length` and retry in the next round.
+#[trdelnik_test]
+async fn test_concurrent_checks(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    const STUDENTS: u16 = 20;
+
+    let assignment_id = *b"space_hero_many_";
+    let hash_chain_length = STUDENTS + 1;
+    let assignment_checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        course_batch_manager::NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt,
+            per_student_salt: false,
+            hash_iterations_per_step: 1,
+            ground_truth_hash_chain_tail: Fixture::hash(
+                hash_chain_length,
+                &f.salt,
+                &f.course_pda,
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let mut students = Vec::new();
+    for _ in 0..STUDENTS {
+        let student = Keypair::new();
+        f.client.airdrop(student.pubkey(), 5_000_000).await?;
+        let token_account = f.enroll_student(student.clone()).await?;
+        let check_result = f
+            .create_check_result(student.clone(), assignment_id)
+            .await?;
+        students.push((student, token_account, check_result));
+    }
+
+    let mut interleaving = Interleaving::new(2022);
+    let mut pending: Vec<usize> = (0..students.len()).collect();
+    let mut retries = 0;
+    let mut rounds = 0;
+    while !pending.is_empty() {
+        rounds += 1;
+        assert!(
+            rounds <= STUDENTS,
+            "every round lets at least one student pass"
+        );
+        let observed = f
+            .get_checker_account(assignment_checker_pda)
+            .await?
+            .hash_chain_length;
+        interleaving.shuffle(&mut pending);
+
+        let mut retrying = Vec::new();
+        for index in pending {
+            let (student, token_account, check_result) = &students[index];
+            let expected_hash_chain_length = if interleaving.rereads() {
+                f.get_checker_account(assignment_checker_pda)
+                    .await?
+                    .hash_chain_length
+            } else {
+                observed
+            };
+            let checked = course_batch_manager_instruction::check_assignment(
+                &f.client,
+                expected_hash_chain_length,
+                Fixture::hash(
+                    expected_hash_chain_length - 1,
+                    &f.salt,
+                    &f.course_pda,
+                    &assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                ),
+                None,
+                0,
+                student.pubkey(),
+                f.course_pda,
+                f.course_batch_pda,
+                assignment_checker_pda,
+                *check_result,
+                course_batch_manager::exhausted_check_result_canonical_pda(*check_result),
+                f.course_batch_mint_pda,
+                *token_account,
+                f.course_batch_escrow_pda,
+                course_batch_manager::student_progress_canonical_pda(
+                    student.pubkey(),
+                    f.course_pda,
+                    &f.batch_id,
+                ),
+                system_program::ID,
+                token::ID,
+                assignment_checker::ID,
+                course_batch_manager::ID,
+                [student.clone()],
+            )
+            .await;
+            if Fixture::is_program_error(&checked, "ExpectedHashLengthDiffers") {
+                retries += 1;
+                retrying.push(index);
+            } else {
+                checked?;
+                assert!(
+                    f.get_check_result_account(*check_result)
+                        .await?
+                        .check_passed
+                );
+            }
+        }
+        pending = retrying;
+    }
+
+    let checker = f.get_checker_account(assignment_checker_pda).await?;
+    assert_eq!(checker.successful_checks, STUDENTS);
+    assert_eq!(checker.hash_chain_length, 1);
+    // a stale student retries at most once for each student who passes before
+    assert!(retries <= STUDENTS * (STUDENTS - 1) / 2);
+}
+
+/// Test if recorded outflow of the reward vault equals balances of students
+#[trdelnik_test]
+async fn test_reward_vault(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let student_a_check_result = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let assert_outflow = || async {
+        let mut balances = 0;
+        for token_account in [f.student_a_token_account, f.student_b_token_account] {
+            let balance = f.client.get_token_balance(token_account).await?;
+            balances += balance.amount.parse::<u64>()?;
+        }
+        let course_batch = f.get_course_batch_account().await?;
+        assert_eq!(course_batch.paid_rewards, balances);
+        Result::<u64>::Ok(balances)
+    };
+
+    // student_a reward waits in the vault during dispute window
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    assert_eq!(assert_outflow().await?, 0);
+
+    // student_b reward leaves the vault at once
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        0,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    assert_eq!(assert_outflow().await?, 100);
+
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    course_batch_manager_instruction::finalize_reward(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        student_a_check_result,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        f.course_batch_escrow_pda,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    assert_eq!(assert_outflow().await?, 200);
+    let vault_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(vault_balance.amount.as_str(), "0");
+}
+
+/// Test if reward is split with referrer and self-referral is rejected
+#[trdelnik_test]
+async fn test_referral(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let tx = course_batch_manager_instruction::set_referral_bps(
+        &f.client,
+        2_000,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let referral_bps_set = Fixture::events::<course_batch_manager::ReferralBpsSet>(&tx);
+    assert_eq!(referral_bps_set.len(), 1);
+    assert_eq!(referral_bps_set[0].1.referral_bps, 2_000);
+
+    let hash_chain_tail_parent = Fixture::hash(
+        f.hash_chain_length - 1,
+        &f.salt,
+        &f.course_pda,
+        &f.assignment_id,
+        f.ground_truth_value.as_bytes(),
+    );
+    let check_with_referrer = |student: &Keypair, token_account: Pubkey, referrer: &Keypair| {
+        let referrer_token_account = if referrer.pubkey() == f.student_a.pubkey() {
+            f.student_a_token_account
+        } else {
+            f.student_b_token_account
+        };
+        let check_result = course_batch_manager::check_result_canonical_pda(
+            student.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        );
+        f.client.send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::CheckAssignment {
+                expected_hash_chain_length: f.hash_chain_length,
+                hash_chain_tail_parent,
+                course_secret: None,
+                shard: 0,
+            },
+            WithRemainingAccounts {
+                accounts: course_batch_manager::accounts::CheckAssignment {
+                    student: student.pubkey(),
+                    course: f.course_pda,
+                    course_batch: f.course_batch_pda,
+                    assignment_checker: f.assignment_checker_pda,
+                    check_result,
+                    exhausted_check_result:
+                        course_batch_manager::exhausted_check_result_canonical_pda(check_result),
+                    mint: f.course_batch_mint_pda,
+                    course_batch_token: token_account,
+                    escrow: f.course_batch_escrow_pda,
+                    student_progress: course_batch_manager::student_progress_canonical_pda(
+                        student.pubkey(),
+                        f.course_pda,
+                        &f.batch_id,
+                    ),
+                    system_program: system_program::ID,
+                    token_program: token::ID,
+                    assignment_checker_program: assignment_checker::ID,
+                    course_batch_manager_program: course_batch_manager::ID,
+                },
+                remaining_accounts: vec![
+                    AccountMeta::new_readonly(
+                        course_batch_manager::student_progress_canonical_pda(
+                            referrer.pubkey(),
+                            f.course_pda,
+                            &f.batch_id,
+                        ),
+                        false,
+                    ),
+                    AccountMeta::new(referrer_token_account, false),
+                ],
+            },
+            [student.clone()],
+        )
+    };
+
+    // student cannot refer themselves
+    assert!(
+        check_with_referrer(&f.student_b, f.student_b_token_account, &f.student_b)
+            .await
+            .is_err()
+    );
+
+    let tx = check_with_referrer(&f.student_a, f.student_a_token_account, &f.student_b).await?;
+    let outcome: course_batch_manager::CheckOutcome =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+    assert_eq!(outcome.first_time, true);
+    assert_eq!(outcome.minted, 80);
+    assert_eq!(outcome.referral_minted, 20);
+
+    for (token_account, expected) in [
+        (f.student_a_token_account, "80"),
+        (f.student_b_token_account, "20"),
+    ] {
+        let balance = f.client.get_token_balance(token_account).await?;
+        assert_eq!(balance.amount.as_str(), expected);
+    }
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.reward.referrer, f.student_b.pubkey());
+}
+
+/// Test if events of state-changing instructions share the header of the transaction
+#[trdelnik_test]
+async fn test_events(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let tx = course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        7,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let events = Fixture::events::<course_batch_manager::DisputeWindowSet>(&tx);
+    assert_eq!(events.len(), 1);
+    let (header, event) = &events[0];
+    assert_eq!(header.program_version, course_manager::PROGRAM_VERSION);
+    assert_eq!(header.slot, tx.slot);
+    assert_eq!(event.course_batch, f.course_batch_pda);
+    assert_eq!(event.dispute_window_seconds, 7);
+
+    // programs invoked by CPI emit events in the same envelope
+    let assignment_id = *b"space_hero_evt__";
+    let assignment_checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    let tx = course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        course_batch_manager::NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt,
+            per_student_salt: false,
+            hash_iterations_per_step: 1,
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                &f.course_pda,
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let created = Fixture::events::<course_batch_manager::AssignmentCheckerCreated>(&tx);
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].0.slot, tx.slot);
+    assert_eq!(created[0].1.assignment_checker, assignment_checker_pda);
+    assert_eq!(created[0].1.assignment_id, assignment_id);
+    let count_changed = Fixture::events::<course_manager::AssignmentCountChanged>(&tx);
+    assert_eq!(count_changed.len(), 1);
+    assert_eq!(count_changed[0].0, created[0].0);
+    assert_eq!(count_changed[0].1.assignment_count, 2);
+}
+
+/// Test if the answer is revealed only after the deadline
+#[trdelnik_test]
+async fn test_answer_reveal(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // explanation of the solution, the checked value stays secret
+    let explanation = b"the ship is named after the hero";
+    let mut answer_plaintext = [0; course_batch_manager::ANSWER_PLAINTEXT_LEN];
+    answer_plaintext[..explanation.len()].copy_from_slice(explanation);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let answer_reveal_pda =
+        course_batch_manager::answer_reveal_canonical_pda(f.course_pda, &f.assignment_id);
+    course_batch_manager_instruction::create_answer_reveal(
+        &f.client,
+        f.assignment_id,
+        answer_plaintext,
+        now + 2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        answer_reveal_pda,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    let reveal_answer = || {
+        course_batch_manager_instruction::reveal_answer(
+            &f.client,
+            f.course_pda,
+            answer_reveal_pda,
+            [f.student_a.clone()],
+        )
+    };
+    let tx = reveal_answer().await?;
+    let answer: Option<[u8; course_batch_manager::ANSWER_PLAINTEXT_LEN]> =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("answer is returned");
+    assert_eq!(answer, None);
+
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    let tx = reveal_answer().await?;
+    let answer: Option<[u8; course_batch_manager::ANSWER_PLAINTEXT_LEN]> =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("answer is returned");
+    assert_eq!(answer, Some(answer_plaintext));
+}
+
+/// Test if exhausted checkers are closed in one call and active ones are left untouched
+#[trdelnik_test]
+async fn test_close_batch_checkers(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // checkers of the shortest chain are exhausted after one pass
+    let mut checkers = Vec::new();
+    for assignment_id in [
+        *b"space_hero_x1___",
+        *b"space_hero_x2___",
+        *b"space_hero_x3___",
+    ] {
+        let assignment_checker_pda =
+            course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+        course_batch_manager_instruction::create_assignment_checker(
+            &f.client,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length: 2,
+                to_mint_on_successful_check: 100,
+                salt: f.salt,
+                per_student_salt: false,
+                hash_iterations_per_step: 1,
+                ground_truth_hash_chain_tail: Fixture::hash(
+                    2,
+                    &f.salt,
+                    &f.course_pda,
+                    &assignment_id,
+                    f.ground_truth_value.as_bytes(),
+                ),
+            },
+            f.course_authority.pubkey(),
+            f.course_pda,
+            assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        checkers.push((assignment_id, assignment_checker_pda));
+    }
+    assert_eq!(f.get_course_account().await?.assignment_count, 4);
+
+    // the second checker stays active
+    for (student, token_account, (assignment_id, checker_pda)) in [
+        (f.student_a.clone(), f.student_a_token_account, checkers[0]),
+        (f.student_b.clone(), f.student_b_token_account, checkers[2]),
+    ] {
+        f.create_check_result(student.clone(), assignment_id)
+            .await?;
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+        assert!(f.get_checker_account(checker_pda).await?.is_exhausted());
+    }
+
+    let remaining_accounts = checkers
+        .iter()
+        .map(|(_, checker_pda)| *checker_pda)
+        .chain([f.assignment_checker_pda])
+        .map(|checker_pda| AccountMeta::new(checker_pda, false))
+        .collect();
+    let tx = f
+        .client
+        .send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::CloseBatchCheckers {},
+            WithRemainingAccounts {
+                accounts: course_batch_manager::accounts::CloseBatchCheckers {
+                    authority: f.course_authority.pubkey(),
+                    course: f.course_pda,
+                    assignment_checker_program: assignment_checker::ID,
+                    course_batch_manager_program: course_batch_manager::ID,
+                    course_manager_program: course_manager::ID,
+                },
+                remaining_accounts,
+            },
+            [f.course_authority.clone()],
+        )
+        .await?;
+    let closed: u16 =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("closed count is returned");
+    assert_eq!(closed, 2);
+
+    for (checker_pda, exhausted) in [
+        (checkers[0].1, true),
+        (checkers[1].1, false),
+        (checkers[2].1, true),
+        (f.assignment_checker_pda, false),
+    ] {
+        assert_eq!(
+            f.client.get_account(checker_pda).await?.is_none(),
+            exhausted
+        );
+    }
+    assert_eq!(f.get_course_account().await?.assignment_count, 2);
+}
+
+/// Test if extension beyond max lifetime passes is rejected
+#[trdelnik_test]
+async fn test_max_lifetime_passes(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let extend_chain = |extension: u16| {
+        course_batch_manager_instruction::extend_chain(
+            &f.client,
+            extension,
+            [1; 32],
+            Fixture::hash(
+                f.hash_chain_length + extension,
+                &[1; 32],
+                &f.course_pda,
+                &f.assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+    let set_max_lifetime_passes = |max_lifetime_passes: u16| {
+        course_batch_manager_instruction::set_max_lifetime_passes(
+            &f.client,
+            max_lifetime_passes,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            f.assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.course_authority.clone()],
+        )
+    };
+
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(
+        checker.lifetime_passes(),
+        u32::from(f.hash_chain_length - 1)
+    );
+    // cap below already possible passes is rejected
+    assert!(set_max_lifetime_passes(f.hash_chain_length - 2)
+        .await
+        .is_err());
+    set_max_lifetime_passes(f.hash_chain_length + 4).await?;
+
+    assert!(extend_chain(6).await.is_err());
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+
+    extend_chain(5).await?;
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length + 5);
+    assert_eq!(
+        checker.lifetime_passes(),
+        u32::from(f.hash_chain_length + 4)
+    );
+    assert!(checker.lifetime_passes_within_cap());
+}
+
+/// Test if emergency withdrawal drains stuck escrow tokens but not rewards owed to students
+#[trdelnik_test]
+async fn test_emergency_withdraw(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let timelocked_action = course_batch_manager::timelocked_action_canonical_pda(
+        f.course_pda,
+        &f.batch_id,
+        course_batch_manager::TimelockedActionKind::EmergencyWithdraw,
+    );
+    let emergency_withdraw = |authority: Keypair| {
+        course_batch_manager_instruction::emergency_withdraw(
+            &f.client,
+            authority.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            timelocked_action,
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            f.student_a_token_account,
+            token::ID,
+            [authority],
+        )
+    };
+
+    // student_a gets the reward directly and sends part of it to the escrow by mistake
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.client
+        .send_transaction(
+            &[token::spl_token::instruction::transfer(
+                &token::ID,
+                &f.student_a_token_account,
+                &f.course_batch_escrow_pda,
+                &f.student_a.pubkey(),
+                &[],
+                30,
+            )?],
+            [&f.student_a],
+        )
+        .await?;
+
+    // reward of student_b is escrowed
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let course_batch = f.get_course_batch_account().await?;
+    assert_eq!(course_batch.escrowed_rewards, 100);
+
+    // only course authority can withdraw
+    f.queue_emergency_withdraw(f.student_a_token_account)
+        .await?;
+    assert!(emergency_withdraw(f.student_b.clone()).await.is_err());
+    // the withdrawal is timelocked for a day, see `test_timelocked_action`
+    if f.warp_to_action(timelocked_action).await? {
+        emergency_withdraw(f.course_authority.clone()).await?;
+        let escrow_balance = f
+            .client
+            .get_token_balance(f.course_batch_escrow_pda)
+            .await?;
+        assert_eq!(escrow_balance.amount.as_str(), "100");
+        let balance_a = f
+            .client
+            .get_token_balance(f.student_a_token_account)
+            .await?;
+        assert_eq!(balance_a.amount.as_str(), "100");
+
+        // reward of student_b is protected after the dispute window too
+        std::thread::sleep(std::time::Duration::from_secs(3));
+        f.queue_emergency_withdraw(f.student_a_token_account)
+            .await?;
+        assert!(f.warp_to_action(timelocked_action).await?);
+        assert!(emergency_withdraw(f.course_authority.clone())
+            .await
+            .is_err());
+        course_batch_manager_instruction::finalize_reward(
+            &f.client,
+            f.student_b.pubkey(),
+            f.course_pda,
+            f.course_batch_pda,
+            course_batch_manager::check_result_canonical_pda(
+                f.student_b.pubkey(),
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            course_batch_manager::student_progress_canonical_pda(
+                f.student_b.pubkey(),
+                f.course_pda,
+                &f.batch_id,
+            ),
+            f.course_batch_escrow_pda,
+            f.course_batch_mint_pda,
+            f.student_b_token_account,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [f.student_b.clone()],
+        )
+        .await?;
+        let balance_b = f
+            .client
+            .get_token_balance(f.student_b_token_account)
+            .await?;
+        assert_eq!(balance_b.amount.as_str(), "100");
+        let course_batch = f.get_course_batch_account().await?;
+        assert_eq!(course_batch.escrowed_rewards, 0);
+    }
+}
+
+/// Test if zero and self-referential course accounts are rejected with error of course account
+#[trdelnik_test]
+async fn test_malformed_course_account(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"space_hero_zero_";
+    let created = course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        course_batch_manager::NewAssignmentChecker {
+            assignment_id,
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt,
+            per_student_salt: false,
+            hash_iterations_per_step: 1,
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                &Pubkey::default(),
+                &assignment_id,
+                f.ground_truth_value.as_bytes(),
+            ),
+        },
+        f.course_authority.pubkey(),
+        Pubkey::default(),
+        course_batch_manager::assignment_checker_canonical_pda(Pubkey::default(), &assignment_id),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        course_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await;
+    assert!(Fixture::is_account_error(created, "course"));
+
+    // assignment checker is passed as the course
+    let check_result_pda = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let checked = course_batch_manager_instruction::check_assignment(
+        &f.client,
+        f.hash_chain_length,
+        Fixture::hash(
+            f.hash_chain_length - 1,
+            &f.salt,
+            &f.course_pda,
+            &f.assignment_id,
+            f.ground_truth_value.as_bytes(),
+        ),
+        None,
+        0,
+        f.student_a.pubkey(),
+        f.assignment_checker_pda,
+        f.course_batch_pda,
+        f.assignment_checker_pda,
+        check_result_pda,
+        course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        f.course_batch_escrow_pda,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        system_program::ID,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await;
+    assert!(Fixture::is_account_error(checked, "course"));
+    let checker = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker.hash_chain_length, f.hash_chain_length);
+}
+
+/// Test if transcript has all check results of the student in the course
+#[trdelnik_test]
+async fn test_course_transcript(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let failed_assignment_id = *b"space_hero_2____";
+    let failed_checker_pda = f
+        .create_assignment_checker(failed_assignment_id, false)
+        .await?;
+    for student in [f.student_a.clone(), f.student_b.clone()] {
+        f.create_check_result(student, failed_assignment_id).await?;
+    }
+    // student_a hasn't started the assignment
+    let unstarted_assignment_id = *b"space_hero_3____";
+    f.create_assignment_checker(unstarted_assignment_id, false)
+        .await?;
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        failed_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        b"Titov",
+        None,
+    )
+    .await?;
+    // results of other students are not in the transcript
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        failed_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+
+    let transcript = f
+        .fetch_transcript(
+            f.student_a.pubkey(),
+            &[
+                f.assignment_id,
+                failed_assignment_id,
+                unstarted_assignment_id,
+            ],
+        )
+        .await?;
+    assert_eq!(transcript.student, f.student_a.pubkey());
+    assert_eq!(transcript.course, f.course_pda);
+    assert_eq!(transcript.entries.len(), 2);
+    for entry in &transcript.entries {
+        let check_result = f.get_check_result_account(entry.check_result).await?;
+        assert_eq!(entry.reward, check_result.reward);
+        let passed = entry.assignment_id == f.assignment_id;
+        assert_eq!(entry.check_passed, passed);
+        assert_eq!(entry.passed_first_time, passed);
+    }
+    assert!(transcript
+        .entries
+        .iter()
+        .any(|entry| entry.assignment_id == failed_assignment_id));
+}
+
+/// Test if rewards are held in escrow during dispute window
+#[trdelnik_test]
+async fn test_dispute_window(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    // both students pass, rewards are escrowed
+    for (student, token_account) in [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+    ] {
+        let (_, check_result) = f
+            .check_assignment(
+                student,
+                token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, true);
+        assert_eq!(check_result.reward.escrowed, 100);
+        let balance = f.client.get_token_balance(token_account).await?;
+        assert_eq!(balance.amount.as_str(), "0");
+    }
+    let escrow_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(escrow_balance.amount.as_str(), "200");
+
+    let student_a_check_result = course_batch_manager::check_result_canonical_pda(
+        f.student_a.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+    let student_b_check_result = course_batch_manager::check_result_canonical_pda(
+        f.student_b.pubkey(),
+        f.course_pda,
+        &f.assignment_id,
+    );
+
+    // reward cannot be finalized during dispute window
+    let finalized = course_batch_manager_instruction::finalize_reward(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        student_a_check_result,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        f.course_batch_escrow_pda,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await;
+    assert!(finalized.is_err());
+
+    // course authority revokes reward of student_b within the window
+    course_batch_manager_instruction::revoke_reward(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        f.student_b.pubkey(),
+        student_b_check_result,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_b.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        f.course_batch_escrow_pda,
+        f.course_batch_mint_pda,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let check_result = f.get_check_result_account(student_b_check_result).await?;
+    assert_eq!(check_result.reward.escrowed, 0);
+    assert_eq!(check_result.reward.revoked, true);
+    let escrow_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(escrow_balance.amount.as_str(), "100");
+
+    // student_a finalizes the reward after the window
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    course_batch_manager_instruction::finalize_reward(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        student_a_check_result,
+        course_batch_manager::student_progress_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.batch_id,
+        ),
+        f.course_batch_escrow_pda,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        token::ID,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+    let escrow_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(escrow_balance.amount.as_str(), "0");
+}
+
+/// Test if escrowed rewards of three students are airdropped in one transaction
+#[trdelnik_test]
+async fn test_airdrop_rewards(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    let student_c_token_account = f.enroll_student(student_c.clone()).await?;
+    f.create_check_result(student_c.clone(), f.assignment_id)
+        .await?;
+
+    course_batch_manager_instruction::set_dispute_window(
+        &f.client,
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let students = [
+        (f.student_a.clone(), f.student_a_token_account),
+        (f.student_b.clone(), f.student_b_token_account),
+        (student_c, student_c_token_account),
+    ];
+    for (student, token_account) in &students {
+        let (_, check_result) = f
+            .check_assignment(
+                student.clone(),
+                *token_account,
+                f.assignment_checker_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                f.ground_truth_value.as_bytes(),
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.reward.escrowed, 100);
+    }
+    std::thread::sleep(std::time::Duration::from_secs(3));
+
+    // repeated item of student_a is skipped
+    let mut remaining_accounts: Vec<AccountMeta> = students
+        .iter()
+        .chain(students.iter().take(1))
+        .flat_map(|(student, token_account)| {
+            [
+                AccountMeta::new_readonly(student.pubkey(), false),
+                AccountMeta::new(
+                    course_batch_manager::check_result_canonical_pda(
+                        student.pubkey(),
+                        f.course_pda,
+                        &f.assignment_id,
+                    ),
+                    false,
+                ),
+                AccountMeta::new(*token_account, false),
+                AccountMeta::new(
+                    course_batch_manager::student_progress_canonical_pda(
+                        student.pubkey(),
+                        f.course_pda,
+                        &f.batch_id,
+                    ),
+                    false,
+                ),
+            ]
+        })
+        .collect();
+    // incomplete item is skipped
+    remaining_accounts.push(AccountMeta::new_readonly(f.student_b.pubkey(), false));
+    let tx = f
+        .client
+        .send_instruction(
+            course_batch_manager::ID,
+            course_batch_manager::instruction::AirdropRewards {},
+            WithRemainingAccounts {
+                accounts: course_batch_manager::accounts::AirdropRewards {
+                    authority: f.course_authority.pubkey(),
+                    course: f.course_pda,
+                    course_batch: f.course_batch_pda,
+                    escrow: f.course_batch_escrow_pda,
+                    mint: f.course_batch_mint_pda,
+                    token_program: token::ID,
+                    assignment_checker_program: assignment_checker::ID,
+                    course_batch_manager_program: course_batch_manager::ID,
+                },
+                remaining_accounts,
+            },
+            [f.course_authority.clone()],
+        )
+        .await?;
+    let airdropped: u16 =
+        Fixture::return_data(&tx, course_batch_manager::ID).expect("airdropped count is returned");
+    assert_eq!(airdropped, 3);
+
+    for (student, token_account) in &students {
+        let balance = f.client.get_token_balance(*token_account).await?;
+        assert_eq!(balance.amount.as_str(), "100");
+        let check_result = f
+            .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+                student.pubkey(),
+                f.course_pda,
+                &f.assignment_id,
+            ))
+            .await?;
+        assert_eq!(check_result.reward.escrowed, 0);
+        let student_progress = f.get_student_progress_account(student.pubkey()).await?;
+        assert_eq!(student_progress.pending_rewards, 0);
+    }
+    let escrow_balance = f
+        .client
+        .get_token_balance(f.course_batch_escrow_pda)
+        .await?;
+    assert_eq!(escrow_balance.amount.as_str(), "0");
+}
+
+/// Test if consistent and inconsistent gradebook entries are detected
+#[trdelnik_test]
+async fn test_verify_gradebook_entry(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // student_a passes, student_b doesn't attempt
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+
+    let student_a_leaf =
+        course_batch_manager::gradebook_leaf(f.student_a.pubkey(), &f.assignment_id, true);
+    for (student_b_passed, expected_consistent) in [(false, true), (true, false)] {
+        let leaves = [
+            student_a_leaf,
+            course_batch_manager::gradebook_leaf(
+                f.student_b.pubkey(),
+                &f.assignment_id,
+                student_b_passed,
+            ),
+        ];
+        let tx = course_batch_manager_instruction::verify_gradebook_entry(
+            &f.client,
+            course_batch_manager::gradebook_root(&leaves),
+            course_batch_manager::gradebook_proof(&leaves, 1),
+            f.student_b.pubkey(),
+            f.course_pda,
+            course_batch_manager::check_result_canonical_pda(
+                f.student_b.pubkey(),
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            Vec::<Keypair>::new(),
+        )
+        .await?;
+        assert!(Fixture::has_log(
+            &tx,
+            &format!("gradebook entry is consistent: {}", expected_consistent)
+        ));
+    }
+}
+
+/// Test if gradebook snapshot includes only check results finalized by students
+#[trdelnik_test]
+async fn test_gradebook_snapshot(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let students = [f.student_a.pubkey(), f.student_b.pubkey()];
+    let finalize = |student: Keypair| {
+        course_batch_manager_instruction::finalize_check_result(
+            &f.client,
+            None,
+            student.pubkey(),
+            f.course_pda,
+            course_batch_manager::check_result_canonical_pda(
+                student.pubkey(),
+                f.course_pda,
+                &f.assignment_id,
+            ),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student],
+        )
+    };
+
+    // nobody has finalized mid-attempt results
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        b"Leonov",
+        None,
+    )
+    .await?;
+    let snapshot = f
+        .fetch_gradebook_snapshot(f.assignment_id, &students)
+        .await?;
+    assert!(snapshot.is_empty());
+
+    let tx = finalize(f.student_a.clone()).await?;
+    let finalized = Fixture::events::<course_batch_manager::CheckResultFinalized>(&tx);
+    assert_eq!(finalized.len(), 1);
+    let student_a_leaf =
+        course_batch_manager::gradebook_leaf(f.student_a.pubkey(), &f.assignment_id, true);
+    let snapshot = f
+        .fetch_gradebook_snapshot(f.assignment_id, &students)
+        .await?;
+    assert_eq!(snapshot, vec![student_a_leaf]);
+
+    // finalized result is locked
+    let checked = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await;
+    assert!(checked.is_err());
+    let finalized_again = finalize(f.student_a.clone()).await;
+    assert!(Fixture::is_program_error(
+        &finalized_again,
+        "ResultFinalized"
+    ));
+
+    // failed result is included once the student submits it
+    finalize(f.student_b.clone()).await?;
+    let student_b_leaf =
+        course_batch_manager::gradebook_leaf(f.student_b.pubkey(), &f.assignment_id, false);
+    let snapshot = f
+        .fetch_gradebook_snapshot(f.assignment_id, &students)
+        .await?;
+    assert_eq!(snapshot.len(), 2);
+    assert!(snapshot.contains(&student_a_leaf) && snapshot.contains(&student_b_leaf));
+}
+
+/// Test if hash chain library matches on-chain comparison and the test client hashes
+#[test]
+fn test_hash_chain_parity() {
+    let salt = [3; 32];
+    let course = Pubkey::new_unique();
+    let assignment_id = *b"space_hero_parit";
+    let value = b"Gagarin";
+    for len in 2..10 {
+        let tail = hash_chain::build_tail(&salt, &course.to_bytes(), &assignment_id, value, len);
+        assert_eq!(
+            tail,
+            Fixture::hash(len, &salt, &course, &assignment_id, value)
+        );
+
+        let parent =
+            hash_chain::build_tail(&salt, &course.to_bytes(), &assignment_id, value, len - 1);
+        for candidate in [parent, tail, [0; 32]] {
+            assert_eq!(
+                hash_chain::verify_parent(&candidate, &tail),
+                blake3::hash(&candidate) == blake3::Hash(tail)
+            );
+        }
+        assert!(hash_chain::verify_parent(&parent, &tail));
+
+        let first = hash_chain::build_tail(&salt, &course.to_bytes(), &assignment_id, value, 1);
+        assert_eq!(
+            first,
+            hash_chain::chain_start(&salt, &course.to_bytes(), &assignment_id, value)
+        );
+        assert_eq!(
+            first,
+            blake3::hashv(&[salt.as_ref(), course.as_ref(), &assignment_id, value]).0
+        );
+        assert!(hash_chain::verify_ancestor(
+            &first,
+            u32::from(len - 1),
+            &tail
+        ));
+        assert!(assignment_checker::verify_hash_chain_parent(
+            &tail, len, &first, 1, 1
+        ));
+        assert!(!assignment_checker::verify_hash_chain_parent(
+            &tail, len, &tail, len, 1
+        ));
+    }
+}
+
+/// Test if hash chains of the same salt and value differ between assignments and courses
+#[test]
+fn test_assignment_bound_chain() {
+    let salt = [3; 32];
+    let course = Pubkey::new_unique();
+    let value = b"Gagarin";
+    let len = 5;
+    let tail_a = hash_chain::build_tail(&salt, &course.to_bytes(), b"space_hero_ass_a", value, len);
+    let parent_a = hash_chain::build_tail(
+        &salt,
+        &course.to_bytes(),
+        b"space_hero_ass_a",
+        value,
+        len - 1,
+    );
+    let tail_b = hash_chain::build_tail(&salt, &course.to_bytes(), b"space_hero_ass_b", value, len);
+    let rerun_tail_a = hash_chain::build_tail(
+        &salt,
+        &Pubkey::new_unique().to_bytes(),
+        b"space_hero_ass_a",
+        value,
+        len,
+    );
+    assert!(hash_chain::verify_parent(&parent_a, &tail_a));
+    for tail in [tail_b, rerun_tail_a] {
+        assert!(!hash_chain::verify_parent(&parent_a, &tail));
+        for distance in 1..u32::from(len) * 2 {
+            assert!(!hash_chain::verify_ancestor(&parent_a, distance, &tail));
+        }
+    }
+}
+
+/// Test if every hash chain slot is accounted across random operation sequences
+#[test]
+fn test_chain_invariant() {
+    use assignment_checker::AssignmentCheckerState;
+
+    // xorshift is good enough to generate operations
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+    for _ in 0..100 {
+        let mut checker = AssignmentCheckerState::default();
+        checker.hash_chain_length = 10;
+        checker.revealed_hash_chain_length = 10;
+        checker.initial_hash_chain_length = 10;
+        let mut reserved = Vec::new();
+        for _ in 0..50 {
+            match random(5) {
+                // reserve
+                0 => {
+                    if let Ok(slot) = checker.take_slot(true) {
+                        reserved.push(slot);
+                    }
+                }
+                // check without reservation
+                1 => {
+                    let slot = checker.hash_chain_length;
+                    if checker.take_slot(false).is_ok() {
+                        checker.revealed_hash_chain_length = slot - 1;
+                    }
+                }
+                // check reserved slot, exposed slot is released
+                2 if !reserved.is_empty() => {
+                    let slot = reserved.swap_remove(random(reserved.len()));
+                    if slot <= checker.revealed_hash_chain_length {
+                        checker.complete_reservation().unwrap();
+                        checker.revealed_hash_chain_length = slot - 1;
+                    } else {
+                        checker.release_reservation(slot);
+                    }
+                }
+                // expired reservation is released
+                3 if !reserved.is_empty() => {
+                    let slot = reserved.swap_remove(random(reserved.len()));
+                    checker.release_reservation(slot);
+                }
+                4 if reserved.is_empty() => {
+                    checker.extend(random(5) as u16).unwrap();
+                }
+                _ => {}
+            }
+            assert!(checker.chain_invariant_holds());
+            assert_eq!(usize::from(checker.reserved_slots), reserved.len());
+            assert!(checker.hash_chain_length <= checker.revealed_hash_chain_length);
+        }
+    }
+}
+
+/// Test if client and signer derivations of assignment checker PDA agree
+#[test]
+fn test_assignment_checker_seeds() {
+    let course = Pubkey::new_unique();
+    let assignment_id = *b"space_hero______";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(course, &assignment_id);
+
+    let (address, bump_seed) = Pubkey::find_program_address(
+        &course_batch_manager::assignment_checker_seeds(&course, &assignment_id),
+        &course_batch_manager::ID,
+    );
+    assert_eq!(address, checker_pda);
+    let signer_address = Pubkey::create_program_address(
+        &course_batch_manager::assignment_checker_signer_seeds(
+            &course,
+            &assignment_id,
+            &[bump_seed],
+        ),
+        &course_batch_manager::ID,
+    )
+    .expect("bump seed is valid");
+    assert_eq!(signer_address, checker_pda);
+
+    // assignment IDs are not interchangeable
+    assert_ne!(
+        course_batch_manager::assignment_checker_canonical_pda(course, b"space_hero_2____"),
+        checker_pda
+    );
+}
+
+/// Test if check result of the layout before versioning is read with its passes and rewards
+#[test]
+fn test_check_result_migration() {
+    use assignment_checker::CheckResult;
+
+    let check_result = CheckResult {
+        assignment_id: *b"space_hero______",
+        check_passed: true,
+        passed_first_time: true,
+        bump_seed: 254,
+        reward: assignment_checker::RewardLedger {
+            escrowed: 100,
+            escrowed_until_unix_ts: 42,
+            ..Default::default()
+        },
+        student_commitment: [1; 32],
+        student_seed: [2; 32],
+        reservation: Default::default(),
+        attempts: 3,
+        schema_version: assignment_checker::CHECK_RESULT_SCHEMA_VERSION,
+        reward_claimable: 7,
+        migrated_to: Pubkey::new_unique(),
+        passed_criteria: 0b101,
+        weighted_score_bps: 5_000,
+        finalized: true,
+        generation: 2,
+        extra_attempts: 1,
+        proctor: Pubkey::new_unique(),
+        reserved: [0; assignment_checker::CHECK_RESULT_RESERVED_LEN],
+    };
+    let mut data = Vec::new();
+    check_result.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), 8 + CheckResult::LEN);
+
+    // the layout before versioning ends before schema_version
+    let legacy = CheckResult::from_versioned_data(&data[..8 + CheckResult::LEN_V0]).unwrap();
+    assert_eq!(legacy.schema_version, 0);
+    assert!(legacy.check_passed && legacy.passed_first_time);
+    assert_eq!(legacy.reward, check_result.reward);
+    assert_eq!(legacy.student_seed, check_result.student_seed);
+    assert_eq!(legacy.bump_seed, check_result.bump_seed);
+    assert_eq!(legacy.attempts, check_result.attempts);
+    // version 1 ends before reserved bytes
+    let mut version_1_data = data[..8 + CheckResult::LEN_V0 + 1].to_vec();
+    version_1_data[8 + CheckResult::LEN_V0] = 1;
+    let version_1 = CheckResult::from_versioned_data(&version_1_data).unwrap();
+    assert_eq!(version_1.schema_version, 1);
+    assert_eq!(version_1.reserved, check_result.reserved);
+    assert_eq!(version_1.reward_claimable, 0);
+    assert!(!version_1.is_migrated());
+    assert_eq!(version_1.generation, 0);
+    assert_eq!(version_1.extra_attempts, 0);
+    assert_eq!(version_1.proctor, Pubkey::default());
+
+    let current = CheckResult::from_versioned_data(&data).unwrap();
+    assert_eq!(
+        current.schema_version,
+        assignment_checker::CHECK_RESULT_SCHEMA_VERSION
+    );
+    assert_eq!(current.reward_claimable, check_result.reward_claimable);
+    assert_eq!(current.migrated_to, check_result.migrated_to);
+    assert_eq!(current.passed_criteria, check_result.passed_criteria);
+    assert_eq!(current.weighted_score_bps, check_result.weighted_score_bps);
+    assert!(current.finalized);
+    assert_eq!(current.generation, check_result.generation);
+    assert_eq!(current.extra_attempts, check_result.extra_attempts);
+    assert_eq!(current.proctor, check_result.proctor);
+    assert!(CheckResult::from_versioned_data(&data[..8 + CheckResult::LEN_V0 - 1]).is_err());
+    let mut checker_data = data.clone();
+    checker_data[..8].copy_from_slice(&assignment_checker::AssignmentCheckerState::discriminator());
+    assert!(CheckResult::from_versioned_data(&checker_data).is_err());
+}
+
+/// Test if courses of layouts before versioning are read with the defaults of new courses
+#[test]
+fn test_course_migration() {
+    use course_manager::Course;
+
+    let course = Course {
+        id: *b"space_hero______",
+        authority: Pubkey::new_unique(),
+        bump_seed: 254,
+        assignment_count: 3,
+        frozen: true,
+        forbid_authority_self_check: true,
+        enrolled_count: 5,
+        checker_generation: 4,
+        event_verbosity: course_manager::EventVerbosity::Compact,
+        created_at_unix_ts: 42,
+        self_enroll_allowed: false,
+        master_secret_hash: [1; 32],
+        proctor: Pubkey::new_unique(),
+        proctored_checks_allowed: true,
+        emit_events: false,
+        reward_mint: Pubkey::new_unique(),
+        schema_version: course_manager::COURSE_SCHEMA_VERSION,
+        reserved: [0; course_manager::COURSE_RESERVED_LEN],
+    };
+    let mut data = Vec::new();
+    course.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), 8 + Course::LEN);
+
+    // the first layout keeps the authority only
+    let legacy = Course::from_versioned_data(&data[..8 + Course::LEN_V0]).unwrap();
+    assert_eq!(legacy.schema_version, 0);
+    assert_eq!(legacy.id, course.id);
+    assert_eq!(legacy.authority, course.authority);
+    assert_eq!(legacy.bump_seed, course.bump_seed);
+    assert_eq!(legacy.assignment_count, 0);
+    assert!(legacy.event_verbosity.is_verbose());
+    assert!(legacy.self_enroll_allowed);
+    assert!(legacy.emit_events);
+    assert_eq!(legacy.reward_mint, Pubkey::default());
+    // the layout before `reward_mint` keeps the settings of the course
+    let unversioned_len = 8 + Course::LEN - 32 - 1 - course_manager::COURSE_RESERVED_LEN;
+    let unversioned = Course::from_versioned_data(&data[..unversioned_len]).unwrap();
+    assert_eq!(unversioned.schema_version, 0);
+    assert_eq!(unversioned.enrolled_count, course.enrolled_count);
+    assert!(!unversioned.event_verbosity.is_verbose());
+    assert!(!unversioned.self_enroll_allowed);
+    assert!(!unversioned.emit_events);
+    assert_eq!(unversioned.proctor, course.proctor);
+    assert_eq!(unversioned.reward_mint, Pubkey::default());
+
+    let current = Course::from_versioned_data(&data).unwrap();
+    assert_eq!(
+        current.schema_version,
+        course_manager::COURSE_SCHEMA_VERSION
+    );
+    assert_eq!(current.reward_mint, course.reward_mint);
+    assert!(Course::from_versioned_data(&data[..8 + Course::LEN_V0 - 1]).is_err());
+    let mut module_data = data.clone();
+    module_data[..8].copy_from_slice(&course_manager::Module::discriminator());
+    assert!(Course::from_versioned_data(&module_data).is_err());
+}
+
+/// Test if transaction size of `create_many_assignment_checkers` matches serialized transaction
+#[test]
+fn test_create_many_transaction_size() {
+    use course_batch_manager::{create_many_transaction_size, NewAssignmentChecker};
+
+    let authority = Keypair::new().pubkey();
+    let course = course_manager::course_canonical_pda(authority, b"the_course_id___");
+    let checker = NewAssignmentChecker {
+        assignment_id: *b"space_hero______",
+        hash_chain_length: 10,
+        to_mint_on_successful_check: 100,
+        salt: [1; 32],
+        per_student_salt: false,
+        hash_iterations_per_step: 1,
+        ground_truth_hash_chain_tail: [2; 32],
+    };
+    for checkers in 0..10 {
+        let mut accounts = course_batch_manager::accounts::CreateManyAssignmentCheckers {
+            authority,
+            course,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+            course_manager_program: course_manager::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend((0..checkers).map(|_| AccountMeta::new(Keypair::new().pubkey(), false)));
+        let instruction = solana_sdk::instruction::Instruction {
+            program_id: course_batch_manager::ID,
+            accounts,
+            data: course_batch_manager::instruction::CreateManyAssignmentCheckers {
+                checkers: vec![checker; checkers],
+            }
+            .data(),
+        };
+        let message = solana_sdk::message::Message::new(&[instruction], Some(&authority));
+        // single signature with its compact length
+        let transaction_size = 1 + 64 + message.serialize().len();
+        assert_eq!(create_many_transaction_size(1, checkers), transaction_size);
+    }
+    let per_transaction = course_batch_manager::checkers_per_transaction(1);
+    assert!(
+        create_many_transaction_size(1, per_transaction)
+            <= course_batch_manager::MAX_TRANSACTION_SIZE
+    );
+    assert!(per_transaction <= course_batch_manager::MAX_CHECKERS_PER_TRANSACTION);
+}
+
+/// Test if zero and assignment derived salts are weak
+#[test]
+fn test_is_weak_salt() {
+    let assignment_id = *b"space_hero______";
+    assert!(assignment_checker::is_weak_salt(&[0; 32], &assignment_id));
+    assert!(assignment_checker::is_weak_salt(
+        b"space_hero______space_hero______",
+        &assignment_id
+    ));
+    assert!(!assignment_checker::is_weak_salt(
+        b"space_hero______space_hero_2____",
+        &assignment_id
+    ));
+    assert!(!assignment_checker::is_weak_salt(
+        &blake3::hash(b"space hero salt").0,
+        &assignment_id
+    ));
+}
+
+/// Test if discriminator filters of accounts sharing the program owner differ
+#[test]
+fn test_discriminator_filter() {
+    use assignment_checker::{AssignmentCheckerState, CheckResult};
+
+    assert_ne!(
+        AssignmentCheckerState::discriminator(),
+        CheckResult::discriminator()
+    );
+    assert_ne!(
+        format!(
+            "{:?}",
+            Fixture::discriminator_filter::<AssignmentCheckerState>()
+        ),
+        format!("{:?}", Fixture::discriminator_filter::<CheckResult>())
+    );
+    let checker_data = AssignmentCheckerState::discriminator();
+    assert!(Fixture::has_discriminator::<AssignmentCheckerState>(
+        &checker_data
+    ));
+    assert!(!Fixture::has_discriminator::<CheckResult>(&checker_data));
+    // truncated data has no discriminator
+    assert!(!Fixture::has_discriminator::<AssignmentCheckerState>(
+        &checker_data[..4]
+    ));
+}
+
+/// Test if event envelope is decoded only for its event type
+#[test]
+fn test_event_envelope() {
+    use course_manager::{decode_event, CourseCreated, CourseFrozen, EventHeader};
+
+    let header = EventHeader {
+        program_version: course_manager::PROGRAM_VERSION,
+        slot: 42,
+    };
+    let course = Pubkey::new_unique();
+    let mut data = CourseFrozen::discriminator().to_vec();
+    header.serialize(&mut data).unwrap();
+    CourseFrozen { course }.serialize(&mut data).unwrap();
+
+    let (decoded_header, event) = decode_event::<CourseFrozen>(&data).expect("event is decoded");
+    assert_eq!(decoded_header, header);
+    assert_eq!(event.course, course);
+    assert!(decode_event::<CourseCreated>(&data).is_none());
+    assert!(decode_event::<CourseFrozen>(&data[..data.len() - 1]).is_none());
+}
+
+/// Test if the same seed reproduces the interleaving
+#[test]
+fn test_interleaving() {
+    let schedule = |seed| {
+        let mut interleaving = Interleaving::new(seed);
+        let mut students: Vec<usize> = (0..20).collect();
+        interleaving.shuffle(&mut students);
+        (students, interleaving.rereads())
+    };
+    let (students, rereads) = schedule(7);
+    assert_eq!(schedule(7), (students.clone(), rereads));
+    assert_ne!(schedule(8).0, students);
+
+    let mut sorted = students;
+    sorted.sort_unstable();
+    assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+}
+
+/// Test if recent passers buffer wraps and keeps the most recent passers
+#[test]
+fn test_recent_passers() {
+    use assignment_checker::RecentPassers;
+
+    let mut recent_passers = RecentPassers::default();
+    assert!(recent_passers.is_empty());
+
+    let passers: Vec<Pubkey> = (0..RecentPassers::CAPACITY + 2)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    for (count, passer) in passers.iter().enumerate() {
+        recent_passers.push(*passer);
+        assert_eq!(
+            recent_passers.len(),
+            (count + 1).min(RecentPassers::CAPACITY)
+        );
+        assert_eq!(recent_passers.iter().next(), Some(passer));
+    }
+
+    let expected: Vec<&Pubkey> = passers.iter().rev().take(RecentPassers::CAPACITY).collect();
+    assert_eq!(recent_passers.iter().collect::<Vec<_>>(), expected);
+}
+
+/// Test if recent parents keep the last consumed parents only
+#[test]
+fn test_recent_parents() {
+    use assignment_checker::RecentParents;
+
+    let mut recent_parents = RecentParents::default();
+    let parents: Vec<[u8; 32]> = (0..RecentParents::CAPACITY + 1)
+        .map(|index| [index as u8 + 1; 32])
+        .collect();
+    // zeroed slots of the empty buffer are not consumed parents
+    assert!(!recent_parents.contains(&[0; 32]));
+    for parent in &parents {
+        recent_parents.push(*parent);
+        assert!(recent_parents.contains(parent));
+    }
+    assert!(!recent_parents.contains(&parents[0]));
+    assert!(parents[1..]
+        .iter()
+        .all(|parent| recent_parents.contains(parent)));
+}
+
+/// Test if attempts histogram buckets attempts and saturates
+#[test]
+fn test_attempts_histogram_buckets() {
+    use assignment_checker::AttemptsHistogram;
+
+    let mut histogram = AttemptsHistogram::default();
+    for attempts in [1, 2, 3, 4, 7, 8, u16::MAX] {
+        histogram.record(attempts);
+    }
+    assert_eq!(histogram.buckets, [1, 2, 2, 2]);
+
+    histogram.buckets[0] = u16::MAX;
+    histogram.record(1);
+    assert_eq!(histogram.buckets[0], u16::MAX);
+}
+
+/// Test if pass rate is the fraction of counted checks that passed
+#[test]
+fn test_pass_rate_bps() {
+    use assignment_checker::AssignmentCheckerState;
+
+    let mut checker = AssignmentCheckerState::default();
+    assert_eq!(checker.pass_rate_bps(), 0);
+    checker.record_check(false).unwrap();
+    assert_eq!(checker.pass_rate_bps(), 0);
+    checker.record_check(true).unwrap();
+    checker.record_check(true).unwrap();
+    assert_eq!((checker.attempts_total, checker.passes_total), (3, 2));
+    assert_eq!(checker.pass_rate_bps(), 6_666);
+
+    checker.passes_total = u16::MAX;
+    assert!(checker.record_check(true).is_err());
+    checker.attempts_total = u32::MAX;
+    assert!(checker.record_check(false).is_err());
+}
+
+/// Test if difficulty score grows with attempts passers needed
+#[test]
+fn test_difficulty_score() {
+    use assignment_checker::AssignmentCheckerState;
+
+    let mut checker = AssignmentCheckerState::default();
+    assert_eq!(checker.attempts_histogram.difficulty_score(), 0);
+    checker.record_first_time_attempts(1);
+    assert_eq!(checker.difficulty_score, 0);
+
+    let mut previous = checker.difficulty_score;
+    for attempts in [2, 4, 8] {
+        checker.record_first_time_attempts(attempts);
+        assert!(checker.difficulty_score > previous);
+        previous = checker.difficulty_score;
+    }
+    for _ in 0..3 {
+        checker.record_first_time_attempts(1);
+        assert!(checker.difficulty_score < previous);
+        previous = checker.difficulty_score;
+    }
+    assert_eq!(
+        checker.difficulty_score,
+        checker.attempts_histogram.difficulty_score()
+    );
+
+    checker.attempts_histogram.buckets = [0, 0, 0, u16::MAX];
+    assert_eq!(checker.attempts_histogram.difficulty_score(), 10_000);
+    checker.attempts_histogram.buckets = [u16::MAX; 4];
+    assert_eq!(checker.attempts_histogram.difficulty_score(), 5_000);
+}
+
+/// Test if weighted score is the weighted fraction of passed criteria
+#[test]
+fn test_weighted_score() {
+    use assignment_checker::AssignmentCriteria;
+
+    let criteria = AssignmentCriteria {
+        weights: [1, 3, 6, 0],
+        criteria_count: 3,
+        ..Default::default()
+    };
+    assert_eq!(criteria.weighted_score_bps(0), 0);
+    assert_eq!(criteria.weighted_score_bps(0b001), 1_000);
+    assert_eq!(criteria.weighted_score_bps(0b010), 3_000);
+    assert_eq!(criteria.weighted_score_bps(0b110), 9_000);
+    assert_eq!(criteria.weighted_score_bps(0b111), 10_000);
+    // weights of unknown criteria are ignored
+    assert_eq!(criteria.weighted_score_bps(0b1111), 10_000);
+
+    let criteria = AssignmentCriteria {
+        weights: [1, 2, 0, 0],
+        criteria_count: 2,
+        ..Default::default()
+    };
+    // partial score is rounded down
+    assert_eq!(criteria.weighted_score_bps(0b01), 3_333);
+    assert_eq!(criteria.weighted_score_bps(0b10), 6_666);
+}
+
+/// Test if test only instructions are left out of default builds
+#[test]
+fn test_default_build_without_test_only_instructions() {
+    assert!(!course_batch_manager::TEST_ONLY_INSTRUCTIONS);
+}
+
+/// Test if keyed hash chains start with blake3 keyed by the salt and differ from prefixed ones
+#[test]
+fn test_keyed_hash_chain() {
+    use assignment_checker::CommitmentMode;
+
+    let salt = [3; 32];
+    let course = Pubkey::new_unique();
+    let assignment_id = *b"space_hero_keyed";
+    let value = b"Gagarin";
+    let keyed_start =
+        hash_chain::keyed_chain_start(&salt, &course.to_bytes(), &assignment_id, value);
+    assert_eq!(
+        keyed_start,
+        *::blake3::keyed_hash(&salt, &[course.as_ref(), &assignment_id, value].concat()).as_bytes()
+    );
+    assert_ne!(
+        keyed_start,
+        hash_chain::chain_start(&salt, &course.to_bytes(), &assignment_id, value)
+    );
+    assert_eq!(CommitmentMode::default(), CommitmentMode::Prefixed);
+
+    let len = 5;
+    for iterations in [1, 3] {
+        let tail = |mode: CommitmentMode, len| {
+            mode.build_iterated_tail(
+                &salt,
+                &course.to_bytes(),
+                &assignment_id,
+                value,
+                len,
+                iterations,
+            )
+        };
+        let keyed_tail = tail(CommitmentMode::Keyed, len);
+        assert_eq!(
+            keyed_tail,
+            hash_chain::build_keyed_iterated_tail(
+                &salt,
+                &course.to_bytes(),
+                &assignment_id,
+                value,
+                len,
+                iterations
+            )
+        );
+        assert_eq!(
+            tail(CommitmentMode::Prefixed, len),
+            hash_chain::build_iterated_tail(
+                &salt,
+                &course.to_bytes(),
+                &assignment_id,
+                value,
+                len,
+                iterations
+            )
+        );
+        for parent_len in 1..len {
+            assert!(assignment_checker::verify_hash_chain_parent(
+                &keyed_tail,
+                len,
+                &tail(CommitmentMode::Keyed, parent_len),
+                parent_len,
+                iterations
+            ));
+            assert!(!assignment_checker::verify_hash_chain_parent(
+                &keyed_tail,
+                len,
+                &tail(CommitmentMode::Prefixed, parent_len),
+                parent_len,
+                iterations
+            ));
+        }
+    }
+}
+
+/// Test if payouts failing the token program are detected before the payout
+#[test]
+fn test_payout_block() {
+    use course_batch_manager::{payout_block, PayoutBlock};
+    use token::spl_token::state::{Account, AccountState, Mint};
+
+    let mint = Mint {
+        supply: 100,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    let account = Account {
+        amount: 10,
+        state: AccountState::Initialized,
+        ..Account::default()
+    };
+    let frozen = Account {
+        state: AccountState::Frozen,
+        ..account
+    };
+    assert_eq!(payout_block(&mint, &account, &account, 100), None);
+    assert_eq!(
+        payout_block(&mint, &frozen, &account, 100),
+        Some(PayoutBlock::Frozen)
+    );
+    assert_eq!(
+        payout_block(&mint, &account, &frozen, 100),
+        Some(PayoutBlock::Frozen)
+    );
+    assert_eq!(
+        payout_block(&mint, &account, &account, u64::MAX - 100),
+        None
+    );
+    // the supply of 100 doesn't take one more token
+    assert_eq!(
+        payout_block(&mint, &account, &account, u64::MAX - 99),
+        Some(PayoutBlock::Overflow)
+    );
+    let full = Account {
+        amount: u64::MAX,
+        ..account
+    };
+    assert_eq!(
+        payout_block(&Mint::default(), &account, &full, 1),
+        Some(PayoutBlock::Overflow)
+    );
+    assert!(PayoutBlock::Frozen.is_transient());
+    assert!(!PayoutBlock::Overflow.is_transient());
+}
+
+/// Test if plaintext parent matches the client built parent within the hash limit
+#[test]
+fn test_plaintext_parent() {
+    use assignment_checker::{AssignmentCheckerState, CommitmentMode, MAX_PLAINTEXT_HASHES};
+
+    let course = Pubkey::new_unique();
+    let value = b"Gagarin";
+    // the tail is private, fields are set on the default checker
+    let mut checker = AssignmentCheckerState::default();
+    checker.assignment_id = *b"space_hero_plain";
+    checker.hash_chain_length = 5;
+    checker.salt = [3; 32];
+    checker.hash_iterations_per_step = 1;
+    for commitment_mode in [CommitmentMode::Prefixed, CommitmentMode::Keyed] {
+        checker.commitment_mode = commitment_mode;
+        assert_eq!(
+            checker.plaintext_parent(&course, 0, value),
+            Some(commitment_mode.build_iterated_tail(
+                &checker.salt,
+                &course.to_bytes(),
+                &checker.assignment_id,
+                value,
+                4,
+                1
+            ))
+        );
+    }
+    // shards are not added
+    assert_eq!(checker.plaintext_parent(&course, 1, value), None);
+
+    // the chain start and steps to the parent of the tail
+    let max_len = MAX_PLAINTEXT_HASHES as u16 + 1;
+    checker.hash_chain_length = max_len;
+    assert!(checker.plaintext_parent(&course, 0, value).is_some());
+    checker.hash_chain_length = max_len + 1;
+    assert_eq!(checker.plaintext_parent(&course, 0, value), None);
+    checker.hash_chain_length = 1;
+    assert_eq!(checker.plaintext_parent(&course, 0, value), None);
+
+    checker.hash_chain_length = 5;
+    checker.per_student_salt = true;
+    assert_eq!(checker.plaintext_parent(&course, 0, value), None);
+}
+
+/// Test if remaining capacity is one less than the hash chain length and reaches zero
+#[test]
+fn test_remaining_capacity() {
+    use assignment_checker::AssignmentCheckerState;
+
+    let mut checker = AssignmentCheckerState::default();
+    for hash_chain_length in [0, 1, 2, 10, u16::MAX] {
+        checker.hash_chain_length = hash_chain_length;
+        assert_eq!(
+            checker.remaining_capacity(),
+            hash_chain_length.saturating_sub(1)
+        );
+        assert_eq!(
+            checker.shard_remaining_capacity(0),
+            checker.remaining_capacity()
+        );
+    }
+
+    // each pass takes one slot until the last hash is left
+    checker.hash_chain_length = 3;
+    let mut capacities = Vec::new();
+    while checker.hash_chain_length > 1 {
+        capacities.push(checker.remaining_capacity());
+        checker.hash_chain_length -= 1;
+    }
+    assert_eq!(capacities, [2, 1]);
+    assert_eq!(checker.remaining_capacity(), 0);
+    assert!(checker.is_exhausted());
+
+    checker.added_shards = 1;
+    checker.shards[0].hash_chain_length = 4;
+    assert_eq!(checker.shard_remaining_capacity(1), 3);
+    assert_eq!(checker.shard_remaining_capacity(2), 0);
+}
+
+/// Test if cached tails of many assignments match tails built one by one and are reused
+#[test]
+fn test_tail_cache() {
+    use hash_chain::cache::{ChainSpec, TailCache};
+
+    let course = Pubkey::new_unique().to_bytes();
+    let mut specs: Vec<ChainSpec> = (0..16u8)
+        .map(|assignment| ChainSpec {
+            salt: [assignment; 32],
+            course,
+            assignment_id: [assignment; 16],
+            value: b"Gagarin".to_vec(),
+            len: 2_000,
+            iterations_per_step: 4,
+            keyed: assignment % 2 == 1,
+        })
+        .collect();
+    // the same chain is built once
+    specs.push(specs[0].clone());
+
+    let expected: Vec<[u8; 32]> = specs.iter().map(ChainSpec::build_tail).collect();
+    assert_eq!(
+        expected[0],
+        hash_chain::build_iterated_tail(&[0; 32], &course, &[0; 16], b"Gagarin", 2_000, 4)
+    );
+    assert_eq!(
+        expected[1],
+        hash_chain::build_keyed_iterated_tail(&[1; 32], &course, &[1; 16], b"Gagarin", 2_000, 4)
+    );
+
+    let mut cache = TailCache::new();
+    assert_eq!(cache.tails(&specs), expected);
+    assert_eq!(cache.len(), 16);
+
+    // cached tails are returned without new entries
+    assert_eq!(cache.tails(&specs), expected);
+    assert_eq!(cache.tail(&specs[3]), expected[3]);
+    assert_eq!(cache.len(), 16);
+}
+
+/// Test if the largest reward of a pass is bounded at and beyond the human scale bound
+#[test]
+fn test_max_pass_reward() {
+    use course_batch_manager::{CourseBatch, MAX_REWARD_WHOLE_TOKENS};
+
+    let mut course_batch = CourseBatch::new(
+        *b"batch___________",
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        255,
+        255,
+        255,
+    );
+    assert_eq!(course_batch.max_pass_reward(100), 100);
+    // the longest streak gets MAX_STREAK_BONUS_STEPS bonus steps
+    course_batch.reward_multiplier_bps = 15_000;
+    course_batch.streak_bonus_bps = 1_000;
+    assert_eq!(course_batch.max_pass_reward(100), 300);
+    // fractions of tokens are rounded up
+    course_batch.reward_multiplier_bps = 15_001;
+    assert_eq!(course_batch.max_pass_reward(1), 4);
+
+    course_batch.reward_multiplier_bps = 10_000;
+    course_batch.streak_bonus_bps = 0;
+    course_batch.first_solver_bonus = MAX_REWARD_WHOLE_TOKENS - u64::from(u16::MAX);
+    assert_eq!(
+        course_batch.max_pass_reward(u16::MAX),
+        MAX_REWARD_WHOLE_TOKENS
+    );
+    assert!(course_batch.check_reward_bound(false).is_ok());
+    course_batch.first_solver_bonus += 1;
+    assert!(course_batch.check_reward_bound(false).is_err());
+    assert!(course_batch.check_reward_bound(true).is_ok());
+    course_batch.first_solver_bonus = u64::MAX;
+    assert_eq!(course_batch.max_pass_reward(u16::MAX), u64::MAX);
+}
+
+/// Test if course manager accepts enrollments of the deployed course batch manager
+#[test]
+fn test_enrollment_program_id() {
+    assert_eq!(
+        course_manager::COURSE_BATCH_MANAGER_ID,
+        course_batch_manager::ID
+    );
+}
+
+/// Test if converted rewards keep their value at the reference price within bounds
+#[test]
+fn test_reward_conversion() {
+    let conversion = course_batch_manager::RewardConversion {
+        price_feed: Pubkey::new_unique(),
+        reference_price: 1_000_000,
+        min_bps: 5_000,
+        max_bps: 20_000,
+    };
+    assert!(conversion.is_enabled());
+    assert!(!course_batch_manager::RewardConversion::default().is_enabled());
+    assert_eq!(conversion.convert(100, 1_000_000), 100);
+    assert_eq!(conversion.convert(100, 1_250_000), 80);
+    assert_eq!(conversion.convert(100, 800_000), 125);
+    // rounded down
+    assert_eq!(conversion.convert(100, 1_500_000), 66);
+    assert_eq!(conversion.convert(10, 1_500_000), 6);
+    // bounded
+    assert_eq!(conversion.convert(100, 3_000_000), 50);
+    assert_eq!(conversion.convert(100, 4_000_000), 50);
+    assert_eq!(conversion.convert(100, 1), 200);
+    assert_eq!(conversion.convert(u64::MAX, 1), u64::MAX);
+}
+
+/// Test if slots are taken from chains of length 2 but not from 1 and 0
+#[test]
+fn test_take_slot_boundary() {
+    use assignment_checker::{AssignmentCheckerState, ChainShard};
+
+    let mut checker = AssignmentCheckerState::default();
+    checker.initial_hash_chain_length = 2;
+    checker.hash_chain_length = 2;
+    assert_eq!(checker.take_slot(false).unwrap(), 2);
+    assert_eq!(checker.hash_chain_length, 1);
+    for hash_chain_length in [1, 0] {
+        checker.hash_chain_length = hash_chain_length;
+        assert!(checker.take_slot(true).is_err());
+        assert!(checker.take_slot(false).is_err());
+        assert_eq!(checker.hash_chain_length, hash_chain_length);
+    }
+    assert_eq!(checker.successful_checks, 1);
+    assert_eq!(checker.reserved_slots, 0);
+
+    let mut shard = ChainShard::default();
+    for hash_chain_length in [1, 0] {
+        shard.hash_chain_length = hash_chain_length;
+        assert!(shard.check(hash_chain_length, &[0; 32], 1).is_err());
+    }
+}
+
+/// Test if reconciliation sums check results and reports discrepancies of the ledger
+#[test]
+fn test_reconcile_rewards() {
+    use course_batch_manager::{reconcile_rewards, CheckResult, CourseBatch};
+
+    let check_result = |escrowed: u64, reward_claimable: u64| {
+        let mut check_result = CheckResult::try_from_slice(&[0; CheckResult::LEN]).unwrap();
+        check_result.reward.escrowed = escrowed;
+        check_result.reward_claimable = reward_claimable;
+        check_result
+    };
+    let mut course_batch = CourseBatch::new(
+        *b"batch___________",
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        255,
+        255,
+        255,
+    );
+    course_batch.paid_rewards = 300;
+    course_batch.escrowed_rewards = 50;
+    let check_results = || vec![check_result(50, 0), check_result(0, 20), check_result(0, 0)];
+
+    let consistent = reconcile_rewards(&course_batch, 50, 350, check_results());
+    assert!(consistent.is_consistent());
+    assert_eq!(consistent.claimable, 20);
+    assert_eq!(consistent.escrowed, 50);
+    assert_eq!(consistent.minted, 350);
+
+    // a holder burned tokens and the vault lost escrowed ones
+    let burned = reconcile_rewards(&course_batch, 40, 330, check_results());
+    assert!(!burned.is_consistent());
+    assert_eq!(burned.vault_discrepancy, -10);
+    assert_eq!(burned.supply_discrepancy, -20);
+    assert_eq!(burned.escrow_discrepancy, 0);
+
+    // the batch ledger has lost track of an escrowed reward
+    course_batch.escrowed_rewards = 0;
+    course_batch.paid_rewards = 350;
+    let untracked = reconcile_rewards(&course_batch, 50, 350, check_results());
+    assert_eq!(untracked.escrow_discrepancy, 50);
+    assert_eq!(untracked.vault_discrepancy, 50);
+    assert_eq!(untracked.supply_discrepancy, 0);
+}
+
+/// Test if config updates apply only their `Some` fields
+#[test]
+fn test_apply_config() {
+    use assignment_checker::{AssignmentCheckerState, CheckerConfigUpdate, RewardRounding};
+
+    let mut checker = AssignmentCheckerState::default();
+    checker.to_mint_on_successful_check = 100;
+    checker.max_attempts = 2;
+    let config = checker.config();
+    checker.apply_config(&CheckerConfigUpdate::default());
+    assert_eq!(checker.config(), config);
+
+    checker.apply_config(&CheckerConfigUpdate {
+        reward_rounding: Some(RewardRounding::Ceil),
+        max_attempts: Some(0),
+        ..Default::default()
+    });
+    assert_eq!(checker.reward_rounding, RewardRounding::Ceil);
+    assert_eq!(checker.max_attempts, 0);
+    assert_eq!(checker.to_mint_on_successful_check, 100);
+
+    // the read config written back changes nothing
+    let config = checker.config();
+    checker.apply_config(&config);
+    assert_eq!(checker.config(), config);
+}
+
+/// Test if the leaderboard keeps the top students and evicts the last one
+#[test]
+fn test_leaderboard_record() {
+    let student = |index: u8| Pubkey::new_from_array([index; 32]);
+    let progress = |index: u8, assignments_passed: u16| course_batch_manager::StudentProgress {
+        student: student(index),
+        course_batch: Pubkey::default(),
+        assignments_passed,
+        certificate_minted: false,
+        bump_seed: 0,
+        current_streak: 0,
+        modules_completed: 0,
+        pending_rewards: 0,
+    };
+    let capacity = course_batch_manager::Leaderboard::CAPACITY as u8;
+    let mut leaderboard = course_batch_manager::Leaderboard::default();
+    for index in 0..capacity {
+        leaderboard.record(&progress(index, 1), i64::from(index));
+    }
+    let ranked = |leaderboard: &course_batch_manager::Leaderboard| {
+        leaderboard
+            .entries()
+            .iter()
+            .map(|entry| entry.student)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ranked(&leaderboard),
+        (0..capacity).map(student).collect::<Vec<_>>()
+    );
+
+    // a later pass of the same number doesn't evict anybody from the full leaderboard
+    leaderboard.record(&progress(capacity, 1), 100);
+    assert!(!ranked(&leaderboard).contains(&student(capacity)));
+    assert_eq!(leaderboard.entries().len(), usize::from(capacity));
+
+    // the student who passes more moves up without a second entry
+    leaderboard.record(&progress(capacity - 1, 2), 101);
+    assert_eq!(
+        leaderboard.entries()[0],
+        course_batch_manager::LeaderboardEntry {
+            student: student(capacity - 1),
+            assignments_passed: 2,
+            reached_unix_ts: 101,
+        }
+    );
+    assert_eq!(leaderboard.entries().len(), usize::from(capacity));
+    assert_eq!(
+        ranked(&leaderboard)
+            .iter()
+            .filter(|ranked| **ranked == student(capacity - 1))
+            .count(),
+        1
+    );
+
+    // the newcomer who outranks the last student evicts it
+    leaderboard.record(&progress(capacity, 2), 102);
+    let students = ranked(&leaderboard);
+    assert_eq!(students[1], student(capacity));
+    assert!(!students.contains(&student(capacity - 2)));
+    assert_eq!(students.last(), Some(&student(capacity - 3)));
+}
+
+/// Test rounding modes of a reward scaled by a fraction that doesn't divide evenly
+#[test]
+fn test_reward_rounding() {
+    use assignment_checker::RewardRounding;
+
+    // 100 tokens scaled by 33.33% and 33.5%
+    for (rounding, expected_a, expected_b) in [
+        (RewardRounding::Floor, 33, 33),
+        (RewardRounding::Ceil, 34, 34),
+        (RewardRounding::Nearest, 33, 34),
+    ] {
+        assert_eq!(rounding.div(100 * 3_333, 10_000), Some(expected_a));
+        assert_eq!(rounding.div(100 * 3_350, 10_000), Some(expected_b));
+        // no rounding is applied when the amount divides evenly
+        assert_eq!(rounding.div(100 * 5_000, 10_000), Some(50));
+        assert_eq!(rounding.div(100, 0), None);
+    }
+    assert_eq!(RewardRounding::default(), RewardRounding::Floor);
+}
+
+/// Input keypairs / pubkeys / programs and data to configure tests
+struct Fixture {
+    client: Client,
+    assignment_checker_program: Keypair,
+    course_program: Keypair,
+    course_batch_program: Keypair,
+
+    course_authority: Keypair,
+    course_id: [u8; 16],
+    course_pda: Pubkey,
+
+    batch_id: [u8; 16],
+    course_batch_pda: Pubkey,
+    course_batch_mint_pda: Pubkey,
+    course_batch_escrow_pda: Pubkey,
+
+    assignment_id: [u8; 16],
+    ground_truth_value: String,
+    hash_chain_length: u16,
+    salt: [u8; 32],
+    assignment_checker_pda: Pubkey,
+
+    // can be related to course_authority
+    student_a: Keypair,
+    student_a_token_account: Pubkey,
+    student_b: Keypair,
+    student_b_token_account: Pubkey,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Fixture {
+            client: Client::new(system_keypair(0)),
+            assignment_checker_program: program_keypair(1),
+            course_program: program_keypair(2),
+            course_batch_program: program_keypair(3),
+            course_authority: keypair(0),
+            course_id: *b"web2_to_web3____",
+            course_pda: Pubkey::default(),
+            batch_id: *b"the_first_batch_",
+            course_batch_pda: Pubkey::default(),
+            course_batch_mint_pda: Pubkey::default(),
+            course_batch_escrow_pda: Pubkey::default(),
+            assignment_id: *b"space_hero______",
+            // Assignment: "Surname of the first man in space"
+            ground_truth_value: "Gagarin".to_string(),
+            // support up to 10 - 1 students
+            hash_chain_length: 10,
+            // zero salt is rejected as weak
+            salt: blake3::hash(b"space hero salt").0,
+            assignment_checker_pda: Pubkey::default(),
+
+            student_a: keypair(1),
+            student_a_token_account: Pubkey::default(),
+            student_b: keypair(2),
+            student_b_token_account: Pubkey::default(),
+        }
+    }
+
+    #[throws]
+    async fn deploy(&mut self) {
+        self.client
+            .deploy_by_name(&self.course_program, "course_manager")
+            .await?;
+        self.client
+            .deploy_by_name(&self.course_batch_program, "course_batch_manager")
+            .await?;
+        self.client
+            .deploy_by_name(&self.assignment_checker_program, "assignment_checker")
+            .await?;
+    }
+
+    /// Creates assignment checker for the ground truth value and returns its address
+    #[throws]
+    async fn create_assignment_checker(
+        &self,
+        assignment_id: [u8; 16],
+        per_student_salt: bool,
+    ) -> Pubkey {
+        self.create_assignment_checker_with_iterations(assignment_id, per_student_salt, 1)
+            .await?
+    }
+
+    /// Creates assignment checker that hashes each step `hash_iterations_per_step` times
+    #[throws]
+    async fn create_assignment_checker_with_iterations(
+        &self,
+        assignment_id: [u8; 16],
+        per_student_salt: bool,
+        hash_iterations_per_step: u8,
+    ) -> Pubkey {
+        let ground_truth_hash_chain_tail = hash_chain::build_iterated_tail(
+            &self.salt,
+            &self.course_pda.to_bytes(),
+            &assignment_id,
+            self.ground_truth_value.as_bytes(),
+            self.hash_chain_length,
+            hash_iterations_per_step,
+        );
+
+        let assignment_checker_pda =
+            course_batch_manager::assignment_checker_canonical_pda(self.course_pda, &assignment_id);
+
+        course_batch_manager_instruction::create_assignment_checker(
+            &self.client,
+            course_batch_manager::NewAssignmentChecker {
+                assignment_id,
+                hash_chain_length: self.hash_chain_length,
+                to_mint_on_successful_check: 100,
+                salt: self.salt.clone(),
+                per_student_salt,
+                hash_iterations_per_step,
+                ground_truth_hash_chain_tail,
+            },
+            self.course_authority.pubkey(),
+            self.course_pda,
+            assignment_checker_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            course_manager::ID,
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+        assignment_checker_pda
+    }
+
+    /// Queues emergency withdrawal of the fixture batch to the destination token account
+    #[throws]
+    async fn queue_emergency_withdraw(&self, destination: Pubkey) -> Pubkey {
+        let action = course_batch_manager::TimelockedActionKind::EmergencyWithdraw;
+        let timelocked_action = course_batch_manager::timelocked_action_canonical_pda(
+            self.course_pda,
+            &self.batch_id,
+            action,
+        );
+        course_batch_manager_instruction::queue_action(
+            &self.client,
+            action,
+            destination,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            timelocked_action,
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+        timelocked_action
+    }
+
+    /// Warps the queued action to its execution time, false without test-only instructions
+    #[throws]
+    async fn warp_to_action(&self, timelocked_action: Pubkey) -> bool {
+        let action = self
+            .client
+            .account_data::<course_batch_manager::TimelockedAction>(timelocked_action)
+            .await?;
+        let warped = course_batch_manager_instruction::warp_timelocked_action(
+            &self.client,
+            (action.execute_after_unix_ts - action.queued_unix_ts) as u32,
+            self.course_authority.pubkey(),
+            action.course_batch,
+            timelocked_action,
+            [self.course_authority.clone()],
+        )
+        .await;
+        if Fixture::is_program_error(&warped, "TestOnlyInstruction") {
+            assert!(!course_batch_manager::TEST_ONLY_INSTRUCTIONS);
+            return false;
+        }
+        warped?;
+        true
+    }
+
+    /// Creates module of the fixture course and adds the checkers to it
+    #[throws]
+    async fn create_module(&self, module_id: [u8; 16], checkers: &[Pubkey]) -> Pubkey {
+        let module_pda = course_manager::module_canonical_pda(self.course_pda, &module_id);
+        course_manager_instruction::create_module(
+            &self.client,
+            module_id,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            module_pda,
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+        for checker_pda in checkers {
+            course_batch_manager_instruction::set_checker_module(
+                &self.client,
+                self.course_authority.pubkey(),
+                self.course_pda,
+                *checker_pda,
+                module_pda,
+                assignment_checker::ID,
+                course_batch_manager::ID,
+                course_manager::ID,
+                [self.course_authority.clone()],
+            )
+            .await?;
+        }
+        module_pda
+    }
+
+    /// Completes the module for the student with check results of the module checkers
+    #[throws]
+    async fn complete_module(
+        &self,
+        student_keypair: Keypair,
+        module_pda: Pubkey,
+        checkers: &[Pubkey],
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let student = student_keypair.pubkey();
+        let mut remaining_accounts = Vec::new();
+        for checker_pda in checkers {
+            let checker = self.get_checker_account(*checker_pda).await?;
+            remaining_accounts.push(AccountMeta::new_readonly(*checker_pda, false));
+            remaining_accounts.push(AccountMeta::new_readonly(
+                course_batch_manager::check_result_canonical_pda(
+                    student,
+                    self.course_pda,
+                    &checker.assignment_id,
+                ),
+                false,
+            ));
+        }
+        let module = self
+            .client
+            .account_data::<course_manager::Module>(module_pda)
+            .await?;
+        self.client
+            .send_instruction(
+                course_batch_manager::ID,
+                course_batch_manager::instruction::CompleteModule {},
+                WithRemainingAccounts {
+                    accounts: course_batch_manager::accounts::CompleteModule {
+                        student,
+                        course: self.course_pda,
+                        course_batch: self.course_batch_pda,
+                        module: module_pda,
+                        student_progress: course_batch_manager::student_progress_canonical_pda(
+                            student,
+                            self.course_pda,
+                            &self.batch_id,
+                        ),
+                        module_completion: course_batch_manager::module_completion_canonical_pda(
+                            student,
+                            self.course_pda,
+                            &self.batch_id,
+                            &module.module_id,
+                        ),
+                        system_program: system_program::ID,
+                    },
+                    remaining_accounts,
+                },
+                [student_keypair],
+            )
+            .await?
+    }
+
+    /// Creates criteria of the assignment from their weights and answers
+    #[throws]
+    async fn create_assignment_criteria(
+        &self,
+        checker_pda: Pubkey,
+        criteria: &[(u16, &[u8])],
+    ) -> Pubkey {
+        let checker = self.get_checker_account(checker_pda).await?;
+        let criteria = criteria
+            .iter()
+            .enumerate()
+            .map(|(index, (weight, value))| {
+                let salt = Self::criterion_salt(index);
+                course_batch_manager::NewCriterion {
+                    salt,
+                    ground_truth_hash_chain_tail: hash_chain::build_iterated_tail(
+                        &salt,
+                        &self.course_pda.to_bytes(),
+                        &checker.assignment_id,
+                        value,
+                        self.hash_chain_length,
+                        checker.hash_iterations_per_step,
+                    ),
+                    hash_chain_length: self.hash_chain_length,
+                    weight: *weight,
+                }
+            })
+            .collect();
+        let criteria_pda = course_batch_manager::assignment_criteria_canonical_pda(
+            self.course_pda,
+            &checker.assignment_id,
+        );
+        course_batch_manager_instruction::create_assignment_criteria(
+            &self.client,
+            criteria,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            checker_pda,
+            criteria_pda,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+        criteria_pda
+    }
+
+    fn criterion_salt(index: usize) -> [u8; 32] {
+        blake3::hashv(&[b"space hero criterion salt", &[index as u8]]).0
+    }
+
+    /// Checks the answer to the criterion of the assignment
+    #[throws]
+    async fn check_criterion(
+        &self,
+        student_keypair: Keypair,
+        student_token_address: Pubkey,
+        checker_pda: Pubkey,
+        criterion: u8,
+        value_to_check: &[u8],
+    ) -> course_batch_manager::CheckResult {
+        let checker = self.get_checker_account(checker_pda).await?;
+        let criteria_pda = course_batch_manager::assignment_criteria_canonical_pda(
+            self.course_pda,
+            &checker.assignment_id,
+        );
+        let assignment_criteria = self
+            .client
+            .account_data::<course_batch_manager::AssignmentCriteria>(criteria_pda)
+            .await?;
+        // criteria out of `criteria_count` have empty chains
+        let chain = assignment_criteria
+            .chains
+            .get(usize::from(criterion))
+            .copied()
+            .unwrap_or_default();
+        let hash_chain_length = chain.hash_chain_length.max(2);
+        let hash_chain_tail_parent = hash_chain::build_iterated_tail(
+            &Self::criterion_salt(usize::from(criterion)),
+            &self.course_pda.to_bytes(),
+            &checker.assignment_id,
+            value_to_check,
+            hash_chain_length - 1,
+            checker.hash_iterations_per_step,
+        );
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student_keypair.pubkey(),
+            self.course_pda,
+            &checker.assignment_id,
+        );
+        course_batch_manager_instruction::check_criterion(
+            &self.client,
+            criterion,
+            hash_chain_length,
+            hash_chain_tail_parent,
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            checker_pda,
+            criteria_pda,
+            check_result_pda,
+            self.course_batch_mint_pda,
+            student_token_address,
+            self.course_batch_escrow_pda,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_pda).await?
+    }
+
+    /// Creates assignment checkers in transactions of `checkers_per_transaction` checkers
+    ///
+    /// Returns checker addresses in the order of `assignment_ids`.
+    #[throws]
+    async fn create_many_assignment_checkers(&self, assignment_ids: &[[u8; 16]]) -> Vec<Pubkey> {
+        let mut checker_pdas = Vec::with_capacity(assignment_ids.len());
+        for chunk in assignment_ids.chunks(course_batch_manager::checkers_per_transaction(1)) {
+            let (checkers, remaining_accounts) = self.new_assignment_checkers(chunk);
+            let tx = self
+                .client
+                .send_instruction(
+                    course_batch_manager::ID,
+                    course_batch_manager::instruction::CreateManyAssignmentCheckers { checkers },
+                    WithRemainingAccounts {
+                        accounts: self.create_many_accounts(),
+                        remaining_accounts: remaining_accounts.clone(),
+                    },
+                    [self.course_authority.clone()],
+                )
+                .await?;
+            let created: u16 = Self::return_data(&tx, course_batch_manager::ID)
+                .expect("created count is returned");
+            assert_eq!(usize::from(created), chunk.len());
+            checker_pdas.extend(remaining_accounts.iter().map(|meta| meta.pubkey));
+        }
+        checker_pdas
+    }
+
+    /// Checker configurations of the fixture and their writable checker accounts
+    fn new_assignment_checkers(
+        &self,
+        assignment_ids: &[[u8; 16]],
+    ) -> (
+        Vec<course_batch_manager::NewAssignmentChecker>,
+        Vec<AccountMeta>,
+    ) {
+        assignment_ids
+            .iter()
+            .map(|assignment_id| {
+                let checker = course_batch_manager::NewAssignmentChecker {
+                    assignment_id: *assignment_id,
+                    hash_chain_length: self.hash_chain_length,
+                    to_mint_on_successful_check: 100,
+                    salt: self.salt,
+                    per_student_salt: false,
+                    hash_iterations_per_step: 1,
+                    ground_truth_hash_chain_tail: hash_chain::build_tail(
+                        &self.salt,
+                        &self.course_pda.to_bytes(),
+                        assignment_id,
+                        self.ground_truth_value.as_bytes(),
+                        self.hash_chain_length,
+                    ),
+                };
+                let checker_pda = course_batch_manager::assignment_checker_canonical_pda(
+                    self.course_pda,
+                    assignment_id,
+                );
+                (checker, AccountMeta::new(checker_pda, false))
+            })
+            .unzip()
+    }
+
+    fn create_many_accounts(&self) -> course_batch_manager::accounts::CreateManyAssignmentCheckers {
+        course_batch_manager::accounts::CreateManyAssignmentCheckers {
+            authority: self.course_authority.pubkey(),
+            course: self.course_pda,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+            course_manager_program: course_manager::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    /// Enrolls the student into the batch and returns course batch token account
+    #[throws]
+    async fn enroll_student(&self, student_keypair: Keypair) -> Pubkey {
+        let token_account =
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda);
+        course_batch_manager_instruction::enroll_batch(
+            &self.client,
+            student_keypair.pubkey(),
+            self.course_authority.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            self.course_batch_mint_pda,
+            token_account,
+            course_batch_manager::student_progress_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_pda,
+                &self.batch_id,
+            ),
+            system_program::ID,
+            token::ID,
+            associated_token::ID,
+            rent::id(),
+            course_manager::ID,
+            course_batch_manager::ID,
+            [student_keypair],
+        )
+        .await?;
+        token_account
+    }
+
+    /// Creates check result of the student and returns its address
+    #[throws]
+    async fn create_check_result(
+        &self,
+        student_keypair: Keypair,
+        assignment_id: [u8; 16],
+    ) -> Pubkey {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student_keypair.pubkey(),
+            self.course_pda,
+            &assignment_id,
+        );
+        course_batch_manager_instruction::create_check_result(
+            &self.client,
+            assignment_id,
+            None,
+            student_keypair.pubkey(),
+            self.course_pda,
+            check_result_pda,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_pda),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            system_program::ID,
+            [student_keypair],
+        )
+        .await?;
+        check_result_pda
+    }
+
+    #[throws]
+    async fn get_course_account(&self) -> course_manager::Course {
+        self.client
+            .account_data::<course_manager::Course>(self.course_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn get_course_batch_account(&self) -> course_batch_manager::CourseBatch {
+        self.client
+            .account_data::<course_batch_manager::CourseBatch>(self.course_batch_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn get_leaderboard_account(&self) -> course_batch_manager::Leaderboard {
+        self.client
+            .account_data::<course_batch_manager::Leaderboard>(
+                course_batch_manager::leaderboard_canonical_pda(self.course_pda, &self.batch_id),
+            )
+            .await?
+    }
+
+    /// Reconciles rewards of the batch with check results of students A and B
+    #[throws]
+    async fn reward_reconciliation(&self) -> course_batch_manager::RewardReconciliation {
+        let vault = token::spl_token::state::Account::unpack(
+            &self
+                .client
+                .get_account(self.course_batch_escrow_pda)
+                .await?
+                .unwrap()
+                .data,
+        )?;
+        let mint = token::spl_token::state::Mint::unpack(
+            &self
+                .client
+                .get_account(self.course_batch_mint_pda)
+                .await?
+                .unwrap()
+                .data,
+        )?;
+        let mut check_results = Vec::new();
+        for student in [self.student_a.pubkey(), self.student_b.pubkey()] {
+            check_results.push(
+                self.get_check_result_account(course_batch_manager::check_result_canonical_pda(
+                    student,
+                    self.course_pda,
+                    &self.assignment_id,
+                ))
+                .await?,
+            );
+        }
+        course_batch_manager::reconcile_rewards(
+            &self.get_course_batch_account().await?,
+            vault.amount,
+            mint.supply,
+            check_results,
+        )
+    }
+
+    #[throws]
+    async fn get_student_progress_account(
+        &self,
+        student: Pubkey,
+    ) -> course_batch_manager::StudentProgress {
+        self.client
+            .account_data::<course_batch_manager::StudentProgress>(
+                course_batch_manager::student_progress_canonical_pda(
+                    student,
+                    self.course_pda,
+                    &self.batch_id,
+                ),
+            )
+            .await?
+    }
+
+    #[throws]
+    async fn get_checker_account(
+        &self,
+        checker_pda: Pubkey,
+    ) -> assignment_checker::AssignmentCheckerState {
+        self.client
+            .account_data::<course_batch_manager::AssignmentCheckerState>(checker_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn get_check_result_account(
+        &self,
+        check_result_pda: Pubkey,
+    ) -> assignment_checker::CheckResult {
+        self.client
+            .account_data::<course_batch_manager::CheckResult>(check_result_pda)
+            .await?
+    }
+
+    /// State of the assignment of the student, missing check result is not attempted
+    #[throws]
+    async fn assignment_state(
+        &self,
+        student: Pubkey,
+        assignment_id: [u8; 16],
+    ) -> course_batch_manager::AssignmentState {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student,
+            self.course_pda,
+            &assignment_id,
+        );
+        match self.client.get_account(check_result_pda).await? {
+            Some(_) => course_batch_manager::AssignmentState::of(Some(
+                &self.get_check_result_account(check_result_pda).await?,
+            )),
+            None => course_batch_manager::AssignmentState::of(None),
+        }
+    }
+
+    /// Whether the student has to submit a check of the assignment
+    ///
+    /// Students without check result create it before the check.
+    #[throws]
+    async fn needs_check(&self, student: Pubkey, assignment_id: [u8; 16]) -> bool {
+        let check_result_pda = course_batch_manager::check_result_canonical_pda(
+            student,
+            self.course_pda,
+            &assignment_id,
+        );
+        match self.client.get_account(check_result_pda).await? {
+            Some(_) => self
+                .get_check_result_account(check_result_pda)
+                .await?
+                .needs_check(),
+            None => true,
+        }
+    }
+
+    /// Fetches any account of the assignment checker program and detects its type
+    ///
+    /// `None` when the account doesn't exist or is owned by another program.
+    #[throws]
+    async fn get_assignment_checker_account(
+        &self,
+        address: Pubkey,
+    ) -> Option<course_batch_manager::AssignmentCheckerAccount> {
+        match self.client.get_account(address).await? {
+            Some(account) if account.owner == assignment_checker::ID => {
+                Some(course_batch_manager::AssignmentCheckerAccount::try_from_data(&account.data)?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Memcmp filter matching accounts of type `T` by their Anchor discriminator
+    ///
+    /// Programs own accounts of several types, scans of program accounts have to filter them.
+    fn discriminator_filter<T: Discriminator>() -> RpcFilterType {
+        RpcFilterType::Memcmp(Memcmp {
+            offset: 0,
+            bytes: MemcmpEncodedBytes::Base64(base64::encode(T::discriminator())),
+            encoding: None,
+        })
+    }
+
+    /// Whether account data starts with the discriminator of `T`
+    fn has_discriminator<T: Discriminator>(data: &[u8]) -> bool {
+        data.starts_with(&T::discriminator())
+    }
+
+    /// Fetches all accounts of type `T` owned by the program
+    ///
+    /// Fetched accounts are deserialized with discriminator verification.
+    #[throws]
+    async fn program_accounts<T>(&self, program_id: Pubkey) -> Vec<(Pubkey, T)>
+    where
+        T: AccountDeserialize + Discriminator + Send + 'static,
+    {
+        self.filtered_program_accounts::<T>(program_id, Vec::new())
+            .await?
+    }
+
+    /// Fetches accounts of type `T` owned by the program that match the filters
+    #[throws]
+    async fn filtered_program_accounts<T>(
+        &self,
+        program_id: Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Vec<(Pubkey, T)>
+    where
+        T: AccountDeserialize + Discriminator + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let payer = Rc::new(Keypair::new());
+            let mut filters = filters;
+            filters.insert(0, Self::discriminator_filter::<T>());
+            AnchorClient::new_with_options(Cluster::Localnet, payer, CommitmentConfig::confirmed())
+                .program(program_id)
+                .accounts::<T>(filters)
+        })
+        .await??
+    }
+
+    /// Fetches progress of students enrolled in the batch ordered for a leaderboard
+    #[throws]
+    async fn fetch_cohort_progress(
+        &self,
+        course_batch: Pubkey,
+    ) -> Vec<(Pubkey, course_batch_manager::StudentProgress)> {
+        let course_batch_filter = RpcFilterType::Memcmp(Memcmp {
+            offset: course_batch_manager::StudentProgress::COURSE_BATCH_OFFSET,
+            bytes: MemcmpEncodedBytes::Base64(base64::encode(course_batch)),
+            encoding: None,
+        });
+        let student_progress = self
+            .filtered_program_accounts::<course_batch_manager::StudentProgress>(
+                course_batch_manager::ID,
+                vec![course_batch_filter],
+            )
+            .await?;
+        course_batch_manager::cohort_leaderboard(course_batch, student_progress)
+    }
+
+    /// Fetches check results of the student for course assignments
+    #[throws]
+    async fn fetch_transcript(
+        &self,
+        student: Pubkey,
+        assignment_ids: &[[u8; 16]],
+    ) -> course_batch_manager::Transcript {
+        let mut check_results = Vec::new();
+        for assignment_id in assignment_ids {
+            let check_result_pda = course_batch_manager::check_result_canonical_pda(
+                student,
+                self.course_pda,
+                assignment_id,
+            );
+            if self.client.get_account(check_result_pda).await?.is_some() {
+                check_results.push((
+                    check_result_pda,
+                    self.get_check_result_account(check_result_pda).await?,
+                ));
+            }
+        }
+        course_batch_manager::course_transcript(student, self.course_pda, check_results)
+    }
+
+    /// Fetches check results of the students for the gradebook snapshot of the assignment
+    #[throws]
+    async fn fetch_gradebook_snapshot(
+        &self,
+        assignment_id: [u8; 16],
+        students: &[Pubkey],
+    ) -> Vec<[u8; 32]> {
+        let mut check_results = Vec::new();
+        for student in students {
+            let check_result_pda = course_batch_manager::check_result_canonical_pda(
+                *student,
+                self.course_pda,
+                &assignment_id,
+            );
+            if self.client.get_account(check_result_pda).await?.is_some() {
+                check_results.push((
+                    check_result_pda,
+                    self.get_check_result_account(check_result_pda).await?,
+                ));
+            }
+        }
+        course_batch_manager::gradebook_snapshot(self.course_pda, &assignment_id, check_results)
+    }
+
+    /// Reserves hash chain slot for the student and returns the reservation
+    #[throws]
+    async fn reserve_check(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+    ) -> assignment_checker::Reservation {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let check_result_address = course_batch_manager::check_result_canonical_pda(
+            student_keypair.pubkey(),
+            self.course_pda,
+            &assignment_checker.assignment_id,
+        );
+        course_batch_manager_instruction::reserve_check(
+            &self.client,
+            None,
+            student_keypair.pubkey(),
+            self.course_pda,
+            checker_data_address,
+            check_result_address,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address)
+            .await?
+            .reservation
+    }
+
+    /// Checks assignment and returns the hashed value_to_check and the result of the check
+    #[throws]
+    async fn check_assignment(
+        &self,
+        student_keypair: Keypair,
+        student_token_address: Pubkey,
+        checker_data_address: Pubkey,
+        course_data_address: Pubkey,
+        course_batch_address: Pubkey,
         value_to_check: &[u8],
         // to use custom hash instead of hasing value_to_check
         use_custom_hash_tail_parent: Option<[u8; 32]>,
         // (hash used for check, check_result)
+    ) -> ([u8; 32], course_batch_manager::CheckResult) {
+        self.check_assignment_with_course_secret(
+            student_keypair,
+            student_token_address,
+            checker_data_address,
+            course_data_address,
+            course_batch_address,
+            value_to_check,
+            use_custom_hash_tail_parent,
+            None,
+        )
+        .await?
+    }
+
+    /// Checks assignment with anonymous check result when course secret is provided
+    #[throws]
+    async fn check_assignment_with_course_secret(
+        &self,
+        student_keypair: Keypair,
+        student_token_address: Pubkey,
+        checker_data_address: Pubkey,
+        course_data_address: Pubkey,
+        course_batch_address: Pubkey,
+        value_to_check: &[u8],
+        use_custom_hash_tail_parent: Option<[u8; 32]>,
+        course_secret: Option<[u8; 32]>,
     ) -> ([u8; 32], course_batch_manager::CheckResult) {
         let assignment_checker = self.get_checker_account(checker_data_address).await?;
         let hash_chain_length = assignment_checker.hash_chain_length;
+        // the default parent hashes each step once
         let hash_chain_tail_parent = use_custom_hash_tail_parent.unwrap_or_else(|| {
-            Self::hash(
-                hash_chain_length - 1,
+            assignment_checker.commitment_mode.build_iterated_tail(
                 &assignment_checker.salt,
+                &course_data_address.to_bytes(),
+                &assignment_checker.assignment_id,
                 value_to_check,
+                hash_chain_length - 1,
+                1,
             )
         });
 
-        let check_result_address = course_batch_manager::check_result_canonical_pda(
-            student_keypair.pubkey(),
-            course_data_address,
-            &assignment_checker.assignment_id,
-        );
+        let check_result_address = match course_secret {
+            Some(course_secret) => course_batch_manager::anonymous_check_result_canonical_pda(
+                student_keypair.pubkey(),
+                &course_secret,
+                course_data_address,
+                &assignment_checker.assignment_id,
+            ),
+            None => course_batch_manager::check_result_canonical_pda(
+                student_keypair.pubkey(),
+                course_data_address,
+                &assignment_checker.assignment_id,
+            ),
+        };
+        let passed_before = self
+            .get_check_result_account(check_result_address)
+            .await?
+            .check_passed;
 
-        course_batch_manager_instruction::check_assignment(
+        let tx = course_batch_manager_instruction::check_assignment(
             &self.client,
             hash_chain_length,
             hash_chain_tail_parent,
+            course_secret,
+            0,
             student_keypair.pubkey(),
             course_data_address,
             course_batch_address,
             checker_data_address,
             check_result_address,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_address),
             self.course_batch_mint_pda,
             student_token_address,
+            self.course_batch_escrow_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                student_keypair.pubkey(),
+                course_data_address,
+                &self.batch_id,
+            ),
             system_program::ID,
             token::ID,
             assignment_checker::ID,
@@ -395,17 +9628,413 @@ impl Fixture {
             [student_keypair],
         )
         .await?;
-        (
-            hash_chain_tail_parent,
-            self.get_check_result_account(check_result_address).await?,
+        let check_result = self.get_check_result_account(check_result_address).await?;
+        let checker = self.get_checker_account(checker_data_address).await?;
+        // return data conveys the same outcome as the accounts
+        let outcome: course_batch_manager::CheckOutcome =
+            Self::return_data(&tx, course_batch_manager::ID).expect("check outcome is returned");
+        assert_eq!(outcome.passed, check_result.check_passed);
+        assert_eq!(
+            outcome.first_time,
+            check_result.check_passed && !passed_before
+        );
+        assert_eq!(outcome.remaining_chain, checker.hash_chain_length);
+        assert_eq!(outcome.remaining_capacity, checker.remaining_capacity());
+        assert_eq!(outcome.minted + outcome.claimable > 0, outcome.first_time);
+        (hash_chain_tail_parent, check_result)
+    }
+
+    /// Checks the answer of the student in plaintext, the program builds the parent
+    #[throws]
+    async fn check_plaintext(
+        &self,
+        student_keypair: Keypair,
+        student_token_address: Pubkey,
+        checker_data_address: Pubkey,
+        value_to_check: &[u8],
+    ) -> course_batch_manager::CheckResult {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let check_result_address = course_batch_manager::check_result_canonical_pda(
+            student_keypair.pubkey(),
+            self.course_pda,
+            &assignment_checker.assignment_id,
+        );
+        course_batch_manager_instruction::check_plaintext(
+            &self.client,
+            assignment_checker.hash_chain_length,
+            value_to_check.to_vec(),
+            None,
+            0,
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            checker_data_address,
+            check_result_address,
+            course_batch_manager::exhausted_check_result_canonical_pda(check_result_address),
+            self.course_batch_mint_pda,
+            student_token_address,
+            self.course_batch_escrow_pda,
+            course_batch_manager::student_progress_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_pda,
+                &self.batch_id,
+            ),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
+
+    /// Checks the answer of the student with the leaderboard of the fixture batch
+    #[throws]
+    async fn check_with_leaderboard(
+        &self,
+        student_keypair: &Keypair,
+        student_token_address: Pubkey,
+        checker_data_address: Pubkey,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let checker = self.get_checker_account(checker_data_address).await?;
+        let hash_chain_tail_parent = checker.commitment_mode.build_iterated_tail(
+            &checker.salt,
+            &self.course_pda.to_bytes(),
+            &checker.assignment_id,
+            self.ground_truth_value.as_bytes(),
+            checker.hash_chain_length - 1,
+            1,
+        );
+        let student = student_keypair.pubkey();
+        let check_result = course_batch_manager::check_result_canonical_pda(
+            student,
+            self.course_pda,
+            &checker.assignment_id,
+        );
+        self.client
+            .send_instruction(
+                course_batch_manager::ID,
+                course_batch_manager::instruction::CheckAssignment {
+                    expected_hash_chain_length: checker.hash_chain_length,
+                    hash_chain_tail_parent,
+                    course_secret: None,
+                    shard: 0,
+                },
+                WithRemainingAccounts {
+                    accounts: course_batch_manager::accounts::CheckAssignment {
+                        student,
+                        course: self.course_pda,
+                        course_batch: self.course_batch_pda,
+                        assignment_checker: checker_data_address,
+                        check_result,
+                        exhausted_check_result: course_batch_manager::exhausted_check_result_canonical_pda(check_result),
+                        mint: self.course_batch_mint_pda,
+                        course_batch_token: student_token_address,
+                        escrow: self.course_batch_escrow_pda,
+                        student_progress: course_batch_manager::student_progress_canonical_pda(
+                            student,
+                            self.course_pda,
+                            &self.batch_id,
+                        ),
+                        system_program: system_program::ID,
+                        token_program: token::ID,
+                        assignment_checker_program: assignment_checker::ID,
+                        course_batch_manager_program: course_batch_manager::ID,
+                    },
+                    remaining_accounts: vec![AccountMeta::new(
+                        course_batch_manager::leaderboard_canonical_pda(
+                            self.course_pda,
+                            &self.batch_id,
+                        ),
+                        false,
+                    )],
+                },
+                [student_keypair.clone()],
+            )
+            .await?
+    }
+
+    /// Check assignment instruction of the student for the fixture checker and batch
+    ///
+    /// Several checks share a transaction to be processed in instruction order.
+    fn check_assignment_instruction(
+        &self,
+        student: Pubkey,
+        student_token_address: Pubkey,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+    ) -> solana_sdk::instruction::Instruction {
+        let check_result = course_batch_manager::check_result_canonical_pda(
+            student,
+            self.course_pda,
+            &self.assignment_id,
+        );
+        solana_sdk::instruction::Instruction {
+            program_id: course_batch_manager::ID,
+            accounts: course_batch_manager::accounts::CheckAssignment {
+                student,
+                course: self.course_pda,
+                course_batch: self.course_batch_pda,
+                assignment_checker: self.assignment_checker_pda,
+                check_result,
+                exhausted_check_result: course_batch_manager::exhausted_check_result_canonical_pda(
+                    check_result,
+                ),
+                mint: self.course_batch_mint_pda,
+                course_batch_token: student_token_address,
+                escrow: self.course_batch_escrow_pda,
+                student_progress: course_batch_manager::student_progress_canonical_pda(
+                    student,
+                    self.course_pda,
+                    &self.batch_id,
+                ),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                assignment_checker_program: assignment_checker::ID,
+                course_batch_manager_program: course_batch_manager::ID,
+            }
+            .to_account_metas(None),
+            data: course_batch_manager::instruction::CheckAssignment {
+                expected_hash_chain_length,
+                hash_chain_tail_parent,
+                course_secret: None,
+                shard: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Checks assignment and retries on `ExpectedHashLengthDiffers` up to `max_retries` times
+    ///
+    /// The first submission uses `expected_hash_chain_length` observed by the client.
+    /// Retries re-read hash chain length of the checker after exponential backoff with
+    /// jitter. Returns the number of retries.
+    #[throws]
+    async fn submit_check_with_retry(
+        &self,
+        student_keypair: Keypair,
+        student_token_address: Pubkey,
+        checker_data_address: Pubkey,
+        value_to_check: &[u8],
+        mut expected_hash_chain_length: u16,
+        max_retries: u16,
+        jitter: &mut Interleaving,
+    ) -> u16 {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let check_result_address = course_batch_manager::check_result_canonical_pda(
+            student_keypair.pubkey(),
+            self.course_pda,
+            &assignment_checker.assignment_id,
+        );
+        let mut retries = 0;
+        loop {
+            let checked = course_batch_manager_instruction::check_assignment(
+                &self.client,
+                expected_hash_chain_length,
+                Self::hash(
+                    expected_hash_chain_length - 1,
+                    &assignment_checker.salt,
+                    &self.course_pda,
+                    &assignment_checker.assignment_id,
+                    value_to_check,
+                ),
+                None,
+                0,
+                student_keypair.pubkey(),
+                self.course_pda,
+                self.course_batch_pda,
+                checker_data_address,
+                check_result_address,
+                course_batch_manager::exhausted_check_result_canonical_pda(check_result_address),
+                self.course_batch_mint_pda,
+                student_token_address,
+                self.course_batch_escrow_pda,
+                course_batch_manager::student_progress_canonical_pda(
+                    student_keypair.pubkey(),
+                    self.course_pda,
+                    &self.batch_id,
+                ),
+                system_program::ID,
+                token::ID,
+                assignment_checker::ID,
+                course_batch_manager::ID,
+                [student_keypair.clone()],
+            )
+            .await;
+            if retries == max_retries
+                || !Self::is_program_error(&checked, "ExpectedHashLengthDiffers")
+            {
+                checked?;
+                break retries;
+            }
+            retries += 1;
+            let backoff_millis = RETRY_BACKOFF_MILLIS << retries.min(RETRY_BACKOFF_MAX_SHIFT);
+            let jitter_millis = jitter.next() % RETRY_BACKOFF_MILLIS;
+            tokio::time::sleep(std::time::Duration::from_millis(
+                backoff_millis + jitter_millis,
+            ))
+            .await;
+            expected_hash_chain_length = self
+                .get_checker_account(checker_data_address)
+                .await?
+                .hash_chain_length;
+        }
+    }
+
+    /// Return data set by the program in the transaction
+    fn return_data<T: AnchorDeserialize>(
+        tx: &EncodedConfirmedTransactionWithStatusMeta,
+        program_id: Pubkey,
+    ) -> Option<T> {
+        let prefix = format!("Program return: {} ", program_id);
+        tx.transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.as_ref())
+            .and_then(|logs| logs.iter().rev().find_map(|log| log.strip_prefix(&prefix)))
+            .and_then(|data| base64::decode(data).ok())
+            .and_then(|data| T::try_from_slice(&data).ok())
+    }
+
+    /// Events of type `T` emitted by `emit_event` in the transaction
+    fn events<T: AnchorDeserialize + Discriminator>(
+        tx: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Vec<(course_manager::EventHeader, T)> {
+        tx.transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|log| log.strip_prefix("Program data: "))
+            .filter_map(|data| base64::decode(data).ok())
+            .filter_map(|data| course_manager::decode_event(&data))
+            .collect()
+    }
+
+    /// Receipt of the check made by the confirmed transaction
+    fn check_receipt(
+        tx: &EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Option<course_batch_manager::CheckReceipt> {
+        tx.transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|log| log.strip_prefix("Program data: "))
+            .filter_map(|data| base64::decode(data).ok())
+            .find_map(|data| course_batch_manager::check_receipt(&data))
+    }
+
+    /// Compute units consumed by the top level instruction of the program
+    ///
+    /// Parses `Program <id> consumed <units> of <limit> compute units` log of the runtime.
+    /// CPI calls log own consumption before the caller, so the last line belongs
+    /// to the top level instruction.
+    fn compute_units(
+        tx: &EncodedConfirmedTransactionWithStatusMeta,
+        program_id: Pubkey,
+    ) -> Option<u64> {
+        let prefix = format!("Program {} consumed ", program_id);
+        tx.transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.as_ref())
+            .and_then(|logs| logs.iter().rev().find_map(|log| log.strip_prefix(&prefix)))
+            .and_then(|consumption| consumption.split_whitespace().next())
+            .and_then(|units| units.parse().ok())
+    }
+
+    /// Whether the transaction has failed on validation of the account
+    fn is_account_error<T, E: std::fmt::Debug>(result: Result<T, E>, account_name: &str) -> bool {
+        result.map_or_else(
+            |error| {
+                format!("{:?}", error).contains(&format!("caused by account: {}.", account_name))
+            },
+            |_| false,
+        )
+    }
+
+    /// Whether the transaction has failed with the program error
+    fn is_program_error<T, E: std::fmt::Debug>(result: &Result<T, E>, error_name: &str) -> bool {
+        result.as_ref().map_or_else(
+            |error| format!("{:?}", error).contains(&format!("Error Code: {}.", error_name)),
+            |_| false,
         )
     }
 
-    fn hash(hash_chain_length: u16, salt: &[u8; 32], value_to_hash: &[u8]) -> [u8; 32] {
-        assert!(hash_chain_length >= 2);
-        let first_hash = blake3::hashv(&[salt, value_to_hash]);
+    /// Whether transaction logs have a line ending with the message
+    fn has_log(tx: &EncodedConfirmedTransactionWithStatusMeta, message: &str) -> bool {
+        tx.transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.as_ref())
+            .is_some_and(|logs| logs.iter().any(|log| log.ends_with(message)))
+    }
+
+    fn hash(
+        hash_chain_length: u16,
+        salt: &[u8; 32],
+        course: &Pubkey,
+        assignment_id: &[u8; 16],
+        value_to_hash: &[u8],
+    ) -> [u8; 32] {
+        // tail parent of the shortest chain has length 1
+        assert!(hash_chain_length >= 1);
+        let first_hash = blake3::hashv(&[salt, course.as_ref(), assignment_id, value_to_hash]);
         (0..hash_chain_length - 1)
             .fold(first_hash, |hash, _| blake3::hash(&hash.0))
             .0
     }
 }
+
+/// Instruction accounts followed by remaining accounts
+struct WithRemainingAccounts<T> {
+    accounts: T,
+    remaining_accounts: Vec<AccountMeta>,
+}
+
+impl<T: ToAccountMetas> ToAccountMetas for WithRemainingAccounts<T> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        let mut account_metas = self.accounts.to_account_metas(is_signer);
+        account_metas.extend(self.remaining_accounts.iter().cloned());
+        account_metas
+    }
+}
+
+/// Deterministic interleaving of concurrent student actions
+///
+/// A linear congruential generator drives the order, so a failing schedule is reproduced
+/// by its seed.
+struct Interleaving {
+    state: u64,
+}
+
+impl Interleaving {
+    fn new(seed: u64) -> Self {
+        Interleaving { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.state >> 33
+    }
+
+    /// Fisher-Yates shuffle of the actions
+    fn shuffle<T>(&mut self, actions: &mut [T]) {
+        for i in (1..actions.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            actions.swap(i, j);
+        }
+    }
+
+    /// The student reads the chain again right before the submission
+    fn rereads(&mut self) -> bool {
+        self.next() % 2 == 0
+    }
+}